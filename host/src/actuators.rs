@@ -0,0 +1,168 @@
+//! ==============================================================================
+//! actuators.rs - one queue per actuator pin (buzzer, fan relay)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a buzzer pattern is a run of relay toggles with sleeps in between.
+//!     two of them at once - an alert action and a dashboard click, or a
+//!     burst of /api/buzzer requests - used to toggle the same relay
+//!     interleaved, and a fan command could land in the middle of a beep.
+//!     now every operation on a pin goes through that pin's queue: one
+//!     worker task per pin takes them in order and runs each to the end,
+//!     the caller waits for its own. a caller that stops waiting (a closed
+//!     http request) doesn't cancel its operation.
+//!
+//! limits:
+//!     a queue holds actuators.queue_depth operations besides the running
+//!     one. an operation that finds it full is refused with Busy: /api/buzzer
+//!     and the test buttons answer 429, commands fail with the message,
+//!     plugins get nothing (the refusal is logged at debug level).
+//!
+//!     [actuators]
+//!     queue_depth = 4
+//!
+//! exposed:
+//!     GET /metrics: wasi_actuator_queue_depth{pin}, wasi_actuator_rejected_total{pin}
+//!
+//! relationships:
+//!     - used by: commands.rs (buzz / fan), runtime.rs (buzzer / fan imports),
+//!       main.rs (buzzer and fan handlers, /metrics)
+//!     - uses: hal.rs (buzz, switch_fan)
+//!     - reads: config.rs (ActuatorConfig, FanConfig)
+//!
+//! ==============================================================================
+
+use crate::config::{ActuatorConfig, FanConfig};
+use anyhow::Result;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+/// what a queued operation does with its pin
+enum Operation {
+    /// (relay on ms, relay off ms) steps, see hal::pattern
+    Buzz(Vec<(u64, u64)>),
+    /// switch the fan relay
+    Fan { fan: FanConfig, on: bool },
+    /// fan on, wait, fan off - nothing else runs on the pin meanwhile
+    FanRun { fan: FanConfig, time: Duration },
+}
+
+struct Job {
+    operation: Operation,
+    /// true = the operation changed something (fan switched)
+    done: oneshot::Sender<Result<bool>>,
+}
+
+/// a pin's queue, the worker holds the other end
+struct Queue {
+    jobs: mpsc::Sender<Job>,
+    rejected: AtomicU64,
+}
+
+/// operations refused because the pin's queue was full
+#[derive(Debug)]
+pub struct Busy {
+    pub pin: u8,
+    pub queued: usize,
+}
+
+impl std::fmt::Display for Busy {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "gpio {} is busy ({} operations queued)", self.pin, self.queued)
+    }
+}
+
+impl std::error::Error for Busy {}
+
+static DEPTH: AtomicUsize = AtomicUsize::new(4);
+static QUEUES: OnceLock<Mutex<BTreeMap<u8, Queue>>> = OnceLock::new();
+
+fn queues() -> &'static Mutex<BTreeMap<u8, Queue>> {
+    QUEUES.get_or_init(Default::default)
+}
+
+/// apply actuators.queue_depth (startup, before the first operation)
+pub fn configure(config: &ActuatorConfig) {
+    DEPTH.store(config.queue_depth, Ordering::Relaxed);
+}
+
+/// actuators.queue_depth leaves room for at least one operation
+pub fn check_config(config: &ActuatorConfig) -> Result<()> {
+    if config.queue_depth == 0 {
+        anyhow::bail!("actuators.queue_depth must be at least 1");
+    }
+    Ok(())
+}
+
+/// play a buzzer pattern on `pin` once what is queued there is done
+pub async fn buzz(pin: u8, steps: Vec<(u64, u64)>) -> Result<()> {
+    submit(pin, Operation::Buzz(steps)).await.map(|_| ())
+}
+
+/// switch the fan once what is queued on its pin is done, true = it changed
+pub async fn switch_fan(fan: &FanConfig, on: bool) -> Result<bool> {
+    submit(fan.gpio_pin, Operation::Fan { fan: fan.clone(), on }).await
+}
+
+/// run the fan for `time`, the pin is held for the whole run
+pub async fn run_fan(fan: &FanConfig, time: Duration) -> Result<()> {
+    submit(fan.gpio_pin, Operation::FanRun { fan: fan.clone(), time }).await.map(|_| ())
+}
+
+async fn submit(pin: u8, operation: Operation) -> Result<bool> {
+    let (done, result) = oneshot::channel();
+    {
+        let mut queues = queues().lock().unwrap();
+        let queue = queues.entry(pin).or_insert_with(|| spawn_worker(pin));
+        match queue.jobs.try_send(Job { operation, done }) {
+            Ok(()) => {}
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                queue.rejected.fetch_add(1, Ordering::Relaxed);
+                return Err(Busy { pin, queued: queue.jobs.max_capacity() }.into());
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => anyhow::bail!("gpio {} worker stopped", pin),
+        }
+    }
+    result.await.unwrap_or_else(|_| Err(anyhow::anyhow!("gpio {} worker stopped", pin)))
+}
+
+/// the pin's single consumer: its operations one after the other
+fn spawn_worker(pin: u8) -> Queue {
+    let (jobs, mut queued) = mpsc::channel::<Job>(DEPTH.load(Ordering::Relaxed).max(1));
+    tokio::spawn(async move {
+        let hal = crate::hal::shared();
+        while let Some(job) = queued.recv().await {
+            let result = match job.operation {
+                Operation::Buzz(steps) => crate::hal::buzz(&hal, pin, &steps).await.map(|_| true),
+                Operation::Fan { fan, on } => crate::hal::switch_fan(&hal, &fan, on),
+                Operation::FanRun { fan, time } => match crate::hal::switch_fan(&hal, &fan, true) {
+                    Ok(_) => {
+                        tokio::time::sleep(time).await;
+                        crate::hal::switch_fan(&hal, &fan, false)
+                    }
+                    Err(e) => Err(e),
+                },
+            };
+            let _ = job.done.send(result);
+        }
+    });
+    Queue { jobs, rejected: AtomicU64::new(0) }
+}
+
+/// append the queue metrics in prometheus text format
+pub fn write_prometheus(out: &mut String) {
+    let queues = queues().lock().unwrap();
+    out.push_str("# HELP wasi_actuator_queue_depth Operations waiting for an actuator pin.\n# TYPE wasi_actuator_queue_depth gauge\n");
+    for (pin, queue) in queues.iter() {
+        let depth = queue.jobs.max_capacity() - queue.jobs.capacity();
+        let _ = writeln!(out, "wasi_actuator_queue_depth{{pin=\"{}\"}} {}", pin, depth);
+    }
+    out.push_str("# HELP wasi_actuator_rejected_total Operations refused because the pin's queue was full.\n# TYPE wasi_actuator_rejected_total counter\n");
+    for (pin, queue) in queues.iter() {
+        let _ = writeln!(out, "wasi_actuator_rejected_total{{pin=\"{}\"}} {}", pin, queue.rejected.load(Ordering::Relaxed));
+    }
+}
@@ -0,0 +1,91 @@
+//! ==============================================================================
+//! admin.rs - token gate for the routes that can take a node over
+//! ==============================================================================
+//!
+//! purpose:
+//!     most of the api reads, or drives a buzzer. a few routes hand out or
+//!     replace the whole node: GET /api/snapshot (config with its secrets,
+//!     the history), POST /api/restore (host.toml and plugin code),
+//!     PUT /api/plugins/{name} and POST /api/nodes/{id}/plugins/{name}
+//!     (plugin code that runs with gpio, buzzer and fan access), POST
+//!     /api/role (turns the node into a hub or takes it off one), POST
+//!     /api/command (set-role, reload-plugin, buzz, fan on any node),
+//!     DELETE /api/nodes/{id} and POST /api/nodes/{id}/register (purge a
+//!     node's data, or let a retired one back in), POST /api/maintenance
+//!     (pause polling, alerting and hardware writes, kept over a reboot),
+//!     POST / DELETE /api/alerts/{id}/silence (mute alert actions and
+//!     notifications, kept over a restart). they
+//!     only answer requests carrying api.admin_token as a bearer token:
+//!
+//!     [api]
+//!     admin_token = "${ADMIN_TOKEN}"
+//!
+//!         curl -H "Authorization: Bearer $ADMIN_TOKEN" http://hub:3000/api/snapshot
+//!
+//!     without an admin_token the routes are off (403) - a node never
+//!     exposes them by accident. a wrong or missing token is a 401.
+//!
+//!     spokes don't hold the admin token, so the routes they use to pick
+//!     up and answer commands (GET /api/command, POST /api/command/{id}/result,
+//!     GET /api/command/{id}/artifact) and to send crash reports (POST
+//!     /api/crash) check the cluster instead: with cluster.tls on they
+//!     need a client cert signed by the cluster ca (a connection without
+//!     one is a 401). a plain http listener can't tell a spoke from anyone
+//!     else and lets them through, like /push.
+//!
+//! relationships:
+//!     - used by: main.rs (router, the layer on the admin and spoke routes)
+//!     - reads: config.rs (ApiConfig.admin_token, ClusterConfig.tls),
+//!       tls.rs (ClientCert of each tls connection)
+//!
+//! ==============================================================================
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use std::sync::Arc;
+
+/// the configured token, empty = admin routes off
+#[derive(Clone)]
+pub struct AdminToken(pub Arc<str>);
+
+/// middleware: the request carries the admin token
+pub async fn admin_only(State(token): State<AdminToken>, request: Request, next: Next) -> Response {
+    if token.0.is_empty() {
+        return (StatusCode::FORBIDDEN, "this route is off - set api.admin_token to enable it").into_response();
+    }
+    let presented = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    match presented {
+        Some(presented) if same(presented.trim().as_bytes(), token.0.as_bytes()) => next.run(request).await,
+        _ => (StatusCode::UNAUTHORIZED, [(header::WWW_AUTHENTICATE, "Bearer")], "admin token required").into_response(),
+    }
+}
+
+/// set on every request of a tls connection (tls.rs): the peer presented a
+/// certificate the cluster ca signed
+#[derive(Clone, Copy)]
+pub struct ClientCert(pub bool);
+
+/// whether the listener runs cluster.tls
+#[derive(Clone, Copy)]
+pub struct ClusterTls(pub bool);
+
+/// middleware: the request comes from a cluster node - a verified client
+/// cert when cluster.tls is on, anyone on a plain http listener
+pub async fn cluster_peer(State(tls): State<ClusterTls>, request: Request, next: Next) -> Response {
+    let verified = request.extensions().get::<ClientCert>().is_some_and(|cert| cert.0);
+    if tls.0 && !verified {
+        return (StatusCode::UNAUTHORIZED, "a cluster client certificate is required").into_response();
+    }
+    next.run(request).await
+}
+
+/// compare without returning early on the first differing byte
+fn same(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+}
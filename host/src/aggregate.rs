@@ -0,0 +1,137 @@
+//! ==============================================================================
+//! aggregate.rs - hub-side aggregation rules (cluster-level synthetic readings)
+//! ==============================================================================
+//!
+//! purpose:
+//!     dashboards and alerts often care about the cluster rather than one
+//!     sensor ("average temperature across all rooms", "is any node
+//!     offline?"). rules in [[aggregations]] are evaluated on the hub after
+//!     every merge and their results are stored as ordinary readings with a
+//!     `cluster:` sensor_id, so everything that reads AppState sees them.
+//!
+//! rule ops:
+//!     avg / min / max / sum  - over data.{field} of the matching sensors
+//!     count                  - number of matching sensors that have {field}
+//!     offline                - matching nodes whose newest reading is older
+//!                              than max_age_seconds
+//!
+//! output:
+//!     sensor_id "cluster:{name}", data {"value": ..., "count": n, ...}.
+//!     readings with a `cluster:` prefix are never used as rule inputs.
+//!
+//! relationships:
+//!     - used by: main.rs (after push/websocket merges and hub polls)
+//!     - used by: derived.rs (glob_match)
+//!     - reads: config.rs (HostConfig.aggregations)
+//!     - writes: domain.rs (AppState)
+//!
+//! ==============================================================================
+
+use crate::config::AggregationRule;
+use crate::domain::{AppState, SensorReading};
+use std::collections::BTreeMap;
+
+/// node prefix of synthetic readings
+pub const CLUSTER_NODE: &str = "cluster";
+
+const OPS: [&str; 6] = ["avg", "min", "max", "sum", "count", "offline"];
+
+/// reject rules with an unknown op or a missing field at startup
+pub fn validate(rules: &[AggregationRule]) -> anyhow::Result<()> {
+    for rule in rules {
+        if !OPS.contains(&rule.op.as_str()) {
+            anyhow::bail!("aggregation '{}': unknown op '{}' (expected one of {:?})", rule.name, rule.op, OPS);
+        }
+        if rule.op != "offline" && rule.field.is_empty() {
+            anyhow::bail!("aggregation '{}': op '{}' needs a field", rule.name, rule.op);
+        }
+    }
+    Ok(())
+}
+
+/// evaluate every rule against `state` and merge the results back in
+pub fn apply(rules: &[AggregationRule], state: &mut AppState) {
+    if rules.is_empty() {
+        return;
+    }
+    let now = crate::domain::now_ms();
+    let synthetic: Vec<SensorReading> = rules
+        .iter()
+        .map(|rule| SensorReading {
+            sensor_id: format!("{}:{}", CLUSTER_NODE, rule.name),
+            metadata: None,
+            timestamp_ms: now,
+            data: evaluate(rule, &state.readings, now),
+            quality: None,
+        })
+        .collect();
+    // merge without bumping last_update - nothing new arrived from a node
+    let last_update = state.last_update;
+    state.merge_readings(synthetic);
+    state.last_update = last_update;
+}
+
+fn evaluate(rule: &AggregationRule, readings: &[SensorReading], now: u64) -> serde_json::Value {
+    let inputs = readings.iter().filter(|r| {
+        !r.sensor_id.starts_with(&format!("{}:", CLUSTER_NODE)) && glob_match(&rule.sensors, &r.sensor_id)
+    });
+
+    if rule.op == "offline" {
+        // newest reading per node
+        let mut newest: BTreeMap<&str, u64> = BTreeMap::new();
+        for r in inputs {
+            let node = r.sensor_id.split(':').next().unwrap_or(&r.sensor_id);
+            let ts = newest.entry(node).or_default();
+            *ts = (*ts).max(r.timestamp_ms);
+        }
+        let max_age_ms = rule.max_age_seconds * 1000;
+        let offline: Vec<&str> = newest
+            .iter()
+            .filter(|(_, &ts)| now.saturating_sub(ts) > max_age_ms)
+            .map(|(node, _)| *node)
+            .collect();
+        return serde_json::json!({
+            "value": !offline.is_empty(),
+            "count": newest.len(),
+            "offline": offline,
+        });
+    }
+
+    let values: Vec<f64> = inputs
+        .filter_map(|r| r.data.get(&rule.field).and_then(|v| v.as_f64()))
+        .collect();
+    let count = values.len();
+    let value = match rule.op.as_str() {
+        _ if count == 0 && rule.op != "count" => None,
+        "avg" => Some(values.iter().sum::<f64>() / count as f64),
+        "min" => values.iter().copied().reduce(f64::min),
+        "max" => values.iter().copied().reduce(f64::max),
+        "sum" => Some(values.iter().sum()),
+        "count" => Some(count as f64),
+        _ => None,
+    };
+    serde_json::json!({ "value": value, "count": count })
+}
+
+/// `*` matches any run of characters; an empty pattern matches everything
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    if pattern.is_empty() || pattern == "*" {
+        return true;
+    }
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(pos) => rest = &rest[pos + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
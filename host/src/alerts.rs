@@ -0,0 +1,698 @@
+//! ==============================================================================
+//! alerts.rs - declarative alert rules evaluated on the hub
+//! ==============================================================================
+//!
+//! purpose:
+//!     turns readings into alerts without a plugin per condition. every
+//!     [[alerts.rules]] entry is a one-line expression:
+//!         expr = "bme680.iaq_score > 150 for 5m"
+//!         expr = "pi4:dht22.temperature >= 30"
+//!     i.e. `<sensor>.<field> <op> <number> [for <duration>]` with ops
+//!     > >= < <= == !=. the sensor part is a glob on the sensor name
+//!     ("bme680" matches every node's bme680) or, when it contains a ':',
+//!     on the full sensor_id. bools compare as 1 / 0.
+//!
+//! states (one alert per rule and matching sensor, id "{rule}@{sensor_id}"):
+//!     pending   - condition true, waiting for the `for` duration
+//!     firing    - condition held for the whole duration (immediately without `for`)
+//!     resolved  - condition false again or the sensor went away; the alert
+//!                 is dropped after the transition is reported
+//!     a pending alert whose condition clears is dropped silently.
+//!
+//! evaluation:
+//!     every alerts.interval_seconds against the current readings in
+//!     AppState, so a `for` window elapses even when no new reading arrives.
+//!     transitions are logged and emitted on the event bus as
+//!     alert_pending / alert_firing / alert_resolved with the alert as data.
+//!
+//! actions:
+//!     a rule's `actions` run when its alert starts firing:
+//!         buzz     - buzzer pattern
+//!         set-led  - led {index} to r/g/b (switched off again on resolve)
+//!         fan      - fan on (off again on resolve)
+//!         webhook  - POST the alert json to {url} (again on resolve)
+//!     hardware actions go through the command channel: they run right
+//!     away when the target node is this one, otherwise they are queued for
+//!     the spoke that owns the hardware (default: the alerting sensor's node).
+//!
+//! ack and silence:
+//!     GET /api/alerts lists the active alerts and silences.
+//!     POST /api/alerts/{id}/ack marks the current alert as acknowledged
+//!     (until it resolves). POST /api/alerts/{id}/silence?duration=2h mutes
+//!     an alert id - or a glob like "*@pi4:dht22" for a sensor being
+//!     replaced - for that long: its transitions are still tracked and
+//!     emitted (with "silenced_until_ms") but actions and notifications are
+//!     skipped. silences are kept in config/alert_silences.json so they
+//!     survive restarts; DELETE /api/alerts/{id}/silence lifts one early.
+//!     both verbs need api.admin_token (admin.rs).
+//!
+//! maintenance:
+//!     while the node is in maintenance (maintenance.rs) neither the rules
+//!     nor anomaly events are evaluated: alerts keep their state and nothing
+//!     fires, resolves, runs actions or notifies until it ends.
+//!
+//! history:
+//!     with storage enabled every alert_* event (pending, firing, resolved,
+//!     acked, silenced) is appended to the alert_history table together with
+//!     the alert as it was - rule, severity, sensor and reading value - and
+//!     served newest first by GET /api/alerts/history.
+//!
+//! config reload:
+//!     the rules and alerts.anomaly_severity can be replaced at runtime
+//!     (config_reload.rs). alerts of a removed rule, or whose condition no
+//!     longer holds under the new threshold, resolve on the next evaluation.
+//!
+//! anomalies:
+//!     with alerts.anomaly_severity set, anomaly events (anomaly.rs) become
+//!     firing alerts of the built-in rule "anomaly" (id "anomaly@{sensor_id}.{field}")
+//!     and resolve with the matching anomaly_resolved event.
+//!
+//! relationships:
+//!     - used by: main.rs (spawned on hubs and standalone nodes, /api/alerts)
+//!     - uses: commands.rs (hardware actions), config.rs (AlertAction)
+//!     - reads: config.rs (AlertsConfig), domain.rs (AppState), maintenance.rs
+//!     - reads / writes: events.rs (consumes anomalies, emits transitions)
+//!     - writes: storage.rs (alert_history)
+//!
+//! ==============================================================================
+
+use crate::aggregate::glob_match;
+use crate::commands::{CommandKind, CommandQueue};
+use crate::config::{AlertAction, AlertsConfig, HostConfig};
+use crate::domain::{now_ms, AppState, SensorReading};
+use crate::events::{Event, EventBus};
+use crate::log_msg;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::RwLock;
+
+/// rule name of alerts raised from anomaly events
+pub const ANOMALY_RULE: &str = "anomaly";
+
+pub const SEVERITIES: [&str; 3] = ["info", "warning", "critical"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertState {
+    Pending,
+    Firing,
+    Resolved,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Alert {
+    pub id: String,
+    pub rule: String,
+    pub severity: String,
+    pub sensor_id: String,
+    pub field: String,
+    pub state: AlertState,
+    pub value: f64,
+    pub expr: String,
+    pub since_ms: u64,                // condition first seen true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fired_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acked_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silenced_until_ms: Option<u64>,
+}
+
+/// a parsed [[alerts.rules]] entry
+struct Rule {
+    name: String,
+    severity: String,
+    expr: String,
+    sensors: String,
+    field: String,
+    op: String,
+    threshold: f64,
+    for_ms: u64,
+    actions: Vec<AlertAction>,
+}
+
+/// what alert actions need to reach the hardware and the network
+pub struct ActionContext {
+    pub commands: Arc<CommandQueue>,
+    pub config: HostConfig,
+    pub runtime: crate::runtime::WasmRuntime,
+    pub client: reqwest::Client,
+}
+
+impl Rule {
+    fn parse(name: &str, severity: &str, expr: &str, actions: Vec<AlertAction>) -> anyhow::Result<Self> {
+        let tokens: Vec<&str> = expr.split_whitespace().collect();
+        let (target, op, threshold, for_ms) = match tokens.as_slice() {
+            [target, op, threshold] => (*target, *op, *threshold, 0),
+            [target, op, threshold, "for", duration] => {
+                let ms = crate::storage::parse_duration_ms(duration)
+                    .ok_or_else(|| anyhow::anyhow!("bad duration '{}'", duration))?;
+                (*target, *op, *threshold, ms)
+            }
+            _ => anyhow::bail!("expected '<sensor>.<field> <op> <number> [for <duration>]'"),
+        };
+        let (sensors, field) = target
+            .rsplit_once('.')
+            .filter(|(sensor, field)| !sensor.is_empty() && !field.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not <sensor>.<field>", target))?;
+        if !["<", "<=", ">", ">=", "==", "!="].contains(&op) {
+            anyhow::bail!("unknown operator '{}'", op);
+        }
+        Ok(Self {
+            name: name.to_string(),
+            severity: severity.to_string(),
+            expr: expr.to_string(),
+            sensors: sensors.to_string(),
+            field: field.to_string(),
+            op: op.to_string(),
+            threshold: threshold.parse().map_err(|_| anyhow::anyhow!("'{}' is not a number", threshold))?,
+            for_ms,
+            actions,
+        })
+    }
+
+    fn matches(&self, sensor_id: &str) -> bool {
+        match self.sensors.contains(':') {
+            true => glob_match(&self.sensors, sensor_id),
+            false => glob_match(&self.sensors, sensor_id.split_once(':').map_or(sensor_id, |(_, sensor)| sensor)),
+        }
+    }
+
+    fn holds(&self, value: f64) -> bool {
+        match self.op.as_str() {
+            "<" => value < self.threshold,
+            "<=" => value <= self.threshold,
+            ">" => value > self.threshold,
+            ">=" => value >= self.threshold,
+            "==" => value == self.threshold,
+            _ => value != self.threshold,
+        }
+    }
+}
+
+pub struct AlertEngine {
+    rules: Mutex<Arc<Vec<Rule>>>,
+    anomaly_severity: Mutex<String>,
+    alerts: Mutex<BTreeMap<String, Alert>>,
+    silences: Mutex<BTreeMap<String, u64>>, // alert id glob -> silenced until (unix ms)
+    silences_file: Option<PathBuf>,
+    events: Arc<EventBus>,
+    actions: Arc<ActionContext>,
+}
+
+impl AlertEngine {
+    /// parse and validate the configured rules
+    pub fn new(config: &AlertsConfig, events: Arc<EventBus>, actions: ActionContext) -> anyhow::Result<Self> {
+        Ok(Self {
+            rules: Mutex::new(Arc::new(parse_rules(config)?)),
+            anomaly_severity: Mutex::new(config.anomaly_severity.clone()),
+            alerts: Mutex::new(BTreeMap::new()),
+            silences: Mutex::new(BTreeMap::new()),
+            silences_file: None,
+            events,
+            actions: Arc::new(actions),
+        })
+    }
+
+    /// replace the rules and anomaly severity (config reload)
+    pub fn set_rules(&self, config: &AlertsConfig) -> anyhow::Result<()> {
+        let rules = parse_rules(config)?;
+        log_msg(&format!("🔧 [ALERT] {} rule(s) active after config reload", rules.len()));
+        *self.rules.lock().unwrap() = Arc::new(rules);
+        *self.anomaly_severity.lock().unwrap() = config.anomaly_severity.clone();
+        Ok(())
+    }
+
+    fn rules(&self) -> Arc<Vec<Rule>> {
+        self.rules.lock().unwrap().clone()
+    }
+
+    /// keep silences in `path` (loading the ones still running)
+    pub fn with_silences_file(mut self, path: PathBuf) -> Self {
+        let now = now_ms();
+        let silences: BTreeMap<String, u64> = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        let silences: BTreeMap<String, u64> = silences.into_iter().filter(|(_, until)| *until > now).collect();
+        if !silences.is_empty() {
+            log_msg(&format!("🔕 [ALERT] {} silence(s) restored: {:?}", silences.len(), silences.keys().collect::<Vec<_>>()));
+        }
+        self.silences = Mutex::new(silences);
+        self.silences_file = Some(path);
+        self
+    }
+
+    /// active (pending / firing) alerts
+    pub fn active(&self) -> Vec<Alert> {
+        let now = now_ms();
+        let mut alerts: Vec<Alert> = self.alerts.lock().unwrap().values().cloned().collect();
+        for alert in &mut alerts {
+            alert.silenced_until_ms = self.silenced_until(&alert.id, now);
+        }
+        alerts
+    }
+
+    /// running silences (id glob -> until, unix ms)
+    pub fn silences(&self) -> BTreeMap<String, u64> {
+        let now = now_ms();
+        self.silences.lock().unwrap().iter().filter(|(_, until)| **until > now).map(|(k, v)| (k.clone(), *v)).collect()
+    }
+
+    /// acknowledge an active alert. None = no such alert
+    pub fn ack(&self, id: &str) -> Option<Alert> {
+        let mut alerts = self.alerts.lock().unwrap();
+        let alert = alerts.get_mut(id)?;
+        alert.acked_ms.get_or_insert(now_ms());
+        let alert = alert.clone();
+        drop(alerts);
+        log_msg(&format!("👍 [ALERT] {} acknowledged", id));
+        self.events.emit("alert_acked", &alert.sensor_id, format!("{} acknowledged", id), serde_json::to_value(&alert).unwrap_or_default());
+        Some(alert)
+    }
+
+    /// silence an alert id (or id glob) for `duration_ms`. returns the end (unix ms)
+    pub fn silence(&self, pattern: &str, duration_ms: u64) -> u64 {
+        let until = now_ms().saturating_add(duration_ms);
+        let mut silences = self.silences.lock().unwrap();
+        silences.insert(pattern.to_string(), until);
+        self.save_silences(&silences);
+        drop(silences);
+        log_msg(&format!("🔕 [ALERT] {} silenced for {}s", pattern, duration_ms / 1000));
+        self.events.emit(
+            "alert_silenced",
+            pattern,
+            format!("{} silenced for {}s", pattern, duration_ms / 1000),
+            serde_json::json!({ "id": pattern, "silenced_until_ms": until }),
+        );
+        until
+    }
+
+    /// lift a silence early. false = there was none
+    pub fn unsilence(&self, pattern: &str) -> bool {
+        let mut silences = self.silences.lock().unwrap();
+        let removed = silences.remove(pattern).is_some();
+        if removed {
+            self.save_silences(&silences);
+            log_msg(&format!("🔔 [ALERT] {} unsilenced", pattern));
+        }
+        removed
+    }
+
+    fn silenced_until(&self, id: &str, now: u64) -> Option<u64> {
+        self.silences
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(pattern, until)| **until > now && glob_match(pattern, id))
+            .map(|(_, until)| *until)
+            .max()
+    }
+
+    fn save_silences(&self, silences: &BTreeMap<String, u64>) {
+        let Some(path) = &self.silences_file else { return };
+        let now = now_ms();
+        let running: BTreeMap<&String, &u64> = silences.iter().filter(|(_, until)| **until > now).collect();
+        let result = serde_json::to_string_pretty(&running)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = result {
+            log_msg(&format!("⚠️ [ALERT] Could not save {}: {}", path.display(), e));
+        }
+    }
+
+    /// evaluate the rules periodically and follow anomaly events
+    pub fn spawn(self: Arc<Self>, state: Arc<RwLock<AppState>>, interval_secs: u64) {
+        let mut anomalies = self.events.subscribe();
+        let engine = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match anomalies.recv().await {
+                    Ok(_) if crate::maintenance::active() => {}
+                    Ok(event) => engine.on_event(&event),
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(_) => return,
+                }
+            }
+        });
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs.max(1)));
+            loop {
+                ticker.tick().await;
+                if crate::maintenance::active() {
+                    continue;
+                }
+                let readings = state.read().await.readings.clone();
+                self.evaluate(&readings, now_ms());
+            }
+        });
+    }
+
+    fn evaluate(&self, readings: &[SensorReading], now: u64) {
+        let mut alerts = self.alerts.lock().unwrap();
+        let mut seen = Vec::new();
+        let mut cleared_values = BTreeMap::new();
+        for rule in self.rules().iter() {
+            for reading in readings.iter().filter(|r| rule.matches(&r.sensor_id)) {
+                let value = match reading.data.get(&rule.field) {
+                    Some(serde_json::Value::Bool(b)) => *b as u8 as f64,
+                    Some(value) => match value.as_f64() {
+                        Some(value) => value,
+                        None => continue,
+                    },
+                    None => continue,
+                };
+                let id = format!("{}@{}", rule.name, reading.sensor_id);
+                if !rule.holds(value) {
+                    cleared_values.insert(id, value);
+                    continue;
+                }
+                seen.push(id.clone());
+                let created = !alerts.contains_key(&id);
+                let alert = alerts.entry(id.clone()).or_insert_with(|| Alert {
+                    id,
+                    rule: rule.name.clone(),
+                    severity: rule.severity.clone(),
+                    sensor_id: reading.sensor_id.clone(),
+                    field: rule.field.clone(),
+                    state: AlertState::Pending,
+                    value,
+                    expr: rule.expr.clone(),
+                    since_ms: now,
+                    fired_ms: None,
+                    acked_ms: None,
+                    silenced_until_ms: None,
+                });
+                alert.value = value;
+                if created && rule.for_ms > 0 {
+                    self.transition(alert, "alert_pending");
+                }
+                if alert.state == AlertState::Pending && now.saturating_sub(alert.since_ms) >= rule.for_ms {
+                    alert.state = AlertState::Firing;
+                    alert.fired_ms = Some(now);
+                    self.transition(alert, "alert_firing");
+                }
+            }
+        }
+
+        // rule alerts whose condition no longer holds (anomaly alerts follow their events)
+        let cleared: Vec<String> = alerts
+            .keys()
+            .filter(|id| !id.starts_with(&format!("{}@", ANOMALY_RULE)) && !seen.contains(id))
+            .cloned()
+            .collect();
+        for id in cleared {
+            if let Some(mut alert) = alerts.remove(&id) {
+                if alert.state == AlertState::Firing {
+                    alert.state = AlertState::Resolved;
+                    alert.value = cleared_values.get(&id).copied().unwrap_or(alert.value);
+                    self.transition(&mut alert, "alert_resolved");
+                }
+            }
+        }
+    }
+
+    fn on_event(&self, event: &Event) {
+        let severity = self.anomaly_severity.lock().unwrap().clone();
+        if severity.is_empty() {
+            return;
+        }
+        let Some(field) = event.data.get("field").and_then(|f| f.as_str()) else {
+            return;
+        };
+        let id = format!("{}@{}.{}", ANOMALY_RULE, event.source, field);
+        let value = event.data.get("value").and_then(|v| v.as_f64()).unwrap_or_default();
+        let mut alerts = self.alerts.lock().unwrap();
+        match event.kind.as_str() {
+            "anomaly" => {
+                let alert = alerts.entry(id.clone()).or_insert_with(|| Alert {
+                    id,
+                    rule: ANOMALY_RULE.to_string(),
+                    severity,
+                    sensor_id: event.source.clone(),
+                    field: field.to_string(),
+                    state: AlertState::Firing,
+                    value,
+                    expr: event.message.clone(),
+                    since_ms: event.timestamp_ms,
+                    fired_ms: Some(event.timestamp_ms),
+                    acked_ms: None,
+                    silenced_until_ms: None,
+                });
+                alert.value = value;
+                self.transition(alert, "alert_firing");
+            }
+            "anomaly_resolved" => {
+                if let Some(mut alert) = alerts.remove(&id) {
+                    alert.state = AlertState::Resolved;
+                    alert.value = value;
+                    self.transition(&mut alert, "alert_resolved");
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn transition(&self, alert: &mut Alert, kind: &str) {
+        alert.silenced_until_ms = self.silenced_until(&alert.id, now_ms());
+        let icon = match alert.state {
+            AlertState::Pending => "⏳",
+            AlertState::Firing => "🚨",
+            AlertState::Resolved => "✅",
+        };
+        let message = format!("{} {} ({}, value {})", alert.rule, kind.trim_start_matches("alert_"), alert.severity, alert.value);
+        let muted = if alert.silenced_until_ms.is_some() { " (silenced)" } else { "" };
+        log_msg(&format!("{} [ALERT] {} on {}{}", icon, message, alert.sensor_id, muted));
+        self.events.emit(kind, &alert.sensor_id, message, serde_json::to_value(&*alert).unwrap_or_default());
+
+        let actions = match self.rules().iter().find(|rule| rule.name == alert.rule) {
+            Some(rule) if !rule.actions.is_empty() && alert.state != AlertState::Pending && alert.silenced_until_ms.is_none() => {
+                rule.actions.clone()
+            }
+            _ => return,
+        };
+        tokio::spawn(run_actions(self.actions.clone(), actions, alert.clone()));
+    }
+}
+
+/// reject rules that don't parse (startup check, config reload)
+pub fn check_config(config: &AlertsConfig) -> anyhow::Result<()> {
+    parse_rules(config).map(|_| ())
+}
+
+/// parse and validate the configured rules
+fn parse_rules(config: &AlertsConfig) -> anyhow::Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    for rule in &config.rules {
+        if rule.name.is_empty() || rule.name == ANOMALY_RULE || rule.name.contains('@') {
+            anyhow::bail!("alert rule name '{}' is empty, reserved or contains '@'", rule.name);
+        }
+        if !SEVERITIES.contains(&rule.severity.as_str()) {
+            anyhow::bail!("alert '{}': unknown severity '{}' (expected one of {:?})", rule.name, rule.severity, SEVERITIES);
+        }
+        rules.push(Rule::parse(&rule.name, &rule.severity, &rule.expr, rule.actions.clone()).map_err(|e| anyhow::anyhow!("alert '{}': {}", rule.name, e))?);
+    }
+    if !config.anomaly_severity.is_empty() && !SEVERITIES.contains(&config.anomaly_severity.as_str()) {
+        anyhow::bail!("alerts.anomaly_severity: unknown severity '{}'", config.anomaly_severity);
+    }
+    Ok(rules)
+}
+
+/// record every alert transition in the store
+pub fn spawn_history(store: Arc<crate::storage::Store>, events: &EventBus) {
+    let mut rx = events.subscribe();
+    tokio::spawn(async move {
+        loop {
+            let event = match rx.recv().await {
+                Ok(event) => event,
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(missed)) => {
+                    log_msg(&format!("⚠️ [ALERT] History fell behind, {} events not recorded", missed));
+                    continue;
+                }
+                Err(_) => return,
+            };
+            let Some(transition) = event.kind.strip_prefix("alert_") else { continue };
+            let text = |key: &str| event.data.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            let record = crate::storage::AlertRecord {
+                timestamp_ms: event.timestamp_ms,
+                transition: transition.to_string(),
+                alert_id: text("id"),
+                rule: text("rule"),
+                severity: text("severity"),
+                sensor_id: text("sensor_id"),
+                value: event.data.get("value").and_then(|v| v.as_f64()),
+                alert: event.data.clone(),
+            };
+            let store = store.clone();
+            let result = tokio::task::spawn_blocking(move || store.record_alert(&record)).await;
+            if let Err(e) = result.map_err(anyhow::Error::from).and_then(|r| r) {
+                log_msg(&format!("❌ [ALERT] Failed to record {}: {}", event.kind, e));
+            }
+        }
+    });
+}
+
+async fn run_actions(ctx: Arc<ActionContext>, actions: Vec<AlertAction>, alert: Alert) {
+    let firing = alert.state == AlertState::Firing;
+    for action in actions {
+        let (node, kind) = match action {
+            AlertAction::Webhook { url } => {
+                let sent = ctx
+                    .client
+                    .post(&url)
+                    .timeout(std::time::Duration::from_secs(10))
+                    .json(&alert)
+                    .send()
+                    .await
+                    .and_then(|r| r.error_for_status());
+                if let Err(e) = sent {
+                    log_msg(&format!("❌ [ALERT] Webhook for {} failed: {}", alert.id, e));
+                }
+                continue;
+            }
+            AlertAction::Buzz { .. } if !firing => continue,
+            AlertAction::Buzz { pattern, node } => (node, CommandKind::Buzz { pattern }),
+            AlertAction::SetLed { index, r, g, b, node } => match firing {
+                true => (node, CommandKind::SetLed { index, r, g, b }),
+                false => (node, CommandKind::SetLed { index, r: 0, g: 0, b: 0 }),
+            },
+            AlertAction::Fan { node } => (node, CommandKind::Fan { on: firing }),
+        };
+        // default to the node the sensor lives on; cluster readings belong to the hub
+        let node = match node.is_empty() {
+            false => node,
+            true => match alert.sensor_id.split_once(':') {
+                Some((owner, _)) if owner != crate::aggregate::CLUSTER_NODE => owner.to_string(),
+                _ => ctx.config.cluster.node_id.clone(),
+            },
+        };
+        let source = format!("alert:{}", alert.rule);
+        let cmd = crate::commands::submit(&ctx.commands, &node, kind, &source, &ctx.config, &ctx.runtime).await;
+        if cmd.status == crate::commands::CommandStatus::Failed {
+            log_msg(&format!("❌ [ALERT] Action for {} failed on {}: {}", alert.id, node, cmd.message));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINUTE: u64 = 60_000;
+
+    async fn engine(expr: &str) -> AlertEngine {
+        let config = AlertsConfig {
+            rules: vec![crate::config::AlertRuleConfig {
+                name: "hot".into(),
+                expr: expr.into(),
+                severity: "warning".into(),
+                actions: Vec::new(),
+            }],
+            ..Default::default()
+        };
+        // no plugins enabled, nothing gets loaded
+        let host = HostConfig::default();
+        let runtime = crate::runtime::WasmRuntime::new(std::env::temp_dir(), &host, Default::default()).await.unwrap();
+        let actions = ActionContext { commands: Arc::default(), config: host, runtime, client: reqwest::Client::new() };
+        AlertEngine::new(&config, Arc::default(), actions).unwrap()
+    }
+
+    fn reading(temperature: f64) -> SensorReading {
+        SensorReading {
+            sensor_id: "pi4:dht22".into(),
+            timestamp_ms: 0,
+            data: serde_json::json!({ "temperature": temperature }),
+            metadata: None,
+            quality: None,
+        }
+    }
+
+    fn state(engine: &AlertEngine) -> Option<AlertState> {
+        engine.alerts.lock().unwrap().get("hot@pi4:dht22").map(|a| a.state)
+    }
+
+    /// event kinds, oldest first (recent() lists the newest first)
+    fn kinds(engine: &AlertEngine) -> Vec<String> {
+        engine.events.recent().into_iter().rev().map(|e| e.kind).collect()
+    }
+
+    #[test]
+    fn test_parse_rule() {
+        let rule = Rule::parse("hot", "warning", "pi4:dht22.temperature >= 30.5 for 5m", Vec::new()).unwrap();
+        assert_eq!((rule.sensors.as_str(), rule.field.as_str(), rule.op.as_str()), ("pi4:dht22", "temperature", ">="));
+        assert_eq!(rule.threshold, 30.5);
+        assert_eq!(rule.for_ms, 5 * MINUTE);
+
+        let rule = Rule::parse("iaq", "info", "bme680.iaq_score != 0", Vec::new()).unwrap();
+        assert_eq!(rule.for_ms, 0);
+        assert!(rule.matches("pi4:bme680") && rule.matches("bme680"));
+        assert!(!rule.matches("pi4:dht22"));
+    }
+
+    #[test]
+    fn test_parse_rejects_bad_rules() {
+        for expr in [
+            "dht22.temperature > 30 for",
+            "dht22.temperature > 30 for ever",
+            "dht22.temperature > 30 during 5m",
+            "temperature > 30",
+            "dht22. > 30",
+            "dht22.temperature => 30",
+            "dht22.temperature > hot",
+        ] {
+            assert!(Rule::parse("hot", "warning", expr, Vec::new()).is_err(), "{}", expr);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_firing_resolved() {
+        let engine = engine("dht22.temperature > 30 for 1m").await;
+        let t0 = 1_000_000;
+
+        engine.evaluate(&[reading(35.0)], t0);
+        assert_eq!(state(&engine), Some(AlertState::Pending));
+        engine.evaluate(&[reading(36.0)], t0 + MINUTE / 2);
+        assert_eq!(state(&engine), Some(AlertState::Pending));
+        engine.evaluate(&[reading(36.0)], t0 + MINUTE);
+        assert_eq!(state(&engine), Some(AlertState::Firing));
+
+        engine.evaluate(&[reading(25.0)], t0 + 2 * MINUTE);
+        assert_eq!(state(&engine), None);
+        assert_eq!(kinds(&engine), ["alert_pending", "alert_firing", "alert_resolved"]);
+    }
+
+    #[tokio::test]
+    async fn test_cleared_pending_alert_is_dropped_silently() {
+        let engine = engine("dht22.temperature > 30 for 1m").await;
+        engine.evaluate(&[reading(35.0)], 1_000_000);
+        engine.evaluate(&[reading(25.0)], 1_000_000 + MINUTE);
+        assert_eq!(state(&engine), None);
+        assert_eq!(kinds(&engine), ["alert_pending"]);
+    }
+
+    #[tokio::test]
+    async fn test_rule_without_for_fires_at_once() {
+        let engine = engine("dht22.temperature > 30").await;
+        engine.evaluate(&[reading(35.0)], 1_000_000);
+        assert_eq!(state(&engine), Some(AlertState::Firing));
+        assert_eq!(kinds(&engine), ["alert_firing"]);
+    }
+
+    #[tokio::test]
+    async fn test_huge_silence_saturates() {
+        let engine = engine("dht22.temperature > 30").await;
+        assert_eq!(engine.silence("*", u64::MAX), u64::MAX);
+        assert!(engine.silenced_until("hot@pi4:dht22", now_ms()).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_clock_stepping_back_keeps_the_alert_pending() {
+        let engine = engine("dht22.temperature > 30 for 1m").await;
+        engine.evaluate(&[reading(35.0)], 1_000_000);
+        // an ntp correction moves the clock behind since_ms
+        engine.evaluate(&[reading(35.0)], 1_000_000 - MINUTE);
+        assert_eq!(state(&engine), Some(AlertState::Pending));
+    }
+}
@@ -0,0 +1,163 @@
+//! ==============================================================================
+//! anomaly.rs - streaming anomaly detection (ewma z-score per sensor field)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a failing sensor rarely goes silent - a DHT22 with a loose wire
+//!     happily reports 0°C / 0% forever. every numeric field of every
+//!     merged reading is compared against an exponentially weighted mean
+//!     and variance of its own history; values more than anomaly.threshold
+//!     standard deviations away are flagged.
+//!
+//! flagging:
+//!     the reading's data gets an "anomaly" object with the z-score of each
+//!     offending field, e.g. {"temperature": 0.0, ..., "anomaly": {"temperature": -9.1}},
+//!     so it ends up in the api, history and exports as-is.
+//!
+//! events:
+//!     when a field turns anomalous an "anomaly" event is emitted on the
+//!     event bus; when it returns within the threshold an "anomaly_resolved"
+//!     event follows. a field that stays anomalous emits nothing further.
+//!
+//! tuning:
+//!     alpha        - ewma weight of a new sample (higher adapts faster)
+//!     threshold    - z-score that counts as anomalous (sensitivity)
+//!     warmup       - samples per field before anything is flagged
+//!     min_stddev   - floor for the deviation so a perfectly flat signal
+//!                    doesn't flag a 0.1 change
+//!     a sample's deviation is clipped to threshold stddevs before it
+//!     updates the baseline, so one glitch can't inflate the variance enough
+//!     to hide the next, while a genuine step change still stops being
+//!     flagged once the average catches up.
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.anomaly, run from merge_readings), main.rs (startup)
+//!     - reads: config.rs (AnomalyConfig)
+//!     - writes: events.rs (EventBus, picked up by alerts.rs)
+//!
+//! ==============================================================================
+
+use crate::aggregate::{glob_match, CLUSTER_NODE};
+use crate::config::AnomalyConfig;
+use crate::domain::SensorReading;
+use crate::events::EventBus;
+use crate::log_msg;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// data key holding the z-scores of flagged fields
+pub const ANOMALY_KEY: &str = "anomaly";
+
+#[derive(Default)]
+struct FieldStats {
+    mean: f64,
+    variance: f64,
+    samples: u64,
+    flagged: bool,
+}
+
+#[derive(Default)]
+struct Baselines {
+    stats: HashMap<(String, String), FieldStats>,
+    last_seen: HashMap<String, u64>,
+}
+
+/// cheap handle, clones share the baselines
+#[derive(Clone)]
+pub struct AnomalyDetector {
+    config: Arc<AnomalyConfig>,
+    events: Arc<EventBus>,
+    baselines: Arc<Mutex<Baselines>>,
+}
+
+impl AnomalyDetector {
+    pub fn new(config: AnomalyConfig, events: Arc<EventBus>) -> Self {
+        Self { config: Arc::new(config), events, baselines: Arc::default() }
+    }
+
+    /// update the baselines with `readings` and flag outliers in place
+    pub fn observe(&self, readings: &mut [SensorReading]) {
+        let mut baselines = self.baselines.lock().unwrap();
+        let Baselines { stats: all_stats, last_seen } = &mut *baselines;
+        for reading in readings.iter_mut() {
+            if reading.sensor_id.starts_with(&format!("{}:", CLUSTER_NODE))
+                || !glob_match(&self.config.sensors, &reading.sensor_id)
+            {
+                continue;
+            }
+            // a re-delivered or older reading must not count twice
+            let last = last_seen.entry(reading.sensor_id.clone()).or_default();
+            if reading.timestamp_ms <= *last {
+                continue;
+            }
+            *last = reading.timestamp_ms;
+
+            let Some(data) = reading.data.as_object_mut() else {
+                continue;
+            };
+            data.remove(ANOMALY_KEY);
+            let mut flagged = serde_json::Map::new();
+            for (field, value) in data.iter() {
+                let Some(value) = value.as_f64() else {
+                    continue;
+                };
+                if !self.config.fields.is_empty() && !self.config.fields.contains(field) {
+                    continue;
+                }
+                let key = (reading.sensor_id.clone(), field.clone());
+                let stats = all_stats.entry(key).or_default();
+                let z = score(&self.config, stats, value);
+                let anomalous = z.is_some_and(|z| z.abs() > self.config.threshold);
+
+                if anomalous && !stats.flagged {
+                    log_msg(&format!(
+                        "⚠️ [ANOMALY] {} {} = {} is {:.1}σ from {:.2}",
+                        reading.sensor_id, field, value, z.unwrap_or_default(), stats.mean
+                    ));
+                    self.events.emit(
+                        "anomaly",
+                        &reading.sensor_id,
+                        format!("{} = {} is {:.1}σ from {:.2}", field, value, z.unwrap_or_default(), stats.mean),
+                        serde_json::json!({ "field": field, "value": value, "z": z, "mean": stats.mean }),
+                    );
+                } else if !anomalous && stats.flagged {
+                    self.events.emit(
+                        "anomaly_resolved",
+                        &reading.sensor_id,
+                        format!("{} back to normal ({})", field, value),
+                        serde_json::json!({ "field": field, "value": value }),
+                    );
+                }
+                stats.flagged = anomalous;
+                if let Some(z) = z.filter(|_| anomalous) {
+                    flagged.insert(field.clone(), serde_json::json!((z * 10.0).round() / 10.0));
+                }
+                update(&self.config, stats, value);
+            }
+            if !flagged.is_empty() {
+                data.insert(ANOMALY_KEY.to_string(), serde_json::Value::Object(flagged));
+            }
+        }
+    }
+}
+
+/// z-score of `value` against the baseline (None while warming up)
+fn score(config: &AnomalyConfig, stats: &FieldStats, value: f64) -> Option<f64> {
+    if stats.samples < config.warmup {
+        return None;
+    }
+    let stddev = stats.variance.sqrt().max(config.min_stddev);
+    Some((value - stats.mean) / stddev)
+}
+
+fn update(config: &AnomalyConfig, stats: &mut FieldStats, value: f64) {
+    if stats.samples == 0 {
+        stats.mean = value;
+    } else {
+        let limit = config.threshold * stats.variance.sqrt().max(config.min_stddev);
+        let diff = (value - stats.mean).clamp(-limit, limit);
+        stats.mean += config.alpha * diff;
+        stats.variance = (1.0 - config.alpha) * (stats.variance + config.alpha * diff * diff);
+    }
+    stats.samples += 1;
+}
@@ -0,0 +1,157 @@
+//! ==============================================================================
+//! audit.rs - append-only log of hardware actions
+//! ==============================================================================
+//!
+//! purpose:
+//!     once more people than the owner can reach the dashboard, "who turned
+//!     the fan off?" needs an answer. every buzzer (relay), fan and led
+//!     action is appended as one json line to audit.path, with who caused it:
+//!         plugin:<name>          a wasm plugin through the host imports
+//!         api:<ip>               a dashboard / api request (api:<user>@<ip>
+//!                                when a reverse proxy sets Remote-User or
+//!                                X-Forwarded-User)
+//!         alert:<rule>           an alert rule action
+//!     commands queued on the hub carry their source to the spoke, so a
+//!     spoke's log names the original caller, not just "the hub". with
+//!     --dry-run or in maintenance (hal.rs) nothing reached the hardware;
+//!     the detail says so.
+//!         {"timestamp_ms": 1767000000000, "actor": "api:192.168.7.20",
+//!          "action": "fan", "detail": "on (command #12)"}
+//!
+//! noise:
+//!     plugins re-apply their status leds and fan state on every poll; only
+//!     changes are logged. the host's own heartbeat led isn't an action and
+//!     isn't logged.
+//!
+//! access:
+//!     GET /api/audit?from=&to=&actor=&action=&limit= reads the file back,
+//!     newest first. the file is only ever appended to.
+//!
+//! relationships:
+//!     - used by: runtime.rs (plugin host imports), commands.rs (buzz / fan / set-led),
+//!       main.rs (buzzer / fan endpoints, /api/audit)
+//!     - reads: config.rs (AuditConfig)
+//!
+//! ==============================================================================
+
+use crate::config::AuditConfig;
+use crate::log_msg;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+static AUDIT: OnceLock<AuditLog> = OnceLock::new();
+
+/// led colors last logged, to skip re-applied ones
+static LEDS: Mutex<[Option<(u8, u8, u8)>; 11]> = Mutex::new([None; 11]);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct AuditEntry {
+    pub timestamp_ms: u64,
+    pub actor: String,
+    pub action: String,
+    pub detail: String,
+}
+
+struct AuditLog {
+    path: PathBuf,
+    file: Mutex<std::fs::File>,
+}
+
+/// open the log file. without this call (audit.enabled = false) nothing is recorded.
+pub fn init(config: &AuditConfig) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let path = PathBuf::from(&config.path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    log_msg(&format!("📝 [AUDIT] Recording hardware actions to {}", path.display()));
+    let _ = AUDIT.set(AuditLog { path, file: Mutex::new(file) });
+    Ok(())
+}
+
+pub fn enabled() -> bool {
+    AUDIT.get().is_some()
+}
+
+/// append one action
+pub fn record(actor: &str, action: &str, detail: impl Into<String>) {
+    let Some(log) = AUDIT.get() else { return };
+    let entry = AuditEntry {
+        timestamp_ms: crate::domain::now_ms(),
+        actor: actor.to_string(),
+        action: action.to_string(),
+        detail: match crate::hal::held() {
+            Some(reason) => format!("{} ({})", detail.into(), reason),
+            None => detail.into(),
+        },
+    };
+    let Ok(mut line) = serde_json::to_string(&entry) else { return };
+    line.push('\n');
+    if let Err(e) = log.file.lock().unwrap().write_all(line.as_bytes()) {
+        log_msg(&format!("❌ [AUDIT] Failed to write {}: {}", log.path.display(), e));
+    }
+}
+
+/// record led colors (index, r, g, b) that differ from the last logged ones
+pub fn leds(actor: &str, colors: &[(u8, u8, u8, u8)], detail_suffix: &str) {
+    if !enabled() {
+        return;
+    }
+    let changed: Vec<String> = {
+        let mut last = LEDS.lock().unwrap();
+        colors
+            .iter()
+            .filter(|(index, r, g, b)| {
+                let Some(slot) = last.get_mut(*index as usize) else { return true };
+                slot.replace((*r, *g, *b)) != Some((*r, *g, *b))
+            })
+            .map(|(index, r, g, b)| format!("{}=#{:02x}{:02x}{:02x}", index, r, g, b))
+            .collect()
+    };
+    if !changed.is_empty() {
+        record(actor, "led", format!("{}{}", changed.join(" "), detail_suffix));
+    }
+}
+
+/// actor string of an api request
+pub fn api_actor(peer: Option<std::net::SocketAddr>, headers: &axum::http::HeaderMap) -> String {
+    let ip = peer.map(|p| p.ip().to_string()).unwrap_or_else(|| "unknown".to_string());
+    let user = ["remote-user", "x-forwarded-user"]
+        .iter()
+        .find_map(|h| headers.get(*h).and_then(|v| v.to_str().ok()).filter(|v| !v.is_empty()));
+    match user {
+        Some(user) => format!("api:{}@{}", user, ip),
+        None => format!("api:{}", ip),
+    }
+}
+
+/// entries between `from` and `to` (unix ms) matching the optional actor
+/// prefix / action, newest first
+pub fn read(from: u64, to: u64, actor: Option<&str>, action: Option<&str>, limit: usize) -> anyhow::Result<Vec<AuditEntry>> {
+    let Some(log) = AUDIT.get() else {
+        anyhow::bail!("audit log is disabled");
+    };
+    let file = std::fs::File::open(&log.path)?;
+    let mut entries = VecDeque::with_capacity(limit.min(1024));
+    for line in std::io::BufReader::new(file).lines() {
+        let Ok(entry) = serde_json::from_str::<AuditEntry>(&line?) else { continue };
+        if entry.timestamp_ms < from
+            || entry.timestamp_ms > to
+            || actor.is_some_and(|a| !entry.actor.starts_with(a))
+            || action.is_some_and(|a| entry.action != a)
+        {
+            continue;
+        }
+        if entries.len() == limit {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+    Ok(entries.into_iter().rev().collect())
+}
@@ -0,0 +1,123 @@
+//! ==============================================================================
+//! backoff.rs - poll a failing sensor plugin less often ([polling.backoff])
+//! ==============================================================================
+//!
+//! purpose:
+//!     a dht22 that stopped answering times out on every poll, and every
+//!     poll runs the plugin, waits for the timeout and logs the failure.
+//!     a plugin poll that fails (error, trap, or no readings from a sensor
+//!     plugin) counts against the plugin; after `after_failures` failures in
+//!     a row it is polled every 2nd cycle, then every 4th, ... up to every
+//!     `max_factor`th. each successful poll halves the factor again, so a
+//!     sensor that comes back is polled at the full rate a few cycles later
+//!     (and falls back quickly if it was a fluke).
+//!
+//!     [polling.backoff]
+//!     enabled = true
+//!     after_failures = 3   # failed polls in a row before backing off
+//!     max_factor = 10      # slowest: every 10th poll cycle
+//!
+//!     a hot reload of the plugin (new .wasm) starts it at the full rate.
+//!
+//! relationships:
+//!     - used by: runtime.rs (poll_sensors asks before polling each plugin
+//!       and records the outcome, reload_plugin resets it), main.rs (the
+//!       skipped plugins' sensors for cadence.rs)
+//!     - reads: config.rs (BackoffConfig)
+//!
+//! ==============================================================================
+
+use crate::config::BackoffConfig;
+use crate::log_msg;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct PluginBackoff {
+    /// failed polls in a row
+    failures: u32,
+    /// polled every `factor` cycles (1 = every cycle)
+    factor: u32,
+    /// cycles skipped since the last poll
+    skipped: u32,
+    /// not polled in the current cycle
+    resting: bool,
+    /// sensor ids of the plugin's last readings
+    sensors: Vec<String>,
+}
+
+pub struct Backoff {
+    config: BackoffConfig,
+    plugins: Mutex<HashMap<&'static str, PluginBackoff>>,
+}
+
+impl Backoff {
+    pub fn new(config: BackoffConfig) -> Self {
+        Self { config, plugins: Mutex::new(HashMap::new()) }
+    }
+
+    /// true when the plugin is polled this cycle, false counts a skipped cycle
+    pub fn due(&self, plugin: &'static str) -> bool {
+        if !self.config.enabled {
+            return true;
+        }
+        let mut plugins = self.plugins.lock().unwrap();
+        let state = plugins.entry(plugin).or_default();
+        state.resting = state.skipped + 1 < state.factor.max(1);
+        if !state.resting {
+            state.skipped = 0;
+            return true;
+        }
+        state.skipped += 1;
+        false
+    }
+
+    /// the sensors a poll of the plugin produced readings for
+    pub fn reported(&self, plugin: &'static str, sensors: Vec<String>) {
+        if !self.config.enabled || sensors.is_empty() {
+            return;
+        }
+        self.plugins.lock().unwrap().entry(plugin).or_default().sensors = sensors;
+    }
+
+    /// sensor ids of the plugins skipped in the current cycle - they were
+    /// not due, so their absence is no miss (cadence.rs)
+    pub fn resting_sensors(&self) -> Vec<String> {
+        let plugins = self.plugins.lock().unwrap();
+        plugins.values().filter(|p| p.resting).flat_map(|p| p.sensors.iter().cloned()).collect()
+    }
+
+    /// the outcome of a poll
+    pub fn record(&self, plugin: &'static str, ok: bool) {
+        if !self.config.enabled {
+            return;
+        }
+        let mut plugins = self.plugins.lock().unwrap();
+        let state = plugins.entry(plugin).or_default();
+        let before = state.factor.max(1);
+        if ok {
+            state.failures = 0;
+            state.factor = before / 2;
+        } else {
+            state.failures += 1;
+            if state.failures >= self.config.after_failures {
+                state.factor = (before * 2).min(self.config.max_factor.max(1));
+            }
+        }
+        let after = state.factor.max(1);
+        match (before, after) {
+            _ if before == after => {}
+            (_, 1) => log_msg(&format!("✅ [BACKOFF] '{}' is answering again, polling it every cycle", plugin)),
+            _ if after > before => log_msg(&format!(
+                "⏳ [BACKOFF] '{}' failed {} polls in a row, polling it every {} cycles",
+                plugin, state.failures, after
+            )),
+            _ => tracing::debug!("{} answered, polling it every {} cycles", plugin, after),
+        }
+    }
+
+    /// back to the full rate (the plugin was reloaded)
+    pub fn reset(&self, plugin: &str) {
+        self.plugins.lock().unwrap().retain(|name, _| *name != plugin);
+    }
+}
@@ -0,0 +1,243 @@
+//! ==============================================================================
+//! cadence.rs - per-sensor sampling statistics (GET /api/sensors)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a plugin that fails 40% of its polls still shows a fresh value most
+//!     of the time. the tracker remembers when each sensor's readings were
+//!     taken and reports, per sensor:
+//!         expected_interval_ms   how often a reading should arrive
+//!         actual_interval_ms     mean spacing of the recent readings
+//!         miss_rate              share of expected readings that never came
+//!         consecutive_failures   misses since the last reading
+//!
+//! local vs remote:
+//!     sensors of this node are checked on every poll cycle: a sensor that
+//!     produced readings before and is absent from a cycle (plugin error,
+//!     empty poll, dropped by validation) counts a miss. a cycle in which
+//!     the sensor's plugin was not due ([polling.backoff] skipped it) is
+//!     neither a hit nor a miss. the expected
+//!     interval is polling.interval_seconds and the miss rate covers the
+//!     last WINDOW polls.
+//!     sensors of other nodes only show up as readings. their expected
+//!     interval is the median spacing of the recent readings (robust as
+//!     long as fewer than half the polls fail), gaps in the series count as
+//!     misses, and so does the time since the last reading.
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.cadence, fed by merge_readings),
+//!       main.rs (poll cycles), catalog.rs (/api/sensors)
+//!     - reads: backoff.rs (via main.rs: the sensors not due this cycle)
+//!
+//! ==============================================================================
+
+use crate::domain::SensorReading;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+
+/// readings / polls looked at per sensor
+const WINDOW: usize = 100;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SamplingStats {
+    pub expected_interval_ms: Option<u64>,
+    pub actual_interval_ms: Option<u64>,
+    /// 0.0 - 1.0
+    pub miss_rate: Option<f64>,
+    pub consecutive_failures: u64,
+    /// readings the stats are based on
+    pub samples: usize,
+}
+
+#[derive(Clone, Default)]
+struct Track {
+    /// timestamps of the recent readings, oldest first
+    taken: VecDeque<u64>,
+    /// outcome of the recent poll cycles (local sensors only), true = hit
+    polls: VecDeque<bool>,
+    poll_interval_ms: Option<u64>,
+    consecutive: u64,
+}
+
+#[derive(Clone, Default)]
+pub struct Cadence {
+    sensors: HashMap<String, Track>,
+}
+
+impl Cadence {
+    /// note readings as they are merged
+    pub fn observe(&mut self, readings: &[SensorReading]) {
+        for reading in readings {
+            let track = self.sensors.entry(reading.sensor_id.clone()).or_default();
+            if track.taken.back().is_some_and(|last| reading.timestamp_ms <= *last) {
+                continue;
+            }
+            track.taken.push_back(reading.timestamp_ms);
+            if track.taken.len() > WINDOW {
+                track.taken.pop_front();
+            }
+        }
+    }
+
+    /// one local poll cycle of `node_id` finished with readings of `polled`.
+    /// every known sensor of the node that is missing counts a miss, unless
+    /// it is in `not_due` (its plugin was backed off this cycle).
+    pub fn poll_cycle(&mut self, node_id: &str, polled: &[String], not_due: &[String], interval_ms: u64) {
+        let prefix = format!("{}:", node_id);
+        for id in polled {
+            let track = self.sensors.entry(id.clone()).or_default();
+            track.poll_interval_ms = Some(interval_ms);
+        }
+        for (id, track) in self.sensors.iter_mut().filter(|(id, t)| id.starts_with(&prefix) && t.poll_interval_ms.is_some()) {
+            if not_due.contains(id) {
+                continue;
+            }
+            let hit = polled.contains(id);
+            track.consecutive = if hit { 0 } else { track.consecutive + 1 };
+            track.polls.push_back(hit);
+            if track.polls.len() > WINDOW {
+                track.polls.pop_front();
+            }
+        }
+    }
+
+    pub fn stats(&self, sensor_id: &str, now_ms: u64) -> Option<SamplingStats> {
+        let track = self.sensors.get(sensor_id)?;
+        let taken = &track.taken;
+        let actual = match (taken.front(), taken.back()) {
+            (Some(first), Some(last)) if taken.len() > 1 => Some((last - first) / (taken.len() as u64 - 1)),
+            _ => None,
+        };
+
+        // local sensor: counted poll outcomes
+        if let Some(interval) = track.poll_interval_ms {
+            let misses = track.polls.iter().filter(|hit| !**hit).count();
+            return Some(SamplingStats {
+                expected_interval_ms: Some(interval),
+                actual_interval_ms: actual,
+                miss_rate: (!track.polls.is_empty()).then(|| round(misses as f64 / track.polls.len() as f64)),
+                consecutive_failures: track.consecutive,
+                samples: taken.len(),
+            });
+        }
+
+        // remote sensor: inferred from the series
+        let mut gaps: Vec<u64> = taken.iter().zip(taken.iter().skip(1)).map(|(a, b)| b - a).collect();
+        gaps.sort_unstable();
+        let expected = gaps.get(gaps.len() / 2).copied().filter(|g| *g > 0);
+        let (miss_rate, consecutive) = match (expected, taken.front(), taken.back()) {
+            (Some(expected), Some(first), Some(last)) => {
+                // a gap of 1.5x the interval or more is one missed reading
+                let slots = |span: u64| ((span as f64 / expected as f64) + 0.5).floor() as u64;
+                let consecutive = slots(now_ms.saturating_sub(*last)).saturating_sub(1);
+                let due = slots(last - first) + consecutive;
+                let received = taken.len() as u64 - 1;
+                let rate = if due == 0 { 0.0 } else { 1.0 - (received as f64 / due as f64).min(1.0) };
+                (Some(round(rate)), consecutive)
+            }
+            _ => (None, 0),
+        };
+        Some(SamplingStats {
+            expected_interval_ms: expected,
+            actual_interval_ms: actual,
+            miss_rate,
+            consecutive_failures: consecutive,
+            samples: taken.len(),
+        })
+    }
+
+    pub fn forget_sensor(&mut self, sensor_id: &str) {
+        self.sensors.remove(sensor_id);
+    }
+
+    pub fn forget_node(&mut self, node_id: &str) {
+        let prefix = format!("{}:", node_id);
+        self.sensors.retain(|id, _| !id.starts_with(&prefix));
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(sensor_id: &str, timestamp_ms: u64) -> SensorReading {
+        SensorReading {
+            sensor_id: sensor_id.into(),
+            timestamp_ms,
+            data: serde_json::json!({ "temperature": 21.0 }),
+            metadata: None,
+            quality: None,
+        }
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn test_local_miss_rate() {
+        let mut cadence = Cadence::default();
+        let dht22 = ids(&["pi4:dht22"]);
+        for hit in [true, false, false, true] {
+            cadence.poll_cycle("pi4", if hit { &dht22 } else { &[] }, &[], 5_000);
+        }
+        let stats = cadence.stats("pi4:dht22", 0).unwrap();
+        assert_eq!((stats.miss_rate, stats.consecutive_failures), (Some(0.5), 0));
+        assert_eq!(stats.expected_interval_ms, Some(5_000));
+
+        cadence.poll_cycle("pi4", &[], &[], 5_000);
+        let stats = cadence.stats("pi4:dht22", 0).unwrap();
+        assert_eq!((stats.miss_rate, stats.consecutive_failures), (Some(0.6), 1));
+    }
+
+    #[test]
+    fn test_backed_off_cycles_are_no_misses() {
+        let mut cadence = Cadence::default();
+        let dht22 = ids(&["pi4:dht22"]);
+        cadence.poll_cycle("pi4", &dht22, &[], 5_000);
+        for _ in 0..3 {
+            cadence.poll_cycle("pi4", &[], &dht22, 5_000);
+        }
+        let stats = cadence.stats("pi4:dht22", 0).unwrap();
+        assert_eq!((stats.miss_rate, stats.consecutive_failures), (Some(0.0), 0));
+    }
+
+    #[test]
+    fn test_poll_cycle_leaves_other_nodes_alone() {
+        let mut cadence = Cadence::default();
+        cadence.poll_cycle("zero", &ids(&["zero:dht22"]), &[], 5_000);
+        cadence.poll_cycle("pi4", &[], &[], 5_000);
+        assert_eq!(cadence.stats("zero:dht22", 0).unwrap().miss_rate, Some(0.0));
+    }
+
+    #[test]
+    fn test_remote_miss_rate_from_gaps() {
+        let mut cadence = Cadence::default();
+        // every 10s, the reading at 30s never came
+        let readings: Vec<_> = [0, 10, 20, 40, 50].iter().map(|s| reading("zero:dht22", s * 1000)).collect();
+        cadence.observe(&readings);
+
+        // 5s after the last reading nothing is overdue: 4 of 5 slots arrived
+        let stats = cadence.stats("zero:dht22", 55_000).unwrap();
+        assert_eq!(stats.expected_interval_ms, Some(10_000));
+        assert_eq!(stats.actual_interval_ms, Some(12_500));
+        assert_eq!((stats.miss_rate, stats.consecutive_failures), (Some(0.2), 0));
+
+        // 35s after it three more are missing: 4 of 8
+        let stats = cadence.stats("zero:dht22", 85_000).unwrap();
+        assert_eq!((stats.miss_rate, stats.consecutive_failures), (Some(0.5), 3));
+    }
+
+    #[test]
+    fn test_single_remote_reading_has_no_rate() {
+        let mut cadence = Cadence::default();
+        cadence.observe(&[reading("zero:dht22", 1_000)]);
+        let stats = cadence.stats("zero:dht22", 60_000).unwrap();
+        assert_eq!((stats.expected_interval_ms, stats.miss_rate), (None, None));
+        assert_eq!(stats.samples, 1);
+    }
+}
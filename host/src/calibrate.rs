@@ -0,0 +1,50 @@
+//! ==============================================================================
+//! calibrate.rs - per-sensor offset / scale corrections
+//! ==============================================================================
+//!
+//! purpose:
+//!     two DHT22s side by side routinely disagree by a degree or more.
+//!     [[calibration]] rules correct one field of matching sensors before
+//!     the reading is validated, derived from or stored:
+//!         value = raw * scale + offset
+//!     rules apply in order, so several rules on the same field compose.
+//!
+//! where it runs:
+//!     in the poll loop of the node that owns the sensor and in
+//!     accept_readings for everything a hub receives (push, backfill,
+//!     websocket, pull mode, coap, nats). every host a reading
+//!     passes through applies its rules, so a correction belongs in the
+//!     config of exactly one of them - the polling node (or its overlay in
+//!     config/nodes/ on the hub), or the hub for devices that push directly.
+//!     `cluster:` readings are never touched.
+//!
+//! relationships:
+//!     - used by: main.rs (poll loop, accept_readings)
+//!     - reads: config.rs (HostConfig.calibration)
+//!
+//! ==============================================================================
+
+use crate::aggregate::{glob_match, CLUSTER_NODE};
+use crate::config::CalibrationRule;
+use crate::domain::SensorReading;
+
+/// correct the fields of `readings` matched by `rules`
+pub fn apply(rules: &[CalibrationRule], readings: &mut [SensorReading]) {
+    if rules.is_empty() {
+        return;
+    }
+    for reading in readings.iter_mut() {
+        if reading.sensor_id.starts_with(&format!("{}:", CLUSTER_NODE)) {
+            continue;
+        }
+        for rule in rules.iter().filter(|rule| glob_match(&rule.sensors, &reading.sensor_id)) {
+            let Some(value) = reading.data.get_mut(&rule.field) else {
+                continue;
+            };
+            if let Some(raw) = value.as_f64() {
+                // drop float noise like 55.00000000000001
+                *value = serde_json::json!(((raw * rule.scale + rule.offset) * 1e6).round() / 1e6);
+            }
+        }
+    }
+}
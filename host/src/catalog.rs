@@ -0,0 +1,103 @@
+//! ==============================================================================
+//! catalog.rs - sensor inventory behind GET /api/sensors
+//! ==============================================================================
+//!
+//! purpose:
+//!     provisioning dashboards and integrations need to know which sensors
+//!     exist before they can ask for their data. the catalog lists every
+//!     sensor in the latest view (readings evicted by [state] limits are
+//!     gone from it too) with:
+//!         - the node it belongs to (the "node:" prefix of its id)
+//!         - the plugin that produced it, when one matches: the node's
+//!           loaded plugins from its heartbeat (this node: the runtime),
+//!           falling back to the bundled plugin names. devices pushing over
+//!           coap / mqtt / http have none.
+//!         - the schema of each of its fields (display units, see units.rs)
+//!         - its last value, quality and last-seen time
+//!         - its sampling cadence: expected vs actual interval, miss rate and
+//!           consecutive failures (see cadence.rs)
+//!
+//! relationships:
+//!     - used by: main.rs (/api/sensors)
+//!     - reads: domain.rs (latest readings), nodes.rs (spoke plugins), units.rs / schema.rs,
+//!              cadence.rs (sampling stats)
+//!
+//! ==============================================================================
+
+use crate::cadence::{Cadence, SamplingStats};
+use crate::config::FieldSchema;
+use crate::domain::{NodeMetadata, Quality, SensorReading};
+use crate::nodes::NodeStatus;
+use crate::schema::SchemaRegistry;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+#[derive(Serialize)]
+pub struct SensorEntry {
+    pub sensor_id: String,
+    pub node_id: String,
+    pub plugin: Option<String>,
+    /// every data field, with its schema when one is known
+    pub fields: BTreeMap<String, Option<FieldSchema>>,
+    pub last_value: serde_json::Value,
+    pub last_seen_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<NodeMetadata>,
+    /// expected vs actual interval, miss rate, consecutive failures
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SamplingStats>,
+}
+
+/// one entry per sensor in `readings`, sorted by sensor id.
+/// `local` is this node's id and loaded plugins.
+pub fn build(
+    readings: &[SensorReading],
+    cadence: &Cadence,
+    schema: &SchemaRegistry,
+    nodes: &[NodeStatus],
+    local: (&str, &[String]),
+) -> Vec<SensorEntry> {
+    let now_ms = crate::domain::now_ms();
+    let mut entries: Vec<SensorEntry> = readings
+        .iter()
+        .map(|reading| {
+            let node_id = crate::cluster::node_of(&reading.sensor_id);
+            let plugins = match node_id == local.0 {
+                true => Some(local.1),
+                false => nodes.iter().find(|n| n.node_id == node_id).map(|n| n.plugins.as_slice()),
+            };
+            let fields = reading
+                .data
+                .as_object()
+                .into_iter()
+                .flat_map(|data| data.keys())
+                .map(|field| (field.clone(), schema.lookup(&reading.sensor_id, field).cloned()))
+                .collect();
+            SensorEntry {
+                sensor_id: reading.sensor_id.clone(),
+                node_id: node_id.to_string(),
+                plugin: plugin_of(&reading.sensor_id, plugins),
+                fields,
+                last_value: reading.data.clone(),
+                last_seen_ms: reading.timestamp_ms,
+                quality: reading.quality,
+                metadata: reading.metadata.clone(),
+                sampling: cadence.stats(&reading.sensor_id, now_ms),
+            }
+        })
+        .collect();
+    entries.sort_by(|a, b| a.sensor_id.cmp(&b.sensor_id));
+    entries
+}
+
+/// the plugin whose name the sensor part of `sensor_id` contains
+/// ("pi4:dht22-gpio4" -> "dht22")
+fn plugin_of(sensor_id: &str, plugins: Option<&[String]>) -> Option<String> {
+    let sensor = sensor_id.split_once(':').map_or(sensor_id, |(_, sensor)| sensor);
+    match plugins.filter(|p| !p.is_empty()) {
+        Some(plugins) => plugins.iter().find(|p| sensor.contains(p.as_str())).cloned(),
+        None => crate::runtime::KNOWN_PLUGINS.iter().find(|p| sensor.contains(*p)).map(|p| p.to_string()),
+    }
+}
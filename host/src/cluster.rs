@@ -0,0 +1,294 @@
+//! ==============================================================================
+//! cluster.rs - hub/spoke data exchange
+//! ==============================================================================
+//!
+//! purpose:
+//!     background tasks that move readings between nodes outside of the
+//!     main polling loop.
+//!
+//! pull mode:
+//!     at some sites the hub can reach the spokes but the spokes cannot
+//!     reach the hub (one-way firewall / nat). in that case the spokes run
+//!     with an empty hub_url and the hub periodically fetches each spoke's
+//!     GET /api/readings?units=raw and merges the result into its own state, exactly
+//!     as if the spoke had pushed it (main.rs ingest_readings: retired nodes
+//!     dropped, calibration / validation, the node marked as heard from).
+//!
+//! hub failover:
+//!     spokes may list several hubs in priority order (cluster.hub_urls).
+//!     pushes go to the active hub; when it fails the spoke walks the list
+//!     and sticks with the first hub that accepts the batch. while running
+//!     on a secondary, a probe task checks higher-priority hubs' /health and
+//!     falls back to the primary as soon as it answers again.
+//!
+//! batch ids:
+//!     every push carries a fresh batch_id (uuid v4). the same id is reused
+//!     while failing over, so a batch that reached a hub whose reply got
+//!     lost is acknowledged as a duplicate instead of being merged twice.
+//!
+//! clock skew:
+//!     spokes without ntp (no rtc, no network time at boot) push readings
+//!     stamped decades or hours off. on every push the hub compares each
+//!     node's newest timestamp with the receive time. nodes beyond
+//!     cluster.clock_skew_warn_ms are logged once and listed in
+//!     AppState.clock_skew_ms; beyond cluster.clock_skew_rewrite_ms their
+//!     timestamps are replaced with the receive time.
+//!
+//! relationships:
+//!     - used by: main.rs (pull loop on the hub, push path on spokes)
+//!     - reads: config.rs (ClusterConfig.pull_spokes, hub_urls)
+//!     - writes: domain.rs (AppState)
+//!
+//! ==============================================================================
+
+use crate::codec::Encoding;
+use crate::config::ClusterConfig;
+use crate::domain::AppState;
+use crate::log_msg;
+use crate::domain::{PushAck, PushBatch, SensorReading};
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// spawn the hub pull loop. one request per spoke per interval; a spoke
+/// that is down only costs a logged error and its readings go stale.
+pub fn spawn_pull_loop(
+    api: crate::ApiState,
+    client: reqwest::Client,
+    spokes: Vec<String>,
+    interval_secs: u64,
+    encoding: Encoding,
+) -> tokio::task::JoinHandle<()> {
+    log_msg(&format!("[CLUSTER] Pull mode: polling {} spoke(s) every {}s", spokes.len(), interval_secs));
+
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs.max(1)));
+        loop {
+            ticker.tick().await;
+            for base in &spokes {
+                // the stored si values, not the spoke's display units
+                let url = format!("{}/api/readings?units=raw", base.trim_end_matches('/'));
+                match pull_spoke(&client, &url, encoding).await {
+                    Ok(remote) => {
+                        let count = remote.readings.len();
+                        log_msg(&format!("📥 [PULL] {} readings from {}", count, base));
+                        if count > 0 {
+                            crate::ingest_readings(&api, remote.readings.into_vec(), "PULL").await;
+                        }
+                    }
+                    Err(e) => log_msg(&format!("❌ [PULL] Failed to pull {}: {}", base, e)),
+                }
+            }
+        }
+    })
+}
+
+async fn pull_spoke(client: &reqwest::Client, url: &str, encoding: Encoding) -> anyhow::Result<AppState> {
+    let resp = client
+        .get(url)
+        .header(reqwest::header::ACCEPT, encoding.content_type())
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await?
+        .error_for_status()?;
+    // older spokes ignore Accept and answer json - trust their Content-Type
+    let received = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Encoding::from_mime)
+        .unwrap_or_default();
+    received.decode(&resp.bytes().await?)
+}
+
+// ==============================================================================
+// hub failover - prioritized push targets
+// ==============================================================================
+
+pub struct HubFailover {
+    urls: Vec<String>,
+    active: AtomicUsize,
+    encoding: Encoding,
+    stats: Mutex<LinkStats>,
+    /// push bodies are encoded into this one (codec.rs, buffer reuse)
+    body: Mutex<bytes::BytesMut>,
+}
+
+/// push link health as seen from the spoke (reported in heartbeats)
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct LinkStats {
+    pub hub: String,
+    pub last_latency_ms: u64,
+    pub last_success_ms: u64,
+    pub consecutive_failures: u64,
+    pub total_failures: u64,
+}
+
+impl HubFailover {
+    pub fn new(urls: Vec<String>, encoding: Encoding) -> Self {
+        Self {
+            urls,
+            active: AtomicUsize::new(0),
+            encoding,
+            stats: Mutex::new(LinkStats::default()),
+            body: Mutex::new(bytes::BytesMut::new()),
+        }
+    }
+
+    pub fn link_stats(&self) -> LinkStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.urls.is_empty()
+    }
+
+    /// base url (scheme://host:port) of the active hub
+    pub fn active_base(&self) -> String {
+        hub_base(&self.urls[self.active.load(Ordering::SeqCst)]).to_string()
+    }
+
+    /// push a batch to the active hub, failing over down the priority list.
+    /// returns the url that accepted the batch.
+    pub async fn push(&self, client: &reqwest::Client, batch: &PushBatch) -> anyhow::Result<String> {
+        let start = self.active.load(Ordering::SeqCst);
+        // active hub first, then the rest in priority order
+        let order = std::iter::once(start).chain((0..self.urls.len()).filter(|&i| i != start));
+
+        // Bytes: every hub tried shares the one encoded body
+        let body = self.encoding.encode_into(batch, &mut self.body.lock().unwrap())?;
+        let mut last_err = None;
+        for idx in order {
+            let url = &self.urls[idx];
+            let started = std::time::Instant::now();
+            let sent = client
+                .post(url)
+                .header(reqwest::header::CONTENT_TYPE, self.encoding.content_type())
+                .header(reqwest::header::ACCEPT, self.encoding.content_type())
+                .body(body.clone())
+                .send()
+                .await;
+            // the hub is up but throttling this node - another hub won't help
+            if let Some(r) = sent.as_ref().ok().filter(|r| r.status() == reqwest::StatusCode::TOO_MANY_REQUESTS) {
+                let retry = r.headers().get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok()).unwrap_or("?");
+                anyhow::bail!("hub {} is rate limiting this node (retry after {}s)", hub_base(url), retry);
+            }
+            // the hub's merge queue is full - the whole fleet moving to the
+            // next hub would only overload that one
+            if let Some(r) = sent.as_ref().ok().filter(|r| r.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE && r.headers().contains_key(reqwest::header::RETRY_AFTER)) {
+                let retry = r.headers().get(reqwest::header::RETRY_AFTER).and_then(|v| v.to_str().ok()).unwrap_or("?");
+                anyhow::bail!("hub {} is busy (retry after {}s)", hub_base(url), retry);
+            }
+            match sent.and_then(|r| r.error_for_status()) {
+                Ok(response) => {
+                    // hubs predating batch ids answer with an empty body - nothing to check
+                    let ack = response.bytes().await.ok().and_then(|b| self.encoding.decode::<PushAck>(&b).ok());
+                    if let Some(ack) = ack.filter(|a| a.duplicate) {
+                        log_msg(&format!("♻️ [PUSH] Hub {} already had batch {}", hub_base(url), ack.batch_id));
+                    }
+                    let mut stats = self.stats.lock().unwrap();
+                    stats.hub = hub_base(url).to_string();
+                    stats.last_latency_ms = started.elapsed().as_millis() as u64;
+                    stats.last_success_ms = crate::domain::now_ms();
+                    stats.consecutive_failures = 0;
+                    if idx != start {
+                        self.active.store(idx, Ordering::SeqCst);
+                        log_msg(&format!("🔀 [FAILOVER] Switched hub {} → {}", self.urls[start], url));
+                    }
+                    return Ok(url.clone());
+                }
+                Err(e) => last_err = Some(e),
+            }
+        }
+        {
+            let mut stats = self.stats.lock().unwrap();
+            stats.consecutive_failures += 1;
+            stats.total_failures += 1;
+        }
+        match last_err {
+            Some(e) => Err(anyhow::anyhow!("all {} hubs unreachable (last error: {})", self.urls.len(), e)),
+            None => Err(anyhow::anyhow!("no hub urls configured")),
+        }
+    }
+
+    /// spawn the fallback probe: while failed over, check higher-priority
+    /// hubs and move back to the best one that is healthy.
+    pub fn spawn_probe(self: Arc<Self>, client: reqwest::Client, interval_secs: u64) -> Option<tokio::task::JoinHandle<()>> {
+        if self.urls.len() < 2 {
+            return None;
+        }
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(tokio::time::Duration::from_secs(interval_secs.max(1)));
+            loop {
+                ticker.tick().await;
+                let active = self.active.load(Ordering::SeqCst);
+                for idx in 0..active {
+                    if probe_health(&client, &self.urls[idx]).await {
+                        self.active.store(idx, Ordering::SeqCst);
+                        log_msg(&format!("🔀 [FAILOVER] Hub {} healthy again, falling back from {}", self.urls[idx], self.urls[active]));
+                        break;
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// base url of the hub that owns a push url (".../push")
+pub fn hub_base(push_url: &str) -> &str {
+    push_url.trim_end_matches('/').trim_end_matches("/push")
+}
+
+/// probe GET /health on the hub that owns a push url
+async fn probe_health(client: &reqwest::Client, push_url: &str) -> bool {
+    client
+        .get(format!("{}/health", hub_base(push_url)))
+        .timeout(std::time::Duration::from_secs(3))
+        .send()
+        .await
+        .map(|r| r.status().is_success())
+        .unwrap_or(false)
+}
+
+// ==============================================================================
+// clock skew
+// ==============================================================================
+
+/// check pushed readings against the hub clock, flag skewed nodes in
+/// `state` and (optionally) rewrite their timestamps to `received_ms`.
+pub fn normalize_timestamps(
+    state: &mut AppState,
+    readings: &mut [SensorReading],
+    cluster: &ClusterConfig,
+    received_ms: u64,
+) {
+    // newest timestamp per node - the freshest reading best reflects its clock
+    let mut newest: BTreeMap<String, u64> = BTreeMap::new();
+    for r in readings.iter() {
+        let ts = newest.entry(node_of(&r.sensor_id).to_string()).or_default();
+        *ts = (*ts).max(r.timestamp_ms);
+    }
+
+    for (node, ts) in newest {
+        let skew = ts as i64 - received_ms as i64;
+        if skew.unsigned_abs() > cluster.clock_skew_warn_ms {
+            if state.clock_skew_ms.insert(node.clone(), skew).is_none() {
+                log_msg(&format!("⏱️ [SKEW] Node '{}' clock is off by {:+.1}s", node, skew as f64 / 1000.0));
+            }
+        } else if state.clock_skew_ms.remove(&node).is_some() {
+            log_msg(&format!("⏱️ [SKEW] Node '{}' clock back in sync", node));
+        }
+
+        if cluster.clock_skew_rewrite_ms > 0 && skew.unsigned_abs() > cluster.clock_skew_rewrite_ms {
+            for r in readings.iter_mut().filter(|r| node_of(&r.sensor_id) == node) {
+                r.timestamp_ms = received_ms;
+            }
+        }
+    }
+}
+
+/// node part of a "node:sensor" id (legacy ids without a prefix map to themselves)
+pub fn node_of(sensor_id: &str) -> &str {
+    sensor_id.split(':').next().unwrap_or(sensor_id)
+}
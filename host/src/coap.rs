@@ -0,0 +1,131 @@
+//! ==============================================================================
+//! coap.rs - CoAP/CBOR readings endpoint (feature = "coap")
+//! ==============================================================================
+//!
+//! purpose:
+//!     microcontroller-class sensors (esp32 etc.) can't comfortably run an
+//!     http+json client. this small udp server accepts their readings over
+//!     coap with a cbor body and merges them into AppState next to the
+//!     wasm-plugin readings - through the same path as /push, so
+//!     decommissioned nodes stay blocked and calibration / validation apply.
+//!
+//! request:
+//!     POST coap://{coap.bind}/readings   (confirmable or non-confirmable)
+//!     body: one reading or an array of readings, cbor encoded:
+//!         { "sensor_id": "esp32-kitchen:sht31",
+//!           "timestamp_ms": 1730000000000,      (optional - no rtc? omit it)
+//!           "data": { "temperature": 21.5 } }
+//!     readings without timestamp_ms are stamped with the receive time.
+//!
+//! response:
+//!     2.04 changed on success, 4.00 bad request for undecodable bodies,
+//!     4.04 / 4.05 for other paths / methods.
+//!
+//! relationships:
+//!     - used by: main.rs (spawned at startup when coap.enabled)
+//!     - reads: config.rs (CoapConfig)
+//!     - writes: domain.rs (AppState, via main.rs ingest_readings)
+//!
+//! ==============================================================================
+
+use crate::domain::SensorReading;
+use crate::log_msg;
+use coap_lite::{CoapRequest, Packet, RequestType, ResponseType};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+
+/// largest datagram we accept (coap over udp stays well below this)
+const MAX_DATAGRAM: usize = 1500;
+
+/// a reading as sent by a constrained device
+#[derive(Deserialize)]
+struct CoapReading {
+    sensor_id: String,
+    #[serde(default)]
+    timestamp_ms: Option<u64>,
+    data: serde_json::Value,
+    #[serde(default)]
+    quality: Option<crate::domain::Quality>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum CoapBatch {
+    Many(Vec<CoapReading>),
+    One(CoapReading),
+}
+
+/// bind the coap socket and serve requests in a background task
+pub async fn spawn_server(bind: &str, api: crate::ApiState) -> anyhow::Result<()> {
+    let socket = UdpSocket::bind(bind).await?;
+    log_msg(&format!("[STARTUP] CoAP listening on udp://{}", bind));
+
+    tokio::spawn(async move {
+        let mut buf = [0u8; MAX_DATAGRAM];
+        loop {
+            let (len, peer) = match socket.recv_from(&mut buf).await {
+                Ok(r) => r,
+                Err(e) => {
+                    tracing::warn!("CoAP recv failed: {}", e);
+                    continue;
+                }
+            };
+            let Ok(packet) = Packet::from_bytes(&buf[..len]) else {
+                tracing::debug!("ignoring malformed CoAP datagram from {}", peer);
+                continue;
+            };
+            if let Some(reply) = handle(packet, peer, &api).await {
+                let _ = socket.send_to(&reply, peer).await;
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn handle(packet: Packet, peer: SocketAddr, api: &crate::ApiState) -> Option<Vec<u8>> {
+    let mut request = CoapRequest::from_packet(packet, peer);
+    let status = match (request.get_method(), request.get_path().as_str()) {
+        (RequestType::Post, "readings") => match decode(&request.message.payload) {
+            Ok(readings) => {
+                log_msg(&format!("📥 [COAP] {} readings from {}", readings.len(), peer));
+                merge(api, readings).await;
+                ResponseType::Changed
+            }
+            Err(e) => {
+                log_msg(&format!("⚠️ [COAP] Bad payload from {}: {}", peer, e));
+                ResponseType::BadRequest
+            }
+        },
+        (_, "readings") => ResponseType::MethodNotAllowed,
+        _ => ResponseType::NotFound,
+    };
+
+    // acks / non-confirmable responses only exist for requests
+    let response = request.response.as_mut()?;
+    response.set_status(status);
+    response.message.to_bytes().ok()
+}
+
+fn decode(payload: &[u8]) -> anyhow::Result<Vec<CoapReading>> {
+    let batch: CoapBatch = crate::codec::Encoding::Cbor.decode(payload)?;
+    Ok(match batch {
+        CoapBatch::Many(readings) => readings,
+        CoapBatch::One(reading) => vec![reading],
+    })
+}
+
+async fn merge(api: &crate::ApiState, readings: Vec<CoapReading>) {
+    let received_ms = crate::domain::now_ms();
+    let readings: Vec<SensorReading> = readings
+        .into_iter()
+        .map(|r| SensorReading {
+            sensor_id: r.sensor_id,
+            metadata: None,
+            timestamp_ms: r.timestamp_ms.unwrap_or(received_ms),
+            data: r.data,
+            quality: r.quality,
+        })
+        .collect();
+    crate::ingest_readings(api, readings, "COAP").await;
+}
@@ -0,0 +1,158 @@
+//! ==============================================================================
+//! codec.rs - payload encodings for /push and the readings api
+//! ==============================================================================
+//!
+//! purpose:
+//!     json repeats every field name in every reading, which adds up with
+//!     high-frequency readings from many spokes. cbor and messagepack carry
+//!     the same data in a compact binary form.
+//!
+//! negotiation:
+//!     - requests: the body encoding is taken from Content-Type
+//!       (application/json, application/cbor, application/msgpack).
+//!       a missing Content-Type is treated as json so the legacy pizero
+//!       service keeps working.
+//!     - responses: the first supported type in Accept wins, json otherwise.
+//!     - spokes choose what they send (and request in pull mode) with
+//!       cluster.encoding = "json" | "cbor" | "msgpack".
+//!
+//! buffer reuse:
+//!     encode_into serializes into a BytesMut the caller keeps (one per
+//!     push target) and splits the result off as Bytes. once the request
+//!     holding those Bytes is done, the next encode writes into the same
+//!     allocation again - a spoke pushing every few seconds for months
+//!     doesn't allocate (and fragment the heap with) a body per cycle.
+//!
+//! relationships:
+//!     - used by: main.rs (push_handler, api_handler), cluster.rs (push, pull)
+//!
+//! ==============================================================================
+
+use axum::extract::{FromRequest, Request};
+use axum::http::{header, HeaderMap, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use bytes::{BufMut, Bytes, BytesMut};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum Encoding {
+    #[default]
+    Json,
+    Cbor,
+    MsgPack,
+}
+
+impl Encoding {
+    /// parse the cluster.encoding config value
+    pub fn from_name(name: &str) -> anyhow::Result<Self> {
+        match name {
+            "" | "json" => Ok(Encoding::Json),
+            "cbor" => Ok(Encoding::Cbor),
+            "msgpack" => Ok(Encoding::MsgPack),
+            other => anyhow::bail!("unknown encoding '{}' (expected json, cbor or msgpack)", other),
+        }
+    }
+
+    /// map a mime type (parameters ignored) to an encoding
+    pub fn from_mime(mime: &str) -> Option<Self> {
+        match mime.split(';').next().unwrap_or("").trim() {
+            "application/json" => Some(Encoding::Json),
+            "application/cbor" => Some(Encoding::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => Some(Encoding::MsgPack),
+            _ => None,
+        }
+    }
+
+    /// pick the response encoding from an Accept header
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        headers
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|accept| accept.split(',').find_map(Self::from_mime))
+            .unwrap_or_default()
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::Cbor => "application/cbor",
+            Encoding::MsgPack => "application/msgpack",
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, value: &T) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Json => serde_json::to_vec(value)?,
+            Encoding::Cbor => {
+                let mut buf = Vec::new();
+                ciborium::ser::into_writer(value, &mut buf)?;
+                buf
+            }
+            // named (map) encoding so spokes and hubs on different versions still agree
+            Encoding::MsgPack => rmp_serde::to_vec_named(value)?,
+        })
+    }
+
+    /// encode into `buf` (cleared first), reusing its allocation when the
+    /// previous result was dropped
+    pub fn encode_into<T: Serialize>(self, value: &T, buf: &mut BytesMut) -> anyhow::Result<Bytes> {
+        buf.clear();
+        let mut writer = buf.writer();
+        match self {
+            Encoding::Json => serde_json::to_writer(&mut writer, value)?,
+            Encoding::Cbor => ciborium::ser::into_writer(value, &mut writer)?,
+            Encoding::MsgPack => rmp_serde::encode::write_named(&mut writer, value)?,
+        }
+        Ok(buf.split().freeze())
+    }
+
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> anyhow::Result<T> {
+        Ok(match self {
+            Encoding::Json => serde_json::from_slice(bytes)?,
+            Encoding::Cbor => ciborium::de::from_reader(bytes)?,
+            Encoding::MsgPack => rmp_serde::from_slice(bytes)?,
+        })
+    }
+}
+
+// ==============================================================================
+// axum integration
+// ==============================================================================
+
+/// request body extractor - like axum::Json but honours Content-Type
+pub struct Decoded<T>(pub T);
+
+#[axum::async_trait]
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for Decoded<T> {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let encoding = match req.headers().get(header::CONTENT_TYPE).and_then(|v| v.to_str().ok()) {
+            None => Encoding::Json,
+            Some(mime) => Encoding::from_mime(mime).ok_or_else(|| {
+                (StatusCode::UNSUPPORTED_MEDIA_TYPE, format!("unsupported content type '{}'", mime))
+            })?,
+        };
+        let body = axum::body::Bytes::from_request(req, state)
+            .await
+            .map_err(|e| (e.status(), e.body_text()))?; // keeps 413 from the body limit
+        encoding
+            .decode(&body)
+            .map(Decoded)
+            .map_err(|e| (StatusCode::BAD_REQUEST, format!("invalid {} body: {}", encoding.content_type(), e)))
+    }
+}
+
+/// response body in a negotiated encoding
+pub struct Encoded<T>(pub Encoding, pub T);
+
+impl<T: Serialize> IntoResponse for Encoded<T> {
+    fn into_response(self) -> Response {
+        let Encoded(encoding, value) = self;
+        match encoding.encode(&value) {
+            Ok(body) => ([(header::CONTENT_TYPE, HeaderValue::from_static(encoding.content_type()))], body).into_response(),
+            Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        }
+    }
+}
@@ -0,0 +1,478 @@
+//! ==============================================================================
+//! commands.rs - hub → spoke command channel
+//! ==============================================================================
+//!
+//! purpose:
+//!     generic replacement for the old `spoke_buzzer_url` forwarding. the hub
+//!     queues commands (buzz, fan, set-led, reload-plugin, set-role) per
+//!     target node_id; spokes long-poll the hub for their queue, execute each
+//!     command against their local hardware/runtime, and report the result back.
+//!
+//! flow:
+//!     1. POST /api/command {node_id, type, ...}      -> queued on the hub (admin token)
+//!     2. spoke: GET /api/command?node_id=X&wait=25   -> blocks until commands
+//!     3. spoke executes locally (execute())
+//!     4. spoke: POST /api/command/{id}/result        -> hub records outcome
+//!     5. GET /api/command/{id}                       -> status for the caller
+//!
+//!     commands addressed to the hub's own node_id are executed immediately.
+//!     steps 2 and 4 need a cluster client cert when cluster.tls is on (admin.rs).
+//!
+//! plugin deployment:
+//!     deploy-plugin commands carry a .wasm artifact held on the hub. the
+//!     spoke downloads it from GET /api/command/{id}/artifact, installs it
+//!     (runtime.install_plugin) and reports back like any other command, so
+//!     deploys work in every topology the command channel does. deploys only
+//!     go to node_ids the registry knows (nodes.rs).
+//!
+//!     commands nobody picks up within PENDING_TTL fail as expired, and an
+//!     artifact goes with its command - when it finishes, expires or falls
+//!     out of the history - so a node that never polls pins nothing.
+//!
+//! relationships:
+//!     - used by: main.rs (handlers, spoke receive loop, buzzer_handler),
+//!       alerts.rs (alert actions)
+//!     - uses: hal.rs (buzzer/fan/led), runtime.rs (plugin reload/install),
+//!       role.rs (set-role)
+//!     - writes: audit.rs (hardware commands with the caller that queued them)
+//!
+//! ==============================================================================
+
+use crate::log_msg;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use tokio::sync::Notify;
+
+/// how many finished commands the hub remembers for status queries
+const HISTORY_LIMIT: usize = 200;
+
+/// how long a command (and its artifact) waits for its node before it expires
+const PENDING_TTL: std::time::Duration = std::time::Duration::from_secs(15 * 60);
+
+/// the action a node should perform
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum CommandKind {
+    Buzz { pattern: String },
+    Fan { on: bool },
+    SetLed { index: u8, r: u8, g: u8, b: u8 },
+    ReloadPlugin { name: String },
+    DeployPlugin { name: String, size: usize },
+    /// switch hub / spoke / standalone (role.rs)
+    SetRole { role: crate::role::Role },
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CommandStatus {
+    Queued,
+    Delivered,
+    Done,
+    Failed,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Command {
+    pub id: u64,
+    pub node_id: String,
+    #[serde(flatten)]
+    pub kind: CommandKind,
+    pub created_ms: u64,
+    pub status: CommandStatus,
+    #[serde(default)]
+    pub message: String,
+    /// who queued it (audit.rs actor), carried to the spoke for its audit log
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub source: String,
+}
+
+/// body of POST /api/command
+#[derive(Deserialize)]
+pub struct CommandRequest {
+    pub node_id: String,
+    #[serde(flatten)]
+    pub kind: CommandKind,
+}
+
+/// body of POST /api/command/{id}/result
+#[derive(Serialize, Deserialize)]
+pub struct CommandResult {
+    pub ok: bool,
+    #[serde(default)]
+    pub message: String,
+}
+
+// ==============================================================================
+// hub-side queue
+// ==============================================================================
+
+#[derive(Default)]
+pub struct CommandQueue {
+    pending: Mutex<HashMap<String, VecDeque<Command>>>,
+    history: Mutex<VecDeque<Command>>,
+    /// artifact of a command by id, with the command's created_ms
+    artifacts: Mutex<HashMap<u64, (u64, bytes::Bytes)>>,
+    next_id: AtomicU64,
+    notify: Notify,
+    finished: Notify,
+}
+
+impl CommandQueue {
+    /// queue a command for a node and wake any waiting long-polls.
+    /// `source` is who asked for it (audit.rs actor).
+    pub fn enqueue(&self, node_id: &str, kind: CommandKind, source: &str) -> Command {
+        self.push(node_id, kind, source, None)
+    }
+
+    /// queue a command that ships a binary artifact (e.g. a plugin .wasm)
+    pub fn enqueue_with_artifact(&self, node_id: &str, kind: CommandKind, source: &str, artifact: bytes::Bytes) -> Command {
+        self.push(node_id, kind, source, Some(artifact))
+    }
+
+    fn push(&self, node_id: &str, kind: CommandKind, source: &str, artifact: Option<bytes::Bytes>) -> Command {
+        let now = crate::domain::now_ms();
+        self.expire(now);
+        let cmd = Command {
+            id: self.next_id.fetch_add(1, Ordering::SeqCst) + 1,
+            node_id: node_id.to_string(),
+            kind,
+            created_ms: now,
+            status: CommandStatus::Queued,
+            message: String::new(),
+            source: source.to_string(),
+        };
+        // store the artifact first so it's there by the time a spoke sees the command
+        if let Some(artifact) = artifact {
+            self.artifacts.lock().unwrap().insert(cmd.id, (cmd.created_ms, artifact));
+        }
+        self.pending.lock().unwrap().entry(node_id.to_string()).or_default().push_back(cmd.clone());
+        self.record(cmd.clone());
+        self.notify.notify_waiters();
+        cmd
+    }
+
+    pub fn artifact(&self, id: u64) -> Option<bytes::Bytes> {
+        self.artifacts.lock().unwrap().get(&id).map(|(_, artifact)| artifact.clone())
+    }
+
+    /// fail the commands still queued after PENDING_TTL and drop the
+    /// artifacts of anything that old, delivered or not
+    fn expire(&self, now: u64) {
+        let cutoff = now.saturating_sub(PENDING_TTL.as_millis() as u64);
+        let mut expired = Vec::new();
+        self.pending.lock().unwrap().retain(|_, queue| {
+            queue.retain(|cmd| {
+                let keep = cmd.created_ms >= cutoff;
+                if !keep {
+                    expired.push((cmd.id, cmd.node_id.clone()));
+                }
+                keep
+            });
+            !queue.is_empty()
+        });
+        self.artifacts.lock().unwrap().retain(|_, (created_ms, _)| *created_ms >= cutoff);
+        if expired.is_empty() {
+            return;
+        }
+        for (id, node_id) in &expired {
+            log_msg(&format!("⌛ [COMMAND] #{} expired, '{}' never picked it up", id, node_id));
+            self.update(*id, CommandStatus::Failed, &format!("expired: not picked up within {}s", PENDING_TTL.as_secs()));
+        }
+        self.finished.notify_waiters();
+    }
+
+    /// wait until a command is done/failed (or the timeout passes)
+    pub async fn wait_finished(&self, id: u64, timeout: std::time::Duration) -> Option<Command> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let notified = self.finished.notified();
+            let cmd = self.get(id)?;
+            if matches!(cmd.status, CommandStatus::Done | CommandStatus::Failed) {
+                return Some(cmd);
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return self.get(id);
+            }
+        }
+    }
+
+    /// drain a node's queue, waiting up to `wait` for something to arrive
+    pub async fn take(&self, node_id: &str, wait: std::time::Duration) -> Vec<Command> {
+        self.expire(crate::domain::now_ms());
+        let deadline = tokio::time::Instant::now() + wait;
+        loop {
+            // register interest before checking so an enqueue in between isn't missed
+            let notified = self.notify.notified();
+            let drained: Vec<Command> = self.pending.lock().unwrap()
+                .get_mut(node_id)
+                .map(|q| q.drain(..).collect())
+                .unwrap_or_default();
+            if !drained.is_empty() {
+                for cmd in &drained {
+                    self.update(cmd.id, CommandStatus::Delivered, "");
+                }
+                return drained;
+            }
+            if tokio::time::timeout_at(deadline, notified).await.is_err() {
+                return Vec::new();
+            }
+        }
+    }
+
+    /// record the outcome a node reported for a command
+    pub fn complete(&self, id: u64, result: &CommandResult) -> bool {
+        let status = if result.ok { CommandStatus::Done } else { CommandStatus::Failed };
+        let found = self.update(id, status, &result.message);
+        self.artifacts.lock().unwrap().remove(&id);
+        self.finished.notify_waiters();
+        found
+    }
+
+    pub fn get(&self, id: u64) -> Option<Command> {
+        self.history.lock().unwrap().iter().find(|c| c.id == id).cloned()
+    }
+
+    fn record(&self, cmd: Command) {
+        let evicted = {
+            let mut history = self.history.lock().unwrap();
+            let evicted = if history.len() >= HISTORY_LIMIT { history.pop_front() } else { None };
+            history.push_back(cmd);
+            evicted
+        };
+        // a command that fell out of the history can't be looked up or
+        // completed any more - don't keep delivering it or its artifact
+        if let Some(old) = evicted {
+            if let Some(queue) = self.pending.lock().unwrap().get_mut(&old.node_id) {
+                queue.retain(|c| c.id != old.id);
+            }
+            self.artifacts.lock().unwrap().remove(&old.id);
+        }
+    }
+
+    fn update(&self, id: u64, status: CommandStatus, message: &str) -> bool {
+        let mut history = self.history.lock().unwrap();
+        match history.iter_mut().find(|c| c.id == id) {
+            Some(cmd) => {
+                cmd.status = status;
+                cmd.message = message.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// ==============================================================================
+// local execution
+// ==============================================================================
+
+/// queue a command for `node_id`, running it right away when that's this node.
+/// returns the command as recorded (done / failed for local ones).
+pub async fn submit(
+    queue: &CommandQueue,
+    node_id: &str,
+    kind: CommandKind,
+    source: &str,
+    config: &crate::config::HostConfig,
+    runtime: &crate::runtime::WasmRuntime,
+) -> Command {
+    let cmd = queue.enqueue(node_id, kind, source);
+    if cmd.node_id != config.cluster.node_id {
+        log_msg(&format!("📨 [COMMAND] Queued #{} {:?} for {}", cmd.id, cmd.kind, cmd.node_id));
+        return cmd;
+    }
+
+    // local target - take it straight back off the queue and run it
+    queue.take(&cmd.node_id, std::time::Duration::ZERO).await;
+    let result = match execute(&cmd, config, runtime).await {
+        Ok(message) => CommandResult { ok: true, message },
+        Err(e) => CommandResult { ok: false, message: format!("{:#}", e) },
+    };
+    queue.complete(cmd.id, &result);
+    queue.get(cmd.id).unwrap_or(cmd)
+}
+
+/// run a command against this node's hardware / runtime
+pub async fn execute(
+    cmd: &Command,
+    config: &crate::config::HostConfig,
+    runtime: &crate::runtime::WasmRuntime,
+) -> anyhow::Result<String> {
+    use crate::hal::HardwareProvider;
+    let hal = crate::hal::shared();
+
+    // commands from hubs predating the source field are attributed to the hub
+    let actor = if cmd.source.is_empty() { "hub" } else { cmd.source.as_str() };
+    let via = format!(" (command #{})", cmd.id);
+    match &cmd.kind {
+        CommandKind::Buzz { pattern } => {
+            let pin = config.buzzer.gpio_pin;
+            let pattern = pattern.clone();
+            crate::audit::record(actor, "buzzer", format!("{}{}", pattern, via));
+            crate::actuators::buzz(pin, crate::hal::pattern(&pattern)).await?;
+            Ok(format!("buzzed on pin {}", pin))
+        }
+        CommandKind::Fan { on } => {
+            crate::actuators::switch_fan(&config.fan, *on).await?;
+            crate::audit::record(actor, "fan", format!("{}{}", if *on { "on" } else { "off" }, via));
+            Ok(format!("fan {}", if *on { "on" } else { "off" }))
+        }
+        CommandKind::SetLed { index, r, g, b } => {
+            hal.set_led(*index, *r, *g, *b)?;
+            hal.flush_leds(config.leds.min_sync())?;
+            crate::audit::record(actor, "led", format!("{}=#{:02x}{:02x}{:02x}{}", index, r, g, b, via));
+            Ok(format!("led {} set", index))
+        }
+        CommandKind::ReloadPlugin { name } => {
+            if !crate::runtime::KNOWN_PLUGINS.contains(&name.as_str()) {
+                anyhow::bail!("unknown plugin '{}'", name);
+            }
+            runtime.reload_plugin(name).await?;
+            Ok(format!("plugin '{}' reloaded", name))
+        }
+        CommandKind::SetRole { role } => {
+            crate::role::set(*role, &format!("{}{}", actor, via))?;
+            Ok(format!("role set to {} from the next poll cycle", role))
+        }
+        CommandKind::DeployPlugin { .. } => {
+            anyhow::bail!("deploy-plugin needs its artifact; use install_artifact()")
+        }
+    }
+}
+
+/// run a deploy-plugin command: install the downloaded artifact
+pub async fn install_artifact(
+    kind: &CommandKind,
+    artifact: bytes::Bytes,
+    runtime: &crate::runtime::WasmRuntime,
+) -> anyhow::Result<String> {
+    match kind {
+        CommandKind::DeployPlugin { name, size } => {
+            if artifact.len() != *size {
+                anyhow::bail!("artifact truncated: got {} of {} bytes", artifact.len(), size);
+            }
+            runtime.install_plugin(name, &artifact).await?;
+            Ok(format!("plugin '{}' deployed ({} bytes)", name, size))
+        }
+        other => anyhow::bail!("{:?} does not take an artifact", other),
+    }
+}
+
+// ==============================================================================
+// spoke-side receive loop
+// ==============================================================================
+
+/// long-poll the hub for commands addressed to this node and execute them.
+/// `hub_base` resolves the current hub (follows failover).
+pub fn spawn_receiver(
+    client: reqwest::Client,
+    hub_base: impl Fn() -> String + Send + 'static,
+    config: crate::config::HostConfig,
+    runtime: crate::runtime::WasmRuntime,
+) -> tokio::task::JoinHandle<()> {
+    let node_id = config.cluster.node_id.clone();
+    tokio::spawn(async move {
+        loop {
+            let base = hub_base();
+            let url = format!("{}/api/command", base);
+            let polled = client
+                .get(&url)
+                .query(&[("node_id", node_id.as_str()), ("wait", "25")])
+                .timeout(std::time::Duration::from_secs(35))
+                .send()
+                .await
+                .and_then(|r| r.error_for_status());
+
+            let commands: Vec<Command> = match polled {
+                Ok(resp) => resp.json().await.unwrap_or_default(),
+                Err(e) => {
+                    tracing::debug!("command poll to {} failed: {}", url, e);
+                    tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                    continue;
+                }
+            };
+
+            for cmd in commands {
+                let result = run(&client, &base, &cmd, &config, &runtime).await;
+                let _ = client
+                    .post(format!("{}/api/command/{}/result", base, cmd.id))
+                    .json(&result)
+                    .send()
+                    .await;
+            }
+        }
+    })
+}
+
+/// execute one received command (downloading its artifact from the hub
+/// at `base` if it has one) and build the result to report back
+pub async fn run(
+    client: &reqwest::Client,
+    base: &str,
+    cmd: &Command,
+    config: &crate::config::HostConfig,
+    runtime: &crate::runtime::WasmRuntime,
+) -> CommandResult {
+    log_msg(&format!("📨 [COMMAND] #{} {:?}", cmd.id, cmd.kind));
+    let outcome = if matches!(cmd.kind, CommandKind::DeployPlugin { .. }) {
+        match fetch_artifact(client, base, cmd.id).await {
+            Ok(artifact) => install_artifact(&cmd.kind, artifact, runtime).await,
+            Err(e) => Err(e),
+        }
+    } else {
+        execute(cmd, config, runtime).await
+    };
+    match outcome {
+        Ok(message) => CommandResult { ok: true, message },
+        Err(e) => {
+            log_msg(&format!("❌ [COMMAND] #{} failed: {:#}", cmd.id, e));
+            CommandResult { ok: false, message: format!("{:#}", e) }
+        }
+    }
+}
+
+async fn fetch_artifact(client: &reqwest::Client, base: &str, id: u64) -> anyhow::Result<bytes::Bytes> {
+    let resp = client
+        .get(format!("{}/api/command/{}/artifact", base, id))
+        .timeout(std::time::Duration::from_secs(120))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(resp.bytes().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deploy(queue: &CommandQueue, node_id: &str) -> Command {
+        let kind = CommandKind::DeployPlugin { name: "dht22".into(), size: 4 };
+        queue.enqueue_with_artifact(node_id, kind, "test", bytes::Bytes::from_static(b"wasm"))
+    }
+
+    #[test]
+    fn test_unpicked_command_expires_with_its_artifact() {
+        let queue = CommandQueue::default();
+        let cmd = deploy(&queue, "typo");
+        assert!(queue.artifact(cmd.id).is_some());
+
+        queue.expire(cmd.created_ms + PENDING_TTL.as_millis() as u64 + 1);
+        assert!(queue.artifact(cmd.id).is_none());
+        assert!(queue.pending.lock().unwrap().is_empty());
+        assert_eq!(queue.get(cmd.id).unwrap().status, CommandStatus::Failed);
+    }
+
+    #[test]
+    fn test_history_eviction_drops_pending_and_artifact() {
+        let queue = CommandQueue::default();
+        let first = deploy(&queue, "retired");
+        for _ in 0..HISTORY_LIMIT {
+            queue.enqueue("pi4", CommandKind::Fan { on: true }, "test");
+        }
+        assert!(queue.get(first.id).is_none());
+        assert!(queue.artifact(first.id).is_none());
+        assert!(queue.pending.lock().unwrap().get("retired").is_none_or(|q| q.is_empty()));
+    }
+}
@@ -10,15 +10,49 @@
 //!     - RaftConfig: Identity (node_id) and Peers (who else is in the cluster).
 //!     - PollingConfig: How often the Leader polls sensors.
 //!     - SensorsConfig: GPIO pins and I2C addresses.
-//!     - PluginsConfig: Toggles for individual WASM plugins.
+//!     - PluginsConfig: Toggles for WASM plugins, keyed by plugin name.
+//!
+//! formats:
+//!     host.toml, or host.yaml / host.yml / host.json with the same layout
+//!     (picked by extension) for provisioning tools that emit yaml or json.
+//!     null values have no toml equivalent and are rejected - leave the key
+//!     out instead. hub overlays and secrets.toml stay toml.
+//!
+//! environment overrides:
+//!     HOST__<SECTION>__<KEY>=value sets any key on top of host.toml (and on
+//!     top of a hub-managed overlay), e.g. HOST__POLLING__INTERVAL_SECONDS=10
+//!     or HOST__CLUSTER__NODE_ID=pi-07 - per-device differences for container
+//!     deployments sharing one image and config. names are matched lowercase,
+//!     "__" separates table levels. values are toml literals (10, true,
+//!     ["a", "b"]); anything else, and any key that is a string in the file,
+//!     is taken as a plain string.
+//!
+//! profiles:
+//!     `wasi-host --profile dev` layers host.dev.toml (next to host.toml) on
+//!     top of host.toml, so per-environment files hold only what differs.
+//!     precedence, lowest first:
+//!         includes < host.toml < host.{profile}.toml < hub overlay (spokes) < HOST__ env
+//!     tables merge key by key, anything else (arrays too) is replaced whole.
+//!
+//! includes:
+//!     include = ["sensors.toml", "cluster.toml"] at the top of a config
+//!     (or profile) file merges those files underneath it, in order, so
+//!     shared sections live in one file used by hub and spoke configs.
+//!     paths are relative to the including file, which wins over what it
+//!     includes; included files may include further (no loops).
+//!
+//! secrets:
+//!     string values may reference ${NAME} (environment, then secrets.toml
+//!     next to host.toml), resolved last - see secrets.rs.
 //!
 //! ==============================================================================
 
-use serde::Deserialize;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 /// Root configuration structure
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct HostConfig {
     pub polling: PollingConfig,
     pub sensors: SensorsConfig,
@@ -27,170 +61,1926 @@ pub struct HostConfig {
     pub buzzer: BuzzerConfig,
     #[serde(default)]
     pub fan: FanConfig,
+    #[serde(default)]
+    pub actuators: ActuatorConfig,
     pub logging: LoggingConfig,
     #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    #[serde(default)]
+    pub crash: CrashConfig,
+    #[serde(default)]
+    pub selftest: SelfTestConfig,
+    #[serde(default)]
+    pub config_reload: ConfigReloadConfig,
+    #[serde(default)]
+    pub api: ApiConfig,
+    #[serde(default)]
+    pub http_client: HttpClientConfig,
+    #[serde(default)]
     pub cluster: ClusterConfig,
     #[serde(default)]
     pub plugins: PluginsConfig,
+    #[serde(default)]
+    pub plugin_watchdog: PluginWatchdogConfig,
+    #[serde(default)]
+    pub plugin_profiler: PluginProfilerConfig,
+    #[serde(default)]
+    pub mqtt: MqttConfig,
+    #[serde(default)]
+    pub kafka: KafkaConfig,
+    #[serde(default)]
+    pub coap: CoapConfig,
+    #[serde(default)]
+    pub aggregations: Vec<AggregationRule>,
+    #[serde(default)]
+    pub derived: Vec<DerivedRule>,
+    #[serde(default)]
+    pub calibration: Vec<CalibrationRule>,
+    #[serde(default)]
+    pub state: StateConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub influx: InfluxConfig,
+    #[serde(default)]
+    pub sinks: Vec<HttpSinkConfig>,
+    #[serde(default)]
+    pub export: ExportConfig,
+    #[serde(default)]
+    pub validation: ValidationConfig,
+    #[serde(default)]
+    pub anomaly: AnomalyConfig,
+    #[serde(default)]
+    pub alerts: AlertsConfig,
+    #[serde(default)]
+    pub notify: NotifyConfig,
+    #[serde(default)]
+    pub reports: ReportsConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub schema: std::collections::BTreeMap<String, FieldSchema>, // "field" or "sensor.field" -> unit / range
+    #[serde(default = "default_units")]
+    pub units: String,            // "metric" or "imperial" - api / dashboard display only
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct PollingConfig {
     pub interval_seconds: u64,
+    /// how long a poll cycle waits for its plugins (0 = the poll interval);
+    /// a plugin answering later is a miss and keeps its previous reading
+    #[serde(default)]
+    pub budget_ms: u64,
+    /// what the loop does with ticks that passed during a long cycle:
+    /// "skip", "delay" or "burst" (see poll_timing.rs)
+    #[serde(default = "default_missed_ticks")]
+    pub missed_ticks: String,
+    #[serde(default)]
+    pub backoff: BackoffConfig,
+}
+
+fn default_missed_ticks() -> String {
+    "skip".to_string()
+}
+
+impl PollingConfig {
+    /// the poll budget of a cycle at `interval`
+    pub fn budget(&self, interval: std::time::Duration) -> std::time::Duration {
+        match self.budget_ms {
+            0 => interval,
+            ms => std::time::Duration::from_millis(ms),
+        }
+    }
+}
+
+/// poll a failing sensor plugin less often ([polling.backoff]), see backoff.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct BackoffConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_backoff_after")]
+    pub after_failures: u32,    // failed polls in a row before backing off
+    #[serde(default = "default_backoff_max")]
+    pub max_factor: u32,        // slowest rate: every max_factor-th poll cycle
+}
+
+fn default_backoff_after() -> u32 {
+    3
+}
+
+fn default_backoff_max() -> u32 {
+    10
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            after_failures: default_backoff_after(),
+            max_factor: default_backoff_max(),
+        }
+    }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct SensorsConfig {
     pub dht22: Dht22Config,
     pub bme680: Bme680Config,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct Dht22Config {
     pub gpio_pin: u8,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct Bme680Config {
     pub i2c_address: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 #[allow(dead_code)]
 pub struct LedConfig {
     pub count: u8,
     pub gpio_pin: u8,
+    /// scales every channel on the way to the strip (255 = as set), see hal.rs
     pub brightness: u8,
+    /// gamma correction of the channels before brightness (1.0 = none,
+    /// ws2812 strips look right around 2.8)
+    #[serde(default = "default_led_gamma")]
+    pub gamma: f32,
+    /// least time between two strip writes; changes in between are written
+    /// with the next one. 0 = once per poll cycle
+    #[serde(default)]
+    pub min_sync_ms: u64,
+}
+
+fn default_led_gamma() -> f32 {
+    1.0
 }
 
-#[derive(Debug, Deserialize, Clone)]
+impl LedConfig {
+    pub fn min_sync(&self) -> std::time::Duration {
+        std::time::Duration::from_millis(self.min_sync_ms)
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct BuzzerConfig {
     pub gpio_pin: u8,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+/// per-pin queues of buzzer / fan operations ([actuators], see actuators.rs)
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ActuatorConfig {
+    #[serde(default = "default_actuator_queue_depth")]
+    pub queue_depth: usize,       // operations waiting per pin, more are refused
+}
+
+impl Default for ActuatorConfig {
+    fn default() -> Self {
+        Self { queue_depth: default_actuator_queue_depth() }
+    }
+}
+
+fn default_actuator_queue_depth() -> usize {
+    4
+}
+
+/// cooling fan on a relay ([fan]). in auto mode the monitor plugin switches
+/// it by cpu temperature (thresholds are handed to it as HARVESTER_FAN_ON /
+/// HARVESTER_FAN_OFF); with auto = false only commands, alert actions and
+/// the dashboard fan test switch it.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct FanConfig {
+    #[serde(default = "default_fan_pin")]
     pub gpio_pin: u8,
+    #[serde(default = "default_true")]
+    pub active_low: bool,    // relay boards switch on a low pin (sainsmart)
+    #[serde(default = "default_fan_on")]
     pub threshold_on: f32,   // Turn fan ON when CPU temp exceeds this
+    #[serde(default = "default_fan_off")]
     pub threshold_off: f32,  // Turn fan OFF when CPU temp drops below this
+    #[serde(default = "default_true")]
+    pub auto: bool,          // false = the plugin leaves the fan alone
+}
+
+impl FanConfig {
+    /// gpio level that puts the fan in the given state
+    #[cfg_attr(not(feature = "fan"), allow(dead_code))]
+    pub fn level(&self, on: bool) -> bool {
+        on != self.active_low
+    }
+}
+
+fn default_fan_pin() -> u8 {
+    27
+}
+
+fn default_fan_on() -> f32 {
+    40.0
+}
+
+fn default_fan_off() -> f32 {
+    28.0
 }
 
 impl Default for FanConfig {
     fn default() -> Self {
         Self {
-            gpio_pin: 27,
-            threshold_on: 40.0,
-            threshold_off: 28.0,
+            gpio_pin: default_fan_pin(),
+            active_low: true,
+            threshold_on: default_fan_on(),
+            threshold_off: default_fan_off(),
+            auto: true,
         }
     }
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
 pub struct LoggingConfig {
     pub level: String,
     #[allow(dead_code)]
     pub show_sensor_data: bool,
+    #[serde(default = "default_log_buffer")]
+    pub buffer_size: usize,       // lines kept in memory for /api/logs
+    #[serde(default = "default_log_overflow")]
+    pub overflow: String,         // drop_oldest | keep_errors (errors / warnings go last)
+    #[serde(default)]
+    pub file: LogFileConfig,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
-pub struct ClusterConfig {
-    pub role: String,      // "hub" or "spoke"
-    pub node_id: String,
-    pub hub_url: String,   // URL to push data to (if spoke)
+fn default_log_buffer() -> usize {
+    100
+}
+
+fn default_log_overflow() -> String {
+    "drop_oldest".to_string()
+}
+
+/// optional rolling log file ([logging.file]), see logfile.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct LogFileConfig {
     #[serde(default)]
-    pub spoke_buzzer_url: String,  // URL to forward buzzer requests to (if hub)
+    pub enabled: bool,
+    #[serde(default = "default_log_path")]
+    pub path: String,
+    #[serde(default = "default_log_max_size")]
+    pub max_size_mb: u64,         // rotate once the file would grow past this (0 = no size limit)
+    #[serde(default = "default_true")]
+    pub daily: bool,              // also rotate at local midnight
+    #[serde(default = "default_log_max_files")]
+    pub max_files: usize,         // rotated files kept (host.log.1 .. host.log.N)
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
-pub struct PluginEntry {
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: default_log_path(),
+            max_size_mb: default_log_max_size(),
+            daily: true,
+            max_files: default_log_max_files(),
+        }
+    }
+}
+
+fn default_log_path() -> String {
+    "logs/host.log".to_string()
+}
+
+fn default_log_max_size() -> u64 {
+    10
+}
+
+fn default_log_max_files() -> usize {
+    7
+}
+
+/// optional otlp trace / metric export ([telemetry], needs the "otel" feature), see telemetry.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "otel"), allow(dead_code))]
+pub struct TelemetryConfig {
+    #[serde(default)]
     pub enabled: bool,
-    #[allow(dead_code)]
     #[serde(default)]
-    pub led: Option<u8>,
+    pub endpoint: String,         // otlp/http base url, e.g. "http://collector:4318" (/v1/traces, /v1/metrics appended)
+    #[serde(default = "default_service_name")]
+    pub service_name: String,
+    #[serde(default)]
+    pub headers: std::collections::BTreeMap<String, String>, // sent with every export (auth tokens, tenant ids)
+    #[serde(default = "default_sample_ratio")]
+    pub sample_ratio: f64,        // share of traces kept, 0.0 - 1.0
+    #[serde(default = "default_metrics_interval")]
+    pub metrics_interval_seconds: u64,
+}
+
+impl Default for TelemetryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            service_name: default_service_name(),
+            headers: Default::default(),
+            sample_ratio: default_sample_ratio(),
+            metrics_interval_seconds: default_metrics_interval(),
+        }
+    }
+}
+
+/// startup hardware probes ([selftest]), see selftest.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct SelfTestConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for SelfTestConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// apply host.toml edits without a restart ([config_reload]), see config_reload.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ConfigReloadConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl Default for ConfigReloadConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// the web server: dashboard, api and the hub endpoints ([api]). a spoke
+/// that never serves a dashboard can run headless (or with --headless):
+/// only the poll / push loop, no listening socket.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ApiConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub admin_token: String,      // bearer token of the admin routes (admin.rs: snapshot, plugins, role, commands), empty = those routes are off
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self { enabled: true, admin_token: String::new() }
+    }
+}
+
+/// the process-wide http clients ([http_client]): one for the hub / spoke
+/// channel, one for influx, sinks, notifications and webhooks. connections
+/// are pooled and reused; a request with its own timeout (command
+/// long-poll, artifact download) keeps it.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct HttpClientConfig {
+    #[serde(default = "default_connect_timeout_seconds")]
+    pub connect_timeout_seconds: u64,
+    #[serde(default = "default_request_timeout_seconds")]
+    pub timeout_seconds: u64,         // whole request, for requests without their own timeout (0 = none)
+    #[serde(default = "default_pool_idle_seconds")]
+    pub pool_idle_seconds: u64,       // idle connections are closed after this
+    #[serde(default = "default_pool_max_idle_per_host")]
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout_seconds: default_connect_timeout_seconds(),
+            timeout_seconds: default_request_timeout_seconds(),
+            pool_idle_seconds: default_pool_idle_seconds(),
+            pool_max_idle_per_host: default_pool_max_idle_per_host(),
+        }
+    }
+}
+
+fn default_connect_timeout_seconds() -> u64 {
+    5
+}
+
+fn default_request_timeout_seconds() -> u64 {
+    60
+}
+
+fn default_pool_idle_seconds() -> u64 {
+    90
+}
+
+fn default_pool_max_idle_per_host() -> usize {
+    4
+}
+
+/// panic hook crash reports ([crash]), see crash.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct CrashConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_crash_dir")]
+    pub dir: String,              // this node's reports are written here
+    #[serde(default = "default_crash_spoke_dir")]
+    pub spoke_dir: String,        // hub: reports received from spokes (POST /api/crash)
+    #[serde(default = "default_crash_spoke_bytes")]
+    pub max_spoke_bytes: u64,     // hub: total size of spoke reports kept, oldest deleted
+    #[serde(default = "default_crash_log_lines")]
+    pub log_lines: usize,         // newest host log lines included in a report
+    #[serde(default = "default_true")]
+    pub report_to_hub: bool,      // spokes post reports to the hub's /api/crash
+    #[serde(default = "default_crash_max_reports")]
+    pub max_reports: usize,       // older reports are deleted
+}
+
+impl Default for CrashConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: default_crash_dir(),
+            spoke_dir: default_crash_spoke_dir(),
+            max_spoke_bytes: default_crash_spoke_bytes(),
+            log_lines: default_crash_log_lines(),
+            report_to_hub: true,
+            max_reports: default_crash_max_reports(),
+        }
+    }
+}
+
+fn default_crash_dir() -> String {
+    "data/crashes".to_string()
+}
+
+fn default_crash_spoke_dir() -> String {
+    "data/crashes/spokes".to_string()
+}
+
+fn default_crash_spoke_bytes() -> u64 {
+    10 * 1024 * 1024
+}
+
+fn default_crash_log_lines() -> usize {
+    100
+}
+
+fn default_crash_max_reports() -> usize {
+    20
+}
+
+fn default_service_name() -> String {
+    "wasi-host".to_string()
+}
+
+fn default_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_metrics_interval() -> u64 {
+    30
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
-pub struct PluginsConfig {
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ClusterConfig {
+    pub role: String,      // "hub", "spoke" or "standalone" (switchable at runtime, see role.rs)
+    pub node_id: String,
+    pub hub_url: String,   // URL to push data to (if spoke)
     #[serde(default)]
-    pub dht22: PluginEntry,
+    pub hub_urls: Vec<String>,     // prioritized push URLs for failover (first = primary)
+    #[serde(default = "default_failover_probe")]
+    pub failover_probe_seconds: u64, // how often a failed-over spoke re-checks higher-priority hubs
     #[serde(default)]
-    pub pi4_monitor: PluginEntry,
+    pub buzzer_node: String,       // node_id whose buzzer /api/buzzer drives (via command channel)
     #[serde(default)]
-    pub revpi_monitor: PluginEntry,
+    pub tls: TlsConfig,
     #[serde(default)]
-    pub bme680: PluginEntry,
-    #[allow(dead_code)]
+    pub pull_spokes: Vec<String>,  // spoke base URLs the hub pulls /api/readings from (pull mode)
     #[serde(default)]
-    pub dashboard: PluginEntry,
-    #[allow(dead_code)]
+    pub pull_interval_seconds: u64, // 0 = use polling.interval_seconds
+    #[serde(default = "default_config_sync")]
+    pub config_sync_seconds: u64,  // spoke: how often to check the hub for a new config overlay (0 = off)
+    #[serde(default = "default_skew_warn")]
+    pub clock_skew_warn_ms: u64,   // hub: flag a node whose timestamps differ from receive time by more than this
     #[serde(default)]
-    pub oled: PluginEntry,
+    pub clock_skew_rewrite_ms: u64, // hub: replace timestamps with receive time beyond this skew (0 = never)
+    #[serde(default = "default_transport")]
+    pub transport: String,         // "http" (push to hub_url), "websocket" or "nats" (needs the "nats" feature)
+    #[serde(default)]
+    pub encoding: String,          // body encoding for push/pull: "json" (default), "cbor" or "msgpack"
+    #[serde(default = "default_heartbeat")]
+    pub heartbeat_seconds: u64,    // spoke: POST /heartbeat interval (0 = off)
+    #[serde(default = "default_stale_after")]
+    pub stale_after_seconds: u64,  // hub: node is stale after this long without heartbeat/push; every role: readings older than this are flagged stale (0 = off)
+    #[serde(default)]
+    pub metadata: crate::domain::NodeMetadata, // location / room / rack / tags attached to this node's readings
+    #[serde(default)]
+    #[cfg_attr(not(feature = "nats"), allow(dead_code))]
+    pub nats: NatsConfig,
+    #[serde(default)]
+    pub limits: PushLimits,
 }
 
-impl HostConfig {
-    /// Load configuration from file
-    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
-        let content = std::fs::read_to_string(path.as_ref())
-            .map_err(|e| anyhow::anyhow!("Failed to read config file: {}", e))?;
-        
-        let config: HostConfig = toml::from_str(&content)
-            .map_err(|e| anyhow::anyhow!("Failed to parse config: {}", e))?;
-        
-        Ok(config)
+/// hub-side per-node limits on POST /push (0 = unlimited).
+/// pushes over the rate get 429 with a Retry-After hint, pushes finding
+/// the merge queue full (all nodes together) 503 with one.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct PushLimits {
+    #[serde(default = "default_pushes_per_minute")]
+    pub pushes_per_minute: u32,    // sustained push rate per node
+    #[serde(default = "default_push_burst")]
+    pub burst: u32,                // pushes allowed back-to-back before the rate applies
+    #[serde(default = "default_max_payload")]
+    pub max_payload_bytes: usize,  // largest accepted /push body
+    #[serde(default = "default_max_readings")]
+    pub max_readings: usize,       // most readings accepted in one push
+    #[serde(default = "default_queue_depth")]
+    pub queue_depth: usize,        // pushes waiting to be merged, hub-wide
+    #[serde(default = "default_busy_retry")]
+    pub busy_retry_secs: u64,      // Retry-After when the queue is full
+}
+
+impl Default for PushLimits {
+    fn default() -> Self {
+        Self {
+            pushes_per_minute: default_pushes_per_minute(),
+            burst: default_push_burst(),
+            max_payload_bytes: default_max_payload(),
+            max_readings: default_max_readings(),
+            queue_depth: default_queue_depth(),
+            busy_retry_secs: default_busy_retry(),
+        }
     }
-    
-    /// Load with default fallback
-    pub fn load_or_default() -> Self {
-        let paths = [
-            std::path::PathBuf::from("config").join("host.toml"),
-            std::path::PathBuf::from("..").join("config").join("host.toml"),
-        ];
-
-        for path in &paths {
-            if path.exists() {
-                match Self::load(path) {
-                    Ok(config) => {
-                        println!("[CONFIG] Loaded from {}", path.display());
-                        return config;
-                    }
-                    Err(e) => {
-                        println!("[CONFIG] Warning: Failed to load {}: {}", path.display(), e);
-                    }
-                }
-            }
+}
+
+fn default_pushes_per_minute() -> u32 {
+    120
+}
+
+fn default_push_burst() -> u32 {
+    20
+}
+
+fn default_max_payload() -> usize {
+    1024 * 1024
+}
+
+fn default_max_readings() -> usize {
+    1000
+}
+
+fn default_queue_depth() -> usize {
+    256
+}
+
+fn default_busy_retry() -> u64 {
+    2
+}
+
+/// optional mutual tls for the hub/spoke channel.
+/// hub verifies spoke client certs against `ca_cert`; spokes pin the hub cert.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct TlsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub ca_cert: String,          // PEM bundle of the CA that signs node certs
+    #[serde(default)]
+    pub cert: String,             // this node's certificate chain (PEM)
+    #[serde(default)]
+    pub key: String,              // this node's private key (PEM)
+    #[serde(default)]
+    pub pinned_hub_cert: String,  // spoke only: exact hub cert to accept (PEM)
+    #[serde(default = "default_true")]
+    pub require_client_cert: bool, // reject connections without a valid client cert
+}
+
+/// nats transport settings (cluster.transport = "nats").
+/// spokes publish batches to `{subject_prefix}.{node_id}`; the hub consumes
+/// `{subject_prefix}.>`. with jetstream the server buffers batches while
+/// the hub is down.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "nats"), allow(dead_code))]
+pub struct NatsConfig {
+    #[serde(default = "default_nats_url")]
+    pub url: String,
+    #[serde(default = "default_nats_prefix")]
+    pub subject_prefix: String,
+    #[serde(default = "default_true")]
+    pub jetstream: bool,
+    #[serde(default = "default_nats_stream")]
+    pub stream: String,            // jetstream stream holding the readings subjects
+}
+
+impl Default for NatsConfig {
+    fn default() -> Self {
+        Self {
+            url: default_nats_url(),
+            subject_prefix: default_nats_prefix(),
+            jetstream: true,
+            stream: default_nats_stream(),
         }
-        
-        println!("[CONFIG] Warning: No config file found - using defaults");
-        Self::default()
     }
-    
-    /// Print configuration summary
-    pub fn print_summary(&self) {
-        println!("┌─────────────────────────────────────────┐");
-        println!("│           HOST CONFIGURATION            │");
-        println!("├─────────────────────────────────────────┤");
-        println!("│ Role: {}                             │", self.cluster.role);
-        println!("│ Node ID: {}                          │", self.cluster.node_id);
-        println!("│ Poll Interval: {}s                      │", self.polling.interval_seconds);
-        println!("│ Log Level: {}                        │", self.logging.level);
-        println!("├─────────────────────────────────────────┤");
+}
+
+fn default_transport() -> String {
+    "http".to_string()
+}
+
+fn default_nats_url() -> String {
+    "nats://127.0.0.1:4222".to_string()
+}
+
+fn default_nats_prefix() -> String {
+    "edge.readings".to_string()
+}
+
+fn default_nats_stream() -> String {
+    "EDGE_READINGS".to_string()
+}
+
+impl Default for ClusterConfig {
+    fn default() -> Self {
+        Self {
+            role: String::new(),
+            node_id: String::new(),
+            hub_url: String::new(),
+            hub_urls: Vec::new(),
+            failover_probe_seconds: default_failover_probe(),
+            buzzer_node: String::new(),
+            tls: TlsConfig::default(),
+            pull_spokes: Vec::new(),
+            pull_interval_seconds: 0,
+            config_sync_seconds: default_config_sync(),
+            clock_skew_warn_ms: default_skew_warn(),
+            clock_skew_rewrite_ms: 0,
+            transport: default_transport(),
+            encoding: String::new(),
+            heartbeat_seconds: default_heartbeat(),
+            stale_after_seconds: default_stale_after(),
+            metadata: crate::domain::NodeMetadata::default(),
+            nats: NatsConfig::default(),
+            limits: PushLimits::default(),
+        }
     }
 }
 
-impl Default for HostConfig {
+impl ClusterConfig {
+    /// push targets in priority order. `hub_urls` wins; a lone `hub_url`
+    /// is treated as a one-entry list.
+    pub fn push_targets(&self) -> Vec<String> {
+        if !self.hub_urls.is_empty() {
+            self.hub_urls.clone()
+        } else if !self.hub_url.is_empty() {
+            vec![self.hub_url.clone()]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl Default for TlsConfig {
     fn default() -> Self {
         Self {
-            polling: PollingConfig { interval_seconds: 5 },
-            sensors: SensorsConfig {
+            enabled: false,
+            ca_cert: String::new(),
+            cert: String::new(),
+            key: String::new(),
+            pinned_hub_cert: String::new(),
+            require_client_cert: true,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_failover_probe() -> u64 {
+    30
+}
+
+fn default_config_sync() -> u64 {
+    60
+}
+
+fn default_heartbeat() -> u64 {
+    5
+}
+
+fn default_stale_after() -> u64 {
+    30
+}
+
+fn default_skew_warn() -> u64 {
+    30_000
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct PluginEntry {
+    pub enabled: bool,
+    #[allow(dead_code)]
+    #[serde(default)]
+    pub led: Option<u8>,
+    #[serde(default)]
+    pub pins: Vec<u8>,    // gpio pins its hardware is wired to, checked for conflicts at startup
+}
+
+/// [plugins.{name}] tables by name ("dht22", "pi4_monitor", ...), a plugin
+/// with no table is off
+pub type PluginsConfig = std::collections::HashMap<String, PluginEntry>;
+
+/// kill plugin calls that run too long ([plugin_watchdog], see watchdog.rs):
+/// the call traps, the plugin is re-instantiated, and after `strikes` kills
+/// (or traps of its own) in a row it is quarantined until it is reloaded
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct PluginWatchdogConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_call_timeout_ms")]
+    pub call_timeout_ms: u64,     // a poll / render running longer is killed
+    #[serde(default = "default_watchdog_strikes")]
+    pub strikes: u32,             // kills / traps in a row before the plugin is quarantined (enabled = false: never)
+}
+
+impl Default for PluginWatchdogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            call_timeout_ms: default_call_timeout_ms(),
+            strikes: default_watchdog_strikes(),
+        }
+    }
+}
+
+fn default_call_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_watchdog_strikes() -> u32 {
+    3
+}
+
+/// sample one plugin's calls with wasmtime's guest profiler ([plugin_profiler],
+/// see profiler.rs), downloaded from GET /api/plugins/{name}/profile
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct PluginProfilerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub plugin: String,           // "revpi-monitor", a runtime plugin name
+    #[serde(default = "default_profiler_interval_ms")]
+    pub interval_ms: u64,         // time between samples, in 10ms epoch ticks
+}
+
+impl Default for PluginProfilerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            plugin: String::new(),
+            interval_ms: default_profiler_interval_ms(),
+        }
+    }
+}
+
+fn default_profiler_interval_ms() -> u64 {
+    10
+}
+
+/// optional mqtt publisher (needs the "mqtt" cargo feature).
+/// each reading is published to `{topic_prefix}/{node_id}/{sensor}`.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "mqtt"), allow(dead_code))]
+pub struct MqttConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub broker_host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub broker_port: u16,
+    #[serde(default)]
+    pub client_id: String,        // empty = cluster.node_id
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub qos: u8,                  // 0 = at most once, 1 = at least once, 2 = exactly once
+    #[serde(default)]
+    pub retain: bool,             // broker keeps the last reading per topic
+    #[serde(default = "default_mqtt_prefix")]
+    pub topic_prefix: String,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            broker_host: String::new(),
+            broker_port: default_mqtt_port(),
+            client_id: String::new(),
+            username: String::new(),
+            password: String::new(),
+            qos: 0,
+            retain: false,
+            topic_prefix: default_mqtt_prefix(),
+        }
+    }
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_prefix() -> String {
+    "edge".to_string()
+}
+
+/// optional kafka producer (needs the "kafka" cargo feature).
+/// every merged reading becomes one message on `topic`, keyed by node_id.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "kafka"), allow(dead_code))]
+pub struct KafkaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub brokers: String,          // bootstrap servers, e.g. "10.0.0.5:9092,10.0.0.6:9092"
+    #[serde(default = "default_kafka_topic")]
+    pub topic: String,
+    #[serde(default)]
+    pub client_id: String,        // empty = cluster.node_id
+    #[serde(default = "default_kafka_acks")]
+    pub acks: String,             // 0 | 1 | all
+    #[serde(default = "default_kafka_compression")]
+    pub compression: String,      // none | gzip | snappy | lz4 | zstd
+    #[serde(default = "default_kafka_linger")]
+    pub linger_ms: u64,           // batch messages for up to this long
+    #[serde(default)]
+    pub properties: std::collections::BTreeMap<String, String>, // extra librdkafka settings (sasl, ssl, ...)
+}
+
+impl Default for KafkaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            brokers: String::new(),
+            topic: default_kafka_topic(),
+            client_id: String::new(),
+            acks: default_kafka_acks(),
+            compression: default_kafka_compression(),
+            linger_ms: default_kafka_linger(),
+            properties: Default::default(),
+        }
+    }
+}
+
+fn default_kafka_topic() -> String {
+    "edge.readings".to_string()
+}
+
+fn default_kafka_acks() -> String {
+    "all".to_string()
+}
+
+fn default_kafka_compression() -> String {
+    "lz4".to_string()
+}
+
+fn default_kafka_linger() -> u64 {
+    100
+}
+
+/// optional coap server for constrained senders (needs the "coap" feature).
+/// accepts cbor readings at POST coap://{bind}/readings.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "coap"), allow(dead_code))]
+pub struct CoapConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_coap_bind")]
+    pub bind: String,
+}
+
+impl Default for CoapConfig {
+    fn default() -> Self {
+        Self { enabled: false, bind: default_coap_bind() }
+    }
+}
+
+fn default_coap_bind() -> String {
+    "0.0.0.0:5683".to_string()
+}
+
+/// bounds of the in-memory latest view (AppState.readings), so sensors
+/// that disappear don't linger for months. 0 = unbounded.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct StateConfig {
+    #[serde(default = "default_state_max_readings")]
+    pub max_readings: usize,      // oldest readings beyond this are evicted
+    #[serde(default = "default_state_ttl")]
+    pub ttl_seconds: u64,         // readings not updated for this long are evicted
+}
+
+impl Default for StateConfig {
+    fn default() -> Self {
+        Self { max_readings: default_state_max_readings(), ttl_seconds: default_state_ttl() }
+    }
+}
+
+fn default_state_max_readings() -> usize {
+    5000
+}
+
+fn default_state_ttl() -> u64 {
+    7 * 24 * 3600
+}
+
+/// sqlite history of every merged reading, see storage.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct StorageConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_storage_path")]
+    pub path: String,             // database file, relative to the working directory
+    #[serde(default = "default_storage_sync")]
+    pub sync: String,             // normal (fsync at checkpoints) | full (fsync every commit)
+    #[serde(default = "default_flush_interval")]
+    pub flush_interval_ms: u64,   // readings collected this long per transaction (0 = commit right away)
+    #[serde(default = "default_integrity_check")]
+    pub integrity_check: String,  // quick | full | off - checked at startup, corrupt files are salvaged
+    #[serde(default)]
+    pub retention: RetentionConfig,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: default_storage_path(),
+            sync: default_storage_sync(),
+            flush_interval_ms: default_flush_interval(),
+            integrity_check: default_integrity_check(),
+            retention: RetentionConfig::default(),
+        }
+    }
+}
+
+fn default_storage_sync() -> String {
+    "normal".to_string()
+}
+
+fn default_flush_interval() -> u64 {
+    2000
+}
+
+fn default_integrity_check() -> String {
+    "quick".to_string()
+}
+
+/// how long history is kept. raw readings older than raw_days are folded
+/// into downsample_minutes averages, which are dropped after downsampled_days.
+/// 0 days = keep forever.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct RetentionConfig {
+    #[serde(default = "default_raw_days")]
+    pub raw_days: u64,
+    #[serde(default = "default_downsample_minutes")]
+    pub downsample_minutes: u64,
+    #[serde(default = "default_downsampled_days")]
+    pub downsampled_days: u64,
+    #[serde(default = "default_compact_interval")]
+    pub compact_interval_minutes: u64, // how often the compaction task runs (0 = never)
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            raw_days: default_raw_days(),
+            downsample_minutes: default_downsample_minutes(),
+            downsampled_days: default_downsampled_days(),
+            compact_interval_minutes: default_compact_interval(),
+        }
+    }
+}
+
+fn default_raw_days() -> u64 {
+    7
+}
+
+fn default_downsample_minutes() -> u64 {
+    5
+}
+
+fn default_downsampled_days() -> u64 {
+    90
+}
+
+fn default_compact_interval() -> u64 {
+    60
+}
+
+fn default_storage_path() -> String {
+    "data/readings.db".to_string()
+}
+
+/// optional influxdb exporter, see influx.rs.
+/// set bucket (+ org, token) for influxdb 2.x, or database for 1.x.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct InfluxConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub url: String,              // e.g. http://192.168.7.1:8086
+    #[serde(default)]
+    pub org: String,
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub database: String,         // 1.x only
+    #[serde(default = "default_influx_measurement")]
+    pub measurement: String,
+    #[serde(default = "default_influx_batch")]
+    pub batch_size: usize,        // points per write request
+    #[serde(default = "default_influx_flush")]
+    pub flush_seconds: u64,       // write at least this often while points are waiting
+    #[serde(default = "default_influx_buffer")]
+    pub max_buffer: usize,        // points kept while influx is unreachable
+}
+
+impl Default for InfluxConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            org: String::new(),
+            bucket: String::new(),
+            token: String::new(),
+            database: String::new(),
+            measurement: default_influx_measurement(),
+            batch_size: default_influx_batch(),
+            flush_seconds: default_influx_flush(),
+            max_buffer: default_influx_buffer(),
+        }
+    }
+}
+
+fn default_influx_measurement() -> String {
+    "sensor".to_string()
+}
+
+fn default_influx_batch() -> usize {
+    500
+}
+
+fn default_influx_flush() -> u64 {
+    5
+}
+
+fn default_influx_buffer() -> usize {
+    50_000
+}
+
+/// generic http forwarder ([[sinks]]), see sink.rs.
+/// url and header values may use {node_id}, {name}, {date} and {env:VAR}.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct HttpSinkConfig {
+    pub name: String,
+    pub url: String,              // e.g. "https://ingest.example.com/v1/{node_id}"
+    #[serde(default)]
+    pub headers: std::collections::BTreeMap<String, String>, // e.g. Authorization = "Bearer {env:INGEST_TOKEN}"
+    #[serde(default)]
+    pub sensors: String,          // sensor_id glob (empty = all)
+    #[serde(default = "default_influx_batch")]
+    pub batch_size: usize,        // readings per request
+    #[serde(default = "default_influx_flush")]
+    pub flush_seconds: u64,       // post at least this often while readings are waiting
+    #[serde(default = "default_influx_buffer")]
+    pub max_buffer: usize,        // readings kept while the endpoint is unreachable
+    #[serde(default = "default_sink_timeout")]
+    pub timeout_seconds: u64,
+    #[serde(default)]
+    pub retry: SinkRetryConfig,
+}
+
+/// retry policy of one http sink
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct SinkRetryConfig {
+    #[serde(default = "default_sink_attempts")]
+    pub max_attempts: u32,        // tries per batch before it is dropped (0 = until the buffer overflows)
+    #[serde(default = "default_sink_backoff")]
+    pub initial_backoff_ms: u64,  // doubled after every failure
+    #[serde(default = "default_sink_max_backoff")]
+    pub max_backoff_seconds: u64,
+}
+
+impl Default for SinkRetryConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_sink_attempts(),
+            initial_backoff_ms: default_sink_backoff(),
+            max_backoff_seconds: default_sink_max_backoff(),
+        }
+    }
+}
+
+fn default_sink_timeout() -> u64 {
+    10
+}
+
+fn default_sink_attempts() -> u32 {
+    5
+}
+
+fn default_sink_backoff() -> u64 {
+    1000
+}
+
+fn default_sink_max_backoff() -> u64 {
+    60
+}
+
+/// sanity checks on polled and pushed readings, see validate.rs.
+/// ranges and max steps come from [schema.*].
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ValidationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sensors: String,          // sensor_id glob (empty = all)
+    #[serde(default = "default_validation_action")]
+    pub action: String,           // "drop" the reading or "mark" the bad fields
+}
+
+impl Default for ValidationConfig {
+    fn default() -> Self {
+        Self { enabled: false, sensors: String::new(), action: default_validation_action() }
+    }
+}
+
+fn default_validation_action() -> String {
+    "drop".to_string()
+}
+
+/// streaming anomaly detection on merged readings, see anomaly.rs.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AnomalyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub sensors: String,          // sensor_id glob (empty = all)
+    #[serde(default)]
+    pub fields: Vec<String>,      // data fields to watch (empty = every numeric field)
+    #[serde(default = "default_anomaly_alpha")]
+    pub alpha: f64,               // ewma weight of a new sample
+    #[serde(default = "default_anomaly_threshold")]
+    pub threshold: f64,           // z-score that counts as anomalous
+    #[serde(default = "default_anomaly_warmup")]
+    pub warmup: u64,              // samples before a field can be flagged
+    #[serde(default = "default_anomaly_min_stddev")]
+    pub min_stddev: f64,          // deviation floor for flat signals
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensors: String::new(),
+            fields: Vec::new(),
+            alpha: default_anomaly_alpha(),
+            threshold: default_anomaly_threshold(),
+            warmup: default_anomaly_warmup(),
+            min_stddev: default_anomaly_min_stddev(),
+        }
+    }
+}
+
+fn default_anomaly_alpha() -> f64 {
+    0.1
+}
+
+fn default_anomaly_threshold() -> f64 {
+    4.0
+}
+
+fn default_anomaly_warmup() -> u64 {
+    20
+}
+
+fn default_anomaly_min_stddev() -> f64 {
+    0.5
+}
+
+/// alert rules evaluated on the hub, see alerts.rs.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AlertsConfig {
+    #[serde(default = "default_alerts_interval")]
+    pub interval_seconds: u64,    // how often rules are evaluated
+    #[serde(default = "default_anomaly_severity")]
+    pub anomaly_severity: String, // severity of anomaly alerts ("" = don't alert on anomalies)
+    #[serde(default)]
+    pub rules: Vec<AlertRuleConfig>,
+}
+
+impl Default for AlertsConfig {
+    fn default() -> Self {
+        Self {
+            interval_seconds: default_alerts_interval(),
+            anomaly_severity: default_anomaly_severity(),
+            rules: Vec::new(),
+        }
+    }
+}
+
+/// one [[alerts.rules]] entry
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AlertRuleConfig {
+    pub name: String,
+    pub expr: String,             // e.g. "bme680.iaq_score > 150 for 5m"
+    #[serde(default = "default_anomaly_severity")]
+    pub severity: String,         // info | warning | critical
+    #[serde(default)]
+    pub actions: Vec<AlertAction>,
+}
+
+/// what a rule does when its alert fires (and undoes when it resolves).
+/// `node` picks the node whose hardware is used; empty = the node the
+/// alerting sensor belongs to.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum AlertAction {
+    Buzz {
+        #[serde(default = "default_alert_buzz")]
+        pattern: String,          // single | triple | long
+        #[serde(default)]
+        node: String,
+    },
+    SetLed {
+        index: u8,
+        r: u8,
+        g: u8,
+        b: u8,
+        #[serde(default)]
+        node: String,
+    },
+    Fan {
+        #[serde(default)]
+        node: String,
+    },
+    Webhook {
+        url: String,              // POSTed the alert json on fire and resolve
+    },
+}
+
+fn default_alert_buzz() -> String {
+    "triple".to_string()
+}
+
+fn default_alerts_interval() -> u64 {
+    5
+}
+
+fn default_anomaly_severity() -> String {
+    "warning".to_string()
+}
+
+/// periodic summary reports, see reports.rs.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct ReportsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_report_at")]
+    pub at: String,               // local time of day the report is built ("HH:MM")
+    #[serde(default = "default_report_period")]
+    pub period_hours: u64,        // time span covered by one report
+    #[serde(default = "default_true")]
+    pub deliver: bool,            // also send it through the [notify] channels
+}
+
+impl Default for ReportsConfig {
+    fn default() -> Self {
+        Self { enabled: false, at: default_report_at(), period_hours: default_report_period(), deliver: true }
+    }
+}
+
+fn default_report_at() -> String {
+    "07:00".to_string()
+}
+
+fn default_report_period() -> u64 {
+    24
+}
+
+/// append-only log of buzzer / fan / led actions, see audit.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AuditConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_audit_path")]
+    pub path: String,             // json lines file, relative to the working directory
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self { enabled: true, path: default_audit_path() }
+    }
+}
+
+fn default_audit_path() -> String {
+    "data/audit.log".to_string()
+}
+
+/// alert / node notifications, see notify.rs.
+/// a channel is used once its credentials are set.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct NotifyConfig {
+    #[serde(default = "default_anomaly_severity")]
+    pub min_severity: String,     // info | warning | critical
+    #[serde(default = "default_true")]
+    pub resolved: bool,           // also report resolved alerts
+    #[serde(default = "default_true")]
+    pub nodes: bool,              // report nodes going offline / coming back
+    #[serde(default = "default_notify_group")]
+    pub group_seconds: u64,       // events within this window go out as one message
+    #[serde(default = "default_notify_cooldown")]
+    pub cooldown_seconds: u64,    // repeats of the same alert transition are suppressed this long
+    #[serde(default = "default_notify_max_per_hour")]
+    pub max_per_hour: usize,      // messages per hour, the rest is dropped (and counted)
+    #[serde(default)]
+    pub slack: SlackConfig,
+    #[serde(default)]
+    pub telegram: TelegramConfig,
+    #[serde(default)]
+    pub email: EmailConfig,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        Self {
+            min_severity: default_anomaly_severity(),
+            resolved: true,
+            nodes: true,
+            group_seconds: default_notify_group(),
+            cooldown_seconds: default_notify_cooldown(),
+            max_per_hour: default_notify_max_per_hour(),
+            slack: SlackConfig::default(),
+            telegram: TelegramConfig::default(),
+            email: EmailConfig::default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct SlackConfig {
+    #[serde(default)]
+    pub webhook_url: String,      // incoming webhook
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Default, JsonSchema)]
+pub struct TelegramConfig {
+    #[serde(default)]
+    pub bot_token: String,
+    #[serde(default)]
+    pub chat_id: String,
+}
+
+/// smtp notifier (needs the "email" feature)
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "email"), allow(dead_code))]
+pub struct EmailConfig {
+    #[serde(default)]
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    #[serde(default = "default_smtp_tls")]
+    pub tls: String,              // starttls | tls | none
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default)]
+    pub from: String,
+    #[serde(default)]
+    pub to: Vec<String>,
+}
+
+impl Default for EmailConfig {
+    fn default() -> Self {
+        Self {
+            smtp_host: String::new(),
+            smtp_port: default_smtp_port(),
+            tls: default_smtp_tls(),
+            username: String::new(),
+            password: String::new(),
+            from: String::new(),
+            to: Vec::new(),
+        }
+    }
+}
+
+fn default_notify_group() -> u64 {
+    30
+}
+
+fn default_notify_cooldown() -> u64 {
+    600
+}
+
+fn default_notify_max_per_hour() -> usize {
+    20
+}
+
+fn default_smtp_port() -> u16 {
+    587
+}
+
+fn default_smtp_tls() -> String {
+    "starttls".to_string()
+}
+
+/// scheduled parquet export of the readings store (needs the "parquet" feature).
+/// one file per complete utc day, see export.rs.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+pub struct ExportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_export_dir")]
+    pub dir: String,
+    #[serde(default = "default_export_backfill")]
+    pub backfill_days: u64,       // how many past days are checked for a missing file
+    #[serde(default = "default_export_interval")]
+    pub check_interval_minutes: u64,
+    #[serde(default)]
+    pub s3: S3Config,
+}
+
+impl Default for ExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_export_dir(),
+            backfill_days: default_export_backfill(),
+            check_interval_minutes: default_export_interval(),
+            s3: S3Config::default(),
+        }
+    }
+}
+
+/// optional upload of exported files to an s3-compatible bucket
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+#[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+pub struct S3Config {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub endpoint: String,         // e.g. https://s3.eu-central-1.amazonaws.com or http://minio:9000
+    #[serde(default)]
+    pub bucket: String,
+    #[serde(default = "default_s3_region")]
+    pub region: String,
+    #[serde(default)]
+    pub prefix: String,           // object key prefix, e.g. "edge/"
+    #[serde(default)]
+    pub access_key: String,
+    #[serde(default)]
+    pub secret_key: String,
+}
+
+impl Default for S3Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: String::new(),
+            bucket: String::new(),
+            region: default_s3_region(),
+            prefix: String::new(),
+            access_key: String::new(),
+            secret_key: String::new(),
+        }
+    }
+}
+
+fn default_export_dir() -> String {
+    "data/export".to_string()
+}
+
+fn default_export_backfill() -> u64 {
+    7
+}
+
+fn default_export_interval() -> u64 {
+    60
+}
+
+fn default_s3_region() -> String {
+    "us-east-1".to_string()
+}
+
+/// unit / display metadata of one reading field ([schema.<field>] or
+/// [schema."<sensor>.<field>"]), see schema.rs
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct FieldSchema {
+    #[serde(default)]
+    pub unit: String,             // e.g. "°C", "%", "hPa"
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min: Option<f64>,         // valid range, for dashboards / validation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_step: Option<f64>,    // largest plausible change between two readings
+}
+
+/// hub-side aggregation rule ([[aggregations]]), see aggregate.rs.
+/// produces a synthetic reading "cluster:{name}".
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct AggregationRule {
+    pub name: String,
+    pub op: String,               // avg | min | max | sum | count | offline
+    #[serde(default)]
+    pub field: String,            // data field to aggregate (not used by offline)
+    #[serde(default)]
+    pub sensors: String,          // sensor_id glob, e.g. "*:dht22" (empty = all)
+    #[serde(default = "default_max_age")]
+    pub max_age_seconds: u64,     // offline: node is offline after this long without readings
+}
+
+fn default_max_age() -> u64 {
+    60
+}
+
+/// derived metric rule ([[derived]]), see derived.rs.
+/// adds the listed metrics to the data of matching readings.
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct DerivedRule {
+    #[serde(default)]
+    pub sensors: String,          // sensor_id glob, e.g. "*:dht22" (empty = all)
+    pub metrics: Vec<String>,     // dew_point | heat_index | absolute_humidity
+    #[serde(default = "default_temperature_field")]
+    pub temperature_field: String, // °C input
+    #[serde(default = "default_humidity_field")]
+    pub humidity_field: String,   // %RH input
+}
+
+fn default_temperature_field() -> String {
+    "temperature".to_string()
+}
+
+fn default_humidity_field() -> String {
+    "humidity".to_string()
+}
+
+/// per-sensor correction of one field ([[calibration]]), see calibrate.rs.
+/// value = raw * scale + offset
+#[derive(Debug, Deserialize, Serialize, Clone, JsonSchema)]
+pub struct CalibrationRule {
+    pub sensors: String,          // sensor_id glob, e.g. "pizero:dht22"
+    pub field: String,            // data field to correct, e.g. "temperature"
+    #[serde(default)]
+    pub offset: f64,
+    #[serde(default = "default_calibration_scale")]
+    pub scale: f64,
+}
+
+fn default_calibration_scale() -> f64 {
+    1.0
+}
+
+impl HostConfig {
+    /// plugins.{name}.enabled; "pi4-monitor" looks up [plugins.pi4_monitor]
+    pub fn plugin_enabled(&self, name: &str) -> bool {
+        self.plugins.get(&name.replace('-', "_")).is_some_and(|p| p.enabled)
+    }
+
+    /// Load configuration from file (environment overrides applied)
+    pub fn load<P: AsRef<Path>>(path: P) -> anyhow::Result<Self> {
+        Self::load_toml(path, None)?
+            .try_into()
+            .map_err(|e| anyhow::anyhow!("Failed to parse config: {}", e))
+    }
+    
+    /// Load configuration from file with an overlay (toml text) merged on top.
+    /// overlay keys win; tables are merged recursively.
+    pub fn load_with_overlay<P: AsRef<Path>>(path: P, overlay: &str) -> anyhow::Result<Self> {
+        Self::load_toml(path, Some(overlay))?
+            .try_into()
+            .map_err(|e| anyhow::anyhow!("Config invalid after overlay: {}", e))
+    }
+
+    /// The config file as toml, with the overlay, environment overrides and
+    /// ${NAME} secrets applied but not yet checked against the schema
+    pub fn load_toml<P: AsRef<Path>>(path: P, overlay: Option<&str>) -> anyhow::Result<toml::Value> {
+        let mut config = Self::load_unresolved(path.as_ref(), overlay)?;
+        crate::secrets::resolve(&mut config, path.as_ref())?;
+        Ok(config)
+    }
+
+    /// load_toml without resolving ${NAME} secrets
+    pub fn load_unresolved<P: AsRef<Path>>(path: P, overlay: Option<&str>) -> anyhow::Result<toml::Value> {
+        let mut config = read_layer(path.as_ref(), &mut Vec::new(), &mut Vec::new())?;
+        if let Some(profile_path) = Self::profile_file(path.as_ref()) {
+            let profile = read_layer(&profile_path, &mut Vec::new(), &mut Vec::new())
+                .map_err(|e| anyhow::anyhow!("Profile: {}", e))?;
+            merge_toml(&mut config, profile);
+        }
+        if let Some(overlay) = overlay {
+            let overlay: toml::Value = toml::from_str(overlay)
+                .map_err(|e| anyhow::anyhow!("Failed to parse config overlay: {}", e))?;
+            merge_toml(&mut config, overlay);
+        }
+
+        apply_env_overrides(&mut config)?;
+        Ok(config)
+    }
+
+    /// every file a load of `path` reads: the file, its profile and their includes
+    pub fn config_files(path: &Path) -> Vec<std::path::PathBuf> {
+        let mut files = Vec::new();
+        for layer in std::iter::once(path.to_path_buf()).chain(Self::profile_file(path)) {
+            let _ = read_layer(&layer, &mut Vec::new(), &mut files);
+        }
+        files
+    }
+
+    /// host.{profile}.toml next to `path` when a --profile is selected
+    /// (same format as `path`: host.yaml -> host.{profile}.yaml)
+    pub fn profile_file(path: &Path) -> Option<std::path::PathBuf> {
+        let profile = PROFILE.get()?;
+        let stem = path.file_stem()?.to_string_lossy();
+        let extension = path.extension().map(|e| e.to_string_lossy()).unwrap_or("toml".into());
+        Some(path.with_file_name(format!("{}.{}.{}", stem, profile, extension)))
+    }
+
+    /// First config file that exists in the usual locations
+    /// (host.toml, then host.yaml / host.yml / host.json, in config/ then ../config/)
+    pub fn find_config_file() -> Option<std::path::PathBuf> {
+        let dirs = [std::path::PathBuf::from("config"), std::path::PathBuf::from("..").join("config")];
+        dirs.iter()
+            .flat_map(|dir| CONFIG_FILES.iter().map(move |file| dir.join(file)))
+            .find(|p| p.exists())
+    }
+
+    /// Load with default fallback
+    pub fn load_or_default() -> Self {
+        let overrides: Vec<String> = env_overrides().into_iter().map(|o| o.name).collect();
+        if !overrides.is_empty() {
+            println!("[CONFIG] Environment overrides: {}", overrides.join(", "));
+        }
+        if let Some(path) = Self::find_config_file() {
+            match Self::load(&path) {
+                Ok(config) => {
+                    match Self::profile_file(&path) {
+                        Some(profile) => println!("[CONFIG] Loaded from {} + {}", path.display(), profile.display()),
+                        None => println!("[CONFIG] Loaded from {}", path.display()),
+                    }
+                    return config;
+                }
+                Err(e) => {
+                    println!("[CONFIG] Warning: Failed to load {}: {}", path.display(), e);
+                }
+            }
+        }
+        
+        println!("[CONFIG] Warning: No config file found - using defaults");
+        if !overrides.is_empty() {
+            println!("[CONFIG] Warning: Environment overrides need a config file to apply to - ignored");
+        }
+        Self::default()
+    }
+    
+    /// Print configuration summary
+    pub fn print_summary(&self) {
+        println!("┌─────────────────────────────────────────┐");
+        println!("│           HOST CONFIGURATION            │");
+        println!("├─────────────────────────────────────────┤");
+        println!("│ Role: {}                             │", self.cluster.role);
+        println!("│ Node ID: {}                          │", self.cluster.node_id);
+        println!("│ Poll Interval: {}s                      │", self.polling.interval_seconds);
+        println!("│ Log Level: {}                        │", self.logging.level);
+        if let Some(profile) = profile() {
+            println!("│ Profile: {}                          │", profile);
+        }
+        if !self.api.enabled {
+            println!("│ Web Server: off (headless)              │");
+        }
+        println!("├─────────────────────────────────────────┤");
+        let mut plugins: Vec<_> = self.plugins.iter().collect();
+        plugins.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, entry) in plugins {
+            let state = if entry.enabled { "enabled" } else { "disabled" };
+            println!("│ Plugin {}: {}                     │", name, state);
+        }
+        if !self.plugins.is_empty() {
+            println!("├─────────────────────────────────────────┤");
+        }
+    }
+}
+
+/// json schema of HostConfig (wasi-host config-schema). a toml / yaml config
+/// converted to json validates against it; defaults are filled in by serde.
+pub fn json_schema() -> String {
+    let schema = schemars::schema_for!(HostConfig);
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+/// config file names looked for, in order
+const CONFIG_FILES: [&str; 4] = ["host.toml", "host.yaml", "host.yml", "host.json"];
+
+/// a config file by its extension: .yaml / .yml, .json, anything else is toml
+fn parse_config(path: &Path, content: &str) -> anyhow::Result<toml::Value> {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_ascii_lowercase());
+    let value = match extension.as_deref() {
+        Some("yaml") | Some("yml") => serde_yaml::from_str(content)?,
+        Some("json") => serde_json::from_str(content)?,
+        _ => toml::from_str(content)?,
+    };
+    Ok(value)
+}
+
+/// deepest chain of includes followed
+const MAX_INCLUDE_DEPTH: usize = 8;
+
+/// one config file with its `include = [...]` files merged underneath it,
+/// in order. `chain` is the files including this one (cycle check), `files`
+/// collects every file read.
+fn read_layer(path: &Path, chain: &mut Vec<std::path::PathBuf>, files: &mut Vec<std::path::PathBuf>) -> anyhow::Result<toml::Value> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow::anyhow!("Failed to read {}: {}", path.display(), e))?;
+    files.push(path.to_path_buf());
+    let mut layer = parse_config(path, &content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse {}: {}", path.display(), e))?;
+    let includes = match layer.as_table_mut().and_then(|t| t.remove("include")) {
+        None => return Ok(layer),
+        Some(toml::Value::Array(includes)) => includes,
+        Some(_) => anyhow::bail!("{}: include must be a list of files", path.display()),
+    };
+    if chain.len() >= MAX_INCLUDE_DEPTH {
+        anyhow::bail!("{}: includes nested deeper than {}", path.display(), MAX_INCLUDE_DEPTH);
+    }
+
+    chain.push(std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()));
+    let dir = path.parent().unwrap_or(Path::new("."));
+    let mut merged = toml::Value::Table(toml::Table::new());
+    for include in includes {
+        let Some(name) = include.as_str() else {
+            anyhow::bail!("{}: include entries must be file names, not {}", path.display(), include);
+        };
+        let include = dir.join(name);
+        let canonical = std::fs::canonicalize(&include).unwrap_or_else(|_| include.clone());
+        if chain.contains(&canonical) {
+            anyhow::bail!("{}: include of {} loops back to itself", path.display(), include.display());
+        }
+        merge_toml(&mut merged, read_layer(&include, chain, files)?);
+    }
+    chain.pop();
+
+    // the including file's own keys win
+    merge_toml(&mut merged, layer);
+    Ok(merged)
+}
+
+/// --profile of this process, set once at startup
+static PROFILE: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+/// select host.{name}.toml as the profile layer of every config load
+pub fn set_profile(name: &str) -> anyhow::Result<()> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("profile '{}' is not a name (letters, digits, - and _)", name);
+    }
+    PROFILE.set(name.to_string()).map_err(|_| anyhow::anyhow!("profile already set"))
+}
+
+/// the selected profile, if any
+pub fn profile() -> Option<&'static str> {
+    PROFILE.get().map(String::as_str)
+}
+
+/// recursively merge `overlay` into `base` (tables merge, everything else replaces)
+pub fn merge_toml(base: &mut toml::Value, overlay: toml::Value) {
+    match (base, overlay) {
+        (toml::Value::Table(base), toml::Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_toml(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// prefix of the config override environment variables
+const ENV_PREFIX: &str = "HOST__";
+
+/// one HOST__... variable
+struct EnvOverride {
+    name: String,
+    /// toml key path, "HOST__POLLING__INTERVAL_SECONDS" -> ["polling", "interval_seconds"]
+    path: Vec<String>,
+    value: String,
+}
+
+/// the HOST__... variables of this process, sorted by name
+fn env_overrides() -> Vec<EnvOverride> {
+    let mut overrides: Vec<EnvOverride> = std::env::vars_os()
+        .filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?)))
+        .filter_map(|(name, value)| {
+            let path = name.strip_prefix(ENV_PREFIX)?.split("__").map(|k| k.to_ascii_lowercase()).collect();
+            Some(EnvOverride { name, path, value })
+        })
+        .collect();
+    overrides.sort_by(|a, b| a.name.cmp(&b.name));
+    overrides
+}
+
+/// set every HOST__... variable in a parsed config
+fn apply_env_overrides(config: &mut toml::Value) -> anyhow::Result<()> {
+    for o in env_overrides() {
+        let Some((key, tables)) = o.path.split_last().filter(|_| !o.path.iter().any(String::is_empty)) else {
+            anyhow::bail!("{}: expected {}SECTION__KEY (empty name between \"__\")", o.name, ENV_PREFIX);
+        };
+        let not_a_table = |name: &str| anyhow::anyhow!("{}: '{}' is not a table in the config", o.name, name);
+        let mut table = config.as_table_mut().ok_or_else(|| not_a_table("(root)"))?;
+        for name in tables {
+            table = table
+                .entry(name.clone())
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| not_a_table(name))?;
+        }
+        let value = match table.get(key) {
+            Some(toml::Value::String(_)) => toml::Value::String(o.value),
+            _ => parse_literal(&o.value),
+        };
+        table.insert(key.clone(), value);
+    }
+    Ok(())
+}
+
+/// "10" -> 10, "true" -> true, "[1, 2]" -> [1, 2], anything else -> a string
+fn parse_literal(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("v = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("v"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+fn default_units() -> String {
+    "metric".to_string()
+}
+
+impl Default for HostConfig {
+    fn default() -> Self {
+        Self {
+            polling: PollingConfig { interval_seconds: 5, budget_ms: 0, missed_ticks: default_missed_ticks(), backoff: BackoffConfig::default() },
+            sensors: SensorsConfig {
                 dht22: Dht22Config { gpio_pin: 4 },
                 bme680: Bme680Config { i2c_address: "0x77".to_string() },
             },
-            leds: LedConfig { count: 11, gpio_pin: 18, brightness: 50 },
+            leds: LedConfig { count: 11, gpio_pin: 18, brightness: 50, gamma: default_led_gamma(), min_sync_ms: 0 },
             buzzer: BuzzerConfig { gpio_pin: 17 },
             fan: FanConfig::default(),
-            logging: LoggingConfig { level: "info".to_string(), show_sensor_data: true },
+            actuators: ActuatorConfig::default(),
+            logging: LoggingConfig {
+                level: "info".to_string(),
+                show_sensor_data: true,
+                buffer_size: default_log_buffer(),
+                overflow: default_log_overflow(),
+                file: LogFileConfig::default(),
+            },
+            telemetry: TelemetryConfig::default(),
+            crash: CrashConfig::default(),
+            selftest: SelfTestConfig::default(),
+            config_reload: ConfigReloadConfig::default(),
+            api: ApiConfig::default(),
+            http_client: HttpClientConfig::default(),
             cluster: ClusterConfig::default(),
             plugins: PluginsConfig::default(),
+            plugin_watchdog: PluginWatchdogConfig::default(),
+            plugin_profiler: PluginProfilerConfig::default(),
+            mqtt: MqttConfig::default(),
+            kafka: KafkaConfig::default(),
+            coap: CoapConfig::default(),
+            aggregations: Vec::new(),
+            derived: Vec::new(),
+            calibration: Vec::new(),
+            state: StateConfig::default(),
+            storage: StorageConfig::default(),
+            influx: InfluxConfig::default(),
+            sinks: Vec::new(),
+            export: ExportConfig::default(),
+            validation: ValidationConfig::default(),
+            anomaly: AnomalyConfig::default(),
+            alerts: AlertsConfig::default(),
+            notify: NotifyConfig::default(),
+            reports: ReportsConfig::default(),
+            audit: AuditConfig::default(),
+            schema: Default::default(),
+            units: default_units(),
         }
     }
 }
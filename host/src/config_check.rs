@@ -0,0 +1,225 @@
+//! ==============================================================================
+//! config_check.rs - `wasi-host validate-config`: check a config before deploying it
+//! ==============================================================================
+//!
+//! purpose:
+//!     a typo in host.toml shows up on the device, after the deploy, as a
+//!     host that won't start - and a spoke that won't start is a spoke
+//!     nobody can reach to fix it. `wasi-host validate-config [path]` runs
+//!     everything startup would reject, plus what startup can't see:
+//!         parse       toml syntax and types, with the key ("in `polling.
+//!                     interval_seconds`") - HOST__ overrides applied
+//!         pins        two drivers on one gpio pin, a pin outside the
+//!                     header (0-27), a driver on the i2c pins (2 / 3)
+//!                     while the bme680 uses the bus (pins.rs)
+//!         i2c         sensors.bme680.i2c_address not a 7-bit address
+//!         plugins     an enabled plugin without plugins/{name}/{name}.wasm
+//!         cluster     role, transport, encoding, a spoke without hubs,
+//!                     tls files that don't exist
+//!         rules       alerts, aggregations, derived fields, validation,
+//!                     reports, units, logging.level, notify.min_severity,
+//!                     polling (interval, missed_ticks), plugin_profiler,
+//!                     leds.gamma, actuators.queue_depth (the startup
+//!                     checks)
+//!     every problem is printed with the offending key; the exit status is
+//!     non-zero when there is an error (warnings alone pass).
+//!
+//! relationships:
+//!     - used by: main.rs (validate-config subcommand), provision.rs (the
+//!       setup page refuses a config with errors)
+//!     - reads: config.rs (HostConfig), runtime.rs (plugin paths), pins.rs (pin claims)
+//!     - runs: the check_config / validate functions startup uses
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Problem {
+    severity: Severity,
+    /// the offending key, e.g. "sensors.dht22.gpio_pin"
+    key: String,
+    message: String,
+}
+
+/// check a config file and print the problems; false when there is an error
+pub fn run(path: Option<PathBuf>) -> anyhow::Result<bool> {
+    let Some(path) = path.or_else(HostConfig::find_config_file) else {
+        anyhow::bail!("no config file given and no host.toml / .yaml / .json found in config/ or ../config/");
+    };
+    let problems = match HostConfig::load(&path) {
+        Ok(config) => check(&config, Path::new("..")),
+        Err(e) => vec![error("", format!("{:#}", e))],
+    };
+
+    let errors = problems.iter().filter(|p| p.severity == Severity::Error).count();
+    let warnings = problems.len() - errors;
+    for problem in &problems {
+        let mark = match problem.severity {
+            Severity::Error => "❌",
+            Severity::Warning => "⚠️ ",
+        };
+        match problem.key.as_str() {
+            "" => println!("{} {}", mark, problem.message),
+            key => println!("{} {}: {}", mark, key, problem.message),
+        }
+    }
+    match errors {
+        0 => println!("✅ {} is valid ({} warning(s))", path.display(), warnings),
+        n => println!("{}: {} error(s), {} warning(s)", path.display(), n, warnings),
+    }
+    Ok(errors == 0)
+}
+
+/// the errors `validate-config` would report for `config`, as "key: message"
+pub fn errors(config: &HostConfig, base: &Path) -> Vec<String> {
+    check(config, base)
+        .into_iter()
+        .filter(|p| p.severity == Severity::Error)
+        .map(|p| match p.key.as_str() {
+            "" => p.message,
+            key => format!("{}: {}", key, p.message),
+        })
+        .collect()
+}
+
+fn error(key: &str, message: String) -> Problem {
+    Problem { severity: Severity::Error, key: key.to_string(), message }
+}
+
+fn warning(key: &str, message: String) -> Problem {
+    Problem { severity: Severity::Warning, key: key.to_string(), message }
+}
+
+/// every problem of a parsed config; `base` is where plugins/ lives
+fn check(config: &HostConfig, base: &Path) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    check_pins(config, &mut problems);
+    check_i2c(config, &mut problems);
+    check_plugins(config, base, &mut problems);
+    check_cluster(config, &mut problems);
+
+    // what startup itself rejects
+    let startup: [(&str, anyhow::Result<()>); 13] = [
+        ("logging.level", crate::loglayer::parse_level(&config.logging.level).map(|_| ())),
+        ("aggregations", crate::aggregate::validate(&config.aggregations)),
+        ("derived", crate::derived::validate(&config.derived)),
+        ("validation", crate::validate::check_config(&config.validation)),
+        ("reports", crate::reports::check_config(&config.reports)),
+        ("units", crate::units::check_config(&config.units)),
+        ("alerts", crate::alerts::check_config(&config.alerts)),
+        ("notify", crate::notify::check_config(&config.notify)),
+        ("cluster.encoding", crate::codec::Encoding::from_name(&config.cluster.encoding).map(|_| ())),
+        ("leds.gamma", crate::hal::check_config(&config.leds)),
+        ("actuators.queue_depth", crate::actuators::check_config(&config.actuators)),
+        ("plugin_profiler", crate::profiler::check_config(&config.plugin_profiler)),
+        ("polling", crate::poll_timing::check_config(&config.polling)),
+    ];
+    for (key, result) in startup {
+        if let Err(e) = result {
+            problems.push(error(key, format!("{:#}", e)));
+        }
+    }
+    if config.polling.budget_ms > config.polling.interval_seconds * 1000 {
+        problems.push(warning(
+            "polling.budget_ms",
+            format!("{}ms is longer than the poll interval, a slow plugin stretches the cycle", config.polling.budget_ms),
+        ));
+    }
+    if config.plugin_watchdog.enabled && config.plugin_watchdog.call_timeout_ms < crate::watchdog::TICK.as_millis() as u64 {
+        problems.push(error(
+            "plugin_watchdog.call_timeout_ms",
+            format!("must be at least {} (every call would be killed)", crate::watchdog::TICK.as_millis()),
+        ));
+    }
+    if config.fan.threshold_off >= config.fan.threshold_on {
+        problems.push(warning(
+            "fan.threshold_off",
+            format!("{} is not below threshold_on ({}), the fan would flap", config.fan.threshold_off, config.fan.threshold_on),
+        ));
+    }
+    problems
+}
+
+/// gpio pins claimed twice or off the header, as startup checks them (pins.rs)
+fn check_pins(config: &HostConfig, problems: &mut Vec<Problem>) {
+    for conflict in crate::pins::conflicts(crate::pins::claims(config)) {
+        let message = match &conflict.claim.plugin {
+            None => conflict.message,
+            Some(plugin) => format!("{} - startup disables the {} plugin", conflict.message, plugin),
+        };
+        problems.push(error(&conflict.claim.key, message));
+    }
+}
+
+fn check_i2c(config: &HostConfig, problems: &mut Vec<Problem>) {
+    if !config.plugin_enabled("bme680") {
+        return;
+    }
+    let key = "sensors.bme680.i2c_address";
+    let address = &config.sensors.bme680.i2c_address;
+    // parsed like runtime.rs does, which falls back to 0x77 on anything else
+    let parsed = match address.strip_prefix("0x") {
+        Some(hex) => u8::from_str_radix(hex, 16).ok(),
+        None => address.parse::<u8>().ok(),
+    };
+    match parsed {
+        None => problems.push(error(key, format!("'{}' is not an address (expected e.g. \"0x77\")", address))),
+        Some(a) if !(0x08..=0x77).contains(&a) => {
+            problems.push(error(key, format!("0x{:02x} is outside the 7-bit range 0x08-0x77", a)))
+        }
+        Some(a) if a != 0x76 && a != 0x77 => {
+            problems.push(warning(key, format!("a bme680 answers at 0x76 or 0x77, not 0x{:02x}", a)))
+        }
+        Some(_) => {}
+    }
+}
+
+/// enabled plugins need their component on disk, runtime.rs fails startup without it
+fn check_plugins(config: &HostConfig, base: &Path, problems: &mut Vec<Problem>) {
+    let mut enabled: Vec<&str> = config.plugins.iter().filter(|(_, p)| p.enabled).map(|(key, _)| key.as_str()).collect();
+    enabled.sort();
+    for key in enabled {
+        let name = key.replace('_', "-");
+        let path = crate::runtime::plugin_path(base, &name);
+        if !crate::runtime::KNOWN_PLUGINS.contains(&name.as_str()) {
+            problems.push(warning(&format!("plugins.{}.enabled", key), format!("the {} plugin is not loaded by this host", key)));
+        } else if !path.exists() {
+            problems.push(error(&format!("plugins.{}.enabled", key), format!("{} not found", path.display())));
+        }
+    }
+}
+
+fn check_cluster(config: &HostConfig, problems: &mut Vec<Problem>) {
+    let cluster = &config.cluster;
+    if let Err(e) = crate::role::Role::from_name(&cluster.role) {
+        problems.push(error("cluster.role", e.to_string()));
+    }
+    if !matches!(cluster.transport.as_str(), "http" | "websocket" | "nats") {
+        problems.push(error("cluster.transport", format!("unknown transport '{}' (http, websocket or nats)", cluster.transport)));
+    }
+    if cluster.node_id.is_empty() {
+        problems.push(error("cluster.node_id", "is empty".to_string()));
+    }
+    if cluster.role == "spoke" && cluster.push_targets().is_empty() && cluster.transport != "nats" {
+        problems.push(warning("cluster.hub_url", "a spoke without hub_url / hub_urls keeps its readings to itself".to_string()));
+    }
+    if cluster.tls.enabled {
+        let tls = &cluster.tls;
+        let files = [("cluster.tls.ca_cert", &tls.ca_cert), ("cluster.tls.cert", &tls.cert), ("cluster.tls.key", &tls.key)];
+        for (key, file) in files {
+            if !Path::new(file).exists() {
+                problems.push(error(key, format!("'{}' not found", file)));
+            }
+        }
+        if cluster.role == "spoke" && !tls.pinned_hub_cert.is_empty() && !Path::new(&tls.pinned_hub_cert).exists() {
+            problems.push(error("cluster.tls.pinned_hub_cert", format!("'{}' not found", tls.pinned_hub_cert)));
+        }
+    }
+}
@@ -0,0 +1,292 @@
+//! ==============================================================================
+//! config_init.rs - `wasi-host init-config`: write a commented sample host.toml
+//! ==============================================================================
+//!
+//! purpose:
+//!     most of host.toml is optional and only discoverable by reading
+//!     config.rs. `wasi-host init-config [path]` writes every section with
+//!     its default values and the comments from config.rs:
+//!         - values: HostConfig::default() serialized, with a [plugins.*]
+//!           table (disabled) for every plugin the runtime knows
+//!         - comments: the doc comments of the config structs and the
+//!           trailing comments of their fields, read from config.rs itself
+//!           (compiled in), so the sample can't drift from the schema
+//!         - lists of tables that are empty by default ([[sinks]],
+//!           [[alerts.rules]], ...) are written commented out, field by field
+//!     an existing file is not overwritten without --force; "-" prints to
+//!     stdout.
+//!
+//! relationships:
+//!     - used by: main.rs (init-config subcommand), provision.rs (the
+//!       host.toml written by the first-run setup page)
+//!     - reads: config.rs (HostConfig, its source), runtime.rs (KNOWN_PLUGINS)
+//!
+//! ==============================================================================
+
+use crate::config::{HostConfig, PluginEntry};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::path::Path;
+
+/// the config structs, as written
+const SOURCE: &str = include_str!("config.rs");
+
+/// one field of a config struct
+struct Field {
+    name: String,
+    /// type as written, e.g. "Vec<AggregationRule>"
+    ty: String,
+    comment: Vec<String>,
+}
+
+/// a type alias of config.rs, e.g. PluginsConfig = HashMap<String, PluginEntry>
+struct Alias {
+    doc: Vec<String>,
+    ty: String,
+}
+
+/// a config struct: its doc comment and fields in declaration order
+struct Struct {
+    doc: Vec<String>,
+    fields: Vec<Field>,
+}
+
+/// write the sample config to `path` ("-" = stdout)
+pub fn run(path: &Path, force: bool) -> anyhow::Result<()> {
+    let mut config = HostConfig::default();
+    for name in crate::runtime::KNOWN_PLUGINS {
+        config.plugins.insert(name.replace('-', "_"), PluginEntry::default());
+    }
+    // no default identity, but a sample should pass validate-config
+    config.cluster.role = "hub".to_string();
+    config.cluster.node_id = "node-1".to_string();
+    let sample = render(&config, "`wasi-host init-config`")?;
+    if path == Path::new("-") {
+        print!("{}", sample);
+        return Ok(());
+    }
+    if path.exists() && !force {
+        anyhow::bail!("{} exists, pass --force to overwrite it", path.display());
+    }
+    std::fs::write(path, sample)?;
+    println!("✅ Wrote {} - check it with `wasi-host validate-config {}`", path.display(), path.display());
+    Ok(())
+}
+
+/// `config` as a commented host.toml, every section included; `origin` names what wrote it
+pub fn render(config: &HostConfig, origin: &str) -> anyhow::Result<String> {
+    let values = toml::Value::try_from(config)?;
+    let (structs, aliases) = parse_source(SOURCE);
+
+    let mut out = String::new();
+    out.push_str("# ==============================================================================\n");
+    let _ = writeln!(out, "# WASI Host Configuration - generated by {}", origin);
+    out.push_str("# ==============================================================================\n");
+    out.push_str("# Every section with its default value. Commented-out sections are lists\n");
+    out.push_str("# that are empty by default. Delete what you don't change.\n\n");
+    let sample = Sample { structs: &structs, aliases: &aliases };
+    sample.table(&mut out, "HostConfig", values.as_table(), "");
+    Ok(out)
+}
+
+struct Sample<'a> {
+    structs: &'a HashMap<String, Struct>,
+    aliases: &'a HashMap<String, Alias>,
+}
+
+/// what a field holds, by its written type
+enum Kind<'a> {
+    /// another config struct: a [section]
+    Section(&'a str),
+    /// a list of config structs: [[section]]
+    List(&'a str),
+    /// a map of config structs: [section.{key}]
+    Map(&'a str),
+    /// anything else: key = value
+    Value,
+}
+
+impl Sample<'_> {
+    /// the fields of struct `name` with the values in `table`, under the table header `path`
+    fn table(&self, out: &mut String, name: &str, table: Option<&toml::Table>, path: &str) {
+        let Some(def) = self.structs.get(name) else { return };
+        let empty = toml::Table::new();
+        let table = table.unwrap_or(&empty);
+
+        // plain values first, toml puts everything after a [header] into that table
+        for field in &def.fields {
+            let value = table.get(&field.name);
+            match (self.kind(&field.ty), value) {
+                (Kind::Value, Some(value)) => {
+                    comment(out, &field.comment);
+                    let _ = writeln!(out, "{} = {}", field.name, literal(value, &field.ty));
+                }
+                (Kind::Value, None) => {
+                    comment(out, &field.comment);
+                    let _ = writeln!(out, "# {} =", field.name);
+                }
+                (Kind::List(_), Some(toml::Value::Array(items))) if !items.is_empty() => {
+                    comment(out, &field.comment);
+                    let _ = writeln!(out, "{} = {}", field.name, literal(&toml::Value::Array(items.clone()), &field.ty));
+                }
+                _ => {}
+            }
+        }
+
+        for field in &def.fields {
+            let path = join(path, &field.name);
+            let value = table.get(&field.name);
+            match self.kind(&field.ty) {
+                Kind::Section(inner) => {
+                    out.push('\n');
+                    self.doc(out, inner, &field.comment);
+                    let _ = writeln!(out, "[{}]", path);
+                    self.table(out, inner, value.and_then(|v| v.as_table()), &path);
+                }
+                Kind::Map(inner) => {
+                    let entries = value.and_then(|v| v.as_table()).filter(|t| !t.is_empty());
+                    let comments = match field.comment.is_empty() {
+                        true => self.aliases.get(&field.ty).map(|a| a.doc.as_slice()).unwrap_or_default(),
+                        false => field.comment.as_slice(),
+                    };
+                    match entries {
+                        Some(entries) => {
+                            let mut keys: Vec<&String> = entries.keys().collect();
+                            keys.sort();
+                            out.push('\n');
+                            comment(out, comments);
+                            for (i, key) in keys.into_iter().enumerate() {
+                                if i > 0 {
+                                    out.push('\n');
+                                }
+                                let _ = writeln!(out, "[{}.{}]", path, key);
+                                self.table(out, inner, entries[key].as_table(), &format!("{}.{}", path, key));
+                            }
+                        }
+                        None => self.example(out, inner, comments, &format!("[{}.name]", path)),
+                    }
+                }
+                Kind::List(inner) if value.and_then(|v| v.as_array()).is_none_or(|a| a.is_empty()) => {
+                    self.example(out, inner, &field.comment, &format!("[[{}]]", path));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// a commented-out example of a struct that has no default entries
+    fn example(&self, out: &mut String, name: &str, comments: &[String], header: &str) {
+        out.push('\n');
+        self.doc(out, name, comments);
+        let _ = writeln!(out, "# {}", header);
+        let Some(def) = self.structs.get(name) else { return };
+        for field in &def.fields {
+            match field.comment.first() {
+                Some(c) => {
+                    let _ = writeln!(out, "# {} =    # {}", field.name, c);
+                }
+                None => {
+                    let _ = writeln!(out, "# {} =", field.name);
+                }
+            }
+        }
+    }
+
+    /// the comment on the field, else the doc comment of its struct
+    fn doc(&self, out: &mut String, name: &str, comments: &[String]) {
+        match comments.is_empty() {
+            true => comment(out, self.structs.get(name).map(|s| s.doc.as_slice()).unwrap_or_default()),
+            false => comment(out, comments),
+        }
+    }
+
+    fn kind<'t>(&'t self, ty: &'t str) -> Kind<'t> {
+        let ty = self.aliases.get(ty).map(|a| a.ty.as_str()).unwrap_or(ty);
+        let ty = ty.rsplit("::").next().unwrap_or(ty);
+        let generic = |prefix: &str| {
+            let inner = ty.strip_prefix(prefix)?.strip_suffix('>')?;
+            Some(inner.rsplit([',', ' ', ':']).next().unwrap_or(inner))
+        };
+        if let Some(inner) = generic("Vec<").filter(|t| self.structs.contains_key(*t)) {
+            return Kind::List(inner);
+        }
+        if let Some(inner) = generic("HashMap<").or_else(|| generic("BTreeMap<")).filter(|t| self.structs.contains_key(*t)) {
+            return Kind::Map(inner);
+        }
+        match self.structs.contains_key(ty) {
+            true => Kind::Section(ty),
+            false => Kind::Value,
+        }
+    }
+}
+
+fn comment(out: &mut String, lines: &[String]) {
+    for line in lines {
+        let _ = writeln!(out, "# {}", line);
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        _ => format!("{}.{}", path, key),
+    }
+}
+
+/// a value as toml; f32 fields print as written (0.1, not 0.10000000149011612)
+fn literal(value: &toml::Value, ty: &str) -> String {
+    match value {
+        toml::Value::Float(f) if ty == "f32" => {
+            let short = (*f as f32).to_string();
+            match short.contains(['.', 'e', 'i', 'N']) {
+                true => short,
+                false => format!("{}.0", short),
+            }
+        }
+        other => other.to_string(),
+    }
+}
+
+/// the structs and type aliases of config.rs
+fn parse_source(source: &str) -> (HashMap<String, Struct>, HashMap<String, Alias>) {
+    let mut structs = HashMap::new();
+    let mut aliases = HashMap::new();
+    let mut doc: Vec<String> = Vec::new();
+    let mut current: Option<(String, Struct)> = None;
+
+    for line in source.lines() {
+        let line = line.trim();
+        if let Some(text) = line.strip_prefix("///") {
+            doc.push(text.trim().to_string());
+        } else if line.starts_with("#[") || line.is_empty() && current.is_some() {
+            // attributes between a doc comment and its item
+        } else if let Some(rest) = line.strip_prefix("pub struct ") {
+            let name = rest.trim_end_matches(['{', ' ']).to_string();
+            current = Some((name, Struct { doc: std::mem::take(&mut doc), fields: Vec::new() }));
+        } else if let Some(rest) = line.strip_prefix("pub type ") {
+            if let Some((name, ty)) = rest.trim_end_matches(';').split_once(" = ") {
+                let alias = Alias { doc: std::mem::take(&mut doc), ty: ty.trim().to_string() };
+                aliases.insert(name.trim().to_string(), alias);
+            }
+            doc.clear();
+        } else if line == "}" {
+            if let Some((name, def)) = current.take() {
+                structs.insert(name, def);
+            }
+            doc.clear();
+        } else if let (Some((_, def)), Some(rest)) = (current.as_mut(), line.strip_prefix("pub ")) {
+            let (decl, trailing) = match rest.split_once("//") {
+                Some((decl, trailing)) => (decl, Some(trailing.trim().to_string())),
+                None => (rest, None),
+            };
+            if let Some((name, ty)) = decl.trim().trim_end_matches(',').split_once(':') {
+                let mut comment = std::mem::take(&mut doc);
+                comment.extend(trailing);
+                def.fields.push(Field { name: name.trim().to_string(), ty: ty.trim().to_string(), comment });
+            }
+        } else {
+            doc.clear();
+        }
+    }
+    (structs, aliases)
+}
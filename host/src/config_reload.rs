@@ -0,0 +1,374 @@
+//! ==============================================================================
+//! config_reload.rs - apply host.toml edits without a restart ([config_reload])
+//! ==============================================================================
+//!
+//! purpose:
+//!     a restart to change the poll interval drops the led and fan state and
+//!     leaves a gap in the readings. host.toml is watched instead; when it,
+//!     its --profile layer or a file it includes changes, it is parsed again
+//!     (with the spoke's hub overlay and the HOST__ environment overrides,
+//!     like at startup) and every changed key is logged as "key: old → new".
+//!     applied right away:
+//!         polling.interval_seconds     from the next poll cycle on
+//!         logging.level                host log and RUST_LOG-less tracing
+//!         alerts.rules, alerts.anomaly_severity
+//!                                      rule thresholds (alerts.rs)
+//!         plugins.{name}.enabled       the plugin is loaded / unloaded
+//!     any other changed key is listed as needing a restart. a file that
+//!     doesn't parse, or that validate-config would reject (unknown log
+//!     level, broken alert rule, a pin used twice), is logged with every
+//!     error and the running settings stay.
+//!
+//! effective config:
+//!     GET /api/config/effective serves the config the host runs with -
+//!     startup's (defaults, profile, hub overlay and environment applied),
+//!     with the keys a reload applied live - and the keys changed in the
+//!     file that wait for a restart. secret-looking keys, urls with
+//!     credentials and every value resolved from a ${NAME} reference read
+//!     "<redacted>".
+//!
+//! last boot:
+//!     the effective config is kept (redacted) in last_boot_config.toml next
+//!     to host.toml. at startup every key that differs from the last boot is
+//!     logged like a reload, so "it behaves differently since yesterday"
+//!     starts from what actually changed. a secret that changed between boots
+//!     doesn't show, its stored value is "<redacted>".
+//!
+//! watching:
+//!     the directory is watched rather than the file, editors replace
+//!     host.toml by renaming a temp file over it. events are debounced so
+//!     one save reloads once.
+//!
+//! relationships:
+//!     - used by: main.rs (spawned after startup, init at startup,
+//!       /api/config/effective)
+//!     - reads: config.rs (load_unresolved), node_config.rs (cached hub overlay),
+//!       secrets.rs (${NAME} references), config_check.rs (errors before applying)
+//!     - applies to: poll_timing.rs (interval), loglayer.rs (level),
+//!       alerts.rs (rules), runtime.rs (plugin load / unload)
+//!
+//! ==============================================================================
+
+use crate::alerts::AlertEngine;
+use crate::config::{HostConfig, PluginEntry};
+use crate::log_msg;
+use crate::poll_timing::PollTiming;
+use crate::runtime::WasmRuntime;
+use ::notify::{RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// quiet time after the last file event before reloading
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// changed values longer than this are logged as "changed" only
+const MAX_LOGGED_VALUE: usize = 60;
+
+/// what the config of the last boot is kept in, next to host.toml
+const LAST_BOOT_FILE: &str = "last_boot_config.toml";
+
+/// the config the host runs with and the last one read from the file
+struct Effective {
+    running: HostConfig,
+    file: HostConfig,
+    /// the files as written, before ${NAME} was resolved - startup's and the
+    /// last one read; a value that came from a reference is redacted
+    references: [toml::Value; 2],
+}
+
+static EFFECTIVE: Mutex<Option<Effective>> = Mutex::new(None);
+
+/// what a reload can change in the running host
+pub struct Targets {
+    pub timing: Arc<PollTiming>,
+    pub alerts: Arc<AlertEngine>,
+    pub runtime: WasmRuntime,
+}
+
+/// remember the startup config; log the keys that changed since the last boot
+pub fn init(config: &HostConfig, hub_overlay: bool) {
+    let source = HostConfig::find_config_file();
+    let unresolved = source
+        .as_deref()
+        .and_then(|path| load(path, hub_overlay).ok())
+        .unwrap_or_else(|| toml::Value::Table(Default::default()));
+    let references = [unresolved.clone(), unresolved];
+    let current = redacted(config, &references);
+    *EFFECTIVE.lock().unwrap() = Some(Effective { running: config.clone(), file: config.clone(), references });
+    let Some(path) = source.map(|p| p.with_file_name(LAST_BOOT_FILE)) else { return };
+    let Ok(current) = current else { return };
+    let last: Option<toml::Value> = std::fs::read_to_string(&path).ok().and_then(|text| toml::from_str(&text).ok());
+    if let Some(last) = last {
+        let changes = diff(&last, &current);
+        if !changes.is_empty() {
+            log_msg(&format!("🔧 [CONFIG] {} key(s) changed since the last boot:", changes.len()));
+            for (key, (before, after)) in &changes {
+                log_msg(&format!("🔧 [CONFIG]   {}: {}", key, describe(key, before, after)));
+            }
+        }
+    }
+    let saved = toml::to_string_pretty(&current).map_err(std::io::Error::other).and_then(|text| std::fs::write(&path, text));
+    if let Err(e) = saved {
+        log_msg(&format!("⚠️ [CONFIG] Could not save {}: {}", path.display(), e));
+    }
+}
+
+/// the running config (redacted) and the changed keys that wait for a restart
+pub fn effective() -> anyhow::Result<(toml::Value, Vec<String>)> {
+    let effective = EFFECTIVE.lock().unwrap();
+    let Some(effective) = effective.as_ref() else { anyhow::bail!("the config is not loaded yet") };
+    let running = redacted(&effective.running, &effective.references)?;
+    let pending = diff(&running, &redacted(&effective.file, &effective.references)?).into_keys().collect();
+    Ok((running, pending))
+}
+
+/// the config as toml with secret-looking values, and values resolved from
+/// a ${NAME} reference in any of `references`, replaced
+fn redacted(config: &HostConfig, references: &[toml::Value]) -> anyhow::Result<toml::Value> {
+    let mut value = toml::Value::try_from(config)?;
+    let references: Vec<&toml::Value> = references.iter().collect();
+    redact(&mut value, false, &references);
+    Ok(value)
+}
+
+/// `references` are the same position in the unresolved files
+fn redact(value: &mut toml::Value, secret: bool, references: &[&toml::Value]) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                let references: Vec<&toml::Value> = references.iter().filter_map(|r| r.get(key.as_str())).collect();
+                redact(value, crate::crash::is_secret(key), &references);
+            }
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                let references: Vec<&toml::Value> = references.iter().filter_map(|r| r.get(i)).collect();
+                redact(item, secret, &references);
+            }
+        }
+        toml::Value::String(text) if !text.is_empty() && (secret || crate::crash::has_credentials(text) || referenced(references)) => {
+            *text = "<redacted>".to_string();
+        }
+        // a number or flag can come from a reference too
+        other if !matches!(other, toml::Value::String(_)) && referenced(references) => {
+            *other = toml::Value::String("<redacted>".to_string());
+        }
+        _ => {}
+    }
+}
+
+/// the unresolved value was (or contained) a ${NAME} reference
+fn referenced(references: &[&toml::Value]) -> bool {
+    references.iter().any(|r| r.as_str().is_some_and(|text| text.contains("${")))
+}
+
+/// watch the config file (no-op with config_reload.enabled = false)
+pub fn spawn(config: &HostConfig, hub_overlay: bool, targets: Targets) -> anyhow::Result<()> {
+    if !config.config_reload.enabled {
+        return Ok(());
+    }
+    let Some(path) = HostConfig::find_config_file() else { return Ok(()) };
+    let mut current = load(&path, hub_overlay)?;
+    // host.toml, the --profile layer and the files they include
+    let files = HostConfig::config_files(&path);
+    let names: Vec<_> = files.iter().filter_map(|p| p.file_name().map(|n| n.to_os_string())).collect();
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = ::notify::recommended_watcher(move |event: ::notify::Result<::notify::Event>| {
+        let Ok(event) = event else { return };
+        let ours = event.paths.iter().any(|p| p.file_name().is_some_and(|n| names.iter().any(|name| name == n)));
+        if ours && (event.kind.is_modify() || event.kind.is_create()) {
+            let _ = tx.send(());
+        }
+    })?;
+    let mut dirs: Vec<&Path> = files
+        .iter()
+        .map(|f| f.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new(".")))
+        .collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    }
+    log_msg(&format!("[CONFIG] Watching {} for changes", path.display()));
+
+    tokio::spawn(async move {
+        let _watcher = watcher;
+        while rx.recv().await.is_some() {
+            // a save is often several events (truncate, write, rename)
+            while let Ok(Some(())) = tokio::time::timeout(DEBOUNCE, rx.recv()).await {}
+            match load(&path, hub_overlay) {
+                Ok(new) => {
+                    if apply(&path, &current, &new, &targets).await {
+                        current = new;
+                    }
+                }
+                Err(e) => log_msg(&format!("❌ [CONFIG] {} not applied: {:#}", path.display(), e)),
+            }
+        }
+    });
+    Ok(())
+}
+
+/// the config as startup saw it: file, hub overlay (spokes), environment
+fn load(path: &Path, hub_overlay: bool) -> anyhow::Result<toml::Value> {
+    let overlay = match hub_overlay {
+        true => crate::node_config::cached_overlay(path),
+        false => None,
+    };
+    // secrets stay unresolved here so the change log never shows them
+    HostConfig::load_unresolved(path, overlay.as_deref())
+}
+
+/// apply the changes between two versions of the config; false if the new one was rejected
+async fn apply(path: &Path, old: &toml::Value, new: &toml::Value, targets: &Targets) -> bool {
+    let mut resolved = new.clone();
+    if let Err(e) = crate::secrets::resolve(&mut resolved, path) {
+        log_msg(&format!("❌ [CONFIG] {} not applied: {:#}", path.display(), e));
+        return false;
+    }
+    let config: HostConfig = match resolved.try_into() {
+        Ok(config) => config,
+        Err(e) => {
+            log_msg(&format!("❌ [CONFIG] {} not applied: {}", path.display(), e));
+            return false;
+        }
+    };
+    let changes = diff(old, new);
+    if changes.is_empty() {
+        return true;
+    }
+
+    // check before touching anything, so a rejected file changes nothing -
+    // whatever validate-config or startup would refuse is refused here too
+    let errors = crate::config_check::errors(&config, Path::new(".."));
+    if !errors.is_empty() {
+        log_msg(&format!("❌ [CONFIG] {} not applied:", path.display()));
+        for error in &errors {
+            log_msg(&format!("❌ [CONFIG]   {}", error));
+        }
+        return false;
+    }
+    let level = match changes.contains_key("logging.level") {
+        true => crate::loglayer::parse_level(&config.logging.level).ok(),
+        false => None,
+    };
+
+    log_msg(&format!("🔧 [CONFIG] {} changed:", path.display()));
+    let mut restart = Vec::new();
+    for (key, (before, after)) in &changes {
+        log_msg(&format!("🔧 [CONFIG]   {}: {}", key, describe(key, before, after)));
+        if !is_live(key) {
+            restart.push(key.as_str());
+        }
+    }
+
+    if let Some(level) = level {
+        crate::loglayer::set_level(level);
+    }
+    if changes.contains_key("polling.interval_seconds") {
+        targets.timing.set_interval(Duration::from_secs(config.polling.interval_seconds));
+    }
+    if changes.keys().any(|key| key.starts_with("alerts.rules") || key == "alerts.anomaly_severity") {
+        if let Err(e) = targets.alerts.set_rules(&config.alerts) {
+            log_msg(&format!("❌ [CONFIG] Alert rules not replaced: {:#}", e));
+        }
+    }
+    for key in changes.keys() {
+        if let Some(plugin) = key.strip_prefix("plugins.").and_then(|k| k.strip_suffix(".enabled")) {
+            toggle_plugin(&targets.runtime, plugin, new).await;
+        }
+    }
+    if !restart.is_empty() {
+        log_msg(&format!("⚠️ [CONFIG] Restart needed to apply: {}", restart.join(", ")));
+    }
+    if let Some(effective) = EFFECTIVE.lock().unwrap().as_mut() {
+        apply_live(&mut effective.running, &config);
+        effective.file = config;
+        effective.references[1] = new.clone();
+    }
+    true
+}
+
+/// copy the keys is_live lists from the file into the running config
+fn apply_live(running: &mut HostConfig, file: &HostConfig) {
+    running.polling.interval_seconds = file.polling.interval_seconds;
+    running.logging.level = file.logging.level.clone();
+    running.alerts.rules = file.alerts.rules.clone();
+    running.alerts.anomaly_severity = file.alerts.anomaly_severity.clone();
+    for (name, entry) in running.plugins.iter_mut() {
+        entry.enabled = file.plugins.get(name).is_some_and(|p| p.enabled);
+    }
+    for (name, entry) in &file.plugins {
+        running.plugins.entry(name.clone()).or_insert_with(|| PluginEntry { enabled: entry.enabled, ..Default::default() });
+    }
+}
+
+/// keys a reload applies without a restart
+fn is_live(key: &str) -> bool {
+    matches!(key, "polling.interval_seconds" | "logging.level" | "alerts.anomaly_severity")
+        || key.starts_with("alerts.rules")
+        || (key.starts_with("plugins.") && key.ends_with(".enabled"))
+}
+
+/// plugins.dht22.enabled / plugins.pi4_monitor.enabled -> load or unload "dht22" / "pi4-monitor"
+async fn toggle_plugin(runtime: &WasmRuntime, key: &str, config: &toml::Value) {
+    let name = key.replace('_', "-");
+    let enabled = config.get("plugins").and_then(|p| p.get(key)).and_then(|p| p.get("enabled")).and_then(|e| e.as_bool());
+    let result = match enabled {
+        Some(true) => runtime.reload_plugin(&name).await.map(|()| "loaded"),
+        _ => runtime.unload_plugin(&name).await.map(|()| "unloaded"),
+    };
+    match result {
+        Ok(what) => log_msg(&format!("🔄 [RELOAD] Plugin '{}' {}", name, what)),
+        Err(e) => log_msg(&format!("❌ [RELOAD] Plugin '{}': {:#}", name, e)),
+    }
+}
+
+/// changed keys with their (old, new) values, None = not set
+fn diff(old: &toml::Value, new: &toml::Value) -> BTreeMap<String, (Option<String>, Option<String>)> {
+    let (mut before, mut after) = (BTreeMap::new(), BTreeMap::new());
+    flatten(old, "", &mut before);
+    flatten(new, "", &mut after);
+    let keys: Vec<String> = before.keys().chain(after.keys()).cloned().collect();
+    keys.into_iter()
+        .filter_map(|key| {
+            let (b, a) = (before.get(&key).cloned(), after.get(&key).cloned());
+            (b != a).then_some((key, (b, a)))
+        })
+        .collect()
+}
+
+/// "section.key" -> value for every leaf; arrays (of tables too) count as one value
+fn flatten(value: &toml::Value, prefix: &str, out: &mut BTreeMap<String, String>) {
+    match value {
+        toml::Value::Table(table) => {
+            for (key, value) in table {
+                let path = match prefix {
+                    "" => key.clone(),
+                    _ => format!("{}.{}", prefix, key),
+                };
+                flatten(value, &path, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.to_string());
+        }
+    }
+}
+
+/// "2 → 5", "set to true", "removed"; secrets and long values only say that they changed
+fn describe(key: &str, before: &Option<String>, after: &Option<String>) -> String {
+    let hidden = key.rsplit('.').next().is_some_and(crate::crash::is_secret)
+        || [before, after].iter().any(|v| v.as_ref().is_some_and(|v| crate::crash::has_credentials(v)))
+        || [before, after].iter().any(|v| v.as_ref().is_some_and(|v| v.len() > MAX_LOGGED_VALUE));
+    match (before, after, hidden) {
+        (_, None, _) => "removed".to_string(),
+        (None, Some(_), true) => "set".to_string(),
+        (None, Some(value), false) => format!("set to {}", value),
+        (Some(_), Some(_), true) => "changed".to_string(),
+        (Some(old), Some(new), false) => format!("{} → {}", old, new),
+    }
+}
@@ -0,0 +1,113 @@
+//! ==============================================================================
+//! derived.rs - derived metrics computed from temperature / humidity readings
+//! ==============================================================================
+//!
+//! purpose:
+//!     raw temperature and relative humidity say little about condensation
+//!     risk or comfort. rules in [[derived]] add computed fields to the data
+//!     of every matching reading, next to the values they were derived from:
+//!         {"temperature": 24.0, "humidity": 60.0,
+//!          "dew_point": 15.79, "heat_index": 24.23, "absolute_humidity": 13.05}
+//!
+//! metrics (temperature in °C, humidity in %RH):
+//!     dew_point          - °C, magnus formula (b = 17.62, c = 243.12)
+//!     heat_index         - °C, noaa rothfusz regression (steadman below 80°F)
+//!     absolute_humidity  - g/m³
+//!
+//! where it runs:
+//!     each poll cycle on the node that owns the sensor (so forwarded, mqtt
+//!     and nats readings carry the fields), and again in
+//!     AppState::merge_readings so a hub with its own rules also enriches
+//!     readings from spokes and devices that don't (e.g. esp32 pushes).
+//!     recomputing is idempotent. `cluster:` readings are never touched.
+//!
+//! relationships:
+//!     - used by: main.rs (poll loop, startup), domain.rs (AppState.derived)
+//!     - reads: config.rs (HostConfig.derived)
+//!
+//! ==============================================================================
+
+use crate::aggregate::{glob_match, CLUSTER_NODE};
+use crate::config::DerivedRule;
+use crate::domain::SensorReading;
+
+const METRICS: [&str; 3] = ["dew_point", "heat_index", "absolute_humidity"];
+
+/// reject rules with an unknown metric at startup
+pub fn validate(rules: &[DerivedRule]) -> anyhow::Result<()> {
+    for rule in rules {
+        if let Some(metric) = rule.metrics.iter().find(|m| !METRICS.contains(&m.as_str())) {
+            anyhow::bail!("derived '{}': unknown metric '{}' (expected one of {:?})", rule.sensors, metric, METRICS);
+        }
+    }
+    Ok(())
+}
+
+/// add the derived fields of every matching rule to `readings`
+pub fn apply(rules: &[DerivedRule], readings: &mut [SensorReading]) {
+    for reading in readings.iter_mut() {
+        if reading.sensor_id.starts_with(&format!("{}:", CLUSTER_NODE)) {
+            continue;
+        }
+        for rule in rules.iter().filter(|rule| glob_match(&rule.sensors, &reading.sensor_id)) {
+            let field = |name: &str| reading.data.get(name).and_then(|v| v.as_f64());
+            let (Some(t), Some(rh)) = (field(&rule.temperature_field), field(&rule.humidity_field)) else {
+                continue;
+            };
+            let Some(data) = reading.data.as_object_mut() else {
+                continue;
+            };
+            for metric in &rule.metrics {
+                let value = match metric.as_str() {
+                    "dew_point" => dew_point(t, rh),
+                    "heat_index" => Some(heat_index(t, rh)),
+                    "absolute_humidity" => Some(absolute_humidity(t, rh)),
+                    _ => None,
+                };
+                if let Some(value) = value.filter(|v| v.is_finite()) {
+                    data.insert(metric.clone(), serde_json::json!((value * 100.0).round() / 100.0));
+                }
+            }
+        }
+    }
+}
+
+/// dew point in °C (None for 0% humidity, where it is undefined)
+fn dew_point(t: f64, rh: f64) -> Option<f64> {
+    const B: f64 = 17.62;
+    const C: f64 = 243.12;
+    if rh <= 0.0 {
+        return None;
+    }
+    let gamma = (rh.min(100.0) / 100.0).ln() + B * t / (C + t);
+    Some(C * gamma / (B - gamma))
+}
+
+/// apparent temperature in °C
+fn heat_index(t: f64, rh: f64) -> f64 {
+    let f = t * 9.0 / 5.0 + 32.0;
+    let simple = 0.5 * (f + 61.0 + (f - 68.0) * 1.2 + rh * 0.094);
+    let hi = if (simple + f) / 2.0 < 80.0 {
+        simple
+    } else {
+        let mut hi = -42.379 + 2.04901523 * f + 10.14333127 * rh
+            - 0.22475541 * f * rh
+            - 0.00683783 * f * f
+            - 0.05481717 * rh * rh
+            + 0.00122874 * f * f * rh
+            + 0.00085282 * f * rh * rh
+            - 0.00000199 * f * f * rh * rh;
+        if rh < 13.0 && (80.0..=112.0).contains(&f) {
+            hi -= (13.0 - rh) / 4.0 * ((17.0 - (f - 95.0).abs()) / 17.0).sqrt();
+        } else if rh > 85.0 && (80.0..=87.0).contains(&f) {
+            hi += (rh - 85.0) / 10.0 * (87.0 - f) / 5.0;
+        }
+        hi
+    };
+    (hi - 32.0) * 5.0 / 9.0
+}
+
+/// water vapour density in g/m³
+fn absolute_humidity(t: f64, rh: f64) -> f64 {
+    6.112 * (17.67 * t / (t + 243.5)).exp() * rh * 2.1674 / (273.15 + t)
+}
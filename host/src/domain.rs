@@ -4,9 +4,258 @@ use serde::{Deserialize, Serialize};
 #[derive(Clone, Default, Serialize, Deserialize)]
 pub struct AppState {
     /// list of all sensor readings from all nodes
-    pub readings: Vec<SensorReading>,
+    pub readings: Readings,
     /// unix timestamp (ms) of last successful update
     pub last_update: u64,
+    /// per-node clock skew (ms, payload minus hub receive time) for nodes
+    /// whose skew exceeds cluster.clock_skew_warn_ms
+    #[serde(default)]
+    pub clock_skew_ms: std::collections::BTreeMap<String, i64>,
+    /// appends merged readings to the sqlite history (None = storage off)
+    #[serde(skip)]
+    pub recorder: Option<crate::storage::Recorder>,
+    /// writes merged readings to influxdb (None = [influx] disabled)
+    #[serde(skip)]
+    pub influx: Option<crate::influx::InfluxExporter>,
+    /// [[sinks]] http forwarders fed with merged readings
+    #[serde(skip)]
+    pub sinks: Vec<crate::sink::HttpSink>,
+    /// produces merged readings to kafka (None = [kafka] disabled)
+    #[cfg(feature = "kafka")]
+    #[serde(skip)]
+    pub kafka: Option<crate::kafka::KafkaProducer>,
+    /// [[derived]] rules applied to every merged batch
+    #[serde(skip)]
+    pub derived: Vec<crate::config::DerivedRule>,
+    /// flags outliers in merged readings (None = [anomaly] disabled)
+    #[serde(skip)]
+    pub anomaly: Option<crate::anomaly::AnomalyDetector>,
+    /// the last readings of every sensor, for plugins (reading-history)
+    #[serde(skip)]
+    pub recent: crate::recent::RecentReadings,
+    /// per-sensor sampling statistics (/api/sensors)
+    #[serde(skip)]
+    pub cadence: crate::cadence::Cadence,
+    /// max count / ttl of `readings` ([state])
+    #[serde(skip)]
+    pub bounds: crate::config::StateConfig,
+}
+
+impl AppState {
+    /// merge readings into state and bump last_update. the newest reading
+    /// of each sensor wins: readings older than the current one (a delayed
+    /// retry, a backfill) go to the history (sqlite / influx / sinks / kafka / recent) but
+    /// never replace the latest view. derived metrics are added to all of
+    /// them, anomaly flags only to the newer ones.
+    /// returns (merged, historical).
+    pub fn merge_readings(&mut self, mut readings: Vec<SensorReading>) -> (usize, usize) {
+        readings.sort_by_key(|r| r.timestamp_ms);
+        crate::derived::apply(&self.derived, &mut readings);
+        let (mut newer, older): (Vec<_>, Vec<_>) = readings.into_iter().partition(|nr| {
+            self.readings
+                .get(&nr.sensor_id)
+                .is_none_or(|r| nr.timestamp_ms >= r.timestamp_ms)
+        });
+        if !older.is_empty() {
+            tracing::debug!("{} out-of-order readings sent to history only", older.len());
+        }
+        if let Some(anomaly) = &self.anomaly {
+            anomaly.observe(&mut newer);
+        }
+        self.cadence.observe(&newer);
+        for batch in [&older, &newer] {
+            if let Some(recorder) = &self.recorder {
+                recorder.record(batch);
+            }
+            if let Some(influx) = &self.influx {
+                influx.export(batch);
+            }
+            for sink in &self.sinks {
+                sink.forward(batch);
+            }
+            #[cfg(feature = "kafka")]
+            if let Some(kafka) = &self.kafka {
+                kafka.publish(batch);
+            }
+            self.recent.record(batch);
+        }
+        let counts = (newer.len(), older.len());
+        if newer.is_empty() {
+            return counts;
+        }
+        for nr in newer {
+            self.readings.upsert(nr);
+        }
+        self.last_update = now_ms();
+        if self.bounds.max_readings > 0 && self.readings.len() > self.bounds.max_readings {
+            self.evict(self.last_update);
+        }
+        counts
+    }
+
+    /// drop readings not updated for state.ttl_seconds, then the oldest
+    /// ones beyond state.max_readings. returns the number evicted.
+    pub fn evict(&mut self, now_ms: u64) -> usize {
+        let ttl_ms = self.bounds.ttl_seconds * 1000;
+        let mut evict: Vec<bool> = self
+            .readings
+            .iter()
+            .map(|r| ttl_ms > 0 && now_ms.saturating_sub(r.timestamp_ms) > ttl_ms)
+            .collect();
+        let max = self.bounds.max_readings;
+        let kept = evict.iter().filter(|e| !**e).count();
+        if max > 0 && kept > max {
+            // oldest first among the survivors, keeping the api order intact
+            let mut by_age: Vec<usize> = (0..self.readings.len()).filter(|&i| !evict[i]).collect();
+            by_age.sort_by_key(|&i| self.readings[i].timestamp_ms);
+            for i in by_age.into_iter().take(kept - max) {
+                evict[i] = true;
+            }
+        }
+        let mut flags = evict.into_iter();
+        let mut evicted = 0;
+        self.readings.retain(|r| {
+            let evict = flags.next().unwrap_or(false);
+            if evict {
+                self.recent.forget_sensor(&r.sensor_id);
+                self.cadence.forget_sensor(&r.sensor_id);
+                evicted += 1;
+            }
+            !evict
+        });
+        evicted
+    }
+
+    /// flag readings older than `max_age_ms` as stale. they stay in the
+    /// latest view until a fresh reading replaces them.
+    /// returns the number of readings newly flagged.
+    pub fn mark_stale(&mut self, now_ms: u64, max_age_ms: u64) -> usize {
+        let mut marked = 0;
+        for reading in self.readings.iter_mut() {
+            if reading.quality != Some(Quality::Stale) && now_ms.saturating_sub(reading.timestamp_ms) > max_age_ms {
+                reading.quality = Some(Quality::Stale);
+                marked += 1;
+            }
+        }
+        marked
+    }
+
+    /// remove every reading (and the skew record) of one node.
+    /// returns the number of readings removed.
+    pub fn remove_node(&mut self, node_id: &str) -> usize {
+        let prefix = format!("{}:", node_id);
+        let before = self.readings.len();
+        self.readings.retain(|r| !r.sensor_id.starts_with(&prefix));
+        self.clock_skew_ms.remove(node_id);
+        self.recent.forget_node(node_id);
+        self.cadence.forget_node(node_id);
+        before - self.readings.len()
+    }
+}
+
+/// the latest reading of every sensor, in the order sensors first reported,
+/// with an index by sensor_id: a merge finds and replaces a sensor's
+/// reading in O(1) instead of scanning every sensor of every node under the
+/// state's write lock. reads go through the slice (Deref); serialized as
+/// the plain array it used to be (/api/readings, pull mode, snapshots).
+#[derive(Clone, Default, Debug)]
+pub struct Readings {
+    list: Vec<SensorReading>,
+    index: std::collections::HashMap<String, usize>,
+}
+
+impl Readings {
+    pub fn get(&self, sensor_id: &str) -> Option<&SensorReading> {
+        self.index.get(sensor_id).map(|&i| &self.list[i])
+    }
+
+    /// replace the sensor's reading, or append it for a new sensor
+    pub fn upsert(&mut self, reading: SensorReading) {
+        match self.index.get(&reading.sensor_id) {
+            Some(&i) => self.list[i] = reading,
+            None => {
+                self.index.insert(reading.sensor_id.clone(), self.list.len());
+                self.list.push(reading);
+            }
+        }
+    }
+
+    /// the readings, mutable - but not their sensor_id, which is the key
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, SensorReading> {
+        self.list.iter_mut()
+    }
+
+    /// keep the readings `keep` says so, in order
+    pub fn retain(&mut self, keep: impl FnMut(&SensorReading) -> bool) {
+        self.list.retain(keep);
+        self.reindex();
+    }
+
+    pub fn into_vec(self) -> Vec<SensorReading> {
+        self.list
+    }
+
+    fn reindex(&mut self) {
+        self.index = self.list.iter().enumerate().map(|(i, r)| (r.sensor_id.clone(), i)).collect();
+    }
+}
+
+impl std::ops::Deref for Readings {
+    type Target = [SensorReading];
+
+    fn deref(&self) -> &[SensorReading] {
+        &self.list
+    }
+}
+
+/// a sensor listed twice (an old snapshot, a spoke's state) keeps its last reading
+impl From<Vec<SensorReading>> for Readings {
+    fn from(readings: Vec<SensorReading>) -> Self {
+        let mut keyed = Readings::default();
+        for reading in readings {
+            keyed.upsert(reading);
+        }
+        keyed
+    }
+}
+
+impl Serialize for Readings {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.list.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Readings {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Vec::<SensorReading>::deserialize(deserializer).map(Readings::from)
+    }
+}
+
+/// evict readings past state.ttl_seconds once a minute (the count limit
+/// is enforced on every merge)
+pub fn spawn_eviction(state: std::sync::Arc<tokio::sync::RwLock<AppState>>) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            ticker.tick().await;
+            let mut app = state.write().await;
+            if app.bounds.ttl_seconds == 0 {
+                return;
+            }
+            let evicted = app.evict(now_ms());
+            if evicted > 0 {
+                crate::log_msg(&format!("🗑️ [STATE] Evicted {} readings not updated for {}s", evicted, app.bounds.ttl_seconds));
+            }
+        }
+    });
+}
+
+/// current unix time in milliseconds
+pub fn now_ms() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64
 }
 
 /// a generic sensor reading
@@ -24,4 +273,177 @@ pub struct SensorReading {
     /// - {"temperature": 22.5, "humidity": 45.0}
     /// - {"cpu_temp": 55.0, "ram_used": 1024, "uptime": 3600}
     pub data: serde_json::Value,
+
+    /// where the reporting node lives (cluster.metadata of that node).
+    /// attached by the node itself, so it travels with pushes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<NodeMetadata>,
+
+    /// how far the value can be trusted. plugin readings start out "ok",
+    /// devices may push their own, validation marks glitches and the hub
+    /// marks readings that stopped updating. None for older nodes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quality: Option<Quality>,
+}
+
+/// trust level of a reading
+#[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Quality {
+    /// measured and plausible
+    Ok,
+    /// not updated for cluster.stale_after_seconds - an old value
+    Stale,
+    /// interpolated / computed by the device rather than measured
+    Estimated,
+    /// failed validation (validation.action = "mark") or reported faulty
+    SensorError,
+}
+
+/// body of POST /push: one spoke batch with a unique id so the hub can
+/// acknowledge it and drop replays (keyed by node_id + batch_id)
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PushBatch {
+    pub batch_id: String,
+    pub node_id: String,
+    pub readings: Vec<SensorReading>,
+}
+
+impl PushBatch {
+    pub fn new(node_id: &str, readings: Vec<SensorReading>) -> Self {
+        Self { batch_id: uuid::Uuid::new_v4().to_string(), node_id: node_id.to_string(), readings }
+    }
+}
+
+/// what /push accepts: a batch envelope, or the bare readings array older
+/// spokes and the pizero service still send (never deduplicated)
+pub enum PushBody {
+    Batch(PushBatch),
+    Legacy(Vec<SensorReading>),
+}
+
+/// picked by the body's shape (map or array) and decoded straight into the
+/// readings. #[serde(untagged)] would first copy the whole body into an
+/// intermediate tree to try the variants on - a second allocation of every
+/// reading on the hub's busiest endpoint.
+impl<'de> Deserialize<'de> for PushBody {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::value::{MapAccessDeserializer, SeqAccessDeserializer};
+
+        struct Shape;
+
+        impl<'de> serde::de::Visitor<'de> for Shape {
+            type Value = PushBody;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                f.write_str("a push batch or an array of readings")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(self, map: A) -> Result<PushBody, A::Error> {
+                PushBatch::deserialize(MapAccessDeserializer::new(map)).map(PushBody::Batch)
+            }
+
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, seq: A) -> Result<PushBody, A::Error> {
+                Vec::deserialize(SeqAccessDeserializer::new(seq)).map(PushBody::Legacy)
+            }
+        }
+
+        deserializer.deserialize_any(Shape)
+    }
+}
+
+/// hub reply to a batch push
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct PushAck {
+    pub batch_id: String,
+    /// true when the batch had already been accepted (a retry/replay)
+    pub duplicate: bool,
+}
+
+/// hub reply to POST /push/backfill
+#[derive(Clone, Serialize, Deserialize, Debug)]
+pub struct BackfillAck {
+    pub batch_id: String,
+    pub duplicate: bool,
+    /// readings newer than the hub's current ones, merged into the latest view
+    pub merged: usize,
+    /// older readings written to the history only
+    pub historical: usize,
+}
+
+/// descriptive node tags from [cluster.metadata]
+#[derive(Clone, Serialize, Deserialize, Debug, Default, PartialEq, schemars::JsonSchema)]
+pub struct NodeMetadata {
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub location: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub room: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub rack: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl NodeMetadata {
+    pub fn is_empty(&self) -> bool {
+        *self == NodeMetadata::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(sensor_id: &str, timestamp_ms: u64) -> SensorReading {
+        SensorReading {
+            sensor_id: sensor_id.into(),
+            timestamp_ms,
+            data: serde_json::json!({ "temperature": 21.0 }),
+            metadata: None,
+            quality: None,
+        }
+    }
+
+    fn state(max_readings: usize, ttl_seconds: u64, readings: &[(&str, u64)]) -> AppState {
+        let mut app = AppState { bounds: crate::config::StateConfig { max_readings, ttl_seconds }, ..Default::default() };
+        for (id, ts) in readings {
+            app.readings.upsert(reading(id, *ts));
+        }
+        app
+    }
+
+    fn ids(app: &AppState) -> Vec<&str> {
+        app.readings.iter().map(|r| r.sensor_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_evict_ttl() {
+        let mut app = state(0, 60, &[("pi4:dht22", 10_000), ("pi4:bme680", 100_000), ("zero:dht22", 30_000)]);
+        // 120s: dht22 readings are older than 60s, bme680 is 20s old
+        assert_eq!(app.evict(120_000), 2);
+        assert_eq!(ids(&app), ["pi4:bme680"]);
+        assert!(app.readings.get("pi4:dht22").is_none());
+    }
+
+    #[test]
+    fn test_evict_oldest_first_keeps_api_order() {
+        let mut app = state(2, 0, &[("b", 3_000), ("a", 1_000), ("d", 4_000), ("c", 2_000)]);
+        assert_eq!(app.evict(5_000), 2);
+        assert_eq!(ids(&app), ["b", "d"]);
+    }
+
+    #[test]
+    fn test_evict_ttl_before_max() {
+        // the expired reading goes, then the oldest survivor beyond max_readings
+        let mut app = state(2, 10, &[("old", 1_000), ("a", 95_000), ("b", 96_000), ("c", 97_000)]);
+        assert_eq!(app.evict(100_000), 2);
+        assert_eq!(ids(&app), ["b", "c"]);
+    }
+
+    #[test]
+    fn test_evict_within_bounds_keeps_everything() {
+        let mut app = state(3, 60, &[("a", 90_000), ("b", 95_000)]);
+        assert_eq!(app.evict(100_000), 0);
+        assert_eq!(ids(&app), ["a", "b"]);
+    }
 }
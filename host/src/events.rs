@@ -0,0 +1,77 @@
+//! ==============================================================================
+//! events.rs - in-process event bus feeding the alerting pipeline
+//! ==============================================================================
+//!
+//! purpose:
+//!     detectors (anomaly.rs, ...) report noteworthy things as events instead
+//!     of only logging them. consumers subscribe to the broadcast channel;
+//!     the newest RECENT_EVENTS are also kept for GET /api/events.
+//!
+//! event:
+//!     {"timestamp_ms": 1730000000000, "kind": "anomaly", "source": "pi4:dht22",
+//!      "message": "temperature = 0 is 9.1σ from 21.4", "data": {...}}
+//!
+//! relationships:
+//!     - used by: anomaly.rs (emits), alerts.rs (consumes anomalies, emits
+//!       alert transitions), main.rs (/api/events)
+//!
+//! ==============================================================================
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+
+/// events kept for /api/events
+const RECENT_EVENTS: usize = 200;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Event {
+    pub timestamp_ms: u64,
+    pub kind: String,             // e.g. "anomaly", "alert_firing"
+    pub source: String,           // sensor_id or node_id the event is about
+    pub message: String,
+    #[serde(skip_serializing_if = "serde_json::Value::is_null")]
+    pub data: serde_json::Value,
+}
+
+pub struct EventBus {
+    tx: broadcast::Sender<Event>,
+    recent: Mutex<VecDeque<Event>>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self { tx: broadcast::channel(RECENT_EVENTS).0, recent: Mutex::new(VecDeque::new()) }
+    }
+}
+
+impl EventBus {
+    pub fn emit(&self, kind: &str, source: &str, message: String, data: serde_json::Value) {
+        let event = Event {
+            timestamp_ms: crate::domain::now_ms(),
+            kind: kind.to_string(),
+            source: source.to_string(),
+            message,
+            data,
+        };
+        {
+            let mut recent = self.recent.lock().unwrap();
+            if recent.len() == RECENT_EVENTS {
+                recent.pop_front();
+            }
+            recent.push_back(event.clone());
+        }
+        // no subscribers is fine
+        let _ = self.tx.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.tx.subscribe()
+    }
+
+    /// newest first
+    pub fn recent(&self) -> Vec<Event> {
+        self.recent.lock().unwrap().iter().rev().cloned().collect()
+    }
+}
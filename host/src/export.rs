@@ -0,0 +1,223 @@
+//! ==============================================================================
+//! export.rs - scheduled parquet export of the readings store (feature = "parquet")
+//! ==============================================================================
+//!
+//! purpose:
+//!     long-term analysis (duckdb, pandas, spark) shouldn't query the pi.
+//!     once a utc day is complete its readings are snapshotted from the
+//!     sqlite store into {export.dir}/readings-YYYY-MM-DD.parquet and,
+//!     optionally, uploaded to an s3-compatible bucket.
+//!
+//! file layout (one row per reading, snappy compressed):
+//!     timestamp     timestamp[ms, UTC]
+//!     node_id       utf8
+//!     sensor_id     utf8
+//!     data          utf8 (the full json payload)
+//!     <field>...    float64, nullable - one column per numeric data field
+//!                   seen that day, so `SELECT avg(temperature)` just works
+//!
+//! schedule:
+//!     every export.check_interval_minutes the job looks at the last
+//!     export.backfill_days complete days and exports those without a file
+//!     yet. a failed upload removes the local file so the day is retried.
+//!
+//! s3:
+//!     plain PUT with aws signature v4 (path-style urls), which works with
+//!     aws s3, minio and garage. objects land at {prefix}readings-YYYY-MM-DD.parquet.
+//!
+//! relationships:
+//!     - used by: main.rs (spawned at startup when export.enabled)
+//!     - reads: storage.rs (Store::range), config.rs (ExportConfig)
+//!
+//! ==============================================================================
+
+use crate::config::{ExportConfig, S3Config};
+use crate::domain::SensorReading;
+use crate::log_msg;
+use crate::storage::Store;
+use arrow_array::{ArrayRef, Float64Array, RecordBatch, StringArray, TimestampMillisecondArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use chrono::{Duration as ChronoDuration, NaiveDate, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const FIXED_COLUMNS: [&str; 4] = ["timestamp", "node_id", "sensor_id", "data"];
+
+/// a day of readings on a slow uplink takes longer than [http_client] timeout_seconds
+const UPLOAD_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(600);
+
+/// spawn the export job
+pub fn spawn(store: Arc<Store>, config: ExportConfig, client: reqwest::Client) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(config.check_interval_minutes.max(1) * 60));
+        loop {
+            ticker.tick().await;
+            for day in pending_days(&config) {
+                let path = Path::new(&config.dir).join(file_name(day));
+                let (store, out) = (store.clone(), path.clone());
+                let written = tokio::task::spawn_blocking(move || write_day(&store, day, &out)).await;
+                match written.map_err(anyhow::Error::from).and_then(|r| r) {
+                    Ok(0) => continue,
+                    Ok(rows) => log_msg(&format!("📦 [EXPORT] Wrote {} readings to {}", rows, path.display())),
+                    Err(e) => {
+                        log_msg(&format!("❌ [EXPORT] {} failed: {:#}", day, e));
+                        let _ = std::fs::remove_file(&path);
+                        continue;
+                    }
+                }
+                if config.s3.enabled {
+                    match upload(&client, &config.s3, &path).await {
+                        Ok(url) => log_msg(&format!("📦 [EXPORT] Uploaded {}", url)),
+                        Err(e) => {
+                            // drop the file so the next run retries the whole day
+                            log_msg(&format!("❌ [EXPORT] Upload of {} failed: {:#}", day, e));
+                            let _ = std::fs::remove_file(&path);
+                        }
+                    }
+                }
+            }
+        }
+    });
+}
+
+/// complete utc days within the backfill window that have no export file yet
+fn pending_days(config: &ExportConfig) -> Vec<NaiveDate> {
+    let today = Utc::now().date_naive();
+    (1..=config.backfill_days.max(1) as i64)
+        .rev()
+        .map(|back| today - ChronoDuration::days(back))
+        .filter(|day| !Path::new(&config.dir).join(file_name(*day)).exists())
+        .collect()
+}
+
+fn file_name(day: NaiveDate) -> String {
+    format!("readings-{}.parquet", day.format("%Y-%m-%d"))
+}
+
+/// export one day. returns the number of rows written (0 = no data, no file).
+fn write_day(store: &Store, day: NaiveDate, path: &Path) -> anyhow::Result<usize> {
+    let start = day.and_hms_opt(0, 0, 0).unwrap().and_utc().timestamp_millis() as u64;
+    let readings = store.range(start, start + 24 * 3600 * 1000 - 1)?;
+    if readings.is_empty() {
+        return Ok(0);
+    }
+    let batch = to_record_batch(&readings)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    // write to a temp name so a crash never leaves a truncated "done" file
+    let tmp = path.with_extension("parquet.tmp");
+    let props = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(&tmp)?, batch.schema(), Some(props))?;
+    writer.write(&batch)?;
+    writer.close()?;
+    std::fs::rename(&tmp, path)?;
+    Ok(readings.len())
+}
+
+fn to_record_batch(readings: &[SensorReading]) -> anyhow::Result<RecordBatch> {
+    let numeric: BTreeSet<&str> = readings
+        .iter()
+        .filter_map(|r| r.data.as_object())
+        .flat_map(|fields| fields.iter().filter(|(_, v)| v.is_number()).map(|(k, _)| k.as_str()))
+        .filter(|name| !FIXED_COLUMNS.contains(name))
+        .collect();
+
+    let mut fields = vec![
+        Field::new("timestamp", DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())), false),
+        Field::new("node_id", DataType::Utf8, false),
+        Field::new("sensor_id", DataType::Utf8, false),
+        Field::new("data", DataType::Utf8, false),
+    ];
+    let mut columns: Vec<ArrayRef> = vec![
+        Arc::new(TimestampMillisecondArray::from_iter_values(readings.iter().map(|r| r.timestamp_ms as i64)).with_timezone("UTC")),
+        Arc::new(StringArray::from_iter_values(
+            readings.iter().map(|r| r.sensor_id.split_once(':').map_or("", |(node, _)| node)),
+        )),
+        Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.sensor_id.as_str()))),
+        Arc::new(StringArray::from_iter_values(readings.iter().map(|r| r.data.to_string()))),
+    ];
+    for name in numeric {
+        fields.push(Field::new(name, DataType::Float64, true));
+        columns.push(Arc::new(Float64Array::from_iter(readings.iter().map(|r| r.data.get(name).and_then(|v| v.as_f64())))));
+    }
+    Ok(RecordBatch::try_new(Arc::new(Schema::new(fields)), columns)?)
+}
+
+// ==============================================================================
+// s3 upload (aws signature v4)
+// ==============================================================================
+
+async fn upload(client: &reqwest::Client, s3: &S3Config, path: &PathBuf) -> anyhow::Result<String> {
+    let body = tokio::fs::read(path).await?;
+    let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let url = reqwest::Url::parse(&format!(
+        "{}/{}/{}{}",
+        s3.endpoint.trim_end_matches('/'),
+        s3.bucket,
+        s3.prefix,
+        name
+    ))?;
+    let host = match url.port() {
+        Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+        None => url.host_str().unwrap_or_default().to_string(),
+    };
+
+    let now = Utc::now();
+    let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = now.format("%Y%m%d").to_string();
+    let payload_hash = hex::encode(sha256(&body));
+    let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+    let canonical_request = format!(
+        "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+        url.path(),
+        host,
+        payload_hash,
+        amz_date,
+        signed_headers,
+        payload_hash
+    );
+    let scope = format!("{}/{}/s3/aws4_request", date, s3.region);
+    let string_to_sign = format!("AWS4-HMAC-SHA256\n{}\n{}\n{}", amz_date, scope, hex::encode(sha256(canonical_request.as_bytes())));
+
+    let mut key = hmac(format!("AWS4{}", s3.secret_key).as_bytes(), date.as_bytes());
+    for part in [s3.region.as_str(), "s3", "aws4_request"] {
+        key = hmac(&key, part.as_bytes());
+    }
+    let signature = hex::encode(hmac(&key, string_to_sign.as_bytes()));
+    let authorization = format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        s3.access_key, scope, signed_headers, signature
+    );
+
+    let response = client
+        .put(url.clone())
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .body(body)
+        .timeout(UPLOAD_TIMEOUT)
+        .send()
+        .await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("{} {}", status, response.text().await.unwrap_or_default().trim());
+    }
+    Ok(url.to_string())
+}
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    use sha2::Digest;
+    sha2::Sha256::digest(data).to_vec()
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::Mac;
+    let mut mac = hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("hmac accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
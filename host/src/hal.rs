@@ -12,10 +12,54 @@
 //!     - "Zero Cost": On the Pi, this compiles down to direct `rppal` calls.
 //!     - "Safety": Enforces proper locking/sharing of I2C bus if needed.
 //!
+//! dry run:
+//!     `wasi-host --dry-run` tries a new config / plugin set on live hardware
+//!     without clicking relays: gpio writes, buzzer patterns, fan switching
+//!     and led strip updates are logged instead of performed (led frames at
+//!     debug level, the heartbeat led changes every cycle). reads - dht22,
+//!     cpu temperature, i2c transfers (a sensor read writes its register
+//!     address first) - still go to the hardware.
+//!     the same writes are held back while the node is in maintenance
+//!     (maintenance.rs), logged with a [MAINTENANCE] tag.
+//!
+//! led strip:
+//!     set_led only changes the frame buffer and marks it dirty; plugins,
+//!     the heartbeat and set-led commands all write to the same buffer.
+//!     the poll loop writes it to the strip once per cycle, after every
+//!     plugin had its say, and only when something changed and at least
+//!     leds.min_sync_ms passed since the last write (each write is a round
+//!     trip to the strip's python driver on the real hal). a set-led command flushes right
+//!     away under the same limit. sync_leds is the write itself.
+//!     the buffer holds the colors as set (plugins use the full 0-255);
+//!     leds.brightness and leds.gamma are applied on the way to the strip,
+//!     through a lookup table built by configure_leds:
+//!         out = 255 * (in / 255) ^ gamma * brightness / 255
+//!     the driver itself runs at full brightness, so the strip looks the
+//!     same whatever drives it. brightness 50 and gamma 1.0 match what the
+//!     python driver's brightness=50 used to do.
+//!
+//! cargo features:
+//!     leds, buzzer and fan (all default) compile the drivers of the host
+//!     hardware. without `leds` the frame buffer and the strip driver are
+//!     left out and set_led / flush_leds do nothing; without `buzzer` or
+//!     `fan`, buzz / switch_fan fail with "built without the ... feature"
+//!     (commands, alert actions and dashboard buttons report it). a spoke
+//!     that only reads sensors and pushes them builds with
+//!     --no-default-features --features hardware.
+//!
+//! one instance:
+//!     the process has a single Hal, created the first time `shared()` is
+//!     called and handed out as an Arc (ApiState, the plugin HostStates, the
+//!     poll loop, commands). the real one opens /dev/gpiomem and the i2c bus
+//!     on first use and keeps them open, instead of every plugin call and
+//!     handler opening the devices again (and logging it).
+//!
 //! relationships:
 //!     - used by: runtime.rs (to fulfill wit contracts for plugins)
+//!     - used by: actuators.rs (buzzer patterns and fan switching, one pin
+//!       operation at a time)
 //!     - uses: rppal (on feature="hardware")
-//!     - uses: std::process::Command (for legacy Python DHT driver until ported)
+//!     - uses: python_driver.rs (led strip and dht22, which have no rust driver)
 //!
 //! ==============================================================================
 
@@ -25,14 +69,18 @@ pub trait HardwareProvider: Send + Sync {
     fn i2c_transfer(&self, addr: u8, write_data: &[u8], read_len: u32) -> Result<Vec<u8>>;
     #[allow(dead_code)]
     fn spi_transfer(&self, data: &[u8]) -> Result<Vec<u8>>;
+    #[cfg_attr(not(any(feature = "buzzer", feature = "fan")), allow(dead_code))]
     fn set_gpio_mode(&self, pin: u8, mode: &str) -> Result<()>;
+    #[cfg_attr(not(any(feature = "buzzer", feature = "fan")), allow(dead_code))]
     fn write_gpio(&self, pin: u8, level: bool) -> Result<()>;
     fn set_led(&self, index: u8, r: u8, g: u8, b: u8) -> Result<()>;
+    #[cfg_attr(not(feature = "leds"), allow(dead_code))]
     fn sync_leds(&self) -> Result<()>;
     fn read_dht22(&self, pin: u8) -> Result<(f32, f32)>;
     fn get_cpu_temp(&self) -> f32;
-    fn buzz(&self, pin: u8, pattern: &str) -> Result<()>;
+    #[allow(dead_code)]
     fn set_fan(&self, pin: u8, on: bool) -> Result<()>;
+    #[allow(dead_code)]
     fn get_fan_state(&self, pin: u8) -> bool;
 }
 
@@ -41,24 +89,211 @@ pub trait HardwareProvider: Send + Sync {
 use std::sync::atomic::{AtomicBool, Ordering};
 pub static GLOBAL_FAN_STATE: AtomicBool = AtomicBool::new(false);
 
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+/// log hardware writes instead of performing them (--dry-run)
+pub fn set_dry_run(on: bool) {
+    DRY_RUN.store(on, Ordering::SeqCst);
+}
+
+pub fn dry_run() -> bool {
+    DRY_RUN.load(Ordering::SeqCst)
+}
+
+/// why hardware writes are held back right now: "dry run" or "maintenance"
+pub fn held() -> Option<&'static str> {
+    if dry_run() {
+        Some("dry run")
+    } else if crate::maintenance::active() {
+        Some("maintenance")
+    } else {
+        None
+    }
+}
+
+/// a write held back by --dry-run or maintenance
+#[cfg_attr(not(any(feature = "buzzer", feature = "hardware")), allow(dead_code))]
+fn skipped(reason: &str, what: String) -> Result<()> {
+    let tag = match reason {
+        "maintenance" => "🔧 [MAINTENANCE]",
+        _ => "🧪 [DRY-RUN]",
+    };
+    crate::log_msg(&format!("{} {} (not written)", tag, what));
+    Ok(())
+}
+
+// ==============================================================================================
+// BUZZER PATTERNS
+// ==============================================================================================
+
+/// (relay on ms, relay off ms) steps of a named pattern (commands, dashboard)
+pub fn pattern(name: &str) -> Vec<(u64, u64)> {
+    match name {
+        "triple" => vec![(100, 100); 3],
+        "long" => vec![(500, 0)],
+        _ => vec![(100, 0)], // "single"
+    }
+}
+
+/// sound the active-low buzzer relay on `pin` through `steps`. timed with
+/// tokio timers on the pin the hal keeps open - a long beep doesn't hold a
+/// blocking-pool thread (a pi zero has few). the relay is switched off
+/// again if the caller gives up halfway.
+#[cfg(feature = "buzzer")]
+pub async fn buzz(hal: &Hal, pin: u8, steps: &[(u64, u64)]) -> Result<()> {
+    if let Some(reason) = held() {
+        let on_ms: u64 = steps.iter().map(|(on, _)| on).sum();
+        return skipped(reason, format!("buzzer on gpio {}: {} beep(s), {}ms", pin, steps.len(), on_ms));
+    }
+    hal.set_gpio_mode(pin, "OUT")?;
+    let _relay = RelayOff { hal, pin };
+    for &(on_ms, off_ms) in steps {
+        hal.write_gpio(pin, false)?; // relay on (low)
+        tokio::time::sleep(std::time::Duration::from_millis(on_ms)).await;
+        hal.write_gpio(pin, true)?; // relay off (high)
+        tokio::time::sleep(std::time::Duration::from_millis(off_ms)).await;
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "buzzer"))]
+pub async fn buzz(_hal: &Hal, _pin: u8, _steps: &[(u64, u64)]) -> Result<()> {
+    anyhow::bail!("this node is built without the 'buzzer' feature")
+}
+
+/// switches a buzzer relay off when its pattern ends (or is dropped)
+#[cfg(feature = "buzzer")]
+struct RelayOff<'a> {
+    hal: &'a Hal,
+    pin: u8,
+}
+
+#[cfg(feature = "buzzer")]
+impl Drop for RelayOff<'_> {
+    fn drop(&mut self) {
+        let _ = self.hal.write_gpio(self.pin, true);
+    }
+}
+
+/// switch the fan relay (fan.active_low decides the level), true = it changed
+#[cfg(feature = "fan")]
+pub fn switch_fan(hal: &Hal, fan: &crate::config::FanConfig, on: bool) -> Result<bool> {
+    hal.set_gpio_mode(fan.gpio_pin, "OUT")?;
+    hal.write_gpio(fan.gpio_pin, fan.level(on))?;
+    Ok(GLOBAL_FAN_STATE.swap(on, Ordering::SeqCst) != on)
+}
+
+#[cfg(not(feature = "fan"))]
+pub fn switch_fan(_hal: &Hal, _fan: &crate::config::FanConfig, _on: bool) -> Result<bool> {
+    anyhow::bail!("this node is built without the 'fan' feature")
+}
+
+/// led frame buffer (11 leds, r-g-b tuples), written to the strip by flush_leds
+#[cfg(feature = "leds")]
+struct LedStrip {
+    frame: [(u8, u8, u8); 11],
+    /// changed since the last write
+    dirty: bool,
+    synced: Option<std::time::Instant>,
+    /// channel value as set -> as written (brightness and gamma)
+    levels: [u8; 256],
+}
+
+#[cfg(feature = "leds")]
+impl Default for LedStrip {
+    fn default() -> Self {
+        Self { frame: Default::default(), dirty: false, synced: None, levels: std::array::from_fn(|i| i as u8) }
+    }
+}
+
+#[cfg(feature = "leds")]
+impl LedStrip {
+    fn set(&mut self, index: u8, rgb: (u8, u8, u8)) {
+        if let Some(led) = self.frame.get_mut(index as usize) {
+            self.dirty |= *led != rgb;
+            *led = rgb;
+        }
+    }
+
+    /// the frame as the strip gets it
+    fn output(&self) -> [(u8, u8, u8); 11] {
+        self.frame.map(|(r, g, b)| (self.levels[r as usize], self.levels[g as usize], self.levels[b as usize]))
+    }
+}
+
+/// leds.gamma is a usable exponent
+pub fn check_config(leds: &crate::config::LedConfig) -> Result<()> {
+    if !(leds.gamma.is_finite() && leds.gamma > 0.0) {
+        anyhow::bail!("leds.gamma must be a positive number, got {}", leds.gamma);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "leds")]
+type LedBuffer = std::sync::Mutex<LedStrip>;
+
+static HAL: std::sync::OnceLock<std::sync::Arc<Hal>> = std::sync::OnceLock::new();
+
+/// the process-wide hal, created on first use
+pub fn shared() -> std::sync::Arc<Hal> {
+    HAL.get_or_init(|| std::sync::Arc::new(Hal::new())).clone()
+}
+
+#[cfg(feature = "leds")]
+impl Hal {
+    /// apply leds.brightness / leds.gamma from the next strip write on
+    pub fn configure_leds(&self, leds: &crate::config::LedConfig) {
+        let mut strip = self.leds.lock().unwrap();
+        strip.levels = std::array::from_fn(|i| {
+            let corrected = (i as f32 / 255.0).powf(leds.gamma) * 255.0;
+            (corrected * leds.brightness as f32 / 255.0).round() as u8
+        });
+        strip.dirty = true;
+    }
+
+    /// write the led frame to the strip if it changed and the last write is
+    /// at least `min_interval` ago (true = written). a change held back by
+    /// the interval stays dirty for the next flush.
+    pub fn flush_leds(&self, min_interval: std::time::Duration) -> Result<bool> {
+        {
+            let mut strip = self.leds.lock().unwrap();
+            if !strip.dirty || strip.synced.is_some_and(|at| at.elapsed() < min_interval) {
+                return Ok(false);
+            }
+            strip.dirty = false;
+            strip.synced = Some(std::time::Instant::now());
+        }
+        self.sync_leds()?;
+        Ok(true)
+    }
+}
+
+#[cfg(not(feature = "leds"))]
+impl Hal {
+    pub fn configure_leds(&self, _leds: &crate::config::LedConfig) {}
+
+    pub fn flush_leds(&self, _min_interval: std::time::Duration) -> Result<bool> {
+        Ok(false)
+    }
+}
+
 // ==============================================================================================
 // MOCK IMPLEMENTATION (For WSL / Non-Hardware Build)
 // ==============================================================================================
 #[cfg(not(feature = "hardware"))]
-pub struct Hal {}
-#[cfg(not(feature = "hardware"))]
-static MOCK_LED_BUFFER: std::sync::OnceLock<std::sync::Arc<std::sync::Mutex<[(u8, u8, u8); 11]>>> = std::sync::OnceLock::new();
+pub struct Hal {
+    #[cfg(feature = "leds")]
+    leds: LedBuffer,
+}
 
 #[cfg(not(feature = "hardware"))]
 impl Hal {
-    pub fn new() -> Self {
-        tracing::info!("Using MOCK HAL (No hardware access)");
-        MOCK_LED_BUFFER.get_or_init(|| std::sync::Arc::new(std::sync::Mutex::new([(0, 0, 0); 11])));
-        Self {}
-    }
-
-    fn get_buffer(&self) -> std::sync::Arc<std::sync::Mutex<[(u8, u8, u8); 11]>> {
-        MOCK_LED_BUFFER.get().unwrap().clone()
+    fn new() -> Self {
+        tracing::debug!("Using MOCK HAL (No hardware access)");
+        Self {
+            #[cfg(feature = "leds")]
+            leds: LedBuffer::default(),
+        }
     }
 }
 
@@ -66,18 +301,16 @@ impl Hal {
 impl HardwareProvider for Hal {
     fn set_led(&self, index: u8, r: u8, g: u8, b: u8) -> Result<()> {
         if index < 11 {
-            let arc = self.get_buffer();
-            let mut buffer = arc.lock().unwrap();
-            buffer[index as usize] = (r, g, b);
+            #[cfg(feature = "leds")]
+            self.leds.lock().unwrap().set(index, (r, g, b));
             tracing::debug!("[MOCK LED] Set LED {} to RBG({}, {}, {})", index, r, g, b);
         }
         Ok(())
     }
 
     fn sync_leds(&self) -> Result<()> {
-        let arc = self.get_buffer();
-        let buffer = arc.lock().unwrap();
-        tracing::debug!("[MOCK LED] Syncing buffer: {:?}", *buffer);
+        #[cfg(feature = "leds")]
+        tracing::debug!("[MOCK LED] Syncing buffer: {:?}", self.leds.lock().unwrap().output());
         Ok(())
     }
     fn i2c_transfer(&self, addr: u8, write_data: &[u8], read_len: u32) -> Result<Vec<u8>> {
@@ -109,11 +342,6 @@ impl HardwareProvider for Hal {
         45.0 // Mock data
     }
 
-    fn buzz(&self, pin: u8, pattern: &str) -> Result<()> {
-        tracing::debug!("[MOCK BUZZER] Pin {} pattern {}", pin, pattern);
-        Ok(())
-    }
-
     fn set_fan(&self, pin: u8, on: bool) -> Result<()> {
         tracing::debug!("[MOCK FAN] Pin {} set to {}", pin, if on { "ON" } else { "OFF" });
         GLOBAL_FAN_STATE.store(on, Ordering::SeqCst);
@@ -129,81 +357,126 @@ impl HardwareProvider for Hal {
 // REAL IMPLEMENTATION (For Raspberry Pi)
 // ==============================================================================================
 #[cfg(feature = "hardware")]
-pub struct Hal {}
+pub struct Hal {
+    #[cfg(feature = "leds")]
+    leds: LedBuffer,
+    /// opened on the first gpio write
+    gpio: std::sync::Mutex<Option<rppal::gpio::Gpio>>,
+    /// output pins, claimed on their first write and kept (buzzer patterns
+    /// toggle the same pin every 100ms)
+    outputs: std::sync::Mutex<std::collections::HashMap<u8, rppal::gpio::OutputPin>>,
+    /// opened on the first transfer, reopened after a failed one
+    i2c: std::sync::Mutex<Option<rppal::i2c::I2c>>,
+    /// rpi_ws281x needs root for its dma channel
+    #[cfg(feature = "leds")]
+    strip: crate::python_driver::PythonDriver,
+    dht22: crate::python_driver::PythonDriver,
+}
+
+/// the strip's python driver (11 leds on gpio 18). full brightness, the
+/// frames come with leds.brightness / leds.gamma applied
+#[cfg(all(feature = "hardware", feature = "leds"))]
+const STRIP_DRIVER: &str = r#"
+from rpi_ws281x import PixelStrip, Color
+strip = PixelStrip(11, 18, brightness=255)
+strip.begin()
+
+def handle(request):
+    for i, (r, g, b) in enumerate(request["frame"]):
+        strip.setPixelColor(i, Color(r, g, b))
+    strip.show()
+    return {}
+"#;
+
+/// the dht22's python driver, one sensor object per pin (adafruit_dht
+/// leaks a pulse reader per object)
 #[cfg(feature = "hardware")]
-static REAL_LED_BUFFER: std::sync::OnceLock<std::sync::Arc<std::sync::Mutex<[(u8, u8, u8); 11]>>> = std::sync::OnceLock::new();
+const DHT22_DRIVER: &str = r#"
+import adafruit_dht, board
+sensors = {}
+
+def handle(request):
+    pin = request["pin"]
+    if pin not in sensors:
+        sensors[pin] = adafruit_dht.DHT22(getattr(board, "D%d" % pin))
+    return {"t": sensors[pin].temperature, "h": sensors[pin].humidity}
+"#;
 
 #[cfg(feature = "hardware")]
 impl Hal {
-    pub fn new() -> Self {
-        tracing::info!("Using REAL HARDWARE HAL (rppal)");
-        REAL_LED_BUFFER.get_or_init(|| std::sync::Arc::new(std::sync::Mutex::new([(0, 0, 0); 11])));
-        Self {}
+    fn new() -> Self {
+        tracing::debug!("Using REAL HARDWARE HAL (rppal)");
+        Self {
+            #[cfg(feature = "leds")]
+            leds: LedBuffer::default(),
+            gpio: std::sync::Mutex::new(None),
+            outputs: std::sync::Mutex::new(std::collections::HashMap::new()),
+            i2c: std::sync::Mutex::new(None),
+            #[cfg(feature = "leds")]
+            strip: crate::python_driver::PythonDriver::new("led strip", &["sudo", "python3"], STRIP_DRIVER),
+            dht22: crate::python_driver::PythonDriver::new("dht22", &["python3"], DHT22_DRIVER),
+        }
     }
 
-    fn get_buffer(&self) -> std::sync::Arc<std::sync::Mutex<[(u8, u8, u8); 11]>> {
-        REAL_LED_BUFFER.get().unwrap().clone()
+    fn gpio(&self) -> Result<rppal::gpio::Gpio> {
+        let mut gpio = self.gpio.lock().unwrap();
+        if gpio.is_none() {
+            *gpio = Some(rppal::gpio::Gpio::new()?);
+        }
+        Ok(gpio.clone().unwrap())
     }
 }
 
 #[cfg(feature = "hardware")]
 impl HardwareProvider for Hal {
+    #[cfg(feature = "leds")]
     fn set_led(&self, index: u8, r: u8, g: u8, b: u8) -> Result<()> {
-        if index < 11 {
-            let arc = self.get_buffer();
-            let mut buffer = arc.lock().unwrap();
-            buffer[index as usize] = (r, g, b);
-        }
+        self.leds.lock().unwrap().set(index, (r, g, b));
         Ok(())
     }
 
+    #[cfg(not(feature = "leds"))]
+    fn set_led(&self, _index: u8, _r: u8, _g: u8, _b: u8) -> Result<()> {
+        Ok(())
+    }
+
+    #[cfg(not(feature = "leds"))]
     fn sync_leds(&self) -> Result<()> {
-        use std::process::Command;
-        
-        let data = {
-            let arc = self.get_buffer();
-            let buffer = arc.lock().unwrap();
-            buffer.clone()
-        };
-        
-        // Generate python script to set the whole strip
-        let mut pixel_logic = String::new();
-        for (i, (r, g, b)) in data.iter().enumerate() {
-            pixel_logic.push_str(&format!("strip.setPixelColor({}, Color({}, {}, {}))\n", i, *r, *g, *b));
+        Ok(())
+    }
+
+    #[cfg(feature = "leds")]
+    fn sync_leds(&self) -> Result<()> {
+        let data = self.leds.lock().unwrap().output();
+        if let Some(reason) = held() {
+            tracing::debug!("[{}] led strip {:?} (not written)", reason, data);
+            return Ok(());
         }
-        
-        let script = format!(
-            r#"
-from rpi_ws281x import PixelStrip, Color
-strip = PixelStrip(11, 18, brightness=50)
-strip.begin()
-{}
-strip.show()
-"#,
-            pixel_logic
-        );
-        
-        let _ = Command::new("sudo")
-            .args(["python3", "-c", &script])
-            .output();
+        self.strip.call(&serde_json::json!({ "frame": data }))?;
         Ok(())
     }
     fn i2c_transfer(&self, addr: u8, write_data: &[u8], read_len: u32) -> Result<Vec<u8>> {
         use rppal::i2c::I2c;
-        let mut i2c = I2c::new()?;
-        i2c.set_slave_address(addr as u16)?;
-        
-        if !write_data.is_empty() {
-             i2c.write(write_data)?;
-        }
-        
-        if read_len > 0 {
+        let mut bus = self.i2c.lock().unwrap();
+        let i2c = match bus.as_mut() {
+            Some(i2c) => i2c,
+            None => bus.insert(I2c::new()?),
+        };
+        let result = (|| -> Result<Vec<u8>> {
+            i2c.set_slave_address(addr as u16)?;
+            if !write_data.is_empty() {
+                i2c.write(write_data)?;
+            }
             let mut read_buf = vec![0u8; read_len as usize];
-            i2c.read(&mut read_buf)?;
+            if read_len > 0 {
+                i2c.read(&mut read_buf)?;
+            }
             Ok(read_buf)
-        } else {
-            Ok(vec![])
+        })();
+        if result.is_err() {
+            *bus = None;
         }
+        result
     }
 
     fn spi_transfer(&self, data: &[u8]) -> Result<Vec<u8>> {
@@ -219,37 +492,29 @@ strip.show()
     }
 
     fn write_gpio(&self, pin: u8, level: bool) -> Result<()> {
-        use rppal::gpio::Gpio;
-        let gpio = Gpio::new()?;
-        let mut p = gpio.get(pin)?.into_output();
-        // CRITICAL: Prevent GPIO from resetting when dropped
-        // Without this, the fan turns off as soon as this function returns
-        p.set_reset_on_drop(false);
+        if let Some(reason) = held() {
+            return skipped(reason, format!("gpio {} → {}", pin, if level { "high" } else { "low" }));
+        }
+        let mut outputs = self.outputs.lock().unwrap();
+        let p = match outputs.entry(pin) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => {
+                let mut p = self.gpio()?.get(pin)?.into_output();
+                // CRITICAL: Prevent GPIO from resetting when dropped
+                // Without this, the fan turns off when the host exits
+                p.set_reset_on_drop(false);
+                entry.insert(p)
+            }
+        };
         if level { p.set_high(); } else { p.set_low(); }
         Ok(())
     }
 
     fn read_dht22(&self, pin: u8) -> Result<(f32, f32)> {
-        // NOTE: For now, we fallback to Python subprocess for DHT22 stability on generic Linux kernels
-        // native bit-banging is notoriously flaky without a kernel driver.
-        use std::process::Command;
-        let script = format!(
-            r#"
-import adafruit_dht, board, json, sys
-try:
-    dht = adafruit_dht.DHT22(board.D{})
-    print(json.dumps({{"t": dht.temperature, "h": dht.humidity}}))
-except Exception:
-    print("null")
-"#,
-            pin
-        );
-        let output = Command::new("python3").args(["-c", &script]).output()?;
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        if stdout.trim() == "null" {
-            anyhow::bail!("DHT22 read failed");
-        }
-        let v: serde_json::Value = serde_json::from_str(&stdout)?;
+        // NOTE: native bit-banging is notoriously flaky without a kernel driver,
+        // the read stays with adafruit_dht (python_driver.rs)
+        use anyhow::Context;
+        let v = self.dht22.call(&serde_json::json!({ "pin": pin })).context("DHT22 read failed")?;
         Ok((
             v["t"].as_f64().unwrap_or(0.0) as f32,
             v["h"].as_f64().unwrap_or(0.0) as f32
@@ -264,107 +529,18 @@ except Exception:
             .unwrap_or(0.0)
     }
 
-    fn buzz(&self, pin: u8, pattern: &str) -> Result<()> {
-        use std::process::Command;
-        
-        // Generate Python script based on pattern
-        // This runs the entire beep sequence in one Python process,
-        // keeping the GPIO handle alive during the full duration
-        let script = match pattern {
-            "single" => format!(
-                r#"
-import RPi.GPIO as GPIO
-import time
-GPIO.setmode(GPIO.BCM)
-GPIO.setwarnings(False)
-GPIO.setup({0}, GPIO.OUT)
-GPIO.output({0}, GPIO.LOW)   # Relay ON (active low)
-time.sleep(0.1)
-GPIO.output({0}, GPIO.HIGH)  # Relay OFF
-GPIO.cleanup({0})
-"#,
-                pin
-            ),
-            "triple" => format!(
-                r#"
-import RPi.GPIO as GPIO
-import time
-GPIO.setmode(GPIO.BCM)
-GPIO.setwarnings(False)
-GPIO.setup({0}, GPIO.OUT)
-for _ in range(3):
-    GPIO.output({0}, GPIO.LOW)
-    time.sleep(0.1)
-    GPIO.output({0}, GPIO.HIGH)
-    time.sleep(0.1)
-GPIO.cleanup({0})
-"#,
-                pin
-            ),
-            "long" => format!(
-                r#"
-import RPi.GPIO as GPIO
-import time
-GPIO.setmode(GPIO.BCM)
-GPIO.setwarnings(False)
-GPIO.setup({0}, GPIO.OUT)
-GPIO.output({0}, GPIO.LOW)
-time.sleep(0.5)
-GPIO.output({0}, GPIO.HIGH)
-GPIO.cleanup({0})
-"#,
-                pin
-            ),
-            _ => format!(
-                r#"
-import RPi.GPIO as GPIO
-import time
-GPIO.setmode(GPIO.BCM)
-GPIO.setwarnings(False)
-GPIO.setup({0}, GPIO.OUT)
-GPIO.output({0}, GPIO.LOW)
-time.sleep(0.1)
-GPIO.output({0}, GPIO.HIGH)
-GPIO.cleanup({0})
-"#,
-                pin
-            ),
-        };
-
-        let output = Command::new("python3").args(["-c", &script]).output()?;
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Buzzer failed: {}", stderr);
-        }
-        Ok(())
-    }
-
     fn set_fan(&self, pin: u8, on: bool) -> Result<()> {
-        use std::process::Command;
-        
+        let what = format!("fan on gpio {} {}", pin, if on { "on" } else { "off" });
+        if crate::maintenance::active() {
+            return skipped("maintenance", what);
+        }
         // Update tracked state
         GLOBAL_FAN_STATE.store(on, Ordering::SeqCst);
-        
-        // Active-low relay: LOW = relay ON = fan running
-        let gpio_level = if on { "LOW" } else { "HIGH" };
-        
-        let script = format!(
-            r#"
-import RPi.GPIO as GPIO
-GPIO.setmode(GPIO.BCM)
-GPIO.setwarnings(False)
-GPIO.setup({0}, GPIO.OUT)
-GPIO.output({0}, GPIO.{1})
-"#,
-            pin, gpio_level
-        );
-        
-        let output = Command::new("python3").args(["-c", &script]).output()?;
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Fan control failed: {}", stderr);
+        if dry_run() {
+            return skipped("dry run", what);
         }
-        Ok(())
+        // Active-low relay: LOW = relay ON = fan running
+        self.write_gpio(pin, !on)
     }
 
     fn get_fan_state(&self, _pin: u8) -> bool {
@@ -0,0 +1,190 @@
+//! ==============================================================================
+//! influx.rs - InfluxDB line protocol exporter
+//! ==============================================================================
+//!
+//! purpose:
+//!     lets existing grafana/influx setups ingest the cluster's readings
+//!     directly. every merged reading (the hub sees the whole cluster) is
+//!     turned into one line protocol point and written in batches.
+//!
+//! line format:
+//!     {measurement},node=pi4,sensor=dht22[,location=..,room=..,rack=..] temperature=21.5,humidity=45 1730000000000
+//!     numbers become float fields, bools bool fields, strings string
+//!     fields; nested values are skipped. timestamps are written with ms
+//!     precision.
+//!
+//! endpoints:
+//!     influx.bucket set  -> v2  POST {url}/api/v2/write?org=&bucket=&precision=ms  (Token auth)
+//!     otherwise          -> v1  POST {url}/write?db={database}&precision=ms
+//!
+//! batching and retry:
+//!     lines are buffered and flushed every flush_seconds or once
+//!     batch_size lines are waiting. a failed write keeps the buffer and
+//!     retries with exponential backoff (up to a minute); past max_buffer
+//!     lines the oldest are dropped so an unreachable influx can't exhaust
+//!     memory.
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.influx), main.rs (startup)
+//!     - reads: config.rs (InfluxConfig)
+//!
+//! ==============================================================================
+
+use crate::config::InfluxConfig;
+use crate::domain::SensorReading;
+use crate::log_msg;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// cheap handle that queues readings for the exporter task
+#[derive(Clone)]
+pub struct InfluxExporter {
+    tx: mpsc::UnboundedSender<Vec<String>>,
+    measurement: String,
+}
+
+impl InfluxExporter {
+    /// validate the config and spawn the writer task
+    pub fn start(config: &InfluxConfig, client: reqwest::Client) -> anyhow::Result<Self> {
+        if config.url.is_empty() {
+            anyhow::bail!("influx.url is not set");
+        }
+        if config.bucket.is_empty() && config.database.is_empty() {
+            anyhow::bail!("influx needs a bucket (v2) or a database (v1)");
+        }
+        let write_url = match config.bucket.is_empty() {
+            false => reqwest::Url::parse_with_params(
+                &format!("{}/api/v2/write", config.url.trim_end_matches('/')),
+                &[("org", config.org.as_str()), ("bucket", config.bucket.as_str()), ("precision", "ms")],
+            )?,
+            true => reqwest::Url::parse_with_params(
+                &format!("{}/write", config.url.trim_end_matches('/')),
+                &[("db", config.database.as_str()), ("precision", "ms")],
+            )?,
+        };
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(write_loop(client, write_url, config.clone(), rx));
+        log_msg(&format!("📈 [INFLUX] Exporting readings to {}", config.url));
+        Ok(Self { tx, measurement: config.measurement.clone() })
+    }
+
+    pub fn export(&self, readings: &[SensorReading]) {
+        let lines: Vec<String> = readings.iter().filter_map(|r| to_line(&self.measurement, r)).collect();
+        if !lines.is_empty() {
+            let _ = self.tx.send(lines);
+        }
+    }
+}
+
+async fn write_loop(
+    client: reqwest::Client,
+    url: reqwest::Url,
+    config: InfluxConfig,
+    mut rx: mpsc::UnboundedReceiver<Vec<String>>,
+) {
+    let flush_every = Duration::from_secs(config.flush_seconds.max(1));
+    let mut buffer: VecDeque<String> = VecDeque::new();
+    let mut backoff = Duration::ZERO;
+    let mut next_flush = tokio::time::Instant::now() + flush_every;
+
+    loop {
+        tokio::select! {
+            lines = rx.recv() => match lines {
+                Some(lines) => buffer.extend(lines),
+                None => return,
+            },
+            _ = tokio::time::sleep_until(next_flush) => {}
+        }
+        let overflow = buffer.len().saturating_sub(config.max_buffer);
+        if overflow > 0 {
+            buffer.drain(..overflow);
+            tracing::warn!("influx buffer full, dropped {} oldest points", overflow);
+        }
+        let due = tokio::time::Instant::now() >= next_flush;
+        if buffer.is_empty() || (!due && buffer.len() < config.batch_size) {
+            if due {
+                next_flush = tokio::time::Instant::now() + flush_every;
+            }
+            continue;
+        }
+
+        while !buffer.is_empty() {
+            let take = buffer.len().min(config.batch_size.max(1));
+            let body = buffer.iter().take(take).cloned().collect::<Vec<_>>().join("\n");
+            match write(&client, &url, &config, body).await {
+                Ok(()) => {
+                    buffer.drain(..take);
+                    if !backoff.is_zero() {
+                        log_msg("📈 [INFLUX] Writes recovered");
+                    }
+                    backoff = Duration::ZERO;
+                }
+                Err(e) => {
+                    backoff = (backoff * 2).clamp(Duration::from_secs(1), MAX_BACKOFF);
+                    log_msg(&format!("❌ [INFLUX] Write failed ({} points buffered, retry in {}s): {}", buffer.len(), backoff.as_secs(), e));
+                    break;
+                }
+            }
+        }
+        next_flush = tokio::time::Instant::now() + backoff.max(flush_every);
+    }
+}
+
+async fn write(client: &reqwest::Client, url: &reqwest::Url, config: &InfluxConfig, body: String) -> anyhow::Result<()> {
+    let mut request = client
+        .post(url.clone())
+        .header(reqwest::header::CONTENT_TYPE, "text/plain; charset=utf-8")
+        .timeout(Duration::from_secs(10))
+        .body(body);
+    if !config.token.is_empty() {
+        request = request.header(reqwest::header::AUTHORIZATION, format!("Token {}", config.token));
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("{} {}", status, response.text().await.unwrap_or_default().trim());
+    }
+    Ok(())
+}
+
+/// one reading as a line protocol point (None if it has no scalar fields)
+fn to_line(measurement: &str, reading: &SensorReading) -> Option<String> {
+    let fields: Vec<String> = reading
+        .data
+        .as_object()?
+        .iter()
+        .filter_map(|(key, value)| {
+            let value = match value {
+                serde_json::Value::Number(n) => format!("{}", n.as_f64()?),
+                serde_json::Value::Bool(b) => b.to_string(),
+                serde_json::Value::String(s) => format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\"")),
+                _ => return None,
+            };
+            Some(format!("{}={}", escape(key), value))
+        })
+        .collect();
+    if fields.is_empty() {
+        return None;
+    }
+
+    let (node, sensor) = reading.sensor_id.split_once(':').unwrap_or(("", reading.sensor_id.as_str()));
+    let mut tags = vec![("node", node), ("sensor", sensor)];
+    if let Some(meta) = &reading.metadata {
+        tags.extend([("location", meta.location.as_str()), ("room", meta.room.as_str()), ("rack", meta.rack.as_str())]);
+    }
+    let tags: String = tags
+        .into_iter()
+        .filter(|(_, v)| !v.is_empty())
+        .map(|(k, v)| format!(",{}={}", k, escape(v)))
+        .collect();
+
+    Some(format!("{}{} {} {}", escape(measurement), tags, fields.join(","), reading.timestamp_ms))
+}
+
+/// escape commas, spaces and equals signs in measurement/tag/field keys
+fn escape(text: &str) -> String {
+    text.replace(',', "\\,").replace(' ', "\\ ").replace('=', "\\=")
+}
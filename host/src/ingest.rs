@@ -0,0 +1,79 @@
+//! ==============================================================================
+//! ingest.rs - bounded queue between POST /push and the hub state
+//! ==============================================================================
+//!
+//! purpose:
+//!     every push used to take the state's write lock in its own handler.
+//!     with a few hundred spokes coming back at once (hub restart, network
+//!     back up, all of them replaying their buffers) the handlers piled up
+//!     on that lock, each holding its decoded batch in memory, and the
+//!     dashboard's readers waited behind all of them. now a push only
+//!     queues its batch; one merger task takes what has queued up and merges
+//!     it under a single lock. the queue holds cluster.limits.queue_depth
+//!     batches - a push that finds it full is answered 503 with a
+//!     Retry-After of cluster.limits.busy_retry_secs, and its batch id is
+//!     not recorded, so the spoke's retry isn't dropped as a replay.
+//!
+//! exposed:
+//!     GET /metrics: wasi_ingest_queue_depth, wasi_ingest_rejected_total
+//!
+//! relationships:
+//!     - used by: main.rs (push_handler queues, the merger task drains)
+//!     - reads: config.rs (PushLimits.queue_depth / busy_retry_secs)
+//!
+//! ==============================================================================
+
+use crate::domain::SensorReading;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// batches the merger takes per lock
+pub const MERGE_MAX: usize = 64;
+
+/// readings of one push, waiting for the merger
+pub struct Batch {
+    pub readings: Vec<SensorReading>,
+    /// when the hub got them (clock skew correction)
+    pub received_ms: u64,
+}
+
+/// cheap handle, clones queue into the same merger
+#[derive(Clone)]
+pub struct IngestQueue {
+    queue: mpsc::Sender<Batch>,
+    rejected: Arc<AtomicU64>,
+}
+
+/// a queue of `depth` batches and the merger's end of it
+pub fn channel(depth: usize) -> (IngestQueue, mpsc::Receiver<Batch>) {
+    let (queue, batches) = mpsc::channel(depth.max(1));
+    (IngestQueue { queue, rejected: Arc::default() }, batches)
+}
+
+impl IngestQueue {
+    /// a place in the queue, None (and counted) when it is full
+    pub fn reserve(&self) -> Option<mpsc::Permit<'_, Batch>> {
+        let permit = self.queue.try_reserve().ok();
+        if permit.is_none() {
+            self.rejected.fetch_add(1, Ordering::Relaxed);
+        }
+        permit
+    }
+
+    /// append the queue metrics in prometheus text format
+    pub fn write_prometheus(&self, out: &mut String) {
+        let depth = self.queue.max_capacity() - self.queue.capacity();
+        let _ = writeln!(
+            out,
+            "# HELP wasi_ingest_queue_depth Pushed batches waiting to be merged.\n# TYPE wasi_ingest_queue_depth gauge\nwasi_ingest_queue_depth {}",
+            depth
+        );
+        let _ = writeln!(
+            out,
+            "# HELP wasi_ingest_rejected_total Pushes answered 503 because the queue was full.\n# TYPE wasi_ingest_rejected_total counter\nwasi_ingest_rejected_total {}",
+            self.rejected.load(Ordering::Relaxed)
+        );
+    }
+}
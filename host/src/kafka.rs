@@ -0,0 +1,124 @@
+//! ==============================================================================
+//! kafka.rs - kafka producer for readings (feature = "kafka")
+//! ==============================================================================
+//!
+//! purpose:
+//!     feeds a site-wide streaming pipeline. every merged reading (the hub
+//!     sees the whole cluster) is produced as one json message to
+//!     kafka.topic, keyed by the node it came from, so all readings of a
+//!     node land in one partition in order:
+//!         key   = pi4-spoke
+//!         value = {"sensor_id": "pi4-spoke:dht22", "timestamp_ms": ..., "data": {...}}
+//!
+//! delivery:
+//!     librdkafka batches (linger_ms), compresses and retries on its own
+//!     thread; send() only queues. a full local queue drops the reading
+//!     rather than stalling the merge. failed deliveries are logged once
+//!     when they start and once when they stop. extra client settings
+//!     (sasl, ssl, message.timeout.ms, ...) go under [kafka.properties].
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.kafka), main.rs (startup)
+//!     - reads: config.rs (KafkaConfig)
+//!
+//! ==============================================================================
+
+use crate::config::KafkaConfig;
+use crate::domain::SensorReading;
+use crate::log_msg;
+use rdkafka::client::ClientContext;
+use rdkafka::config::ClientConfig;
+use rdkafka::error::KafkaError;
+use rdkafka::producer::{BaseRecord, DeliveryResult, ProducerContext, ThreadedProducer};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// logs delivery failures on their transitions instead of per message
+#[derive(Default)]
+struct Delivery {
+    failing: AtomicBool,
+    failed: AtomicU64,
+}
+
+impl ClientContext for Delivery {
+    fn error(&self, error: KafkaError, reason: &str) {
+        tracing::warn!("kafka client error: {} ({})", error, reason);
+    }
+}
+
+impl ProducerContext for Delivery {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: Self::DeliveryOpaque) {
+        match result {
+            Ok(_) => {
+                if self.failing.swap(false, Ordering::Relaxed) {
+                    let failed = self.failed.swap(0, Ordering::Relaxed);
+                    log_msg(&format!("📨 [KAFKA] Deliveries recovered ({} readings lost)", failed));
+                }
+            }
+            Err((e, _)) => {
+                self.failed.fetch_add(1, Ordering::Relaxed);
+                if !self.failing.swap(true, Ordering::Relaxed) {
+                    log_msg(&format!("❌ [KAFKA] Delivery failed: {}", e));
+                }
+            }
+        }
+    }
+}
+
+/// cheap handle to the shared producer
+#[derive(Clone)]
+pub struct KafkaProducer {
+    producer: Arc<ThreadedProducer<Delivery>>,
+    topic: String,
+    node_id: String,
+}
+
+impl KafkaProducer {
+    /// validate the config and create the producer (connects in the background)
+    pub fn start(config: &KafkaConfig, node_id: &str) -> anyhow::Result<Self> {
+        if config.brokers.is_empty() {
+            anyhow::bail!("kafka.brokers is not set");
+        }
+        if config.topic.is_empty() {
+            anyhow::bail!("kafka.topic is not set");
+        }
+        if !["0", "1", "all", "-1"].contains(&config.acks.as_str()) {
+            anyhow::bail!("kafka.acks must be 0, 1 or all (got '{}')", config.acks);
+        }
+        if !["none", "gzip", "snappy", "lz4", "zstd"].contains(&config.compression.as_str()) {
+            anyhow::bail!("unknown kafka.compression '{}'", config.compression);
+        }
+
+        let client_id = if config.client_id.is_empty() { node_id } else { &config.client_id };
+        let mut client = ClientConfig::new();
+        client
+            .set("bootstrap.servers", &config.brokers)
+            .set("client.id", client_id)
+            .set("acks", &config.acks)
+            .set("compression.type", &config.compression)
+            .set("linger.ms", config.linger_ms.to_string());
+        for (key, value) in &config.properties {
+            client.set(key, value);
+        }
+        let producer: ThreadedProducer<Delivery> = client.create_with_context(Delivery::default())?;
+
+        log_msg(&format!("📨 [KAFKA] Producing readings to '{}' on {}", config.topic, config.brokers));
+        Ok(Self { producer: Arc::new(producer), topic: config.topic.clone(), node_id: node_id.to_string() })
+    }
+
+    /// queue each reading. returns how many were queued.
+    pub fn publish(&self, readings: &[SensorReading]) -> usize {
+        let mut queued = 0;
+        for reading in readings {
+            let Ok(payload) = serde_json::to_vec(reading) else { continue };
+            let key = reading.sensor_id.split_once(':').map_or(self.node_id.as_str(), |(node, _)| node);
+            match self.producer.send(BaseRecord::to(&self.topic).key(key).payload(&payload)) {
+                Ok(()) => queued += 1,
+                Err((e, _)) => tracing::debug!("kafka produce for {} dropped: {}", reading.sensor_id, e),
+            }
+        }
+        queued
+    }
+}
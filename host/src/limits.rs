@@ -0,0 +1,65 @@
+//! ==============================================================================
+//! limits.rs - per-node push rate limiting on the hub
+//! ==============================================================================
+//!
+//! purpose:
+//!     one misconfigured spoke (polling every 100ms, a plugin emitting
+//!     thousands of readings) shouldn't be able to starve the hub. each node
+//!     gets a token bucket: cluster.limits.burst pushes back-to-back, refilled
+//!     at pushes_per_minute. a push without a token is rejected with 429 and
+//!     a Retry-After telling the spoke when the next one will be accepted.
+//!
+//! relationships:
+//!     - used by: main.rs (push_handler)
+//!     - reads: config.rs (ClusterConfig.limits)
+//!
+//! ==============================================================================
+
+use crate::config::PushLimits;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+pub struct PushLimiter {
+    limits: PushLimits,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+impl PushLimiter {
+    pub fn new(limits: PushLimits) -> Self {
+        Self { limits, buckets: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn limits(&self) -> &PushLimits {
+        &self.limits
+    }
+
+    /// take one push token for `node_id`. Err = rate exceeded, retry after the duration.
+    pub fn check(&self, node_id: &str) -> Result<(), Duration> {
+        if self.limits.pushes_per_minute == 0 {
+            return Ok(());
+        }
+        let capacity = self.limits.burst.max(1) as f64;
+        let per_sec = self.limits.pushes_per_minute as f64 / 60.0;
+        let now = Instant::now();
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets
+            .entry(node_id.to_string())
+            .or_insert(Bucket { tokens: capacity, updated: now });
+        bucket.tokens = (bucket.tokens + now.duration_since(bucket.updated).as_secs_f64() * per_sec).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_sec))
+        }
+    }
+}
@@ -0,0 +1,170 @@
+//! ==============================================================================
+//! logbuffer.rs - in-memory host log lines for /api/logs, lock-free
+//! ==============================================================================
+//!
+//! purpose:
+//!     every log_msg line lands here, from async handlers, the poll loop
+//!     and the plugin workers. the buffer used to be a VecDeque behind a
+//!     std mutex: a burst of lines (a hub restart, every spoke logging its
+//!     reconnect) had the tokio workers queueing on that lock, and
+//!     keep_errors scanned the deque for a victim while holding it. now
+//!     the lines sit in fixed rings of arc-swap slots: a writer takes the
+//!     next slot with an atomic counter and swaps its line in, a reader
+//!     loads the slots - neither ever waits for the other.
+//!
+//! sequence numbers:
+//!     every line gets the next number (from 1) as it is written. /ws/logs
+//!     entries carry it, and GET /api/logs?since=N returns the buffered
+//!     lines after N, so a stream consumer that reconnected or lagged
+//!     fetches exactly what it missed (or learns from `dropped` that the
+//!     buffer no longer has it).
+//!
+//! overflow (logging.overflow):
+//!     drop_oldest   one ring of logging.buffer_size lines
+//!     keep_errors   errors / warnings (❌ / ⚠️) get a ring of their own,
+//!                   so a flood of info lines can't push them out; the
+//!                   buffer shows all of them and fills up with the newest
+//!                   info lines. both rings hold buffer_size slots
+//!
+//! relationships:
+//!     - used by: main.rs (write_log_line, /api/logs, startup log file),
+//!       crash.rs (last lines of a crash report)
+//!     - uses: logstream.rs (levels)
+//!     - reads: config.rs (LoggingConfig.buffer_size / overflow)
+//!
+//! ==============================================================================
+
+use crate::config::LoggingConfig;
+use crate::logstream::Level;
+use arc_swap::{ArcSwap, ArcSwapOption};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
+
+/// lines kept before logging.buffer_size is applied
+const STARTUP_CAPACITY: usize = 100;
+
+/// a buffered line and its sequence number
+pub struct Line {
+    pub seq: u64,
+    pub text: String,
+}
+
+struct Ring {
+    slots: Box<[ArcSwapOption<Line>]>,
+    /// writes so far, the next slot is cursor % slots
+    cursor: AtomicU64,
+}
+
+impl Ring {
+    fn new(capacity: usize) -> Self {
+        Self { slots: (0..capacity).map(|_| ArcSwapOption::empty()).collect(), cursor: AtomicU64::new(0) }
+    }
+
+    fn push(&self, line: Arc<Line>) {
+        let slot = self.cursor.fetch_add(1, Ordering::Relaxed) % self.slots.len() as u64;
+        self.slots[slot as usize].store(Some(line));
+    }
+
+    /// the lines in it, oldest first
+    fn lines(&self) -> Vec<Arc<Line>> {
+        let mut lines: Vec<Arc<Line>> = self.slots.iter().filter_map(|slot| slot.load_full()).collect();
+        lines.sort_unstable_by_key(|line| line.seq);
+        lines
+    }
+}
+
+struct LogBuffer {
+    capacity: usize,
+    /// the last sequence number handed out
+    seq: AtomicU64,
+    lines: Ring,
+    /// errors / warnings, keep_errors only
+    errors: Option<Ring>,
+}
+
+impl LogBuffer {
+    fn new(capacity: usize, keep_errors: bool, seq: u64) -> Self {
+        Self {
+            capacity,
+            seq: AtomicU64::new(seq),
+            lines: Ring::new(capacity),
+            errors: keep_errors.then(|| Ring::new(capacity)),
+        }
+    }
+
+    fn insert(&self, line: Arc<Line>) {
+        match &self.errors {
+            Some(errors) if Level::of(&line.text) != Level::Info => errors.push(line),
+            _ => self.lines.push(line),
+        }
+    }
+
+    /// what the buffer shows, oldest first
+    fn view(&self) -> Vec<Arc<Line>> {
+        let Some(errors) = &self.errors else {
+            return self.lines.lines();
+        };
+        let mut kept = errors.lines();
+        let mut infos = self.lines.lines();
+        let room = self.capacity.saturating_sub(kept.len());
+        kept.extend(infos.drain(infos.len().saturating_sub(room)..));
+        kept.sort_unstable_by_key(|line| line.seq);
+        kept
+    }
+}
+
+static BUFFER: OnceLock<ArcSwap<LogBuffer>> = OnceLock::new();
+
+fn buffer() -> &'static ArcSwap<LogBuffer> {
+    BUFFER.get_or_init(|| ArcSwap::from_pointee(LogBuffer::new(STARTUP_CAPACITY, false, 0)))
+}
+
+/// apply logging.buffer_size / logging.overflow, keeping the lines logged so far
+pub fn configure(logging: &LoggingConfig) -> anyhow::Result<()> {
+    let keep_errors = match logging.overflow.as_str() {
+        "drop_oldest" => false,
+        "keep_errors" => true,
+        other => anyhow::bail!("unknown logging.overflow '{}' (expected drop_oldest or keep_errors)", other),
+    };
+    if logging.buffer_size == 0 {
+        anyhow::bail!("logging.buffer_size must be at least 1");
+    }
+    let old = buffer().load_full();
+    let new = LogBuffer::new(logging.buffer_size, keep_errors, old.seq.load(Ordering::Relaxed));
+    old.view().into_iter().for_each(|line| new.insert(line));
+    buffer().store(Arc::new(new));
+    Ok(())
+}
+
+/// add a line, returns its sequence number
+pub fn push(text: String) -> u64 {
+    let buffer = buffer().load();
+    let seq = buffer.seq.fetch_add(1, Ordering::Relaxed) + 1;
+    buffer.insert(Arc::new(Line { seq, text }));
+    seq
+}
+
+/// the buffered lines after `since` (0 = all of them), oldest first
+pub struct Snapshot {
+    pub lines: Vec<Arc<Line>>,
+    pub capacity: usize,
+    /// lines written since startup that the buffer no longer has
+    pub dropped: u64,
+    /// sequence number of the newest line written
+    pub seq: u64,
+}
+
+pub fn snapshot(since: u64) -> Snapshot {
+    let buffer = buffer().load();
+    let seq = buffer.seq.load(Ordering::Relaxed);
+    let mut lines = buffer.view();
+    let dropped = seq.saturating_sub(lines.len() as u64);
+    lines.retain(|line| line.seq > since);
+    Snapshot { lines, capacity: buffer.capacity, dropped, seq }
+}
+
+/// the newest `count` lines (crash reports)
+pub fn tail(count: usize) -> Vec<String> {
+    let lines = buffer().load().view();
+    lines[lines.len().saturating_sub(count)..].iter().map(|line| line.text.clone()).collect()
+}
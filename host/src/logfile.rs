@@ -0,0 +1,149 @@
+//! ==============================================================================
+//! logfile.rs - rolling log file for host logs ([logging.file])
+//! ==============================================================================
+//!
+//! purpose:
+//!     minimal images have no journald, and /api/logs only keeps the last
+//!     100 lines in memory. with [logging.file] enabled every log_msg line
+//!     and every tracing event (warnings, debug output) is appended to
+//!     logging.file.path as well.
+//!
+//! rotation:
+//!     the file is rotated when the next line would take it past
+//!     max_size_mb, and at local midnight when daily = true (a file left
+//!     over from an earlier day is rotated on startup). rotated files are
+//!     numbered like logrotate's: host.log.1 is the newest, host.log.N the
+//!     oldest, and the one past max_files is deleted.
+//!
+//! tail:
+//!     tail() reads the last lines of a log file without loading it: it
+//!     seeks to the last TAIL_BYTES and reads only those, asynchronously,
+//!     so a log that grew to gigabytes can't stall the api's executor.
+//!
+//! relationships:
+//!     - used by: main.rs (log_msg, tracing subscriber, /api/logs tail of
+//!       wasi-logs.log)
+//!     - reads: config.rs (LogFileConfig)
+//!
+//! ==============================================================================
+
+use crate::config::LogFileConfig;
+use chrono::{Local, NaiveDate};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+/// how far back from the end tail() reads
+const TAIL_BYTES: u64 = 64 * 1024;
+
+static LOG_FILE: OnceLock<Mutex<RollingFile>> = OnceLock::new();
+
+struct RollingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    day: NaiveDate,
+    max_bytes: u64,
+    daily: bool,
+    max_files: usize,
+}
+
+/// open the log file. without this call (logging.file.enabled = false) nothing is written.
+pub fn init(config: &LogFileConfig) -> anyhow::Result<()> {
+    if !config.enabled {
+        return Ok(());
+    }
+    let path = PathBuf::from(&config.path);
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+    let meta = file.metadata()?;
+    let day = meta
+        .modified()
+        .map(|t| chrono::DateTime::<Local>::from(t).date_naive())
+        .unwrap_or_else(|_| Local::now().date_naive());
+    let _ = LOG_FILE.set(Mutex::new(RollingFile {
+        path,
+        file,
+        size: meta.len(),
+        day,
+        max_bytes: config.max_size_mb * 1024 * 1024,
+        daily: config.daily,
+        max_files: config.max_files,
+    }));
+    Ok(())
+}
+
+/// append one line (a newline is added)
+pub fn write_line(line: &str) {
+    let Some(file) = LOG_FILE.get() else { return };
+    let mut file = file.lock().unwrap();
+    let _ = file.append(format!("{}\n", line).as_bytes());
+}
+
+/// io::Write target for the tracing subscriber; a no-op until init
+pub struct Writer;
+
+impl Write for Writer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if let Some(file) = LOG_FILE.get() {
+            file.lock().unwrap().append(buf)?;
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl RollingFile {
+    fn append(&mut self, data: &[u8]) -> std::io::Result<()> {
+        let today = Local::now().date_naive();
+        let too_big = self.max_bytes > 0 && self.size > 0 && self.size + data.len() as u64 > self.max_bytes;
+        if too_big || (self.daily && today != self.day) {
+            self.rotate()?;
+            self.day = today;
+        }
+        self.file.write_all(data)?;
+        self.size += data.len() as u64;
+        Ok(())
+    }
+
+    /// host.log -> host.log.1 -> ... -> host.log.{max_files}, then start a new file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let numbered = |n: usize| PathBuf::from(format!("{}.{}", self.path.display(), n));
+        if self.max_files > 0 {
+            let _ = std::fs::remove_file(numbered(self.max_files));
+            for n in (1..self.max_files).rev() {
+                let _ = std::fs::rename(numbered(n), numbered(n + 1));
+            }
+            std::fs::rename(&self.path, numbered(1))?;
+        }
+        self.file = OpenOptions::new().create(true).write(true).truncate(true).open(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// the last `lines` non-empty lines of the file at `path`, from at most its
+/// last TAIL_BYTES (a line cut by that window is left out)
+pub async fn tail(path: impl AsRef<Path>, lines: usize) -> std::io::Result<Vec<String>> {
+    let mut file = tokio::fs::File::open(path).await?;
+    let size = file.metadata().await?.len();
+    let start = size.saturating_sub(TAIL_BYTES);
+    file.seek(std::io::SeekFrom::Start(start)).await?;
+    let mut bytes = Vec::with_capacity((size - start) as usize);
+    file.take(TAIL_BYTES).read_to_end(&mut bytes).await?;
+    let text = String::from_utf8_lossy(&bytes);
+    let mut window: Vec<&str> = text.lines().collect();
+    if start > 0 && !window.is_empty() {
+        window.remove(0);
+    }
+    let mut tail: Vec<String> = window.into_iter().rev().filter(|l| !l.trim().is_empty()).take(lines).map(str::to_string).collect();
+    tail.reverse();
+    Ok(tail)
+}
@@ -0,0 +1,139 @@
+//! ==============================================================================
+//! loglayer.rs - tracing layer feeding the host log (buffer, stdout, file, /ws/logs)
+//! ==============================================================================
+//!
+//! purpose:
+//!     host messages went through log_msg() into the /api/logs buffer while
+//!     tracing::warn! / error! from runtime.rs, hal.rs, tls.rs ... only
+//!     reached the terminal (and only with RUST_LOG set). both now take the
+//!     same path: log_msg() is a tracing event with target TARGET, and this
+//!     layer writes the events it accepts as host log lines - log buffer,
+//!     stdout, [logging.file] and /ws/logs.
+//!
+//! accepted events:
+//!     - every log_msg() line, as written ("⚠️ [POLL] Cycle took ...")
+//!     - events from the host's own modules at logging.level or above
+//!       (set_level, changed at runtime by a config reload)
+//!     - warnings and errors from dependencies (wasmtime, rumqttc, ...)
+//!     other events get the log_msg look: "❌ " / "⚠️ " by level, then
+//!     "[SOURCE] " from the module ("wasi_host::runtime" -> "[RUNTIME]"),
+//!     the message and any extra fields as key=value.
+//!
+//! terminal / file output:
+//!     the fmt layers (RUST_LOG) skip what this layer accepts, so a line is
+//!     never printed twice. before the subscriber is installed (config
+//!     loading) log_msg() writes its line directly.
+//!
+//! relationships:
+//!     - used by: main.rs (log_msg, tracing subscriber setup)
+//!     - reads: config.rs (logging.level)
+//!
+//! ==============================================================================
+
+use std::fmt::Write;
+use std::sync::RwLock;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Metadata, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+
+/// target of log_msg() events
+pub const TARGET: &str = "host";
+
+/// logging.level in effect
+static LEVEL: RwLock<Level> = RwLock::new(Level::INFO);
+
+/// parse logging.level ("trace", "debug", "info", "warn", "error")
+pub fn parse_level(level: &str) -> anyhow::Result<Level> {
+    level
+        .parse()
+        .map_err(|_| anyhow::anyhow!("unknown logging.level '{}' (expected trace, debug, info, warn or error)", level))
+}
+
+/// set logging.level (startup and config reload)
+pub fn set_level(level: Level) {
+    *LEVEL.write().unwrap() = level;
+    // the callsites have cached whether anyone is interested in them
+    tracing::callsite::rebuild_interest_cache();
+}
+
+/// true for the events the host log takes (metadata only, so the fmt layers can filter on it)
+pub fn accepts(metadata: &Metadata<'_>) -> bool {
+    if !metadata.is_event() || metadata.fields().field("message").is_none() {
+        return false; // spans, metric events (telemetry.rs)
+    }
+    match metadata.target() {
+        TARGET => true,
+        target if target.starts_with(env!("CARGO_CRATE_NAME")) => *metadata.level() <= *LEVEL.read().unwrap(),
+        _ => *metadata.level() <= Level::WARN,
+    }
+}
+
+/// writes accepted events through `emit` (main.rs: buffer, stdout, file, stream)
+pub struct HostLogLayer {
+    emit: fn(&str),
+}
+
+impl HostLogLayer {
+    pub fn new(emit: fn(&str)) -> Self {
+        Self { emit }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for HostLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        if metadata.target() == TARGET {
+            (self.emit)(&fields.message);
+            return;
+        }
+        let mark = match *metadata.level() {
+            Level::ERROR => "❌ ",
+            Level::WARN => "⚠️ ",
+            _ => "",
+        };
+        (self.emit)(&format!("{}[{}] {}{}", mark, source(metadata), fields.message, fields.extra));
+    }
+}
+
+/// "wasi_host::runtime" -> "RUNTIME", "wasi_host" (main.rs) -> "HOST", "rumqttc::state" -> "RUMQTTC"
+fn source(metadata: &Metadata<'_>) -> String {
+    let target = metadata.target();
+    let module = match target.split_once("::") {
+        Some((env!("CARGO_CRATE_NAME"), rest)) => rest.split("::").next().unwrap_or(rest),
+        Some((crate_name, _)) => crate_name,
+        None if target == env!("CARGO_CRATE_NAME") => "host",
+        None => target,
+    };
+    module.to_ascii_uppercase()
+}
+
+#[derive(Default)]
+struct Fields {
+    message: String,
+    /// " key=value" for every other field
+    extra: String,
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message.push_str(value),
+            name => {
+                let _ = write!(self.extra, " {}={}", name, value);
+            }
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        match field.name() {
+            "message" => {
+                let _ = write!(self.message, "{:?}", value);
+            }
+            name => {
+                let _ = write!(self.extra, " {}={:?}", name, value);
+            }
+        }
+    }
+}
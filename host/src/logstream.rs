@@ -0,0 +1,171 @@
+//! ==============================================================================
+//! logstream.rs - live host log entries over a websocket (GET /ws/logs)
+//! ==============================================================================
+//!
+//! purpose:
+//!     /api/logs returns the whole buffer, so the dashboard re-fetched it
+//!     every few seconds. /ws/logs pushes each new log_msg line the moment
+//!     it is logged, as a structured entry:
+//!         {"type": "log", "seq": 4711, "timestamp_ms": 1730000000000,
+//!          "level": "warn", "source": "POLL",
+//!          "message": "⚠️ [POLL] Cycle took 9.1s, ...",
+//!          "line": "[2026/10/17 @ 10:21pm] ⚠️ [POLL] Cycle took 9.1s, ..."}
+//!     level is error (❌), warn (⚠️) or info; source is the [TAG] the
+//!     message starts with, "HOST" for untagged lines. seq numbers the
+//!     host's lines (logbuffer.rs): after a reconnect or a "lagged" frame,
+//!     GET /api/logs?since=<last seq> has the lines in between.
+//!
+//! filters:
+//!     the client may send a subscribe message at any time; it replaces
+//!     the current filters (default: everything) and is acknowledged:
+//!         {"type": "subscribe", "level": "warn", "sources": ["POLL", "KAFKA"]}
+//!         {"type": "subscribed", "level": "warn", "sources": ["POLL", "KAFKA"]}
+//!     level is the minimum level sent, sources match case-insensitively
+//!     (empty = all). a client too slow to keep up gets
+//!     {"type": "lagged", "dropped": n} instead of the entries it missed.
+//!
+//! relationships:
+//!     - used by: main.rs (log_msg publishes, /ws/logs route), logbuffer.rs (levels)
+//!
+//! ==============================================================================
+
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+use tokio::sync::broadcast;
+
+/// entries buffered per subscriber before it counts as lagging
+const CHANNEL: usize = 256;
+
+static STREAM: OnceLock<broadcast::Sender<LogEntry>> = OnceLock::new();
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Info,
+    Warn,
+    Error,
+}
+
+impl Level {
+    /// level of a log_msg line, from its ❌ / ⚠️ marker
+    pub fn of(message: &str) -> Self {
+        if message.contains('❌') {
+            Level::Error
+        } else if message.contains("⚠️") {
+            Level::Warn
+        } else {
+            Level::Info
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LogEntry {
+    /// the line's sequence number in the log buffer
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub level: Level,
+    pub source: String,
+    pub message: String,
+    /// the line as it appears in /api/logs and the log file
+    pub line: String,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Frame<'a> {
+    Log(&'a LogEntry),
+    Subscribed(&'a Filter),
+    Lagged { dropped: u64 },
+}
+
+#[derive(Deserialize, Serialize, Default)]
+struct Filter {
+    #[serde(default)]
+    level: Option<Level>,
+    #[serde(default)]
+    sources: Vec<String>,
+}
+
+impl Filter {
+    fn accepts(&self, entry: &LogEntry) -> bool {
+        self.level.is_none_or(|min| entry.level >= min)
+            && (self.sources.is_empty() || self.sources.iter().any(|s| s.eq_ignore_ascii_case(&entry.source)))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe(Filter),
+}
+
+fn sender() -> &'static broadcast::Sender<LogEntry> {
+    STREAM.get_or_init(|| broadcast::channel(CHANNEL).0)
+}
+
+/// hand a logged line to the connected streams (free when nobody listens)
+pub fn publish(seq: u64, message: &str, line: &str) {
+    let tx = sender();
+    if tx.receiver_count() == 0 {
+        return;
+    }
+    let _ = tx.send(LogEntry {
+        seq,
+        timestamp_ms: crate::domain::now_ms(),
+        level: Level::of(message),
+        source: source_of(message),
+        message: message.to_string(),
+        line: line.to_string(),
+    });
+}
+
+/// "⚠️ [POLL] Cycle took ..." -> "POLL"
+fn source_of(message: &str) -> String {
+    message
+        .trim_start_matches(|c: char| c != '[' && !c.is_alphanumeric())
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+        .map(|(tag, _)| tag.to_string())
+        .filter(|tag| !tag.is_empty() && !tag.contains(' '))
+        .unwrap_or_else(|| "HOST".to_string())
+}
+
+/// stream entries to one client until it disconnects
+pub async fn serve(socket: axum::extract::ws::WebSocket) {
+    use axum::extract::ws::Message;
+
+    let mut entries = sender().subscribe();
+    let (mut sink, mut stream) = socket.split();
+    let mut filter = Filter::default();
+
+    loop {
+        let frame = tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<ClientMessage>(&text) {
+                    Ok(ClientMessage::Subscribe(subscribe)) => {
+                        filter = subscribe;
+                        serde_json::to_string(&Frame::Subscribed(&filter))
+                    }
+                    Err(e) => {
+                        tracing::debug!("bad /ws/logs message: {}", e);
+                        continue;
+                    }
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue, // ping/pong/binary
+            },
+            entry = entries.recv() => match entry {
+                Ok(entry) if filter.accepts(&entry) => serde_json::to_string(&Frame::Log(&entry)),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(dropped)) => serde_json::to_string(&Frame::Lagged { dropped }),
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+        };
+        let Ok(frame) = frame else { continue };
+        if sink.send(Message::Text(frame)).await.is_err() {
+            break;
+        }
+    }
+}
@@ -11,31 +11,146 @@
 //!     1. loads configuration from toml (hub.toml, spoke.toml, etc.)
 //!     2. initializes shared state for sensor readings
 //!     3. creates the wasm runtime with all enabled plugins
-//!     4. starts an axum http server with api endpoints
+//!     4. starts an axum http server with api endpoints (not headless)
 //!     5. runs the main polling loop that:
-//!        - toggles led 0 as a heartbeat indicator
+//!        - toggles led 0 as a heartbeat indicator, writes the led strip once per cycle
 //!        - checks for plugin hot-reloads
 //!        - polls all sensors via wasm plugins
 //!        - pushes data to hub (if spoke) or updates local state (if hub)
+//!     6. in pull mode, the hub fetches /api/readings from each spoke instead
+//!
+//! command line:
+//!     wasi-host                      run the host (config/host.toml)
+//!     wasi-host --tui [--url URL]    live terminal dashboard of a running host
+//!     wasi-host validate-config [path]   check a config (pins, i2c, plugin files, rules)
+//!     wasi-host config-schema        json schema of the config, for provisioning pipelines
+//!     wasi-host init-config [path]   write a commented host.toml with every default
+//!     --profile NAME                 layer config/host.NAME.toml over host.toml
+//!     wasi-host --headless           no web server, only the poll / push loop ([api] enabled = false)
+//!     wasi-host --dry-run            log gpio / led / buzzer / fan writes instead of performing them
+//!     (no config file yet: a setup page on port 3000 writes one first, see provision.rs)
 //!
 //! http endpoints:
 //!     GET  /             - dashboard html (rendered by wasm plugin)
-//!     GET  /api/readings - json sensor readings
-//!     GET  /api/logs     - combined host + wasm plugin logs
-//!     POST /api/buzzer   - control buzzer (forwards to spoke if hub)
-//!     POST /api/buzzer/test - manual 3-beep test
-//!     POST /push         - hub receives data from spokes
+//!     GET  /api/readings - sensor readings with field units (json, or cbor/msgpack via Accept;
+//!                          ?units=raw = stored si values, no display conversion)
+//!     GET  /api/schema   - unit, display name and valid range of every known field
+//!     GET  /api/sensors  - sensor inventory: node, plugin, field schema, last value / seen, sampling stats
+//!     GET  /api/events   - recent events (anomalies, ...), newest first
+//!     GET  /api/alerts   - active alerts and running silences
+//!     GET  /api/alerts/history       - stored alert transitions, newest first
+//!     POST /api/alerts/{id}/ack      - acknowledge an active alert
+//!     POST /api/alerts/{id}/silence  - mute an alert id / glob (?duration=2h), DELETE lifts it (admin token)
+//!     GET  /api/history  - stored readings of one sensor (?sensor_id=&from=&to=&limit=)
+//!     GET  /api/aggregate - windowed stats of one sensor (?sensor=&fn=avg|min|max&window=1h&range=24h)
+//!     GET  /api/chart    - one sensor binned to ~N points for plotting (?sensor=&range=7d&points=300)
+//!     GET  /api/logs     - combined host + wasm plugin logs, with the buffer's dropped-line count
+//!                          (?since=<seq>: the host lines after a /ws/logs entry's seq)
+//!     GET  /api/audit    - buzzer / fan / led actions and who caused them (?from=&to=&actor=&action=&limit=)
+//!     GET  /api/selftest - startup hardware probes (i2c, gpio, python modules, led strip)
+//!     GET  /api/snapshot - .tar.gz of state, config, history and plugins (?plugins=false, admin token)
+//!     POST /api/restore  - restore a snapshot archive onto this node (?force=true, admin token)
+//!     GET  /reports/latest - newest scheduled summary report (html or markdown)
+//!     POST /api/buzzer   - control buzzer (queued for cluster.buzzer_node if remote,
+//!                          429 while the buzzer pin's queue is full - actuators.rs)
+//!     POST /api/buzzer/test - manual 3-beep test (429 likewise)
+//!     POST /push         - hub receives data from spokes (acks batch ids, drops replays;
+//!                          readings older than a sensor's current one go to history only)
+//!     POST /push/backfill - buffered historical readings, reply counts merged / historical
+//!     GET  /ws           - persistent spoke websocket (readings up, commands down)
+//!     GET  /ws/logs      - live structured log entries (level / source filters)
+//!     GET  /health       - liveness probe (spoke failover)
+//!     POST /api/crash    - hub stores a spoke's crash report (crash.spoke_dir, cluster client cert)
+//!     POST /heartbeat    - spoke liveness ping {node_id, uptime_secs, version}
+//!     GET  /api/nodes    - known nodes with last-seen / stale state
+//!     DELETE /api/nodes/{id}       - decommission a spoke: purge its data, block its pushes (admin token)
+//!     POST /api/nodes/{id}/register - lift a decommission block (admin token)
+//!     GET  /api/cluster  - topology: this hub, its spokes, link health, plugins, poll loop timing
+//!     GET  /api/config/effective - the config the host runs with (redacted) and keys waiting for a restart
+//!     GET  /api/role     - the role this node runs as, and the one host.toml sets
+//!     POST /api/role     - switch between hub / spoke / standalone without a restart (admin token)
+//!     GET  /api/maintenance - the running maintenance window, if any
+//!     POST /api/maintenance - pause polling / alerting / hardware writes ({enabled, duration}, admin token)
+//!     POST /api/command  - queue a command (buzz/fan/set-led/reload-plugin/set-role) for a node (admin token)
+//!     GET  /api/command  - spokes long-poll their queued commands (cluster client cert)
+//!     GET  /api/command/{id}         - command status
+//!     POST /api/command/{id}/result  - spokes report command outcome (cluster client cert)
+//!     GET  /api/command/{id}/artifact - payload of a deploy-plugin command (cluster client cert)
+//!     PUT  /api/plugins/{name}       - upload a plugin .wasm to this node (hot reload, admin token)
+//!     GET  /api/plugins/{name}/stats - calls, errors, traps, p50/p99 latency, last failure
+//!     GET  /api/plugins/{name}/profile - guest profile since the last download ([plugin_profiler])
+//!                          (trap, wasm backtrace, recent plugin output)
+//!     GET  /metrics      - prometheus metrics (per-plugin calls / errors / traps / latency, poll loop timing,
+//!                          plugin / process memory)
+//!     GET  /api/system   - process rss / cpu time and linear memory / tables of each plugin store
+//!     POST /api/nodes/{id}/plugins/{name} - deploy a plugin .wasm to a node (admin token)
+//!     GET  /api/nodes/{id}/config    - hub-managed config overlay for a node
 //!
 //! relationships:
 //!     - uses: config.rs (loads toml configuration)
 //!     - uses: runtime.rs (wasm plugin loading and execution)
 //!     - uses: domain.rs (appstate and sensorreading types)
 //!     - uses: hal.rs (hardware abstraction for led heartbeat)
+//!     - uses: tls.rs (optional mtls for the hub/spoke channel)
+//!     - uses: cluster.rs (hub pull mode, failover)
+//!     - uses: commands.rs (hub → spoke command channel)
+//!     - uses: node_config.rs (centralized spoke config overlays)
+//!     - uses: ws.rs (persistent spoke ↔ hub websocket)
+//!     - uses: codec.rs (json / cbor / msgpack bodies for /push and /api/readings)
+//!     - uses: aggregate.rs (cluster-level synthetic readings on the hub)
+//!     - uses: derived.rs (dew point / heat index / absolute humidity fields)
+//!     - uses: calibrate.rs (per-sensor offset / scale corrections)
+//!     - uses: validate.rs (range / nan / jump checks on polled and pushed readings)
+//!     - uses: anomaly.rs (ewma z-score outlier flags)
+//!     - uses: recent.rs (last readings per sensor, served to plugins)
+//!     - uses: cadence.rs (per-sensor sampling stats in /api/sensors)
+//!     - uses: logfile.rs (optional rolling log file)
+//!     - uses: logstream.rs (live log entries for /ws/logs)
+//!     - uses: loglayer.rs (tracing events into the log buffer)
+//!     - uses: memory.rs (plugin store / process memory for /api/system)
+//!     - uses: crash.rs (panic hook crash reports, /api/crash on the hub)
+//!     - uses: config_reload.rs (applies host.toml edits while running)
+//!     - uses: config_check.rs (validate-config subcommand)
+//!     - uses: config_init.rs (init-config subcommand)
+//!     - uses: selftest.rs (startup hardware probes, /api/selftest)
+//!     - uses: pins.rs (gpio pin conflicts at startup)
+//!     - uses: poll_timing.rs (poll cycle duration, overruns and drift)
+//!     - uses: backoff.rs (failing sensor plugins polled less often)
+//!     - uses: plugin_stats.rs (per-plugin call counters for /api/plugins/{name}/stats, /metrics)
+//!     - uses: profiler.rs (guest profile download, /api/plugins/{name}/profile)
+//!     - uses: plugin_output.rs (recent plugin stdout / stderr for failure records)
+//!     - uses: telemetry.rs (optional otlp traces / metrics, "otel" feature)
+//!     - uses: events.rs (event bus behind /api/events)
+//!     - uses: alerts.rs (declarative alert rules, pending / firing / resolved)
+//!     - uses: notify.rs (slack / telegram / email notifications)
+//!     - uses: reports.rs (scheduled summary reports)
+//!     - uses: nodes.rs (node registry, heartbeats, stale-node detection)
+//!     - uses: role.rs (live hub / spoke / standalone switching, role tasks)
+//!     - uses: limits.rs (per-node push rate limiting)
+//!     - uses: ingest.rs (bounded queue between /push and the merger task)
+//!     - uses: storage.rs (sqlite history of readings)
+//!     - uses: influx.rs (optional influxdb line protocol export)
+//!     - uses: sink.rs (optional [[sinks]] http forwarding)
+//!     - uses: schema.rs (field units / ranges in the readings api)
+//!     - uses: units.rs (imperial display units in the api and dashboard)
+//!     - uses: catalog.rs (sensor inventory for /api/sensors)
+//!     - uses: snapshot.rs (backup / restore archives)
+//!     - uses: admin.rs (admin token on snapshot / restore / plugin upload and deploy)
+//!     - uses: secrets.rs (${NAME} secrets in the config)
+//!     - uses: audit.rs (append-only log of hardware actions)
+//!     - uses: export.rs (daily parquet export, "parquet" feature)
+//!     - uses: mqtt.rs (optional mqtt telemetry, "mqtt" feature)
+//!     - uses: kafka.rs (optional kafka producer, "kafka" feature)
+//!     - uses: nats.rs (optional nats/jetstream transport, "nats" feature)
+//!     - uses: coap.rs (optional coap/cbor ingest, "coap" feature)
+//!     - uses: tui.rs (--tui live terminal dashboard, "tui" feature)
 //!
 //! log buffer:
-//!     the log_msg() function adds messages to a global buffer that the
-//!     /api/logs endpoint returns. note: wasm plugin stdout (python print)
-//!     goes to terminal only, not this buffer. this is a known limitation.
+//!     log_msg() messages and tracing warnings / errors (loglayer.rs) go to
+//!     a global lock-free buffer (logbuffer.rs) that the /api/logs endpoint
+//!     returns. note: wasm
+//!     plugin stdout (python print) goes to terminal only, not this buffer.
+//!     this is a known limitation.
 //!
 //! ==============================================================================
 
@@ -43,18 +158,84 @@ mod config;
 mod runtime;
 mod domain;
 mod hal;
+mod actuators;
+mod tls;
+mod cluster;
+mod commands;
+mod node_config;
+mod ws;
+mod codec;
+mod aggregate;
+mod derived;
+mod calibrate;
+mod anomaly;
+mod events;
+mod recent;
+mod cadence;
+mod alerts;
+mod notify;
+mod reports;
+mod nodes;
+mod limits;
+mod ingest;
+mod storage;
+mod influx;
+mod sink;
+mod schema;
+mod validate;
+mod units;
+mod catalog;
+mod snapshot;
+mod admin;
+mod audit;
+mod logbuffer;
+mod logfile;
+mod logstream;
+mod loglayer;
+mod crash;
+mod config_reload;
+mod secrets;
+mod config_check;
+mod config_init;
+mod provision;
+mod selftest;
+mod telemetry;
+mod plugin_stats;
+mod plugin_output;
+mod plugin_worker;
+mod memory;
+mod poll_timing;
+mod backoff;
+mod watchdog;
+mod profiler;
+mod pins;
+mod role;
+mod maintenance;
+#[cfg(feature = "parquet")]
+mod export;
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "coap")]
+mod coap;
+#[cfg(feature = "tui")]
+mod tui;
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "hardware")]
+mod python_driver;
 
 use anyhow::Result;
 use axum::{
     Router,
-    routing::{get, post},
+    routing::{delete, get, post, put},
     response::{Html, Json, IntoResponse},
-    extract::{State, Query},
+    extract::{State, Query, DefaultBodyLimit, ConnectInfo},
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use std::sync::{Mutex, OnceLock};
-use std::collections::VecDeque;
+use tracing::Instrument;
 use tower_http::cors::CorsLayer;
 use crate::domain::{AppState, SensorReading};
 
@@ -91,24 +272,36 @@ fn format_sensor_summary(sensor_id: &str, data: &serde_json::Value) -> String {
 }
 
 // ==============================================================================
-// log buffer - stores messages for /api/logs endpoint
+// host log lines
 // ==============================================================================
 //
-// this is a circular buffer that holds the last 100 log messages.
-// messages are added via log_msg() which also prints to terminal.
+// lines are added via write_log_line() - for log_msg() messages and
+// tracing events at logging.level (loglayer.rs) - which keeps them in the
+// /api/logs buffer (logbuffer.rs), prints them to terminal, appends them
+// to the [logging.file] log file when enabled and streams them to
+// /ws/logs clients.
 // note: wasm plugin print() statements bypass this buffer and go
 // directly to terminal via inherit_stdio().
 
-static LOG_BUFFER: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
-
-fn get_log_buffer() -> &'static Mutex<VecDeque<String>> {
-    LOG_BUFFER.get_or_init(|| Mutex::new(VecDeque::with_capacity(100)))
+/// log a host message. this is the primary logging function for host-side
+/// messages: a tracing event that loglayer.rs turns into a log line (buffer,
+/// stdout, log file, /ws/logs). the tracing level follows the ❌ / ⚠️ marker.
+fn log_msg(msg: &str) {
+    if !tracing::dispatcher::has_been_set() {
+        // config still loading, no subscriber yet
+        write_log_line(msg);
+        return;
+    }
+    match logstream::Level::of(msg) {
+        logstream::Level::Error => tracing::error!(target: loglayer::TARGET, "{}", msg),
+        logstream::Level::Warn => tracing::warn!(target: loglayer::TARGET, "{}", msg),
+        logstream::Level::Info => tracing::info!(target: loglayer::TARGET, "{}", msg),
+    }
 }
 
-/// add a message to the log buffer with est timestamp.
-/// this is the primary logging function for host-side messages.
-/// messages are also printed to stdout for terminal viewing.
-fn log_msg(msg: &str) {
+/// add a line to the log buffer with est timestamp, print it to stdout,
+/// append it to the log file and stream it to /ws/logs
+fn write_log_line(msg: &str) {
     use chrono::{Utc, FixedOffset};
     
     // est is utc-5
@@ -117,15 +310,15 @@ fn log_msg(msg: &str) {
     let timestamp = now.format("[%Y/%m/%d @ %I:%M%P]").to_string();
     let timestamped_msg = format!("{} {}", timestamp, msg);
     
-    if let Ok(mut buf) = get_log_buffer().lock() {
-        if buf.len() >= 100 {
-            buf.pop_front();
-        }
-        buf.push_back(timestamped_msg.clone());
-    }
+    let seq = logbuffer::push(timestamped_msg.clone());
     println!("{}", timestamped_msg);
+    logfile::write_line(&timestamped_msg);
+    logstream::publish(seq, msg, &timestamped_msg);
 }
 
+/// max size of an uploaded plugin component (python plugins are ~40mb)
+const PLUGIN_UPLOAD_LIMIT: usize = 128 * 1024 * 1024;
+
 // ==============================================================================
 // api state - shared across all http handlers
 // ==============================================================================
@@ -140,64 +333,353 @@ struct ApiState {
     runtime: runtime::WasmRuntime,
     #[allow(dead_code)]
     config: config::HostConfig,
+    commands: Arc<commands::CommandQueue>,
+    nodes: Arc<nodes::NodeRegistry>,
+    limiter: Arc<limits::PushLimiter>,
+    /// pushes waiting for the merger (hub)
+    ingest: ingest::IngestQueue,
+    timing: Arc<poll_timing::PollTiming>,
+    store: Option<Arc<storage::Store>>,
+    units: Arc<units::Units>,
+    validator: Arc<validate::Validator>,
+    events: Arc<events::EventBus>,
+    alerts: Arc<alerts::AlertEngine>,
+    reports: Arc<reports::Reports>,
+    /// startup hardware probes (None with selftest.enabled = false)
+    selftest: Arc<Option<selftest::SelfTestReport>>,
+    /// spoke push targets and link health (empty on a hub)
+    hubs: Arc<cluster::HubFailover>,
+    started: std::time::Instant,
 }
 
 // ==============================================================================
 // main - entry point
 // ==============================================================================
 
+/// command line - everything else is configured in config/host.toml
+#[derive(clap::Parser)]
+#[command(version, about = "WASI sensor host")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// show a live terminal dashboard of a running host instead of starting one
+    #[arg(long)]
+    tui: bool,
+    /// api of the host the --tui dashboard watches
+    #[arg(long, default_value = "http://localhost:3000")]
+    #[cfg_attr(not(feature = "tui"), allow(dead_code))]
+    url: String,
+    /// layer config/host.{PROFILE}.toml on top of host.toml (e.g. dev, prod, pi-zero)
+    #[arg(long, global = true)]
+    profile: Option<String>,
+    /// run without the web server, only the poll / push loop (api.enabled = false)
+    #[arg(long)]
+    headless: bool,
+    /// log gpio / led / buzzer / fan writes instead of performing them, reads still happen
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(clap::Subcommand)]
+enum Command {
+    /// parse and cross-check a config file without starting the host
+    ValidateConfig {
+        /// config file (default: host.toml / .yaml / .yml / .json in config/, then ../config/)
+        path: Option<std::path::PathBuf>,
+    },
+    /// print the json schema of host.toml (as json) for provisioning tools
+    ConfigSchema,
+    /// write a host.toml with every section, its defaults and comments
+    InitConfig {
+        /// where to write it ("-" = stdout)
+        #[arg(default_value = "host.toml")]
+        path: std::path::PathBuf,
+        /// overwrite an existing file
+        #[arg(long)]
+        force: bool,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // initialize tracing/logging subscriber
-    tracing_subscriber::fmt()
-        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
-        .init();
+    let cli = <Cli as clap::Parser>::parse();
+    if let Some(profile) = &cli.profile {
+        config::set_profile(profile)?;
+    }
+    match cli.command {
+        Some(Command::ValidateConfig { path }) => {
+            // non-zero exit status on errors, for deploy scripts
+            if !config_check::run(path)? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Some(Command::ConfigSchema) => {
+            println!("{}", config::json_schema());
+            return Ok(());
+        }
+        Some(Command::InitConfig { path, force }) => return config_init::run(&path, force),
+        None => {}
+    }
+    // a --profile typo shouldn't quietly fall back to the built-in defaults
+    let missing_profile = config::HostConfig::find_config_file()
+        .and_then(|path| config::HostConfig::profile_file(&path))
+        .filter(|file| !file.exists());
+    if let Some(file) = missing_profile {
+        anyhow::bail!("profile selected but {} does not exist", file.display());
+    }
+    if cli.tui {
+        #[cfg(feature = "tui")]
+        return tui::run(&cli.url).await;
+        #[cfg(not(feature = "tui"))]
+        anyhow::bail!("--tui needs a build with the \"tui\" feature (cargo build --features tui)");
+    }
 
     log_msg("===========================================================");
     log_msg("  WASI Host - Standalone Edition");
     log_msg("===========================================================");
     
-    // 1. load config from toml file
-    let config = config::HostConfig::load_or_default();
+    // 0. first run: no config file yet - serve the setup page until one is written
+    if config::HostConfig::find_config_file().is_none() && !cli.headless {
+        provision::run().await?;
+    }
+
+    // 1. load config from toml file (spokes merge the hub's overlay on top)
+    let mut config = config::HostConfig::load_or_default();
+    let mut overlay_version = String::new();
+    if config.cluster.role == "spoke" {
+        if let Some(hub) = config.cluster.push_targets().first() {
+            let client = tls::build_client(&config.cluster.tls, &config.http_client)?;
+            let base = cluster::hub_base(hub).to_string();
+            (config, overlay_version) = node_config::apply_startup_overlay(config, &client, &base).await;
+        }
+    }
+    if cli.headless {
+        config.api.enabled = false;
+    }
+    hal::set_dry_run(cli.dry_run);
     config.print_summary();
+    logbuffer::configure(&config.logging)?;
+    // rolling log file - starts with the lines logged so far
+    logfile::init(&config.logging.file)?;
+    logbuffer::snapshot(0).lines.iter().for_each(|line| logfile::write_line(&line.text));
+    // initialize tracing/logging subscriber: log_msg lines and events at logging.level
+    // become host log lines (loglayer.rs), the rest goes to stdout and [logging.file]
+    // as RUST_LOG says. the optional otlp export ([telemetry]) sees spans and metric events
+    loglayer::set_level(loglayer::parse_level(&config.logging.level)?);
+    let (otel, _telemetry) = telemetry::layer(&config.telemetry, &config.cluster.node_id, &config.cluster.role).await?;
+    {
+        use tracing_subscriber::filter::{filter_fn, FilterExt};
+        use tracing_subscriber::layer::SubscriberExt;
+        use tracing_subscriber::util::SubscriberInitExt;
+        use tracing_subscriber::{EnvFilter, Layer};
+        tracing_subscriber::registry()
+            .with(otel)
+            .with(loglayer::HostLogLayer::new(write_log_line).with_filter(filter_fn(loglayer::accepts)))
+            .with(tracing_subscriber::fmt::layer().with_filter(EnvFilter::from_default_env().and(filter_fn(|m| !loglayer::accepts(m)))))
+            .with(tracing_subscriber::fmt::layer().with_ansi(false).with_writer(|| logfile::Writer).with_filter(EnvFilter::from_default_env().and(filter_fn(|m| !loglayer::accepts(m)))))
+            .init();
+    }
+    aggregate::validate(&config.aggregations)?;
+    derived::validate(&config.derived)?;
+    validate::check_config(&config.validation)?;
+    reports::check_config(&config.reports)?;
+    alerts::check_config(&config.alerts)?;
+    notify::check_config(&config.notify)?;
+    units::check_config(&config.units)?;
+    profiler::check_config(&config.plugin_profiler)?;
+    if config.plugin_profiler.enabled {
+        log_msg(&format!(
+            "🔬 [PROFILER] Sampling '{}' every {}ms, download it from /api/plugins/{}/profile",
+            config.plugin_profiler.plugin, config.plugin_profiler.interval_ms, config.plugin_profiler.plugin
+        ));
+    }
+    poll_timing::check_config(&config.polling)?;
+    let missed_ticks = poll_timing::missed_tick_behavior(&config.polling.missed_ticks)?;
+    // two consumers on one gpio pin: refuse to start, or drop the plugin
+    pins::check(&mut config)?;
+    if hal::dry_run() {
+        log_msg("🧪 [DRY-RUN] Hardware writes (gpio, leds, buzzer, fan) are logged, not performed");
+    }
+    // the effective config, and what changed since the last boot
+    config_reload::init(&config, !overlay_version.is_empty());
     
+    // one pooled http client for the hub / spoke channel, one for everything
+    // else (the cluster client pins the hub cert and would reject influx's)
+    let client = tls::build_client(&config.cluster.tls, &config.http_client)?;
+    let external_client = match config.cluster.tls.enabled {
+        true => tls::plain_client(&config.http_client)?,
+        false => client.clone(),
+    };
+    // one hal for the whole process - the real one keeps its devices open
+    let hal = hal::shared();
+    hal::check_config(&config.leds)?;
+    hal.configure_leds(&config.leds);
+    actuators::check_config(&config.actuators)?;
+    actuators::configure(&config.actuators);
+
     // 2. initialize shared state for sensor readings
-    let state = Arc::new(RwLock::new(AppState::default()));
+    let events = Arc::new(events::EventBus::default());
+    let recent = recent::RecentReadings::default();
+    let state = Arc::new(RwLock::new(AppState {
+        derived: config.derived.clone(),
+        recent: recent.clone(),
+        bounds: config.state.clone(),
+        anomaly: config
+            .anomaly
+            .enabled
+            .then(|| anomaly::AnomalyDetector::new(config.anomaly.clone(), events.clone())),
+        ..Default::default()
+    }));
+
+    domain::spawn_eviction(state.clone());
+
+    // 2a. audit log of hardware actions (before plugins can touch anything)
+    audit::init(&config.audit)?;
+
+    // 2b. open the sqlite history and restore the last known readings
+    #[cfg(not(feature = "storage"))]
+    if config.storage.enabled {
+        log_msg("⚠️ [STORAGE] storage.enabled is set but this build lacks the 'storage' feature - no history");
+    }
+    let store = match config.storage.enabled && cfg!(feature = "storage") {
+        true => {
+            let (store, recorder) = storage::open(&config.storage)?;
+            let latest = store.latest()?;
+            log_msg(&format!("💾 [STORAGE] {} readings stored, restored {} sensors", store.count()?, latest.len()));
+            let mut app = state.write().await;
+            app.last_update = latest.iter().map(|r| r.timestamp_ms).max().unwrap_or(0);
+            app.readings = latest.into();
+            app.evict(domain::now_ms());
+            app.recorder = Some(recorder);
+            let store = Arc::new(store);
+            storage::spawn_compaction(store.clone(), config.storage.retention.clone());
+            #[cfg(feature = "parquet")]
+            if config.export.enabled {
+                export::spawn(store.clone(), config.export.clone(), external_client.clone());
+            }
+            Some(store)
+        }
+        false => None,
+    };
+    #[cfg(not(feature = "parquet"))]
+    if config.export.enabled {
+        log_msg("⚠️ [EXPORT] export.enabled is set but this build lacks the 'parquet' feature");
+    }
+    if config.export.enabled && !config.storage.enabled {
+        log_msg("⚠️ [EXPORT] export.enabled needs storage.enabled - nothing to export");
+    }
     
+    // probe the hardware the plugins will need before loading them
+    let selftest = match config.selftest.enabled {
+        true => Some(selftest::run(&config).await),
+        false => None,
+    };
+
     // 3. initialize wasm runtime (loads all enabled plugins)
     log_msg("[STARTUP] Initializing WASM Runtime...");
-    let runtime = runtime::WasmRuntime::new(std::path::PathBuf::from(".."), &config).await?;
+    let runtime = runtime::WasmRuntime::new(std::path::PathBuf::from(".."), &config, recent).await?;
+    crash::install(&config, &runtime);
+
+    // 3b. alert rules (actions run through the command channel)
+    let commands = Arc::new(commands::CommandQueue::default());
+    let alerts = Arc::new(
+        alerts::AlertEngine::new(
+            &config.alerts,
+            events.clone(),
+            alerts::ActionContext {
+                commands: commands.clone(),
+                config: config.clone(),
+                runtime: runtime.clone(),
+                client: external_client.clone(),
+            },
+        )?
+        .with_silences_file(
+            config::HostConfig::find_config_file()
+                .map(|p| p.with_file_name("alert_silences.json"))
+                .unwrap_or_else(|| std::path::PathBuf::from("config/alert_silences.json")),
+        ),
+    );
     
+    maintenance::load(
+        config::HostConfig::find_config_file()
+            .map(|p| p.with_file_name("maintenance.json"))
+            .unwrap_or_else(|| std::path::PathBuf::from("config/maintenance.json")),
+    );
+
+    // spoke push targets, in failover order
+    let encoding = codec::Encoding::from_name(&config.cluster.encoding)?;
+    let hubs = Arc::new(cluster::HubFailover::new(config.cluster.push_targets(), encoding));
+
     // 4. create api state for handlers
+    let (ingest, pushed) = ingest::channel(config.cluster.limits.queue_depth);
+    let schema = Arc::new(schema::SchemaRegistry::new(&config.schema));
     let api_state = ApiState {
         state: state.clone(),
         runtime: runtime.clone(),
         config: config.clone(),
+        commands,
+        nodes: Arc::new(nodes::NodeRegistry::load(
+            config::HostConfig::find_config_file()
+                .map(|p| p.with_file_name("retired_nodes.json"))
+                .unwrap_or_else(|| std::path::PathBuf::from("config/retired_nodes.json")),
+        ).with_events(events.clone())),
+        limiter: Arc::new(limits::PushLimiter::new(config.cluster.limits.clone())),
+        ingest,
+        timing: Arc::new(poll_timing::PollTiming::new(std::time::Duration::from_secs(config.polling.interval_seconds))),
+        store,
+        units: Arc::new(units::Units::new(&config.units, schema.clone())),
+        validator: Arc::new(validate::Validator::new(config.validation.clone(), schema)),
+        events,
+        alerts: alerts.clone(),
+        reports: Arc::new(reports::Reports::default()),
+        selftest: Arc::new(selftest),
+        hubs: hubs.clone(),
+        started: std::time::Instant::now(),
     };
 
-    // start web/api server on port 3000
-    let bind_addr = "0.0.0.0:3000";
-    log_msg(&format!("[STARTUP] API listening on {}", bind_addr));
-    
-    let app = Router::new()
-        .route("/", get(dashboard_handler))
-        .route("/api/readings", get(api_handler))
-        .route("/api/logs", get(logs_handler))            // dashboard log viewing
-        .route("/api/buzzer", post(buzzer_handler))       // dashboard buzzer buttons
-        .route("/api/buzzer/test", post(buzzer_test_handler)) // manual trigger
-        .route("/api/fan/status", get(fan_status_handler))    // get fan state
-        .route("/api/fan/test", post(fan_test_handler))       // manual fan test
-        .route("/push", post(push_handler)) // hub endpoint to receive data from spokes
-        .fallback(fallback_handler)
-        .layer(CorsLayer::permissive())
-        .with_state(api_state.clone());
-        
-    let listener = tokio::net::TcpListener::bind(bind_addr).await?;
-    
-    // spawn server in background task
-    tokio::spawn(async move {
-        axum::serve(listener, app).await.unwrap();
-    });
+    tokio::spawn(merge_pushes(api_state.clone(), pushed));
+
+    // apply host.toml edits (poll interval, log level, alert rules, plugins) while running
+    let targets = config_reload::Targets { timing: api_state.timing.clone(), alerts: alerts.clone(), runtime: runtime.clone() };
+    config_reload::spawn(&config, !overlay_version.is_empty(), targets)?;
+
+    // start web/api server on port 3000 - skipped headless (api.enabled = false / --headless)
+    if config.api.enabled {
+        let bind_addr = "0.0.0.0:3000";
+        log_msg(&format!("[STARTUP] API listening on {}", bind_addr));
+        let app = router(&config, api_state.clone());
+
+        // spawn server in background task (https with client cert checks if cluster.tls is enabled)
+        let listener = tokio::net::TcpListener::bind(bind_addr).await?;
+        if config.cluster.tls.enabled {
+            let tls_config = tls::server_config(&config.cluster.tls)?;
+            log_msg("[STARTUP] TLS enabled for cluster channel");
+            tokio::spawn(tls::serve(listener, app, tls_config));
+        } else {
+            if config.cluster.role == "hub" {
+                log_msg("⚠️ [STARTUP] No cluster.tls: command polls and results from spokes are not authenticated");
+            }
+            tokio::spawn(async move {
+                axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
+            });
+        }
+    } else {
+        log_msg("[STARTUP] Headless: no web server, only the poll / push loop");
+        if config.cluster.role != "spoke" {
+            log_msg("⚠️ [STARTUP] A headless hub can't receive pushes, heartbeats or command polls");
+        }
+    }
+
+    // optional coap server for microcontroller sensors (cbor readings)
+    #[cfg(feature = "coap")]
+    if config.coap.enabled {
+        coap::spawn_server(&config.coap.bind, api_state.clone()).await?;
+    }
+    #[cfg(not(feature = "coap"))]
+    if config.coap.enabled {
+        log_msg("⚠️ [COAP] coap.enabled is set but this build lacks the 'coap' feature");
+    }
 
     // ==============================================================================
     // polling loop - main runtime loop
@@ -211,70 +693,213 @@ async fn main() -> Result<()> {
     // - pushes to hub (spoke) or updates local state (hub)
 
     let poll_interval = config.polling.interval_seconds;
-    let hub_url = config.cluster.hub_url.clone();
-    let is_spoke = config.cluster.role == "spoke";
+    let startup_role = role::Role::from_name(&config.cluster.role)?;
+    let is_spoke = startup_role == role::Role::Spoke;
     let node_id = config.cluster.node_id.clone();
+    let node_metadata = Some(config.cluster.metadata.clone()).filter(|m| !m.is_empty());
 
     log_msg(&format!("[RUNTIME] Starting sensor polling loop ({}s interval) as {}", poll_interval, config.cluster.role));
     
-    let client = reqwest::Client::new();
     let mut heartbeat = false;
 
+    // spoke: report crashes the hub hasn't seen yet
+    if is_spoke {
+        let (config, client) = (config.clone(), client.clone());
+        tokio::spawn(async move { crash::upload_pending(&config, &client).await });
+    }
+
+    // optional mqtt publisher - alongside http push, or instead of it with an empty hub_url
+    #[cfg(feature = "mqtt")]
+    let mqtt = match config.mqtt.enabled {
+        true => match mqtt::MqttPublisher::start(&config.mqtt, &node_id) {
+            Ok(publisher) => Some(publisher),
+            Err(e) => {
+                log_msg(&format!("❌ [MQTT] Publisher disabled: {:#}", e));
+                None
+            }
+        },
+        false => None,
+    };
+    #[cfg(not(feature = "mqtt"))]
+    if config.mqtt.enabled {
+        log_msg("⚠️ [MQTT] mqtt.enabled is set but this build lacks the 'mqtt' feature");
+    }
+
+    // optional influxdb exporter - fed from every merge (the hub exports the whole cluster)
+    if config.influx.enabled {
+        match influx::InfluxExporter::start(&config.influx, external_client.clone()) {
+            Ok(exporter) => state.write().await.influx = Some(exporter),
+            Err(e) => log_msg(&format!("❌ [INFLUX] Exporter disabled: {:#}", e)),
+        }
+    }
+
+    // optional generic http sinks, same feed and client as influx
+    if !config.sinks.is_empty() {
+        state.write().await.sinks = sink::start_all(&config.sinks, &node_id, &external_client);
+    }
+
+    // optional kafka producer - fed from every merge, keyed by node
+    #[cfg(feature = "kafka")]
+    if config.kafka.enabled {
+        match kafka::KafkaProducer::start(&config.kafka, &node_id) {
+            Ok(producer) => state.write().await.kafka = Some(producer),
+            Err(e) => log_msg(&format!("❌ [KAFKA] Producer disabled: {:#}", e)),
+        }
+    }
+    #[cfg(not(feature = "kafka"))]
+    if config.kafka.enabled {
+        log_msg("⚠️ [KAFKA] kafka.enabled is set but this build lacks the 'kafka' feature");
+    }
+
+    // cluster transport - http push (default) or nats/jetstream
+    let use_nats = config.cluster.transport == "nats";
+    #[cfg(not(feature = "nats"))]
+    let use_nats = {
+        if use_nats {
+            log_msg("⚠️ [NATS] cluster.transport = \"nats\" but this build lacks the 'nats' feature - using http");
+        }
+        false
+    };
+
+    // tasks of the role (push, heartbeat, stale check, ...), restarted when it's switched
+    let roles = role::Context {
+        config: config.clone(),
+        client: client.clone(),
+        external_client: external_client.clone(),
+        hubs: hubs.clone(),
+        runtime: runtime.clone(),
+        state: state.clone(),
+        nodes: api_state.nodes.clone(),
+        events: api_state.events.clone(),
+        alerts: alerts.clone(),
+        reports: api_state.reports.clone(),
+        store: api_state.store.clone(),
+        api: api_state.clone(),
+        encoding,
+        use_nats,
+        overlay_version: is_spoke.then_some(overlay_version),
+    };
+    let mut role_rx = role::init(startup_role, !hubs.is_empty() || use_nats, config.api.enabled);
+    let mut running = roles.start(startup_role).await?;
+
+    // cycles start on a fixed schedule, whatever the previous one took (poll_timing.rs)
+    let mut poll_period = api_state.timing.interval();
+    let mut ticker = poll_ticker(poll_period, missed_ticks);
     loop {
-        tokio::time::sleep(tokio::time::Duration::from_secs(poll_interval)).await;
+        // polling.interval_seconds as of now (config_reload.rs may change it)
+        if api_state.timing.interval() != poll_period {
+            poll_period = api_state.timing.interval();
+            ticker = poll_ticker(poll_period, missed_ticks);
+        }
+        let poll_interval = poll_period.as_secs();
+        let scheduled = ticker.tick().await;
+        api_state.timing.ticked(scheduled.into_std());
+        let cycle_started = std::time::Instant::now();
+
+        // a role switch (role.rs) takes effect here
+        if role_rx.has_changed().unwrap_or(false) {
+            let role = *role_rx.borrow_and_update();
+            roles.switch(&mut running, role).await;
+        }
+        // maintenance (maintenance.rs) - the api stays up, the cycle is skipped
+        if maintenance::active() {
+            api_state.timing.cycle_skipped(cycle_started);
+            continue;
+        }
+        let is_spoke = running.role == role::Role::Spoke;
+        let use_http_push = is_spoke && !use_nats && !hubs.is_empty();
 
         // 0. host heartbeat (led 0) - visual indicator that host is running
         heartbeat = !heartbeat;
         {
-            let hal = crate::hal::Hal::new();
             use crate::hal::HardwareProvider;
             if heartbeat {
                 let _ = hal.set_led(0, 0, 0, 255); // solid blue
             } else {
                 let _ = hal.set_led(0, 0, 100, 255); // cyan-ish blink
             }
-            let _ = hal.sync_leds();
         }
 
         // 1. check for hot-reloaded plugins (modified wasm files)
-        runtime.check_hot_reload().await;
+        for (name, result) in runtime.check_hot_reload().await {
+            match result {
+                Ok(()) => log_msg(&format!("🔄 [RELOAD] Hot-reloaded plugin '{}'", name)),
+                Err(e) => log_msg(&format!("❌ [RELOAD] Plugin '{}' failed to reload: {:#}", name, e)),
+            }
+        }
 
         // 2. poll sensors and update local state
-        match runtime.poll_sensors().await {
+        let mut polled: Vec<String> = Vec::new();
+        let poll_started = std::time::Instant::now();
+        let poll_span = tracing::info_span!("poll_cycle", node_id = %node_id, readings = tracing::field::Empty);
+        let budget = config.polling.budget(api_state.timing.interval());
+        let poll = runtime.poll_sensors(budget).instrument(poll_span.clone()).await;
+        let count = poll.as_ref().map_or(0, |readings| readings.len());
+        poll_span.record("readings", count);
+        // the cycle's led changes (heartbeat, plugins) in one strip write
+        let _ = hal.flush_leds(config.leds.min_sync());
+        tracing::trace!(histogram.poll_duration_ms = telemetry::elapsed_ms(poll_started), ok = poll.is_ok());
+        tracing::trace!(monotonic_counter.readings_polled = count as u64);
+        match poll {
             Ok(mut readings) => {
                 // add node_id prefix to sensor_id for clarity (e.g., "pi4:dht22")
+                // and tag readings with where this node lives
                 for r in &mut readings {
                     r.sensor_id = format!("{}:{}", node_id, r.sensor_id);
+                    r.metadata = node_metadata.clone();
                 }
+                // correct, then drop / mark glitches before anything else sees them
+                calibrate::apply(&config.calibration, &mut readings);
+                api_state.validator.apply(&mut readings, "POLL");
+                // derive here too so forwarded readings carry the fields
+                derived::apply(&config.derived, &mut readings);
+                polled = readings.iter().map(|r| r.sensor_id.clone()).collect();
 
                 if !readings.is_empty() {
-                    let mut s = state.write().await;
-                    
-                    // merge local readings into state (update existing or add new)
-                    for nr in &readings {
-                        if let Some(pos) = s.readings.iter().position(|r| r.sensor_id == nr.sensor_id) {
-                            s.readings[pos] = nr.clone();
-                        } else {
-                            s.readings.push(nr.clone());
-                        }
-                    }
-                    
-                    s.last_update = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64;
-                    
                     // 3. log detailed readings for dashboard visibility
                     for r in &readings {
                         let summary = format_sensor_summary(&r.sensor_id, &r.data);
                         log_msg(&format!("📡 {}", summary));
                     }
                     
-                    // 4. if spoke, forward readings to hub via http post
-                    if is_spoke && !hub_url.is_empty() {
-                        match client.post(&hub_url).json(&readings).send().await {
-                            Ok(_) => log_msg(&format!("✅ Pushed {} readings to hub", readings.len())),
-                            Err(e) => log_msg(&format!("❌ Failed to push to hub: {}", e)),
+                    #[cfg(feature = "mqtt")]
+                    if let Some(mqtt) = &mqtt {
+                        mqtt.publish(&readings);
+                    }
+
+                    // a spoke forwards a copy, anywhere else the state takes the only one
+                    let forward = is_spoke.then(|| readings.clone());
+
+                    // merge local readings into state (update existing or add new)
+                    {
+                        let mut app = state.write().await;
+                        app.merge_readings(readings);
+                        if !is_spoke {
+                            aggregate::apply(&config.aggregations, &mut app);
+                        }
+                    }
+
+                    if let Some(readings) = forward {
+                        // 4. if spoke, forward readings to hub via http post (or nats)
+                        #[cfg(feature = "nats")]
+                        if let Some(nats) = &running.nats {
+                            match nats.publish(&readings).await {
+                                Ok(subject) => log_msg(&format!("✅ Published {} readings to {}", readings.len(), subject)),
+                                Err(e) => log_msg(&format!("❌ Failed to publish to NATS: {}", e)),
+                            }
+                        }
+                        if running.uplink.as_ref().is_some_and(|u| u.send(&readings)) {
+                            log_msg(&format!("✅ Sent {} readings over hub websocket", readings.len()));
+                        } else if use_http_push {
+                            let count = readings.len();
+                            let batch = domain::PushBatch::new(&node_id, readings);
+                            let push_started = std::time::Instant::now();
+                            let pushed = hubs.push(&client, &batch).instrument(tracing::info_span!("hub.push", readings = count)).await;
+                            tracing::trace!(histogram.hub_push_ms = telemetry::elapsed_ms(push_started), ok = pushed.is_ok());
+                            match pushed {
+                                Ok(url) => log_msg(&format!("✅ Pushed {} readings to hub {}", count, url)),
+                                Err(e) => log_msg(&format!("❌ Failed to push to hub: {}", e)),
+                            }
                         }
                     }
                 }
@@ -283,9 +908,101 @@ async fn main() -> Result<()> {
                 log_msg(&format!("❌ Sensor polling failed: {}", e));
             }
         }
+        // sensors missing from this cycle count a miss (/api/sensors sampling stats),
+        // except those whose plugin backoff skipped - they weren't due
+        let not_due: Vec<String> = runtime.backed_off_sensors().into_iter().map(|id| format!("{}:{}", node_id, id)).collect();
+        state.write().await.cadence.poll_cycle(&node_id, &polled, &not_due, poll_interval * 1000);
+        api_state.timing.cycle_done(cycle_started);
     }
 }
 
+/// the poll loop's schedule: first tick one `period` from now, like the old sleep
+fn poll_ticker(period: std::time::Duration, missed: tokio::time::MissedTickBehavior) -> tokio::time::Interval {
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    ticker.set_missed_tick_behavior(missed);
+    ticker
+}
+
+/// every route of the web/api server
+fn router(config: &config::HostConfig, api_state: ApiState) -> Router {
+    // what spokes feed a hub through - 503 while the node isn't a hub (role.rs)
+    let hub_routes = Router::new()
+        .route("/push", post(push_handler).layer(push_body_limit(&config.cluster.limits))) // hub endpoint to receive data from spokes
+        .route("/push/backfill", post(backfill_handler).layer(push_body_limit(&config.cluster.limits))) // buffered history from spokes
+        .route("/ws", get(ws_handler))      // persistent spoke channel (transport = "websocket")
+        .route("/heartbeat", post(heartbeat_handler)) // cheap spoke liveness signal
+        .route(
+            "/api/crash",
+            post(crash_report_handler)
+                .layer(DefaultBodyLimit::max(crash::REPORT_LIMIT))
+                .layer(axum::middleware::from_fn_with_state(admin::ClusterTls(config.cluster.tls.enabled), admin::cluster_peer)),
+        ) // spoke crash reports (crash.spoke_dir)
+        .route_layer(axum::middleware::from_fn(role::hub_only));
+
+    // what can take the node over - only with api.admin_token (admin.rs)
+    let admin = admin::AdminToken(config.api.admin_token.as_str().into());
+    let admin_routes = Router::new()
+        .route("/api/snapshot", get(snapshot_handler))    // full backup archive
+        .route("/api/restore", post(restore_handler))     // streamed to disk, snapshot::RESTORE_LIMIT
+        .route("/api/plugins/:name", put(plugin_upload_handler).layer(DefaultBodyLimit::max(PLUGIN_UPLOAD_LIMIT)))
+        .route("/api/nodes/:id/plugins/:name", post(plugin_deploy_handler).layer(DefaultBodyLimit::max(PLUGIN_UPLOAD_LIMIT)))
+        .route("/api/role", post(role_switch_handler)) // switch hub / spoke / standalone live
+        .route("/api/command", post(command_post_handler)) // hub → spoke commands
+        .route("/api/nodes/:id", delete(node_delete_handler)) // decommission a retired spoke
+        .route("/api/nodes/:id/register", post(node_register_handler))
+        .route("/api/maintenance", post(maintenance_set_handler)) // pause polling, alerts and hardware writes
+        .route("/api/alerts/:id/silence", post(alert_silence_handler).delete(alert_unsilence_handler)) // mute actions / notifications
+        .route_layer(axum::middleware::from_fn_with_state(admin, admin::admin_only));
+
+    // how spokes pick up and answer their commands - a cluster client cert under cluster.tls
+    let spoke_routes = Router::new()
+        .route("/api/command", get(command_poll_handler))
+        .route("/api/command/:id/result", post(command_result_handler))
+        .route("/api/command/:id/artifact", get(command_artifact_handler))
+        .route_layer(axum::middleware::from_fn_with_state(admin::ClusterTls(config.cluster.tls.enabled), admin::cluster_peer));
+
+    Router::new()
+        .route("/", get(dashboard_handler))
+        .route("/api/readings", get(api_handler))
+        .route("/api/schema", get(schema_handler))        // units / ranges of reading fields
+        .route("/api/sensors", get(sensors_handler))      // sensor inventory for integrations
+        .route("/api/events", get(events_handler))        // anomalies and other detector events
+        .route("/api/alerts", get(alerts_handler))        // active alerts + silences
+        .route("/api/alerts/history", get(alert_history_handler)) // stored transitions
+        .route("/api/alerts/:id/ack", post(alert_ack_handler))
+        .route("/api/history", get(history_handler))      // stored readings of one sensor
+        .route("/api/aggregate", get(aggregate_handler))  // windowed avg/min/max over stored readings
+        .route("/api/chart", get(chart_handler))          // pre-binned series for plotting
+        .route("/api/logs", get(logs_handler))            // dashboard log viewing
+        .route("/api/audit", get(audit_handler))          // who did what to the hardware
+        .route("/api/selftest", get(selftest_handler))    // startup hardware probe results
+        .route("/reports/latest", get(latest_report_handler)) // scheduled summary report
+        .route("/api/buzzer", post(buzzer_handler))       // dashboard buzzer buttons
+        .route("/api/buzzer/test", post(buzzer_test_handler)) // manual trigger
+        .route("/api/fan/status", get(fan_status_handler))    // get fan state
+        .route("/api/fan/test", post(fan_test_handler))       // manual fan test
+        .merge(hub_routes)
+        .merge(admin_routes)
+        .merge(spoke_routes)
+        .route("/ws/logs", get(log_stream_handler)) // live log panel
+        .route("/health", get(health_handler)) // liveness probe for spoke failover
+        .route("/api/nodes", get(nodes_handler))
+        .route("/api/cluster", get(cluster_handler)) // topology for the dashboard diagram
+        .route("/api/config/effective", get(effective_config_handler)) // running config, secrets redacted
+        .route("/api/role", get(role_handler))
+        .route("/api/maintenance", get(maintenance_handler)) // the running window
+        .route("/api/command/:id", get(command_status_handler))
+        .route("/api/plugins/:name/stats", get(plugin_stats_handler))
+        .route("/api/plugins/:name/profile", get(plugin_profile_handler))
+        .route("/metrics", get(metrics_handler))  // prometheus scrape target
+        .route("/api/system", get(system_handler)) // process and plugin memory usage
+        .route("/api/nodes/:id/config", get(node_config_handler)) // centralized spoke config
+        .fallback(fallback_handler)
+        .layer(axum::middleware::from_fn(telemetry::http))
+        .layer(CorsLayer::permissive())
+        .with_state(api_state)
+}
+
 // ==============================================================================
 // http handlers
 // ==============================================================================
@@ -295,19 +1012,20 @@ async fn main() -> Result<()> {
 /// then calls the wasm plugin to render html.
 async fn dashboard_handler(State(api_state): State<ApiState>) -> impl IntoResponse {
     let s = api_state.state.read().await;
+    let readings = api_state.units.readings(&s.readings);
     
     // transform readings list into the format the dashboard plugin expects:
     // {dht22: {...}, bme680: {...}, hub: {...}, pi4: {...}, pizero: {...}}
     let mut dashboard_data = serde_json::json!({});
     
-    for reading in &s.readings {
+    for reading in readings.iter() {
         let sensor_id = &reading.sensor_id;
         
         // parse sensor_id like "pi4:dht22" or "revpi-hub:revpi-monitor"
         if sensor_id.contains("dht22") {
             dashboard_data["dht22"] = reading.data.clone();
         } else if sensor_id.contains("bme680") {
-            let mut bme = reading.data.clone();
+            let bme = reading.data.clone();
             // add iaq_score at top level if it's nested
             if let Some(_iaq) = bme.get("iaq_score") {
                 dashboard_data["bme680"] = bme.clone();
@@ -336,6 +1054,14 @@ async fn dashboard_handler(State(api_state): State<ApiState>) -> impl IntoRespon
         }
     }
     
+    // display units of the converted fields, e.g. {"temperature": "°F", "pressure": "inHg"}
+    let schema = api_state.units.schema();
+    for field in ["temperature", "pressure"] {
+        if let Some(unit) = schema.lookup("", field) {
+            dashboard_data["units"][field] = serde_json::json!(unit.unit);
+        }
+    }
+
     let json_data = serde_json::to_string(&dashboard_data).unwrap_or_else(|_| "{}".to_string());
     
     // call the wasm dashboard plugin to render the html
@@ -350,32 +1076,174 @@ async fn dashboard_handler(State(api_state): State<ApiState>) -> impl IntoRespon
 
 /// api handler - returns raw sensor readings as json.
 /// used by dashboard for live updates via javascript fetch.
-async fn api_handler(State(state): State<ApiState>) -> Json<AppState> {
+/// ?units=raw skips the display unit conversion (hub pull mode).
+async fn api_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
     let s = state.state.read().await;
-    Json(s.clone())
+    let (readings, schema) = match params.get("units").is_some_and(|u| u == "raw") {
+        true => (std::borrow::Cow::Borrowed(&*s.readings), state.units.stored_schema()),
+        false => (state.units.readings(&s.readings), state.units.schema()),
+    };
+    // same shape as AppState, with each reading's field schema attached
+    let body = ReadingsResponse {
+        readings: schema.annotate(&readings),
+        last_update: s.last_update,
+        clock_skew_ms: &s.clock_skew_ms,
+    };
+    codec::Encoded(codec::Encoding::from_accept(&headers), body).into_response()
+}
+
+#[derive(serde::Serialize)]
+struct ReadingsResponse<'a> {
+    readings: Vec<schema::AnnotatedReading<'a>>,
+    last_update: u64,
+    clock_skew_ms: &'a std::collections::BTreeMap<String, i64>,
+}
+
+/// schema handler - unit / display name / valid range of every known field
+async fn schema_handler(State(state): State<ApiState>) -> Json<std::collections::BTreeMap<String, config::FieldSchema>> {
+    Json(state.units.schema().all().clone())
+}
+
+/// sensors handler - every known sensor with node, plugin, field schema and last value
+async fn sensors_handler(State(state): State<ApiState>) -> Json<serde_json::Value> {
+    let plugins = state.runtime.loaded_plugins().await;
+    let s = state.state.read().await;
+    let readings = state.units.readings(&s.readings);
+    let nodes = state.nodes.list();
+    let sensors = catalog::build(&readings, &s.cadence, state.units.schema(), &nodes, (&state.config.cluster.node_id, &plugins));
+    Json(serde_json::json!({ "sensors": sensors }))
+}
+
+/// events handler - recent detector events, newest first
+async fn events_handler(State(state): State<ApiState>) -> Json<Vec<events::Event>> {
+    Json(state.events.recent())
+}
+
+/// alerts handler - active (pending / firing) alerts and running silences
+async fn alerts_handler(State(state): State<ApiState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "alerts": state.alerts.active(), "silences": state.alerts.silences() }))
+}
+
+/// alert history handler - stored alert transitions, newest first.
+/// from= / to= (unix ms, default last 7 days), id=, rule=, limit= (default 500)
+async fn alert_history_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let Some(store) = state.store.clone() else {
+        return (axum::http::StatusCode::NOT_FOUND, "storage is disabled on this node").into_response();
+    };
+    let now = domain::now_ms();
+    let num = |key: &str, default: u64| params.get(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+    let (from, to, limit) = (num("from", now.saturating_sub(7 * 24 * 3600 * 1000)), num("to", now), num("limit", 500));
+    let (id, rule) = (params.get("id").cloned(), params.get("rule").cloned());
+
+    let result = tokio::task::spawn_blocking(move || store.alert_history(from, to, id.as_deref(), rule.as_deref(), limit as usize)).await;
+    match result.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(records) => Json(serde_json::json!({ "transitions": records })).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// audit handler - logged hardware actions, newest first.
+/// from= / to= (unix ms, default last 7 days), actor= (prefix, e.g. "api:" or
+/// "plugin:pi4-monitor"), action= (buzzer / fan / led), limit= (default 500)
+async fn audit_handler(Query(params): Query<std::collections::HashMap<String, String>>) -> axum::response::Response {
+    if !audit::enabled() {
+        return (axum::http::StatusCode::NOT_FOUND, "audit log is disabled on this node").into_response();
+    }
+    let now = domain::now_ms();
+    let num = |key: &str, default: u64| params.get(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+    let (from, to, limit) = (num("from", now.saturating_sub(7 * 24 * 3600 * 1000)), num("to", now), num("limit", 500));
+    let (actor, action) = (params.get("actor").cloned(), params.get("action").cloned());
+
+    let result = tokio::task::spawn_blocking(move || audit::read(from, to, actor.as_deref(), action.as_deref(), limit as usize)).await;
+    match result.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(entries) => Json(serde_json::json!({ "entries": entries })).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// latest report handler - newest scheduled summary report, 404 until the first one ran
+async fn latest_report_handler(State(state): State<ApiState>) -> axum::response::Response {
+    match state.reports.latest() {
+        Some(report) => ([(axum::http::header::CONTENT_TYPE, report.content_type)], report.body).into_response(),
+        None => (axum::http::StatusCode::NOT_FOUND, "no report has been generated yet").into_response(),
+    }
+}
+
+/// self-test handler - hardware probe results from startup
+async fn selftest_handler(State(state): State<ApiState>) -> axum::response::Response {
+    match state.selftest.as_ref() {
+        Some(report) => Json(report).into_response(),
+        None => (axum::http::StatusCode::NOT_FOUND, "self-test is disabled on this node").into_response(),
+    }
+}
+
+/// alert ack handler - acknowledge an active alert until it resolves
+async fn alert_ack_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    match state.alerts.ack(&id) {
+        Some(alert) => Json(alert).into_response(),
+        None => (axum::http::StatusCode::NOT_FOUND, format!("no active alert '{}'", id)).into_response(),
+    }
+}
+
+/// alert silence handler - mute an alert id (or id glob) for ?duration=
+async fn alert_silence_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let Some(duration_ms) = params.get("duration").and_then(|d| storage::parse_duration_ms(d)) else {
+        return (axum::http::StatusCode::BAD_REQUEST, "duration is required, e.g. ?duration=2h").into_response();
+    };
+    let until = state.alerts.silence(&id, duration_ms);
+    Json(serde_json::json!({ "id": id, "silenced_until_ms": until })).into_response()
+}
+
+/// alert unsilence handler - lift a silence early
+async fn alert_unsilence_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> axum::http::StatusCode {
+    match state.alerts.unsilence(&id) {
+        true => axum::http::StatusCode::NO_CONTENT,
+        false => axum::http::StatusCode::NOT_FOUND,
+    }
 }
 
 /// logs handler - returns logs for the dashboard.
 /// merges host logs from log_buffer + any wasm logs from file.
+/// `dropped` counts host lines pushed out of the buffer since startup,
+/// `seq` is the sequence number of the newest host line. with since=<seq>
+/// only the host lines after it are returned, each with its seq, for
+/// stream consumers catching up (/ws/logs entries carry the same numbers).
 /// note: wasm plugin stdout currently bypasses the log buffer.
-async fn logs_handler() -> impl IntoResponse {
-    let mut all_logs: Vec<String> = Vec::new();
-    
+async fn logs_handler(Query(params): Query<std::collections::HashMap<String, String>>) -> impl IntoResponse {
     // 1. add host logs from in-memory buffer
-    if let Ok(buf) = get_log_buffer().lock() {
-        all_logs.extend(buf.iter().cloned());
+    let buffered = match params.get("since").map(|since| since.parse::<u64>()) {
+        Some(Ok(since)) => logbuffer::snapshot(since),
+        Some(Err(_)) => return (axum::http::StatusCode::BAD_REQUEST, "since must be a sequence number").into_response(),
+        None => logbuffer::snapshot(0),
+    };
+    let (capacity, dropped, seq) = (buffered.capacity, buffered.dropped, buffered.seq);
+    if params.contains_key("since") {
+        let lines: Vec<_> = buffered.lines.iter().map(|line| serde_json::json!({"seq": line.seq, "line": line.text})).collect();
+        return Json(serde_json::json!({"lines": lines, "capacity": capacity, "dropped": dropped, "seq": seq})).into_response();
     }
+    let mut all_logs: Vec<String> = buffered.lines.iter().map(|line| line.text.clone()).collect();
     
     // 2. add wasm plugin logs from file (last 50 lines)
     // note: this file may not exist if wasm stdout isn't redirected
-    if let Ok(content) = std::fs::read_to_string("wasi-logs.log") {
-        let lines: Vec<&str> = content.lines().collect();
-        let start = if lines.len() > 50 { lines.len() - 50 } else { 0 };
-        for line in &lines[start..] {
-            if !line.trim().is_empty() {
-                all_logs.push(line.to_string());
-            }
-        }
+    if let Ok(lines) = logfile::tail("wasi-logs.log", 50).await {
+        all_logs.extend(lines);
     }
     
     // 3. sort by timestamp if present
@@ -393,61 +1261,242 @@ async fn logs_handler() -> impl IntoResponse {
         }
     });
     
-    // keep last 100 logs
-    if all_logs.len() > 100 {
-        all_logs = all_logs.split_off(all_logs.len() - 100);
+    // keep the last buffer_size logs
+    if all_logs.len() > capacity {
+        all_logs = all_logs.split_off(all_logs.len() - capacity);
     }
     
-    Json(serde_json::json!({"logs": all_logs}))
+    Json(serde_json::json!({"logs": all_logs, "capacity": capacity, "dropped": dropped, "seq": seq})).into_response()
 }
 
 /// push handler - receives sensor data from spoke nodes.
 /// hub uses this endpoint to aggregate data from all spokes.
+/// batches are acknowledged with {batch_id, duplicate}; replays are not merged again.
 async fn push_handler(
     State(state): State<ApiState>,
-    Json(new_readings): Json<Vec<SensorReading>>,
-) -> impl axum::response::IntoResponse {
-    let mut s = state.state.write().await;
-    
-    // log detailed incoming data for each sensor
-    for nr in &new_readings {
-        let summary = format_sensor_summary(&nr.sensor_id, &nr.data);
-        log_msg(&format!("📥 [PUSH] {}", summary));
+    headers: axum::http::HeaderMap,
+    codec::Decoded(body): codec::Decoded<domain::PushBody>,
+) -> axum::response::Response {
+    let (node_id, count) = match &body {
+        domain::PushBody::Batch(batch) => (batch.node_id.as_str(), batch.readings.len()),
+        domain::PushBody::Legacy(readings) => (readings.first().map_or("", |r| cluster::node_of(&r.sensor_id)), readings.len()),
+    };
+    if let Some(rejected) = push_limit_rejection(&state, node_id, count) {
+        return rejected;
     }
-    
-    // merge readings from this spoke into global state
-    // update/replace readings with the same sensor_id
-    for nr in new_readings {
-        if let Some(pos) = s.readings.iter().position(|r| r.sensor_id == nr.sensor_id) {
-            s.readings[pos] = nr;
-        } else {
-            s.readings.push(nr);
+    let received_ms = crate::domain::now_ms();
+    let batch = match body {
+        domain::PushBody::Legacy(readings) => {
+            let Some(slot) = state.ingest.reserve() else { return hub_busy(&state) };
+            slot.send(ingest::Batch { readings, received_ms });
+            return axum::http::StatusCode::OK.into_response();
         }
+        domain::PushBody::Batch(batch) => batch,
+    };
+    if state.nodes.is_retired(&batch.node_id) {
+        return (axum::http::StatusCode::GONE, format!("node '{}' is decommissioned", batch.node_id)).into_response();
     }
-    
-    s.last_update = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
-    
-    axum::http::StatusCode::OK
+    // a place in the queue before the batch id is recorded, or the retry
+    // of a rejected batch would be dropped as a replay
+    let Some(slot) = state.ingest.reserve() else { return hub_busy(&state) };
+    let duplicate = !state.nodes.accept_batch(&batch.node_id, &batch.batch_id);
+    if duplicate {
+        log_msg(&format!("♻️ [PUSH] Dropped replayed batch {} from '{}'", batch.batch_id, batch.node_id));
+    } else {
+        slot.send(ingest::Batch { readings: batch.readings, received_ms });
+    }
+    let ack = domain::PushAck { batch_id: batch.batch_id, duplicate };
+    codec::Encoded(codec::Encoding::from_accept(&headers), ack).into_response()
+}
+
+/// backfill handler - historical readings from spokes that buffered while
+/// the hub was unreachable. same body, limits and batch ids as /push, but
+/// timestamps are never rewritten for clock skew and the reply counts how
+/// many readings were merged vs. only written to history (older than the
+/// sensor's current reading, see AppState::merge_readings).
+async fn backfill_handler(
+    State(state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    codec::Decoded(body): codec::Decoded<domain::PushBody>,
+) -> axum::response::Response {
+    let batch = match body {
+        domain::PushBody::Batch(batch) => batch,
+        domain::PushBody::Legacy(readings) => {
+            let node_id = readings.first().map_or("", |r| cluster::node_of(&r.sensor_id)).to_string();
+            domain::PushBatch::new(&node_id, readings)
+        }
+    };
+    if let Some(rejected) = push_limit_rejection(&state, &batch.node_id, batch.readings.len()) {
+        return rejected;
+    }
+    if state.nodes.is_retired(&batch.node_id) {
+        return (axum::http::StatusCode::GONE, format!("node '{}' is decommissioned", batch.node_id)).into_response();
+    }
+    let mut ack = domain::BackfillAck { batch_id: batch.batch_id.clone(), duplicate: false, merged: 0, historical: 0 };
+    ack.duplicate = !state.nodes.accept_batch(&batch.node_id, &batch.batch_id);
+    if ack.duplicate {
+        log_msg(&format!("♻️ [BACKFILL] Dropped replayed batch {} from '{}'", batch.batch_id, batch.node_id));
+        return codec::Encoded(codec::Encoding::from_accept(&headers), ack).into_response();
+    }
+
+    let mut readings = batch.readings;
+    if accept_readings(&state, &mut readings, "BACKFILL") {
+        let mut app = state.state.write().await;
+        (ack.merged, ack.historical) = app.merge_readings(readings);
+        if ack.merged > 0 {
+            aggregate::apply(&state.config.aggregations, &mut app);
+        }
+        log_msg(&format!(
+            "📦 [BACKFILL] '{}': {} readings merged, {} written to history{}",
+            batch.node_id,
+            ack.merged,
+            ack.historical,
+            if state.store.is_none() { " (storage disabled)" } else { "" }
+        ));
+    }
+    codec::Encoded(codec::Encoding::from_accept(&headers), ack).into_response()
+}
+
+/// body size cap for /push from cluster.limits.max_payload_bytes (0 = axum's default)
+fn push_body_limit(limits: &config::PushLimits) -> DefaultBodyLimit {
+    match limits.max_payload_bytes {
+        0 => DefaultBodyLimit::disable(),
+        max => DefaultBodyLimit::max(max),
+    }
+}
+
+/// per-node push quota: 429 + Retry-After over the rate, 413 for oversized batches
+fn push_limit_rejection(state: &ApiState, node_id: &str, count: usize) -> Option<axum::response::Response> {
+    let max_readings = state.limiter.limits().max_readings;
+    if max_readings > 0 && count > max_readings {
+        log_msg(&format!("⛔ [PUSH] Rejected {} readings from '{}' (max {})", count, node_id, max_readings));
+        return Some((axum::http::StatusCode::PAYLOAD_TOO_LARGE, format!("at most {} readings per push", max_readings)).into_response());
+    }
+    if let Err(retry) = state.limiter.check(node_id) {
+        let secs = retry.as_secs_f64().ceil().max(1.0) as u64;
+        tracing::debug!("rate limited push from {} (retry after {}s)", node_id, secs);
+        return Some((
+            axum::http::StatusCode::TOO_MANY_REQUESTS,
+            [(axum::http::header::RETRY_AFTER, secs.to_string())],
+            format!("node '{}' is pushing too often", node_id),
+        )
+            .into_response());
+    }
+    None
+}
+
+/// the merge queue is full: 503 + Retry-After, for every node alike
+fn hub_busy(state: &ApiState) -> axum::response::Response {
+    let secs = state.limiter.limits().busy_retry_secs.max(1);
+    tracing::debug!("push queue full, rejecting (retry after {}s)", secs);
+    (
+        axum::http::StatusCode::SERVICE_UNAVAILABLE,
+        [(axum::http::header::RETRY_AFTER, secs.to_string())],
+        "hub is busy merging pushes",
+    )
+        .into_response()
+}
+
+/// drop readings of retired nodes, calibrate / validate the rest and mark
+/// their nodes as heard from. false if nothing is left to merge.
+fn accept_readings(state: &ApiState, readings: &mut Vec<SensorReading>, via: &str) -> bool {
+    let dropped = state.nodes.drop_retired(readings);
+    if dropped > 0 {
+        log_msg(&format!("🚫 [{}] Dropped {} readings from decommissioned nodes", via, dropped));
+    }
+    calibrate::apply(&state.config.calibration, readings);
+    state.validator.apply(readings, via);
+    if readings.is_empty() {
+        return false;
+    }
+
+    let mut senders: Vec<&str> = readings.iter().filter_map(|r| r.sensor_id.split_once(':').map(|(node, _)| node)).collect();
+    senders.sort_unstable();
+    senders.dedup();
+    for node in senders {
+        state.nodes.pushed(node);
+    }
+    true
+}
+
+/// merge readings that didn't come through the /push queue (websocket,
+/// pull mode, coap, nats) into hub state, by the same rules as a push
+async fn ingest_readings(state: &ApiState, readings: Vec<SensorReading>, via: &str) {
+    let batch = ingest::Batch { readings, received_ms: crate::domain::now_ms() };
+    merge_batches(state, vec![batch], via).await;
+}
+
+/// the hub's merger: drains the push queue (ingest.rs), everything that
+/// queued up meanwhile under one write lock
+async fn merge_pushes(state: ApiState, mut queue: tokio::sync::mpsc::Receiver<ingest::Batch>) {
+    let mut batches = Vec::with_capacity(ingest::MERGE_MAX);
+    while queue.recv_many(&mut batches, ingest::MERGE_MAX).await > 0 {
+        merge_batches(&state, std::mem::take(&mut batches), "PUSH").await;
+    }
+}
+
+/// merge batches from spokes into hub state
+async fn merge_batches(state: &ApiState, batches: Vec<ingest::Batch>, via: &str) {
+    let mut accepted = Vec::with_capacity(batches.len());
+    for mut batch in batches {
+        if !accept_readings(state, &mut batch.readings, via) {
+            continue;
+        }
+        // log detailed incoming data for each sensor
+        for nr in &batch.readings {
+            let summary = format_sensor_summary(&nr.sensor_id, &nr.data);
+            log_msg(&format!("📥 [{}] {}", via, summary));
+        }
+        accepted.push(batch);
+    }
+    if accepted.is_empty() {
+        return;
+    }
+
+    // merge readings from the spokes into global state
+    // update/replace readings with the same sensor_id
+    let mut app = state.state.write().await;
+    for mut batch in accepted {
+        cluster::normalize_timestamps(&mut app, &mut batch.readings, &state.config.cluster, batch.received_ms);
+        app.merge_readings(batch.readings);
+    }
+    aggregate::apply(&state.config.aggregations, &mut app);
+}
+
+/// websocket upgrade for spokes running cluster.transport = "websocket"
+async fn ws_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    upgrade: axum::extract::ws::WebSocketUpgrade,
+) -> axum::response::Response {
+    let Some(node_id) = params.get("node_id").filter(|id| !id.is_empty()).cloned() else {
+        return (axum::http::StatusCode::BAD_REQUEST, "node_id is required").into_response();
+    };
+    if state.nodes.is_retired(&node_id) {
+        return (axum::http::StatusCode::GONE, "node is decommissioned").into_response();
+    }
+    upgrade.on_upgrade(move |socket| ws::serve_spoke(socket, node_id, state))
+}
+
+/// log stream handler - new log lines as they are logged (see logstream.rs)
+async fn log_stream_handler(upgrade: axum::extract::ws::WebSocketUpgrade) -> axum::response::Response {
+    upgrade.on_upgrade(logstream::serve)
 }
 
 /// buzzer test handler - manual 3-beep test.
 /// directly controls gpio without going through wasm plugin.
-async fn buzzer_test_handler() -> impl IntoResponse {
-    let hal = crate::hal::Hal::new();
-    use crate::hal::HardwareProvider;
+async fn buzzer_test_handler(
+    State(state): State<ApiState>,
+    peer: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    audit::record(&audit::api_actor(peer.map(|p| p.0), &headers), "buzzer", "test (3 beeps)");
     
-    // 3 short beeps (active low relay)
-    for _ in 0..3 {
-        let _ = hal.write_gpio(17, false); // active low on
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = hal.write_gpio(17, true); // active low off
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+    // 3 short beeps (active low relay), after what the pin has queued
+    match actuators::buzz(state.config.buzzer.gpio_pin, hal::pattern("triple")).await {
+        Err(e) if e.downcast_ref::<actuators::Busy>().is_some() => axum::http::StatusCode::TOO_MANY_REQUESTS,
+        _ => axum::http::StatusCode::OK,
     }
-    
-    axum::http::StatusCode::OK
 }
 
 /// fan status handler - returns current fan state for dashboard button logic
@@ -459,40 +1508,42 @@ async fn fan_status_handler() -> impl IntoResponse {
 
 /// fan test handler - runs fan for 10 seconds with 2 beeps
 /// only runs if fan is currently off (dashboard should disable button if on)
-async fn fan_test_handler(State(state): State<ApiState>) -> impl IntoResponse {
+async fn fan_test_handler(
+    State(state): State<ApiState>,
+    peer: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
     use std::sync::atomic::Ordering;
-    use crate::hal::HardwareProvider;
+    
+    if !cfg!(feature = "fan") {
+        return (axum::http::StatusCode::NOT_IMPLEMENTED, "This node is built without the fan feature");
+    }
     
     // Check if fan is already on
     if crate::hal::GLOBAL_FAN_STATE.load(Ordering::SeqCst) {
         return (axum::http::StatusCode::CONFLICT, "Fan already running");
     }
     
-    let hal = crate::hal::Hal::new();
-    let fan_pin = state.config.fan.gpio_pin;
+    let fan = &state.config.fan;
     let buzzer_pin = state.config.buzzer.gpio_pin;
     
     // 2 beeps to signal fan test starting
-    for _ in 0..2 {
-        let _ = hal.write_gpio(buzzer_pin, false);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-        let _ = hal.write_gpio(buzzer_pin, true);
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-    }
+    let _ = actuators::buzz(buzzer_pin, vec![(100, 100); 2]).await;
     
     log_msg("🌀 [FAN TEST] Starting 10-second fan test");
+    let actor = audit::api_actor(peer.map(|p| p.0), &headers);
+    audit::record(&actor, "fan", "on (10s test)");
     
-    // Turn fan on (active low)
-    let _ = hal.set_gpio_mode(fan_pin, "OUT");
-    let _ = hal.write_gpio(fan_pin, false); // LOW = relay ON = fan running
-    crate::hal::GLOBAL_FAN_STATE.store(true, Ordering::SeqCst);
-    
-    // Run for 10 seconds
-    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
-    
-    // Turn fan off
-    let _ = hal.write_gpio(fan_pin, true); // HIGH = relay OFF = fan stopped
-    crate::hal::GLOBAL_FAN_STATE.store(false, Ordering::SeqCst);
+    // on, 10 seconds, off - one operation, so no fan command lands in between
+    let run = actuators::run_fan(fan, std::time::Duration::from_secs(10)).await;
+    if let Err(e) = run {
+        log_msg(&format!("❌ [FAN TEST] {}", e));
+        return match e.downcast_ref::<actuators::Busy>() {
+            Some(_) => (axum::http::StatusCode::TOO_MANY_REQUESTS, "Fan is busy"),
+            None => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, "Fan test failed"),
+        };
+    }
+    audit::record(&actor, "fan", "off (10s test done)");
     
     log_msg("🌀 [FAN TEST] Fan test complete");
     
@@ -512,10 +1563,13 @@ struct BuzzerBody {
 }
 
 /// buzzer handler - controls buzzer from dashboard.
-/// if hub: forwards request to spoke (where buzzer is physically connected).
-/// if spoke: controls local gpio directly.
+/// if the buzzer lives on another node (cluster.buzzer_node): queues a buzz
+/// command for it on the command channel.
+/// otherwise: controls local gpio directly.
 async fn buzzer_handler(
     State(state): State<ApiState>,
+    peer: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
     Query(params): Query<BuzzerQuery>,
     body: Option<axum::Json<BuzzerBody>>,
 ) -> impl IntoResponse {
@@ -531,71 +1585,630 @@ async fn buzzer_handler(
         .unwrap_or_else(|| "single".to_string());
     
     let action = params.action.unwrap_or_else(|| pattern.clone());
-    let spoke_url = &state.config.cluster.spoke_buzzer_url;
+    let target = &state.config.cluster.buzzer_node;
+    let actor = audit::api_actor(peer.map(|p| p.0), &headers);
     
-    log_msg(&format!("🔔 [BUZZER] Received action='{}', spoke_url='{}'", action, spoke_url));
+    log_msg(&format!("🔔 [BUZZER] Received action='{}', buzzer_node='{}'", action, target));
     
-    // if we have a spoke buzzer url configured (hub mode), forward the request
-    if !spoke_url.is_empty() {
-        log_msg(&format!("🔔 [BUZZER] Forwarding to spoke: {}", spoke_url));
-        
-        let client = reqwest::Client::new();
-        
-        // map dashboard actions to spoke buzzer patterns
-        let pattern = match action.as_str() {
-            "beep" => "single",
-            "beep3" => "triple",
-            "long" => "long",
-            _ => "single",
-        };
-        
-        log_msg(&format!("🔔 [BUZZER] Sending pattern='{}' to {}", pattern, spoke_url));
-        
-        let body = serde_json::json!({
-            "pattern": pattern
-        });
-        
-        match client.post(spoke_url)
-            .json(&body)
-            .timeout(std::time::Duration::from_secs(5))
-            .send()
-            .await 
-        {
-            Ok(resp) => {
-                let status = resp.status();
-                log_msg(&format!("🔔 [BUZZER] Spoke responded with status: {}", status));
-                if status.is_success() {
-                    return axum::http::StatusCode::OK;
-                } else {
-                    log_msg(&format!("❌ [BUZZER] Spoke error: {:?}", resp.text().await));
-                    return axum::http::StatusCode::BAD_GATEWAY;
-                }
-            }
-            Err(e) => {
-                log_msg(&format!("❌ [BUZZER] Failed to reach spoke: {}", e));
-                return axum::http::StatusCode::BAD_GATEWAY;
-            }
-        }
+    // buzzer lives on another node (hub mode) - queue it on the command channel
+    if !target.is_empty() && *target != state.config.cluster.node_id {
+        let cmd = state.commands.enqueue(target, commands::CommandKind::Buzz { pattern }, &actor);
+        log_msg(&format!("🔔 [BUZZER] Queued command #{} for {}", cmd.id, target));
+        return axum::http::StatusCode::ACCEPTED;
     }
     
     // fallback: try local gpio (for when running on spoke directly)
-    log_msg(&format!("🔔 [BUZZER] No spoke URL, trying local GPIO pin {}", state.config.buzzer.gpio_pin));
-    
-    let hal = crate::hal::Hal::new();
-    use crate::hal::HardwareProvider;
+    log_msg(&format!("🔔 [BUZZER] Local buzzer, using GPIO pin {}", state.config.buzzer.gpio_pin));
     
     let pin = state.config.buzzer.gpio_pin;
     
     log_msg(&format!("🔔 [BUZZER] Local pattern='{}' on pin {}", pattern, pin));
+    audit::record(&actor, "buzzer", pattern.as_str());
     
-    match hal.buzz(pin, &pattern) {
+    match actuators::buzz(pin, hal::pattern(&pattern)).await {
         Ok(_) => log_msg("🔔 [BUZZER] Done."),
+        Err(e) if e.downcast_ref::<actuators::Busy>().is_some() => {
+            log_msg(&format!("⚠️ [BUZZER] Refused: {}", e));
+            return axum::http::StatusCode::TOO_MANY_REQUESTS;
+        }
         Err(e) => log_msg(&format!("❌ [BUZZER] Failed: {}", e)),
     }
     
     axum::http::StatusCode::OK
 }
 
+/// command post handler - queue a command for a node.
+/// commands for this node itself are executed immediately.
+async fn command_post_handler(
+    State(state): State<ApiState>,
+    peer: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    Json(req): Json<commands::CommandRequest>,
+) -> impl IntoResponse {
+    let actor = audit::api_actor(peer.map(|p| p.0), &headers);
+    let cmd = commands::submit(&state.commands, &req.node_id, req.kind, &actor, &state.config, &state.runtime).await;
+    let status = match cmd.status {
+        commands::CommandStatus::Queued => axum::http::StatusCode::ACCEPTED,
+        commands::CommandStatus::Failed => axum::http::StatusCode::INTERNAL_SERVER_ERROR,
+        _ => axum::http::StatusCode::OK,
+    };
+    (status, Json(cmd))
+}
+
+#[derive(serde::Deserialize)]
+struct CommandPollQuery {
+    node_id: String,
+    #[serde(default)]
+    wait: u64,
+}
+
+/// command poll handler - spokes long-poll for their queued commands.
+/// waits up to `wait` seconds (max 30) for something to arrive.
+async fn command_poll_handler(
+    State(state): State<ApiState>,
+    Query(q): Query<CommandPollQuery>,
+) -> Json<Vec<commands::Command>> {
+    let wait = std::time::Duration::from_secs(q.wait.min(30));
+    Json(state.commands.take(&q.node_id, wait).await)
+}
+
+/// command result handler - spokes report how a command went
+async fn command_result_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    Json(result): Json<commands::CommandResult>,
+) -> axum::http::StatusCode {
+    if !result.ok {
+        log_msg(&format!("❌ [COMMAND] #{} failed on node: {}", id, result.message));
+    }
+    if state.commands.complete(id, &result) {
+        axum::http::StatusCode::OK
+    } else {
+        axum::http::StatusCode::NOT_FOUND
+    }
+}
+
+/// command status handler - look up a queued/finished command
+async fn command_status_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> impl IntoResponse {
+    match state.commands.get(id) {
+        Some(cmd) => Json(cmd).into_response(),
+        None => axum::http::StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// command artifact handler - spokes download the payload of a
+/// deploy-plugin command from here
+async fn command_artifact_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> impl IntoResponse {
+    match state.commands.artifact(id) {
+        Some(bytes) => bytes.into_response(),
+        None => axum::http::StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+/// plugin upload handler - install a new .wasm on this node and hot-reload it
+async fn plugin_upload_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+    body: bytes::Bytes,
+) -> impl IntoResponse {
+    log_msg(&format!("📦 [PLUGIN] Upload for '{}' ({} bytes)", name, body.len()));
+    match state.runtime.install_plugin(&name, &body).await {
+        Ok(()) => {
+            log_msg(&format!("📦 [PLUGIN] '{}' installed and reloaded", name));
+            (axum::http::StatusCode::OK, Json(serde_json::json!({"ok": true, "plugin": name})))
+        }
+        Err(e) => {
+            log_msg(&format!("❌ [PLUGIN] '{}' install failed: {:#}", name, e));
+            (axum::http::StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({"ok": false, "plugin": name, "error": format!("{:#}", e)})))
+        }
+    }
+}
+
+/// snapshot handler - state, config, history and plugins as one .tar.gz
+async fn snapshot_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let include_plugins = params.get("plugins").is_none_or(|v| v != "false");
+    match snapshot::create(&state.state, state.store.clone(), &state.runtime, &state.config, include_plugins).await {
+        Ok(archive) => {
+            let file = format!("{}-{}.tar.gz", state.config.cluster.node_id, chrono::Local::now().format("%Y-%m-%d"));
+            log_msg(&format!("🗄️ [SNAPSHOT] Created {} ({} bytes)", file, archive.len()));
+            (
+                [
+                    (axum::http::header::CONTENT_TYPE, "application/gzip".to_string()),
+                    (axum::http::header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file)),
+                ],
+                archive,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            log_msg(&format!("❌ [SNAPSHOT] Failed: {:#}", e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+        }
+    }
+}
+
+/// restore handler - unpack a snapshot archive over this node
+async fn restore_handler(
+    State(state): State<ApiState>,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+    body: axum::body::Body,
+) -> axum::response::Response {
+    let force = params.get("force").is_some_and(|v| v == "true");
+    let archive = match snapshot::spool(body).await {
+        Ok(archive) => archive,
+        Err(e) => {
+            log_msg(&format!("❌ [SNAPSHOT] Restore upload failed: {:#}", e));
+            let status = match e.downcast_ref::<snapshot::TooLarge>() {
+                Some(_) => axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+                None => axum::http::StatusCode::BAD_REQUEST,
+            };
+            return (status, format!("{:#}", e)).into_response();
+        }
+    };
+    log_msg(&format!("🗄️ [SNAPSHOT] Restore requested ({} bytes)", archive.size));
+    match snapshot::restore(&archive, &state.state, state.store.clone(), &state.runtime, &state.config, force).await {
+        Ok(summary) => {
+            log_msg(&format!(
+                "🗄️ [SNAPSHOT] Restored '{}': {} sensors, {} config files, {} plugins{}",
+                summary.node_id,
+                summary.sensors,
+                summary.config_files.len(),
+                summary.plugins.len(),
+                if summary.restart_required { " - restart to apply the config" } else { "" }
+            ));
+            Json(summary).into_response()
+        }
+        Err(e) => {
+            log_msg(&format!("❌ [SNAPSHOT] Restore failed: {:#}", e));
+            (axum::http::StatusCode::UNPROCESSABLE_ENTITY, format!("{:#}", e)).into_response()
+        }
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct DeployQuery {
+    #[serde(default = "default_deploy_wait")]
+    wait: u64,
+}
+
+fn default_deploy_wait() -> u64 {
+    90
+}
+
+/// plugin deploy handler - hub pushes a .wasm to a node via the command
+/// channel and waits (up to `wait` seconds) for the node to report back.
+/// 200 = deployed, 502 = node reported failure, 202 = still in flight,
+/// 404 = a node the hub has never heard from.
+async fn plugin_deploy_handler(
+    State(state): State<ApiState>,
+    peer: Option<ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path((node_id, name)): axum::extract::Path<(String, String)>,
+    Query(q): Query<DeployQuery>,
+    body: bytes::Bytes,
+) -> axum::response::Response {
+    if node_id == state.config.cluster.node_id {
+        return plugin_upload_handler(State(state), axum::extract::Path(name), body).await.into_response();
+    }
+    if !runtime::KNOWN_PLUGINS.contains(&name.as_str()) {
+        return (axum::http::StatusCode::BAD_REQUEST, format!("unknown plugin '{}'", name)).into_response();
+    }
+    // a typo or a retired spoke would never fetch the artifact
+    if !state.nodes.is_known(&node_id) {
+        return (axum::http::StatusCode::NOT_FOUND, format!("unknown node '{}' (see /api/nodes)", node_id)).into_response();
+    }
+
+    let kind = commands::CommandKind::DeployPlugin { name: name.clone(), size: body.len() };
+    let cmd = state.commands.enqueue_with_artifact(&node_id, kind, &audit::api_actor(peer.map(|p| p.0), &headers), body);
+    log_msg(&format!("📦 [DEPLOY] Queued '{}' for {} as command #{}", name, node_id, cmd.id));
+
+    let cmd = state.commands
+        .wait_finished(cmd.id, std::time::Duration::from_secs(q.wait))
+        .await
+        .unwrap_or(cmd);
+    let status = match cmd.status {
+        commands::CommandStatus::Done => axum::http::StatusCode::OK,
+        commands::CommandStatus::Failed => axum::http::StatusCode::BAD_GATEWAY,
+        _ => axum::http::StatusCode::ACCEPTED,
+    };
+    log_msg(&format!("📦 [DEPLOY] '{}' on {}: {:?} {}", name, node_id, cmd.status, cmd.message));
+    (status, Json(cmd)).into_response()
+}
+
+/// plugin stats handler - call counters and latency of one plugin
+async fn plugin_stats_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> axum::response::Response {
+    if !runtime::KNOWN_PLUGINS.contains(&name.as_str()) {
+        return (axum::http::StatusCode::NOT_FOUND, format!("unknown plugin '{}'", name)).into_response();
+    }
+    let mut stats = serde_json::json!(state.runtime.stats().get(&name));
+    stats["watchdog"] = serde_json::json!(state.runtime.watchdog().status(&name));
+    Json(stats).into_response()
+}
+
+/// plugin profile handler - firefox profiler json of what was sampled since
+/// the last download, 404 when the plugin isn't the [plugin_profiler] one
+async fn plugin_profile_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(name): axum::extract::Path<String>,
+) -> axum::response::Response {
+    use axum::http::{header, StatusCode};
+    if !runtime::KNOWN_PLUGINS.contains(&name.as_str()) {
+        return (StatusCode::NOT_FOUND, format!("unknown plugin '{}'", name)).into_response();
+    }
+    let profiler = &state.config.plugin_profiler;
+    if !profiler.enabled || profiler.plugin != name {
+        return (StatusCode::NOT_FOUND, format!("'{}' is not profiled, see [plugin_profiler]", name)).into_response();
+    }
+    match state.runtime.take_profile(&name).await {
+        Ok(Some(json)) => (
+            [
+                (header::CONTENT_TYPE, "application/json".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}.profile.json\"", name)),
+            ],
+            json,
+        )
+            .into_response(),
+        Ok(None) => (StatusCode::NOT_FOUND, format!("no samples of '{}' yet (is it loaded and being called?)", name)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// metrics handler - prometheus text format
+async fn metrics_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    let mut body = String::new();
+    state.runtime.stats().write_prometheus(&mut body);
+    state.timing.write_prometheus(&mut body);
+    state.runtime.memory().write_prometheus(&mut body);
+    state.ingest.write_prometheus(&mut body);
+    actuators::write_prometheus(&mut body);
+    ([(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")], body)
+}
+
+/// system handler - process rss and memory of every plugin store
+async fn system_handler(State(state): State<ApiState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "node_id": state.config.cluster.node_id,
+        "uptime_secs": state.started.elapsed().as_secs(),
+        "process": memory::process(),
+        "cpu": memory::cpu(),
+        "plugins": state.runtime.memory().plugins(),
+    }))
+}
+
+/// health handler - cheap liveness probe used by spokes deciding whether
+/// to fall back to this hub.
+async fn health_handler() -> &'static str {
+    "ok"
+}
+
+/// crash report handler - store a spoke's panic report for postmortems
+async fn crash_report_handler(State(state): State<ApiState>, Json(report): Json<serde_json::Value>) -> axum::response::Response {
+    match crash::store_remote(&state.config.crash, &report) {
+        Ok(path) => {
+            let node = report.get("node_id").and_then(|v| v.as_str()).unwrap_or_default();
+            let message = report.get("message").and_then(|v| v.as_str()).unwrap_or_default();
+            log_msg(&format!("💥 [CRASH] '{}' panicked: {} (report: {})", node, message, path.display()));
+            axum::http::StatusCode::NO_CONTENT.into_response()
+        }
+        Err(e) => (axum::http::StatusCode::BAD_REQUEST, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// heartbeat handler - spokes signal liveness without sending readings
+async fn heartbeat_handler(State(state): State<ApiState>, Json(hb): Json<nodes::Heartbeat>) -> axum::http::StatusCode {
+    if state.nodes.is_retired(&hb.node_id) {
+        return axum::http::StatusCode::GONE;
+    }
+    state.nodes.heartbeat(&hb);
+    axum::http::StatusCode::NO_CONTENT
+}
+
+/// nodes handler - last-seen and stale state of every known node
+async fn nodes_handler(State(state): State<ApiState>) -> Json<Vec<nodes::NodeStatus>> {
+    Json(state.nodes.list())
+}
+
+/// history handler - stored readings of one sensor, oldest first.
+/// ?sensor_id= (required), from= / to= (unix ms, default last 24h), limit= (default 1000)
+async fn history_handler(
+    State(state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let Some(store) = state.store.clone() else {
+        return (axum::http::StatusCode::NOT_FOUND, "storage is disabled on this node").into_response();
+    };
+    let Some(sensor_id) = params.get("sensor_id").filter(|s| !s.is_empty()).cloned() else {
+        return (axum::http::StatusCode::BAD_REQUEST, "sensor_id is required").into_response();
+    };
+    let now = domain::now_ms();
+    let num = |key: &str, default: u64| params.get(key).and_then(|v| v.parse().ok()).unwrap_or(default);
+    let (from, to, limit) = (num("from", now.saturating_sub(24 * 3600 * 1000)), num("to", now), num("limit", 1000));
+
+    let result = tokio::task::spawn_blocking(move || store.history(&sensor_id, from, to, limit as usize)).await;
+    match result.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(mut readings) => {
+            state.units.convert(&mut readings);
+            codec::Encoded(codec::Encoding::from_accept(&headers), serde_json::json!({ "readings": readings })).into_response()
+        }
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// aggregate handler - windowed stats over stored readings, computed on the node
+/// ?sensor= (required), fn=avg|min|max|sum|count (default avg), window= (default 1h),
+/// range= (default 24h, ending now) or explicit from= / to= in unix ms
+async fn aggregate_handler(
+    State(state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let bad_request = |msg: String| (axum::http::StatusCode::BAD_REQUEST, msg).into_response();
+    let Some(store) = state.store.clone() else {
+        return (axum::http::StatusCode::NOT_FOUND, "storage is disabled on this node").into_response();
+    };
+    let Some(sensor) = params.get("sensor").filter(|s| !s.is_empty()).cloned() else {
+        return bad_request("sensor is required".into());
+    };
+    let func = params.get("fn").cloned().unwrap_or_else(|| "avg".into());
+    if !["avg", "min", "max", "sum", "count"].contains(&func.as_str()) {
+        return bad_request(format!("unknown fn '{}' (expected avg, min, max, sum or count)", func));
+    }
+    let duration = |key: &str, default: &str| {
+        let text = params.get(key).map(String::as_str).unwrap_or(default);
+        storage::parse_duration_ms(text).ok_or_else(|| format!("invalid {} '{}' (e.g. 30s, 15m, 1h, 7d)", key, text))
+    };
+    let (window, range) = match (duration("window", "1h"), duration("range", "24h")) {
+        (Ok(w), Ok(r)) => (w, r),
+        (Err(e), _) | (_, Err(e)) => return bad_request(e),
+    };
+    let now = domain::now_ms();
+    let to = params.get("to").and_then(|v| v.parse().ok()).unwrap_or(now);
+    let from = params.get("from").and_then(|v| v.parse().ok()).unwrap_or(to.saturating_sub(range));
+    if (to.saturating_sub(from)) / window > 10_000 {
+        return bad_request("too many windows (max 10000) - use a larger window".into());
+    }
+
+    let (id, f) = (sensor.clone(), func.clone());
+    let result = tokio::task::spawn_blocking(move || store.aggregate(&id, &f, window, from, to)).await;
+    match result.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(mut points) => {
+            state.units.convert_points(&sensor, &func, &mut points);
+            codec::Encoded(
+                codec::Encoding::from_accept(&headers),
+                serde_json::json!({ "sensor": sensor, "fn": func, "window_ms": window, "from": from, "to": to, "points": points }),
+            )
+            .into_response()
+        }
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    }
+}
+
+/// chart handler - one sensor binned to at most `points` windows, sized for plotting.
+/// ?sensor= (required), range= (default 24h, ending now) or from= / to= in unix ms,
+/// points= (default 300, max 5000), fields= comma list (default all numeric fields).
+/// columnar reply: t[] / samples[] per bin and avg[] / min[] / max[] per field,
+/// null where a field has no value in a bin.
+async fn chart_handler(
+    State(state): State<ApiState>,
+    headers: axum::http::HeaderMap,
+    Query(params): Query<std::collections::HashMap<String, String>>,
+) -> axum::response::Response {
+    let bad_request = |msg: String| (axum::http::StatusCode::BAD_REQUEST, msg).into_response();
+    let Some(store) = state.store.clone() else {
+        return (axum::http::StatusCode::NOT_FOUND, "storage is disabled on this node").into_response();
+    };
+    let Some(sensor) = params.get("sensor").filter(|s| !s.is_empty()).cloned() else {
+        return bad_request("sensor is required".into());
+    };
+    let range_text = params.get("range").map(String::as_str).unwrap_or("24h");
+    let Some(range) = storage::parse_duration_ms(range_text) else {
+        return bad_request(format!("invalid range '{}' (e.g. 6h, 7d)", range_text));
+    };
+    let points: u64 = match params.get("points").map(|p| p.parse()) {
+        None => 300,
+        Some(Ok(n @ 1..=5000)) => n,
+        Some(_) => return bad_request("points must be between 1 and 5000".into()),
+    };
+    let now = domain::now_ms();
+    let to = params.get("to").and_then(|v| v.parse().ok()).unwrap_or(now);
+    let from = params.get("from").and_then(|v| v.parse().ok()).unwrap_or(to.saturating_sub(range));
+    // whole seconds, so bins line up between requests
+    let bin_ms = to.saturating_sub(from).div_ceil(points).div_ceil(1000).max(1) * 1000;
+    let wanted: Option<Vec<&str>> = params.get("fields").map(|f| f.split(',').map(str::trim).filter(|f| !f.is_empty()).collect());
+
+    let id = sensor.clone();
+    let result = tokio::task::spawn_blocking(move || store.chart(&id, bin_ms, from, to)).await;
+    let mut bins = match result.map_err(anyhow::Error::from).and_then(|r| r) {
+        Ok(bins) => bins,
+        Err(e) => return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    state.units.convert_bins(&sensor, &mut bins);
+
+    let names: std::collections::BTreeSet<&String> = bins
+        .iter()
+        .flat_map(|bin| bin.values.keys())
+        .filter(|name| wanted.as_ref().is_none_or(|w| w.contains(&name.as_str())))
+        .collect();
+    let fields: serde_json::Map<String, serde_json::Value> = names
+        .into_iter()
+        .map(|name| {
+            let column = |pick: fn(&storage::FieldSummary) -> f64| -> Vec<Option<f64>> {
+                bins.iter().map(|bin| bin.values.get(name).map(pick)).collect()
+            };
+            let unit = state.units.schema().lookup(&sensor, name).map(|s| s.unit.clone());
+            let series = serde_json::json!({
+                "unit": unit,
+                "avg": column(|s| s.avg),
+                "min": column(|s| s.min),
+                "max": column(|s| s.max),
+            });
+            (name.clone(), series)
+        })
+        .collect();
+    let t: Vec<u64> = bins.iter().map(|bin| bin.timestamp_ms).collect();
+    let samples: Vec<u64> = bins.iter().map(|bin| bin.samples).collect();
+    codec::Encoded(
+        codec::Encoding::from_accept(&headers),
+        serde_json::json!({ "sensor": sensor, "from": from, "to": to, "bin_ms": bin_ms, "t": t, "samples": samples, "fields": fields }),
+    )
+    .into_response()
+}
+
+/// node delete handler - decommission a retired spoke.
+/// purges its readings from AppState and blocks it until re-registered.
+async fn node_delete_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> axum::response::Response {
+    if id == state.config.cluster.node_id || id == aggregate::CLUSTER_NODE {
+        return (axum::http::StatusCode::BAD_REQUEST, format!("'{}' cannot be decommissioned", id)).into_response();
+    }
+    state.nodes.decommission(&id);
+    let mut app = state.state.write().await;
+    let removed = app.remove_node(&id);
+    aggregate::apply(&state.config.aggregations, &mut app);
+    drop(app);
+    let history_removed = match &state.store {
+        Some(store) => {
+            let (store, node) = (store.clone(), id.clone());
+            match tokio::task::spawn_blocking(move || store.purge_node(&node)).await.map_err(anyhow::Error::from).and_then(|r| r) {
+                Ok(n) => n,
+                Err(e) => {
+                    log_msg(&format!("❌ [STORAGE] Failed to purge history of '{}': {}", id, e));
+                    0
+                }
+            }
+        }
+        None => 0,
+    };
+    log_msg(&format!("🗑️ [NODE] Purged {} readings ({} stored) of '{}'", removed, history_removed, id));
+    Json(serde_json::json!({ "node_id": id, "readings_removed": removed, "history_removed": history_removed })).into_response()
+}
+
+/// node register handler - allow a decommissioned node to push again
+async fn node_register_handler(
+    State(state): State<ApiState>,
+    axum::extract::Path(id): axum::extract::Path<String>,
+) -> axum::http::StatusCode {
+    match state.nodes.register(&id) {
+        true => axum::http::StatusCode::NO_CONTENT,
+        false => axum::http::StatusCode::NOT_FOUND,
+    }
+}
+
+/// cluster handler - topology view (this node + every known spoke)
+async fn cluster_handler(State(state): State<ApiState>) -> Json<serde_json::Value> {
+    let cluster = &state.config.cluster;
+    Json(serde_json::json!({
+        "hub": {
+            "node_id": cluster.node_id,
+            "role": role::current(),
+            "version": env!("CARGO_PKG_VERSION"),
+            "uptime_secs": state.started.elapsed().as_secs(),
+            "dry_run": hal::dry_run(),
+            "maintenance": maintenance::current(),
+            "transport": cluster.transport,
+            "plugins": state.runtime.loaded_plugins().await,
+            "quarantined": state.runtime.watchdog().quarantined(),
+            "pull_spokes": cluster.pull_spokes,
+            "metadata": cluster.metadata,
+            "polling": state.timing.stats(),
+            // spokes: health of the push link to the active hub
+            "link": (!state.hubs.is_empty()).then(|| state.hubs.link_stats()),
+        },
+        "spokes": state.nodes.list(),
+    }))
+}
+
+/// role handler - the role this node runs as now and the one host.toml sets
+async fn role_handler(State(state): State<ApiState>) -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "role": role::current(), "configured": state.config.cluster.role }))
+}
+
+#[derive(serde::Deserialize)]
+struct RoleRequest {
+    role: role::Role,
+}
+
+/// role switch handler - promote / demote this node without a restart (role.rs)
+async fn role_switch_handler(
+    peer: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<RoleRequest>,
+) -> axum::response::Response {
+    match role::set(body.role, &audit::api_actor(peer.map(|p| p.0), &headers)) {
+        Ok(previous) => Json(serde_json::json!({ "role": body.role, "previous": previous })).into_response(),
+        Err(e) => (axum::http::StatusCode::CONFLICT, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// effective config handler - what the host runs with, not what host.toml says now
+async fn effective_config_handler() -> axum::response::Response {
+    match config_reload::effective() {
+        Ok((config, pending)) => Json(serde_json::json!({ "config": config, "pending_restart": pending })).into_response(),
+        Err(e) => (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response(),
+    }
+}
+
+/// maintenance handler - the running maintenance window (null outside one)
+async fn maintenance_handler() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "maintenance": maintenance::current() }))
+}
+
+#[derive(serde::Deserialize)]
+struct MaintenanceRequest {
+    enabled: bool,
+    /// e.g. "30m"; none = until it is turned off
+    #[serde(default)]
+    duration: Option<String>,
+}
+
+/// maintenance switch handler - pause polling, alerting and hardware writes (maintenance.rs)
+async fn maintenance_set_handler(
+    peer: Option<axum::extract::ConnectInfo<std::net::SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    Json(body): Json<MaintenanceRequest>,
+) -> axum::response::Response {
+    let actor = audit::api_actor(peer.map(|p| p.0), &headers);
+    if !body.enabled {
+        return match maintenance::end(&actor) {
+            true => Json(serde_json::json!({ "maintenance": null })).into_response(),
+            false => (axum::http::StatusCode::NOT_FOUND, "not in maintenance").into_response(),
+        };
+    }
+    let duration_ms = match body.duration.as_deref().map(storage::parse_duration_ms) {
+        Some(None) => return (axum::http::StatusCode::BAD_REQUEST, "invalid duration, e.g. \"30m\"").into_response(),
+        Some(Some(ms)) => Some(ms),
+        None => None,
+    };
+    Json(serde_json::json!({ "maintenance": maintenance::start(duration_ms, &actor) })).into_response()
+}
+
+/// serve the hub-managed config overlay for a node (404 if none exists)
+async fn node_config_handler(axum::extract::Path(node_id): axum::extract::Path<String>) -> axum::response::Response {
+    match node_config::build_overlay(&node_id) {
+        Ok(Some(overlay)) => Json(overlay).into_response(),
+        Ok(None) => (axum::http::StatusCode::NOT_FOUND, "no overlay for node").into_response(),
+        Err(e) => {
+            log_msg(&format!("❌ [CONFIG] Overlay for '{}' is invalid: {:#}", node_id, e));
+            (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response()
+        }
+    }
+}
+
 /// fallback handler - returns 404 for unknown routes
 async fn fallback_handler() -> (axum::http::StatusCode, String) {
     (axum::http::StatusCode::NOT_FOUND, "Not Found".to_string())
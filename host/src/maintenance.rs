@@ -0,0 +1,130 @@
+//! ==============================================================================
+//! maintenance.rs - pause polling, alerting and hardware actions for a while
+//! ==============================================================================
+//!
+//! purpose:
+//!     swapping a sensor used to mean a burst of failed polls, offline
+//!     alerts and the buzzer going off while the wires were loose. a node
+//!     can be put into maintenance instead:
+//!         POST /api/maintenance  {"enabled": true, "duration": "30m"}
+//!         POST /api/maintenance  {"enabled": false}
+//!     the POST needs api.admin_token (admin.rs) - a window pauses alerting
+//!     and survives a reboot.
+//!     while it lasts:
+//!         - the poll loop skips its cycles (no plugin runs, no pushes)
+//!         - alert rules and anomaly alerts are not evaluated - alerts keep
+//!           the state they had, nothing fires, resolves or notifies
+//!         - gpio, buzzer, fan and led writes are held back (hal.rs) and
+//!           audit entries say so
+//!         - spokes report it in their heartbeat, the hub doesn't raise
+//!           node_offline for a spoke in maintenance (it may be powered off)
+//!     the api keeps running; GET /api/maintenance and /api/cluster show the
+//!     window. without a duration it lasts until it is turned off. the window
+//!     is kept in config/maintenance.json, so a reboot in the middle of a
+//!     sensor swap doesn't end it early.
+//!
+//! relationships:
+//!     - used by: main.rs (poll loop, /api/maintenance, /api/cluster),
+//!       alerts.rs (evaluation), hal.rs (writes), audit.rs, nodes.rs (heartbeat)
+//!
+//! ==============================================================================
+
+use crate::domain::now_ms;
+use crate::log_msg;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// a maintenance window
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Window {
+    pub since_ms: u64,
+    /// None = until it is turned off
+    pub until_ms: Option<u64>,
+    /// who started it (audit actor, e.g. "api:192.168.7.20")
+    pub actor: String,
+}
+
+static WINDOW: Mutex<Option<Window>> = Mutex::new(None);
+static FILE: OnceLock<PathBuf> = OnceLock::new();
+
+/// keep the window in `path`, restoring one that is still running
+pub fn load(path: PathBuf) {
+    let restored: Option<Window> = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .filter(|w: &Window| w.until_ms.is_none_or(|until| until > now_ms()));
+    if let Some(window) = &restored {
+        log_msg(&format!("🔧 [MAINTENANCE] Restored, started by {} ({})", window.actor, remaining(window)));
+    }
+    *WINDOW.lock().unwrap() = restored;
+    let _ = FILE.set(path);
+}
+
+/// start (or extend) maintenance; `duration_ms` None = until it is turned off
+pub fn start(duration_ms: Option<u64>, actor: &str) -> Window {
+    let now = now_ms();
+    // a huge duration is as good as no end, not an overflow under the lock
+    let until_ms = duration_ms.map(|d| now.saturating_add(d));
+    let mut window = WINDOW.lock().unwrap();
+    let since_ms = window.as_ref().map_or(now, |w| w.since_ms);
+    let started = Window { since_ms, until_ms, actor: actor.to_string() };
+    log_msg(&format!(
+        "🔧 [MAINTENANCE] {} started maintenance ({}): polling, alerting and hardware writes paused",
+        actor,
+        remaining(&started)
+    ));
+    *window = Some(started.clone());
+    save(window.as_ref());
+    started
+}
+
+/// end maintenance early. false = the node wasn't in maintenance
+pub fn end(actor: &str) -> bool {
+    let mut window = WINDOW.lock().unwrap();
+    if window.take().is_none() {
+        return false;
+    }
+    log_msg(&format!("🔧 [MAINTENANCE] {} ended maintenance, polling and alerting resume", actor));
+    save(None);
+    true
+}
+
+/// the running window, None outside maintenance (an expired one ends here)
+pub fn current() -> Option<Window> {
+    let mut window = WINDOW.lock().unwrap();
+    if window.as_ref()?.until_ms.is_some_and(|until| until <= now_ms()) {
+        *window = None;
+        log_msg("🔧 [MAINTENANCE] Window is over, polling and alerting resume");
+        save(None);
+    }
+    window.clone()
+}
+
+pub fn active() -> bool {
+    current().is_some()
+}
+
+/// "1800s left" / "until turned off"
+fn remaining(window: &Window) -> String {
+    match window.until_ms {
+        Some(until) => format!("{}s left", until.saturating_sub(now_ms()) / 1000),
+        None => "until turned off".to_string(),
+    }
+}
+
+fn save(window: Option<&Window>) {
+    let Some(path) = FILE.get() else { return };
+    let result = match window {
+        Some(window) => serde_json::to_string_pretty(window)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json)),
+        None => match std::fs::remove_file(path) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            other => other,
+        },
+    };
+    if let Err(e) = result {
+        log_msg(&format!("⚠️ [MAINTENANCE] Could not save {}: {}", path.display(), e));
+    }
+}
@@ -0,0 +1,217 @@
+//! ==============================================================================
+//! memory.rs - plugin store and process memory usage (GET /api/system)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a pi zero has 512mb and a python plugin starts at ~13mb of linear
+//!     memory. when the host gets oom-killed after a week there is no way
+//!     to tell which plugin kept growing. tracked per plugin store:
+//!         linear_memory_bytes   sum of the store's wasm memories (a memory
+//!                               never shrinks, so this is also its peak)
+//!         table_elements        sum of the store's wasm tables
+//!         code_bytes            the compiled component's machine code
+//!     and for the process: resident set size now and at its peak, and
+//!     the cpu time of the host and of the subprocesses it started that
+//!     exited (children_cpu_seconds - a python interpreter per led write
+//!     shows up there, a long-running python driver doesn't spawn any).
+//!
+//! how:
+//!     every plugin store gets a Limiter (wasmtime ResourceLimiter) that
+//!     allows all growth and counts it. the store's resource table (wasi
+//!     streams, pollables) is filled by wasmtime-wasi and has no public
+//!     size, so it isn't tracked. a hot reload replaces the store and starts its counters from zero.
+//!     unloading a plugin (disabled, quarantined) drops its store
+//!     and with it the component: the linear memories and the code are
+//!     unmapped, and the rss logged before / after shows what came back.
+//!
+//! exposed:
+//!     GET /api/system (json) and GET /metrics (wasi_plugin_linear_memory_bytes,
+//!     wasi_plugin_table_elements, wasi_plugin_code_bytes
+//!     labelled by plugin,
+//!     wasi_process_resident_memory_bytes, wasi_process_cpu_seconds_total,
+//!     wasi_process_children_cpu_seconds_total).
+//!
+//! relationships:
+//!     - used by: runtime.rs (limiter on every plugin store), main.rs (endpoints)
+//!
+//! ==============================================================================
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PluginMemory {
+    pub linear_memory_bytes: u64,
+    pub table_elements: u64,
+    pub code_bytes: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProcessMemory {
+    /// None where /proc isn't available
+    pub rss_bytes: Option<u64>,
+    pub peak_rss_bytes: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ProcessCpu {
+    /// user + system time of the host, None where /proc isn't available
+    pub cpu_seconds: Option<f64>,
+    /// user + system time of subprocesses that exited
+    pub children_cpu_seconds: Option<f64>,
+}
+
+#[derive(Default)]
+struct StoreUsage {
+    linear_memory_bytes: AtomicU64,
+    table_elements: AtomicU64,
+    code_bytes: AtomicU64,
+}
+
+/// ResourceLimiter of one plugin store: allows everything, counts it
+#[derive(Default)]
+pub struct Limiter {
+    usage: Arc<StoreUsage>,
+}
+
+impl StoreUsage {
+    fn snapshot(&self) -> PluginMemory {
+        PluginMemory {
+            linear_memory_bytes: self.linear_memory_bytes.load(Ordering::Relaxed),
+            table_elements: self.table_elements.load(Ordering::Relaxed),
+            code_bytes: self.code_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl wasmtime::ResourceLimiter for Limiter {
+    fn memory_growing(&mut self, current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        self.usage.linear_memory_bytes.fetch_add(desired.saturating_sub(current) as u64, Ordering::Relaxed);
+        Ok(true)
+    }
+
+    fn table_growing(&mut self, current: usize, desired: usize, _maximum: Option<usize>) -> anyhow::Result<bool> {
+        self.usage.table_elements.fetch_add(desired.saturating_sub(current) as u64, Ordering::Relaxed);
+        Ok(true)
+    }
+}
+
+/// cheap handle, clones share the per-plugin usage
+#[derive(Clone, Default)]
+pub struct MemoryUsage {
+    stores: Arc<Mutex<BTreeMap<String, Arc<StoreUsage>>>>,
+}
+
+impl MemoryUsage {
+    /// the store behind `limiter` (running `code_bytes` of compiled code)
+    /// is now the plugin's running instance
+    pub fn register(&self, plugin: &str, limiter: &Limiter, code_bytes: u64) {
+        limiter.usage.code_bytes.store(code_bytes, Ordering::Relaxed);
+        self.stores.lock().unwrap().insert(plugin.to_string(), limiter.usage.clone());
+    }
+
+    /// the plugin was unloaded, its last usage
+    pub fn unregister(&self, plugin: &str) -> Option<PluginMemory> {
+        self.stores.lock().unwrap().remove(plugin).map(|usage| usage.snapshot())
+    }
+
+    /// usage of every loaded plugin
+    pub fn plugins(&self) -> BTreeMap<String, PluginMemory> {
+        let stores = self.stores.lock().unwrap();
+        stores
+            .iter()
+            .map(|(plugin, usage)| (plugin.clone(), usage.snapshot()))
+            .collect()
+    }
+
+    /// append the memory metrics in prometheus text format
+    pub fn write_prometheus(&self, out: &mut String) {
+        let plugins = self.plugins();
+        let gauges = [
+            ("wasi_plugin_linear_memory_bytes", "Linear memory of the plugin's store.", Gauge::LinearMemory),
+            ("wasi_plugin_table_elements", "Wasm table elements of the plugin's store.", Gauge::TableElements),
+            ("wasi_plugin_code_bytes", "Compiled code of the plugin's component.", Gauge::Code),
+        ];
+        for (name, help, gauge) in gauges {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} gauge", name, help, name);
+            for (plugin, memory) in &plugins {
+                let _ = writeln!(out, "{}{{plugin=\"{}\"}} {}", name, plugin, gauge.of(memory));
+            }
+        }
+        if let Some(rss) = process().rss_bytes {
+            let name = "wasi_process_resident_memory_bytes";
+            let _ = writeln!(out, "# HELP {} Resident set size of the host process.\n# TYPE {} gauge\n{} {}", name, name, name, rss);
+        }
+        let cpu = cpu();
+        let counters = [
+            ("wasi_process_cpu_seconds_total", "User and system cpu time of the host process.", cpu.cpu_seconds),
+            ("wasi_process_children_cpu_seconds_total", "User and system cpu time of exited subprocesses.", cpu.children_cpu_seconds),
+        ];
+        for (name, help, seconds) in counters {
+            if let Some(seconds) = seconds {
+                let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter\n{} {}", name, help, name, name, seconds);
+            }
+        }
+    }
+}
+
+enum Gauge {
+    LinearMemory,
+    TableElements,
+    Code,
+}
+
+impl Gauge {
+    fn of(&self, memory: &PluginMemory) -> u64 {
+        match self {
+            Gauge::LinearMemory => memory.linear_memory_bytes,
+            Gauge::TableElements => memory.table_elements,
+            Gauge::Code => memory.code_bytes,
+        }
+    }
+}
+
+/// resident set size of this process, from /proc/self/status
+pub fn process() -> ProcessMemory {
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(status) = std::fs::read_to_string("/proc/self/status") {
+            // "VmRSS:     51234 kB"
+            let kb = |key: &str| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix(key))
+                    .and_then(|rest| rest.split_whitespace().next())
+                    .and_then(|v| v.parse::<u64>().ok())
+                    .map(|kb| kb * 1024)
+            };
+            return ProcessMemory { rss_bytes: kb("VmRSS:"), peak_rss_bytes: kb("VmHWM:") };
+        }
+    }
+    ProcessMemory { rss_bytes: None, peak_rss_bytes: None }
+}
+
+/// cpu time of this process and its exited children, from /proc/self/stat
+pub fn cpu() -> ProcessCpu {
+    #[cfg(target_os = "linux")]
+    {
+        if let Ok(stat) = std::fs::read_to_string("/proc/self/stat") {
+            // "pid (comm) state ..." - utime, stime, cutime, cstime are fields
+            // 14-17, in clock ticks (USER_HZ, 100 on linux)
+            let fields: Vec<u64> = stat
+                .rsplit_once(')')
+                .map(|(_, rest)| rest.split_whitespace().skip(11).take(4).filter_map(|v| v.parse().ok()).collect())
+                .unwrap_or_default();
+            if let [utime, stime, cutime, cstime] = fields[..] {
+                return ProcessCpu {
+                    cpu_seconds: Some((utime + stime) as f64 / 100.0),
+                    children_cpu_seconds: Some((cutime + cstime) as f64 / 100.0),
+                };
+            }
+        }
+    }
+    ProcessCpu { cpu_seconds: None, children_cpu_seconds: None }
+}
@@ -0,0 +1,119 @@
+//! ==============================================================================
+//! mqtt.rs - MQTT telemetry publisher (feature = "mqtt")
+//! ==============================================================================
+//!
+//! purpose:
+//!     publishes every local reading to an existing mqtt broker so the
+//!     cluster plugs into mqtt-based scada / home-automation setups. this
+//!     runs alongside (or instead of, with an empty hub_url) the http push.
+//!
+//! topics:
+//!     {topic_prefix}/{node_id}/{sensor}   e.g. edge/pi4-spoke/dht22
+//!     payload is the SensorReading as json. qos and retain come from [mqtt].
+//!
+//! connection handling:
+//!     rumqttc only makes progress while its event loop is polled, so a
+//!     background task drives it. on broker errors the task waits and polls
+//!     again, which makes rumqttc reconnect. publishes made while the broker
+//!     is down queue up in the client's bounded channel (try_publish drops
+//!     them once it is full rather than stalling the polling loop).
+//!
+//! relationships:
+//!     - used by: main.rs (polling loop)
+//!     - reads: config.rs (MqttConfig)
+//!
+//! ==============================================================================
+
+use crate::config::MqttConfig;
+use crate::domain::SensorReading;
+use crate::log_msg;
+use rumqttc::{AsyncClient, Event, MqttOptions, Packet, QoS};
+use std::time::Duration;
+
+/// how many publishes may wait for the broker before new ones are dropped
+const QUEUE_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct MqttPublisher {
+    client: AsyncClient,
+    qos: QoS,
+    retain: bool,
+    prefix: String,
+    node_id: String,
+}
+
+impl MqttPublisher {
+    /// connect to the broker and spawn the event loop task
+    pub fn start(config: &MqttConfig, node_id: &str) -> anyhow::Result<Self> {
+        if config.broker_host.is_empty() {
+            anyhow::bail!("mqtt.broker_host is not set");
+        }
+        let qos = match config.qos {
+            0 => QoS::AtMostOnce,
+            1 => QoS::AtLeastOnce,
+            2 => QoS::ExactlyOnce,
+            n => anyhow::bail!("mqtt.qos must be 0, 1 or 2 (got {})", n),
+        };
+
+        let client_id = if config.client_id.is_empty() { node_id } else { &config.client_id };
+        let mut options = MqttOptions::new(client_id, &config.broker_host, config.broker_port);
+        options.set_keep_alive(Duration::from_secs(30));
+        if !config.username.is_empty() {
+            options.set_credentials(&config.username, &config.password);
+        }
+
+        let (client, mut eventloop) = AsyncClient::new(options, QUEUE_CAPACITY);
+        let broker = format!("{}:{}", config.broker_host, config.broker_port);
+        tokio::spawn(async move {
+            let mut connected = false;
+            loop {
+                match eventloop.poll().await {
+                    Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                        connected = true;
+                        log_msg(&format!("📶 [MQTT] Connected to {}", broker));
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        if connected {
+                            log_msg(&format!("❌ [MQTT] Connection to {} lost: {}", broker, e));
+                        }
+                        connected = false;
+                        tracing::debug!("mqtt event loop error: {}", e);
+                        tokio::time::sleep(Duration::from_secs(5)).await;
+                    }
+                }
+            }
+        });
+
+        Ok(Self {
+            client,
+            qos,
+            retain: config.retain,
+            prefix: config.topic_prefix.trim_end_matches('/').to_string(),
+            node_id: node_id.to_string(),
+        })
+    }
+
+    /// topic for a reading; the "node:" prefix of the sensor_id is dropped
+    /// since the node is already part of the topic
+    fn topic(&self, sensor_id: &str) -> String {
+        let sensor = sensor_id.split_once(':').map(|(_, s)| s).unwrap_or(sensor_id);
+        format!("{}/{}/{}", self.prefix, self.node_id, sensor)
+    }
+
+    /// queue each reading for publishing. returns how many were queued.
+    pub fn publish(&self, readings: &[SensorReading]) -> usize {
+        let mut queued = 0;
+        for r in readings {
+            let payload = match serde_json::to_vec(r) {
+                Ok(p) => p,
+                Err(_) => continue,
+            };
+            match self.client.try_publish(self.topic(&r.sensor_id), self.qos, self.retain, payload) {
+                Ok(()) => queued += 1,
+                Err(e) => tracing::debug!("mqtt publish for {} dropped: {}", r.sensor_id, e),
+            }
+        }
+        queued
+    }
+}
@@ -0,0 +1,185 @@
+//! ==============================================================================
+//! nats.rs - NATS / JetStream transport for hub/spoke data (feature = "nats")
+//! ==============================================================================
+//!
+//! purpose:
+//!     alternative to http push for deployments that already run a nats
+//!     server on the edge gateway. selected with cluster.transport = "nats".
+//!
+//! subjects:
+//!     each spoke publishes its batches (json Vec<SensorReading>, the same
+//!     body as POST /push) to `{subject_prefix}.{node_id}`. the hub consumes
+//!     `{subject_prefix}.>` and merges every batch into AppState through the
+//!     same path as a push (main.rs ingest_readings).
+//!
+//! jetstream:
+//!     with cluster.nats.jetstream = true (default) the readings subjects are
+//!     captured in a stream and the hub reads them through a durable pull
+//!     consumer, so batches published while the hub is down or restarting
+//!     are delivered once it comes back. spokes wait for the stream ack, so
+//!     a failed publish is reported like a failed http push. each batch
+//!     carries a Nats-Msg-Id (uuid), so the stream's duplicate window drops
+//!     republished batches before the hub ever sees them.
+//!     without jetstream plain core-nats publish/subscribe is used.
+//!
+//! relationships:
+//!     - used by: main.rs (spoke polling loop, hub consumer task)
+//!     - reads: config.rs (ClusterConfig.nats)
+//!     - writes: domain.rs (AppState, via main.rs ingest_readings - retired
+//!       nodes dropped, calibration / validation, the node marked as heard from)
+//!
+//! ==============================================================================
+
+use crate::config::{ClusterConfig, NatsConfig};
+use crate::domain::SensorReading;
+use crate::log_msg;
+use async_nats::jetstream;
+use futures::StreamExt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// how long jetstream keeps batches nobody has consumed
+const STREAM_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// connect in the background - the client keeps reconnecting on its own,
+/// so an unreachable server at startup only delays delivery
+async fn connect(config: &NatsConfig) -> anyhow::Result<async_nats::Client> {
+    let client = async_nats::ConnectOptions::new()
+        .retry_on_initial_connect()
+        .connect(config.url.as_str())
+        .await?;
+    Ok(client)
+}
+
+/// make sure the readings stream exists (idempotent)
+async fn ensure_stream(js: &jetstream::Context, config: &NatsConfig) -> anyhow::Result<jetstream::stream::Stream> {
+    let stream = js
+        .get_or_create_stream(jetstream::stream::Config {
+            name: config.stream.clone(),
+            subjects: vec![format!("{}.>", config.subject_prefix)],
+            max_age: STREAM_MAX_AGE,
+            ..Default::default()
+        })
+        .await
+        .map_err(|e| anyhow::anyhow!("jetstream stream '{}': {}", config.stream, e))?;
+    Ok(stream)
+}
+
+// ==============================================================================
+// spoke side - publisher
+// ==============================================================================
+
+pub struct NatsPublisher {
+    client: async_nats::Client,
+    jetstream: Option<jetstream::Context>,
+    config: NatsConfig,
+    stream_ready: AtomicBool,
+    subject: String,
+    /// batches are encoded into this one (codec.rs, buffer reuse)
+    payload: std::sync::Mutex<bytes::BytesMut>,
+}
+
+impl NatsPublisher {
+    pub async fn connect(config: &NatsConfig, node_id: &str) -> anyhow::Result<Self> {
+        let client = connect(config).await?;
+        let jetstream = config.jetstream.then(|| jetstream::new(client.clone()));
+        Ok(Self {
+            client,
+            jetstream,
+            config: config.clone(),
+            stream_ready: AtomicBool::new(false),
+            subject: format!("{}.{}", config.subject_prefix, node_id),
+            payload: std::sync::Mutex::new(bytes::BytesMut::new()),
+        })
+    }
+
+    /// publish one batch. with jetstream this waits for the stream's ack.
+    pub async fn publish(&self, readings: &[SensorReading]) -> anyhow::Result<String> {
+        let payload = crate::codec::Encoding::Json.encode_into(&readings, &mut self.payload.lock().unwrap())?;
+        match &self.jetstream {
+            Some(js) => {
+                // the hub normally creates the stream; create it here too so
+                // spokes can buffer before the hub has ever started
+                if !self.stream_ready.load(Ordering::Relaxed) && ensure_stream(js, &self.config).await.is_ok() {
+                    self.stream_ready.store(true, Ordering::Relaxed);
+                }
+                let mut headers = async_nats::HeaderMap::new();
+                headers.insert("Nats-Msg-Id", uuid::Uuid::new_v4().to_string().as_str());
+                js.publish_with_headers(self.subject.clone(), headers, payload)
+                    .await?
+                    .await
+                    .map_err(|e| anyhow::anyhow!("jetstream ack: {}", e))?;
+            }
+            None => {
+                self.client.publish(self.subject.clone(), payload).await?;
+                self.client.flush().await?;
+            }
+        }
+        Ok(self.subject.clone())
+    }
+}
+
+// ==============================================================================
+// hub side - consumer
+// ==============================================================================
+
+/// spawn the hub consumer that merges spoke batches into AppState.
+/// restarts itself (after a pause) if the subscription ends or fails.
+pub fn spawn_consumer(api: crate::ApiState, cluster: ClusterConfig) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = consume(&api, &cluster).await {
+                log_msg(&format!("❌ [NATS] Consumer stopped: {:#}", e));
+            }
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    })
+}
+
+async fn consume(api: &crate::ApiState, cluster: &ClusterConfig) -> anyhow::Result<()> {
+    let config = &cluster.nats;
+    let client = connect(config).await?;
+    let subject = format!("{}.>", config.subject_prefix);
+
+    if config.jetstream {
+        let js = jetstream::new(client);
+        let stream = ensure_stream(&js, config).await?;
+        let durable = format!("hub-{}", cluster.node_id);
+        let consumer: jetstream::consumer::PullConsumer = stream
+            .get_or_create_consumer(&durable, jetstream::consumer::pull::Config {
+                durable_name: Some(durable.clone()),
+                ..Default::default()
+            })
+            .await
+            .map_err(|e| anyhow::anyhow!("jetstream consumer '{}': {}", durable, e))?;
+        log_msg(&format!("📶 [NATS] Consuming {} via JetStream ({})", subject, config.stream));
+
+        let mut messages = consumer.messages().await?;
+        while let Some(msg) = messages.next().await {
+            let msg = msg.map_err(|e| anyhow::anyhow!("{}", e))?;
+            merge_batch(api, msg.subject.as_str(), &msg.payload).await;
+            if let Err(e) = msg.ack().await {
+                tracing::debug!("jetstream ack failed: {}", e);
+            }
+        }
+    } else {
+        let mut subscriber = client.subscribe(subject.clone()).await?;
+        log_msg(&format!("📶 [NATS] Subscribed to {}", subject));
+        while let Some(msg) = subscriber.next().await {
+            merge_batch(api, msg.subject.as_str(), &msg.payload).await;
+        }
+    }
+    anyhow::bail!("subscription to {} closed", subject)
+}
+
+async fn merge_batch(api: &crate::ApiState, subject: &str, payload: &[u8]) {
+    let readings: Vec<SensorReading> = match serde_json::from_slice(payload) {
+        Ok(r) => r,
+        Err(e) => {
+            log_msg(&format!("⚠️ [NATS] Dropping malformed batch on {}: {}", subject, e));
+            return;
+        }
+    };
+    log_msg(&format!("📥 [NATS] {} readings on {}", readings.len(), subject));
+    crate::ingest_readings(api, readings, "NATS").await;
+}
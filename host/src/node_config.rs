@@ -0,0 +1,203 @@
+//! ==============================================================================
+//! node_config.rs - centralized per-node config overlays served by the hub
+//! ==============================================================================
+//!
+//! purpose:
+//!     lets fleet-wide settings (poll intervals, thresholds, plugin enablement)
+//!     live on the hub instead of in every device's host.toml.
+//!
+//! hub side:
+//!     overlays are toml fragments in config/nodes/ next to host.toml:
+//!       - _all.toml        applied to every node
+//!       - {node_id}.toml   applied to one node (wins over _all.toml)
+//!     GET /api/nodes/{id}/config returns the combined overlay text plus a
+//!     content version so spokes can cheaply detect changes.
+//!
+//! spoke side:
+//!     at startup the spoke fetches its overlay and merges it over the local
+//!     host.toml before anything else is initialized. the last overlay is
+//!     cached (config/overlay.cache.toml) so a spoke that boots while the hub
+//!     is down still comes up with fleet settings. a background task checks
+//!     for a new version every cluster.config_sync_seconds; when it changes,
+//!     the new overlay is cached and the host exits so the service manager
+//!     restarts it with the new settings.
+//!
+//! relationships:
+//!     - used by: main.rs (startup merge, sync task, hub endpoint)
+//!     - uses: config.rs (load_with_overlay, merge_toml)
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use crate::log_msg;
+use serde::{Deserialize, Serialize};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const CACHE_FILE: &str = "overlay.cache.toml";
+
+/// response of GET /api/nodes/{id}/config
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct NodeOverlay {
+    pub node_id: String,
+    pub version: String,
+    pub overlay: String,
+}
+
+// ==============================================================================
+// hub side
+// ==============================================================================
+
+/// directory holding per-node overlays (config/nodes)
+pub fn overlay_dir() -> PathBuf {
+    HostConfig::find_config_file()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("config"))
+        .join("nodes")
+}
+
+/// build the overlay for a node from _all.toml + {node_id}.toml.
+/// returns None if neither file exists.
+pub fn build_overlay(node_id: &str) -> anyhow::Result<Option<NodeOverlay>> {
+    if node_id.is_empty() || node_id.contains(['/', '\\', '.']) {
+        anyhow::bail!("invalid node id '{}'", node_id);
+    }
+    let dir = overlay_dir();
+    let mut merged: Option<toml::Value> = None;
+
+    for file in ["_all.toml".to_string(), format!("{}.toml", node_id)] {
+        let path = dir.join(&file);
+        let Ok(text) = std::fs::read_to_string(&path) else { continue };
+        let value: toml::Value = toml::from_str(&text)
+            .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+        match merged.as_mut() {
+            Some(base) => crate::config::merge_toml(base, value),
+            None => merged = Some(value),
+        }
+    }
+
+    let Some(value) = merged else { return Ok(None) };
+    let overlay = toml::to_string(&value)?;
+    Ok(Some(NodeOverlay {
+        node_id: node_id.to_string(),
+        version: content_version(&overlay),
+        overlay,
+    }))
+}
+
+fn content_version(text: &str) -> String {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    text.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+// ==============================================================================
+// spoke side
+// ==============================================================================
+
+async fn fetch(client: &reqwest::Client, hub_base: &str, node_id: &str) -> anyhow::Result<Option<NodeOverlay>> {
+    let resp = client
+        .get(format!("{}/api/nodes/{}/config", hub_base, node_id))
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    Ok(Some(resp.error_for_status()?.json().await?))
+}
+
+fn cache_path(config_file: &Path) -> PathBuf {
+    config_file.with_file_name(CACHE_FILE)
+}
+
+/// the overlay text a spoke last applied (None without one)
+pub fn cached_overlay(config_file: &Path) -> Option<String> {
+    read_cache(config_file).map(|o| o.overlay)
+}
+
+fn read_cache(config_file: &Path) -> Option<NodeOverlay> {
+    let text = std::fs::read_to_string(cache_path(config_file)).ok()?;
+    Some(NodeOverlay { version: content_version(&text), overlay: text, ..Default::default() })
+}
+
+fn write_cache(config_file: &Path, overlay: Option<&NodeOverlay>) {
+    let path = cache_path(config_file);
+    let result = match overlay {
+        Some(o) => std::fs::write(&path, &o.overlay),
+        None => std::fs::remove_file(&path).or(Ok(())),
+    };
+    if let Err(e) = result {
+        log_msg(&format!("⚠️ [CONFIG] Could not update overlay cache {}: {}", path.display(), e));
+    }
+}
+
+/// startup: fetch this node's overlay from the hub (or the cache if the hub
+/// is unreachable) and return the merged config plus the applied version.
+pub async fn apply_startup_overlay(config: HostConfig, client: &reqwest::Client, hub_base: &str) -> (HostConfig, String) {
+    let Some(config_file) = HostConfig::find_config_file() else { return (config, String::new()) };
+    let node_id = config.cluster.node_id.clone();
+
+    let overlay = match fetch(client, hub_base, &node_id).await {
+        Ok(fetched) => {
+            write_cache(&config_file, fetched.as_ref());
+            fetched
+        }
+        Err(e) => {
+            log_msg(&format!("⚠️ [CONFIG] Hub overlay unavailable ({}), using cache", e));
+            read_cache(&config_file)
+        }
+    };
+
+    let Some(overlay) = overlay else { return (config, String::new()) };
+    match HostConfig::load_with_overlay(&config_file, &overlay.overlay) {
+        Ok(merged) => {
+            log_msg(&format!("🔧 [CONFIG] Applied hub overlay v{}", overlay.version));
+            (merged, overlay.version)
+        }
+        Err(e) => {
+            log_msg(&format!("❌ [CONFIG] Hub overlay rejected: {}", e));
+            (config, String::new())
+        }
+    }
+}
+
+/// background: poll the hub for overlay changes. a changed (and valid)
+/// overlay is cached and the process exits so it restarts with it.
+pub fn spawn_sync(
+    client: reqwest::Client,
+    hub_base: impl Fn() -> String + Send + 'static,
+    node_id: String,
+    applied_version: String,
+    interval_secs: u64,
+) -> Option<tokio::task::JoinHandle<()>> {
+    let config_file = HostConfig::find_config_file()?;
+    if interval_secs == 0 {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        ticker.tick().await; // startup already fetched
+        loop {
+            ticker.tick().await;
+            let fetched = match fetch(&client, &hub_base(), &node_id).await {
+                Ok(f) => f,
+                Err(_) => continue, // hub down - keep current settings
+            };
+            let version = fetched.as_ref().map(|o| o.version.clone()).unwrap_or_default();
+            if version == applied_version {
+                continue;
+            }
+            if let Some(o) = &fetched {
+                if let Err(e) = HostConfig::load_with_overlay(&config_file, &o.overlay) {
+                    log_msg(&format!("❌ [CONFIG] Ignoring invalid hub overlay v{}: {}", o.version, e));
+                    continue;
+                }
+            }
+            write_cache(&config_file, fetched.as_ref());
+            log_msg(&format!("🔧 [CONFIG] Hub overlay changed ({} → {}), restarting to apply", applied_version, version));
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            std::process::exit(0);
+        }
+    }))
+}
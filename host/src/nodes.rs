@@ -0,0 +1,320 @@
+//! ==============================================================================
+//! nodes.rs - hub-side node registry and stale-node detection
+//! ==============================================================================
+//!
+//! purpose:
+//!     the hub tracks when it last heard from each spoke. a node counts as
+//!     seen whenever it sends a heartbeat or pushes readings; a background
+//!     task marks nodes stale once they have been silent for
+//!     cluster.stale_after_seconds and logs the transition both ways. the
+//!     transitions are also emitted as node_offline / node_online events.
+//!     a second task, started in every role (role.rs), flags every reading
+//!     older than stale_after_seconds with quality "stale", whichever node
+//!     it came from.
+//!
+//! heartbeats:
+//!     spokes POST /heartbeat {node_id, uptime_secs, version} every
+//!     cluster.heartbeat_seconds. it's a few bytes, so liveness stays
+//!     accurate even when a node has no sensor data to push. heartbeats
+//!     also carry the spoke's loaded plugins and its view of the push link
+//!     (latency, failures) and its [cluster.metadata], which GET /api/cluster
+//!     reports per node. a spoke in maintenance (maintenance.rs) says so in
+//!     its heartbeat; when it goes quiet it is marked stale without a
+//!     node_offline event (nor node_online when it is back).
+//!
+//! batch dedupe:
+//!     the registry also remembers the last RECENT_BATCHES batch ids per
+//!     node, so a push retried after a lost reply is acknowledged without
+//!     being merged (and recorded in history) a second time.
+//!
+//! decommissioning:
+//!     DELETE /api/nodes/{id} forgets a retired spoke, purges its readings
+//!     and stored history, and puts its id on a blocklist
+//!     (config/retired_nodes.json, survives restarts). pushes, websocket
+//!     connects and heartbeats from a blocked id are refused, and its
+//!     readings are dropped whichever way they arrive (pull mode, coap,
+//!     nats), until it is re-registered with POST /api/nodes/{id}/register.
+//!     both routes need api.admin_token (admin.rs).
+//!
+//! relationships:
+//!     - used by: main.rs (/heartbeat, /api/nodes, /api/cluster, push path, spoke sender)
+//!     - reads: config.rs (ClusterConfig.heartbeat_seconds, stale_after_seconds)
+//!     - writes: events.rs (node_offline / node_online)
+//!
+//! ==============================================================================
+
+use crate::cluster::{HubFailover, LinkStats};
+use crate::domain::{NodeMetadata, SensorReading};
+use crate::events::EventBus;
+use crate::log_msg;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// body of POST /heartbeat
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Heartbeat {
+    pub node_id: String,
+    #[serde(default)]
+    pub uptime_secs: u64,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub plugins: Vec<String>,
+    #[serde(default)]
+    pub link: Option<LinkStats>,
+    #[serde(default)]
+    pub metadata: NodeMetadata,
+    /// the spoke is in maintenance (maintenance.rs)
+    #[serde(default)]
+    pub maintenance: bool,
+}
+
+/// what the hub knows about one node
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct NodeStatus {
+    pub node_id: String,
+    pub last_seen_ms: u64,
+    pub last_heartbeat_ms: u64,
+    pub last_push_ms: u64,
+    pub uptime_secs: u64,
+    pub version: String,
+    pub plugins: Vec<String>,
+    pub pushes: u64,
+    pub link: Option<LinkStats>,
+    pub metadata: NodeMetadata,
+    pub stale: bool,
+    /// in maintenance as of its last heartbeat - no node_offline when it goes quiet
+    pub maintenance: bool,
+}
+
+/// batch ids remembered per node for duplicate detection
+const RECENT_BATCHES: usize = 256;
+
+#[derive(Default)]
+pub struct NodeRegistry {
+    nodes: Mutex<BTreeMap<String, NodeStatus>>,
+    batches: Mutex<BTreeMap<String, VecDeque<String>>>,
+    retired: Mutex<BTreeSet<String>>,
+    retired_file: Option<PathBuf>,
+    events: Arc<EventBus>,
+}
+
+impl NodeRegistry {
+    /// registry whose decommissioned-node blocklist is persisted in `retired_file`
+    pub fn load(retired_file: PathBuf) -> Self {
+        let retired: BTreeSet<String> = std::fs::read_to_string(&retired_file)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default();
+        if !retired.is_empty() {
+            log_msg(&format!("🚫 [NODE] {} decommissioned node(s) blocked: {:?}", retired.len(), retired));
+        }
+        Self { retired: Mutex::new(retired), retired_file: Some(retired_file), ..Default::default() }
+    }
+
+    /// emit stale / back-online transitions on `events`
+    pub fn with_events(mut self, events: Arc<EventBus>) -> Self {
+        self.events = events;
+        self
+    }
+
+    /// record a heartbeat
+    pub fn heartbeat(&self, hb: &Heartbeat) {
+        let now = crate::domain::now_ms();
+        self.touch(&hb.node_id, now, |node| {
+            node.last_heartbeat_ms = now;
+            node.uptime_secs = hb.uptime_secs;
+            node.version = hb.version.clone();
+            node.plugins = hb.plugins.clone();
+            node.link = hb.link.clone();
+            node.metadata = hb.metadata.clone();
+            node.maintenance = hb.maintenance;
+        });
+    }
+
+    /// record a readings push from a node
+    pub fn pushed(&self, node_id: &str) {
+        let now = crate::domain::now_ms();
+        self.touch(node_id, now, |node| {
+            node.last_push_ms = now;
+            node.pushes += 1;
+        });
+    }
+
+    /// record a batch id. false = already seen from this node (a replay)
+    pub fn accept_batch(&self, node_id: &str, batch_id: &str) -> bool {
+        let mut batches = self.batches.lock().unwrap();
+        let recent = batches.entry(node_id.to_string()).or_default();
+        if recent.iter().any(|id| id == batch_id) {
+            return false;
+        }
+        if recent.len() == RECENT_BATCHES {
+            recent.pop_front();
+        }
+        recent.push_back(batch_id.to_string());
+        true
+    }
+
+    fn touch(&self, node_id: &str, now: u64, update: impl FnOnce(&mut NodeStatus)) {
+        let mut nodes = self.nodes.lock().unwrap();
+        let node = nodes.entry(node_id.to_string()).or_insert_with(|| {
+            log_msg(&format!("🟢 [NODE] '{}' registered", node_id));
+            NodeStatus { node_id: node_id.to_string(), ..Default::default() }
+        });
+        if node.stale {
+            log_msg(&format!("🟢 [NODE] '{}' is back online", node_id));
+            if !node.maintenance {
+                self.events.emit("node_online", node_id, format!("node '{}' is back online", node_id), serde_json::Value::Null);
+            }
+            node.stale = false;
+        }
+        node.last_seen_ms = now;
+        update(node);
+    }
+
+    pub fn list(&self) -> Vec<NodeStatus> {
+        self.nodes.lock().unwrap().values().cloned().collect()
+    }
+
+    /// the node has heartbeated or pushed, and isn't decommissioned
+    pub fn is_known(&self, node_id: &str) -> bool {
+        self.nodes.lock().unwrap().contains_key(node_id) && !self.is_retired(node_id)
+    }
+
+    pub fn is_retired(&self, node_id: &str) -> bool {
+        self.retired.lock().unwrap().contains(node_id)
+    }
+
+    /// forget a node and block it until re-registered
+    pub fn decommission(&self, node_id: &str) {
+        self.nodes.lock().unwrap().remove(node_id);
+        self.batches.lock().unwrap().remove(node_id);
+        let mut retired = self.retired.lock().unwrap();
+        retired.insert(node_id.to_string());
+        self.save_retired(&retired);
+        log_msg(&format!("🚫 [NODE] '{}' decommissioned", node_id));
+    }
+
+    /// lift a decommission block. false = the node wasn't blocked
+    pub fn register(&self, node_id: &str) -> bool {
+        let mut retired = self.retired.lock().unwrap();
+        if !retired.remove(node_id) {
+            return false;
+        }
+        self.save_retired(&retired);
+        log_msg(&format!("🟢 [NODE] '{}' re-registered", node_id));
+        true
+    }
+
+    /// drop readings whose node is decommissioned, returning how many were dropped
+    pub fn drop_retired(&self, readings: &mut Vec<SensorReading>) -> usize {
+        let retired = self.retired.lock().unwrap();
+        if retired.is_empty() {
+            return 0;
+        }
+        let before = readings.len();
+        readings.retain(|r| !retired.contains(crate::cluster::node_of(&r.sensor_id)));
+        before - readings.len()
+    }
+
+    fn save_retired(&self, retired: &BTreeSet<String>) {
+        let Some(path) = &self.retired_file else { return };
+        let result = serde_json::to_string_pretty(retired)
+            .map_err(std::io::Error::other)
+            .and_then(|json| std::fs::write(path, json));
+        if let Err(e) = result {
+            log_msg(&format!("⚠️ [NODE] Could not save {}: {}", path.display(), e));
+        }
+    }
+
+    /// periodically flag nodes that have gone quiet
+    pub fn spawn_stale_check(self: Arc<Self>, stale_after_secs: u64) -> Option<tokio::task::JoinHandle<()>> {
+        if stale_after_secs == 0 {
+            return None;
+        }
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(std::time::Duration::from_secs(stale_after_secs.div_ceil(2).max(1)));
+            loop {
+                ticker.tick().await;
+                let now = crate::domain::now_ms();
+                for node in self.nodes.lock().unwrap().values_mut() {
+                    if !node.stale && now.saturating_sub(node.last_seen_ms) > stale_after_secs * 1000 {
+                        node.stale = true;
+                        let silent_secs = (now - node.last_seen_ms) / 1000;
+                        if node.maintenance {
+                            log_msg(&format!("🔧 [NODE] '{}' is silent for {}s (in maintenance)", node.node_id, silent_secs));
+                            continue;
+                        }
+                        log_msg(&format!("🔴 [NODE] '{}' is stale (silent for {}s)", node.node_id, silent_secs));
+                        self.events.emit(
+                            "node_offline",
+                            &node.node_id,
+                            format!("node '{}' is offline (silent for {}s)", node.node_id, silent_secs),
+                            serde_json::json!({ "silent_secs": silent_secs }),
+                        );
+                    }
+                }
+            }
+        }))
+    }
+}
+
+/// periodically flag readings that have gone quiet. unlike the node check
+/// this runs in every role - a standalone node's or a spoke's own sensors
+/// go stale too.
+pub fn spawn_stale_readings(state: Arc<tokio::sync::RwLock<crate::domain::AppState>>, stale_after_secs: u64) -> Option<tokio::task::JoinHandle<()>> {
+    if stale_after_secs == 0 {
+        return None;
+    }
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(stale_after_secs.div_ceil(2).max(1)));
+        loop {
+            ticker.tick().await;
+            let marked = state.write().await.mark_stale(crate::domain::now_ms(), stale_after_secs * 1000);
+            if marked > 0 {
+                tracing::debug!("{} readings flagged stale", marked);
+            }
+        }
+    }))
+}
+
+/// spoke side: send a heartbeat to the active hub every `interval_secs`
+pub fn spawn_heartbeat(
+    client: reqwest::Client,
+    hubs: Arc<HubFailover>,
+    runtime: crate::runtime::WasmRuntime,
+    node_id: String,
+    metadata: NodeMetadata,
+    interval_secs: u64,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if interval_secs == 0 {
+        return None;
+    }
+    let started = std::time::Instant::now();
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(interval_secs));
+        loop {
+            ticker.tick().await;
+            let hb = Heartbeat {
+                node_id: node_id.clone(),
+                uptime_secs: started.elapsed().as_secs(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                plugins: runtime.loaded_plugins().await,
+                link: Some(hubs.link_stats()),
+                metadata: metadata.clone(),
+                maintenance: crate::maintenance::active(),
+            };
+            let sent = client
+                .post(format!("{}/heartbeat", hubs.active_base()))
+                .json(&hb)
+                .timeout(std::time::Duration::from_secs(3))
+                .send()
+                .await;
+            if let Err(e) = sent {
+                tracing::debug!("heartbeat failed: {}", e);
+            }
+        }
+    }))
+}
@@ -0,0 +1,237 @@
+//! ==============================================================================
+//! notify.rs - slack / telegram / email notifications for alerts and nodes
+//! ==============================================================================
+//!
+//! purpose:
+//!     firing alerts and nodes going offline should reach a phone, not just
+//!     the log. the notifier follows the event bus and forwards
+//!         alert_firing / alert_resolved   (severity >= notify.min_severity)
+//!         node_offline / node_online      (notify.nodes)
+//!     to every channel configured under [notify.*]. transitions of
+//!     silenced alerts (POST /api/alerts/{id}/silence) are not sent.
+//!
+//! channels:
+//!     slack     - incoming webhook url
+//!     telegram  - bot token + chat id (sendMessage)
+//!     email     - smtp (starttls / tls / none), "email" feature
+//!
+//! throttling:
+//!     - grouping: events arriving within group_seconds of the first one
+//!       go out as a single message.
+//!     - cooldown: the same transition of the same alert (or node) is only
+//!       reported once per cooldown_seconds; a flapping sensor produces one
+//!       firing + one resolved message and a count of suppressed repeats.
+//!     - cap: at most max_per_hour messages; events past the cap are
+//!       dropped and counted in the next message that goes out.
+//!
+//! relationships:
+//!     - used by: main.rs (spawned on hubs and standalone nodes)
+//!     - used by: reports.rs (delivers summary reports)
+//!     - reads: config.rs (NotifyConfig), events.rs (EventBus)
+//!
+//! ==============================================================================
+
+use crate::alerts::SEVERITIES;
+use crate::config::NotifyConfig;
+use crate::events::{Event, EventBus};
+use crate::log_msg;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::Instant;
+
+const HOUR: Duration = Duration::from_secs(3600);
+
+/// start the notifier if any channel is configured
+pub fn spawn(config: NotifyConfig, events: &EventBus, node_id: String, client: reqwest::Client) {
+    let slack = !config.slack.webhook_url.is_empty();
+    let telegram = !config.telegram.bot_token.is_empty() && !config.telegram.chat_id.is_empty();
+    let email = !config.email.smtp_host.is_empty() && !config.email.to.is_empty();
+    #[cfg(not(feature = "email"))]
+    if email {
+        log_msg("⚠️ [NOTIFY] [notify.email] is set but this build lacks the 'email' feature");
+    }
+    let channels: Vec<&str> = [("slack", slack), ("telegram", telegram), ("email", email && cfg!(feature = "email"))]
+        .into_iter()
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| name)
+        .collect();
+    if channels.is_empty() {
+        return;
+    }
+    log_msg(&format!("📣 [NOTIFY] Sending alerts to {}", channels.join(", ")));
+
+    let mut rx = events.subscribe();
+    tokio::spawn(async move {
+        let group = Duration::from_secs(config.group_seconds);
+        let cooldown = Duration::from_secs(config.cooldown_seconds);
+        let mut pending: Vec<String> = Vec::new();
+        let mut flush_at: Option<Instant> = None;
+        let mut last_sent: HashMap<String, Instant> = HashMap::new();
+        let mut sent: VecDeque<Instant> = VecDeque::new();
+        let (mut suppressed, mut dropped) = (0usize, 0usize);
+
+        loop {
+            tokio::select! {
+                event = rx.recv() => match event {
+                    Ok(event) => {
+                        let Some((key, line)) = describe(&config, &event) else { continue };
+                        let now = Instant::now();
+                        if last_sent.get(&key).is_some_and(|at| now.duration_since(*at) < cooldown) {
+                            suppressed += 1;
+                            continue;
+                        }
+                        last_sent.insert(key, now);
+                        pending.push(line);
+                        flush_at.get_or_insert(now + group);
+                    }
+                    Err(RecvError::Lagged(missed)) => dropped += missed as usize,
+                    Err(RecvError::Closed) => return,
+                },
+                _ = tokio::time::sleep_until(flush_at.unwrap_or_else(|| Instant::now() + HOUR)), if flush_at.is_some() => {
+                    flush_at = None;
+                    let lines = std::mem::take(&mut pending);
+                    while sent.front().is_some_and(|at| at.elapsed() > HOUR) {
+                        sent.pop_front();
+                    }
+                    if sent.len() >= config.max_per_hour {
+                        dropped += lines.len();
+                        tracing::warn!("notification cap reached, dropped {} events", lines.len());
+                        continue;
+                    }
+                    sent.push_back(Instant::now());
+                    let text = compose(&lines, std::mem::take(&mut suppressed), std::mem::take(&mut dropped));
+                    let subject = match lines.len() {
+                        1 => format!("[{}] {}", node_id, lines[0]),
+                        n => format!("[{}] {} notifications", node_id, n),
+                    };
+                    deliver(&config, &client, &subject, &text).await;
+                }
+            }
+        }
+    });
+}
+
+/// notify.min_severity is one of the alert severities
+pub fn check_config(config: &NotifyConfig) -> anyhow::Result<()> {
+    if !SEVERITIES.contains(&config.min_severity.as_str()) {
+        anyhow::bail!("notify.min_severity: unknown severity '{}' (expected one of {:?})", config.min_severity, SEVERITIES);
+    }
+    Ok(())
+}
+
+/// cooldown key and message line for a relevant event
+fn describe(config: &NotifyConfig, event: &Event) -> Option<(String, String)> {
+    let rank = |severity: &str| SEVERITIES.iter().position(|s| *s == severity);
+    match event.kind.as_str() {
+        "alert_firing" | "alert_resolved" => {
+            let severity = event.data.get("severity")?.as_str()?;
+            if rank(severity) < rank(&config.min_severity) {
+                return None;
+            }
+            if event.data.get("silenced_until_ms").is_some() {
+                return None;
+            }
+            let firing = event.kind == "alert_firing";
+            if !firing && !config.resolved {
+                return None;
+            }
+            let id = event.data.get("id")?.as_str()?;
+            let value = event.data.get("value").and_then(|v| v.as_f64()).unwrap_or_default();
+            let line = match firing {
+                true => format!(
+                    "🚨 {} {} on {} (value {}, {})",
+                    severity.to_uppercase(),
+                    event.data.get("rule")?.as_str()?,
+                    event.source,
+                    value,
+                    event.data.get("expr").and_then(|e| e.as_str()).unwrap_or_default()
+                ),
+                false => format!("✅ resolved: {} on {} (value {})", event.data.get("rule")?.as_str()?, event.source, value),
+            };
+            Some((format!("{}/{}", event.kind, id), line))
+        }
+        "node_offline" | "node_online" if config.nodes => {
+            let icon = if event.kind == "node_offline" { "🔴" } else { "🟢" };
+            Some((format!("{}/{}", event.kind, event.source), format!("{} {}", icon, event.message)))
+        }
+        _ => None,
+    }
+}
+
+fn compose(lines: &[String], suppressed: usize, dropped: usize) -> String {
+    let mut text = lines.join("\n");
+    if suppressed > 0 {
+        text.push_str(&format!("\n({} repeated notification(s) suppressed by cooldown)", suppressed));
+    }
+    if dropped > 0 {
+        text.push_str(&format!("\n({} notification(s) dropped (hourly limit or backlog))", dropped));
+    }
+    text
+}
+
+/// send one message to every configured channel
+pub async fn deliver(config: &NotifyConfig, client: &reqwest::Client, subject: &str, text: &str) {
+    if !config.slack.webhook_url.is_empty() {
+        let sent = post_json(client, &config.slack.webhook_url, serde_json::json!({ "text": text })).await;
+        report("slack", sent);
+    }
+    if !config.telegram.bot_token.is_empty() && !config.telegram.chat_id.is_empty() {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", config.telegram.bot_token);
+        let sent = post_json(client, &url, serde_json::json!({ "chat_id": config.telegram.chat_id, "text": text })).await;
+        report("telegram", sent);
+    }
+    #[cfg(feature = "email")]
+    if !config.email.smtp_host.is_empty() && !config.email.to.is_empty() {
+        report("email", send_email(&config.email, subject, text).await);
+    }
+    #[cfg(not(feature = "email"))]
+    let _ = subject;
+}
+
+fn report(channel: &str, sent: anyhow::Result<()>) {
+    if let Err(e) = sent {
+        log_msg(&format!("❌ [NOTIFY] {} delivery failed: {:#}", channel, e));
+    }
+}
+
+async fn post_json(client: &reqwest::Client, url: &str, body: serde_json::Value) -> anyhow::Result<()> {
+    // the url is a secret (telegram's carries the bot token), keep it out of the error
+    let response = client
+        .post(url)
+        .timeout(Duration::from_secs(10))
+        .json(&body)
+        .send()
+        .await
+        .map_err(|e| e.without_url())?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("{} {}", status, response.text().await.unwrap_or_default().trim());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "email")]
+async fn send_email(config: &crate::config::EmailConfig, subject: &str, text: &str) -> anyhow::Result<()> {
+    use lettre::transport::smtp::authentication::Credentials;
+    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+
+    let mut message = Message::builder().from(config.from.parse()?).subject(subject);
+    for to in &config.to {
+        message = message.to(to.parse()?);
+    }
+    let message = message.body(text.to_string())?;
+
+    let mut transport = match config.tls.as_str() {
+        "tls" => AsyncSmtpTransport::<Tokio1Executor>::relay(&config.smtp_host)?,
+        "none" => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&config.smtp_host),
+        _ => AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?,
+    }
+    .port(config.smtp_port)
+    .timeout(Some(Duration::from_secs(15)));
+    if !config.username.is_empty() {
+        transport = transport.credentials(Credentials::new(config.username.clone(), config.password.clone()));
+    }
+    transport.build().send(message).await?;
+    Ok(())
+}
@@ -0,0 +1,142 @@
+//! ==============================================================================
+//! pins.rs - gpio pin claims, checked for conflicts at startup
+//! ==============================================================================
+//!
+//! purpose:
+//!     two drivers on one gpio pin don't fail loudly - the buzzer clicks when
+//!     the fan switches, or the dht22 reads garbage while the led strip
+//!     updates. every consumer of a pin is collected as a claim:
+//!         host hardware   leds.gpio_pin, buzzer.gpio_pin, fan.gpio_pin
+//!                         (the ones the build has a driver for, see the
+//!                         leds / buzzer / fan cargo features)
+//!         plugins         dht22 (sensors.dht22.gpio_pin), bme680 (the i2c
+//!                         bus, gpio 2 / 3), plus the pins listed in
+//!                         [plugins.{name}] pins = [...] - the wiring of a
+//!                         plugin the host doesn't know the hardware of
+//!     i2c claims share the bus with each other, any other pin has one owner.
+//!     at startup (check()):
+//!         - a pin off the header or two pieces of host hardware on one pin
+//!           refuse the start, with every conflicting key
+//!         - a plugin claiming a pin that is already taken is disabled, with
+//!           a logged error naming both claims (plugins in name order, so
+//!           the same config always disables the same plugin)
+//!
+//! relationships:
+//!     - used by: main.rs (startup, before the selftest and plugin loading),
+//!       config_check.rs (validate-config reports the same conflicts)
+//!     - reads: config.rs (pins of the hardware sections, [plugins.*])
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use crate::log_msg;
+
+/// gpio pins on the 40-pin header
+pub const MAX_GPIO: u8 = 27;
+/// gpio pins of the i2c bus (sda, scl)
+pub const I2C_PINS: [u8; 2] = [2, 3];
+
+/// one consumer of a gpio pin
+pub struct Claim {
+    /// the config key that sets the pin, e.g. "sensors.dht22.gpio_pin"
+    pub key: String,
+    pub pin: u8,
+    /// the [plugins.*] key of the claiming plugin, None for host hardware
+    pub plugin: Option<String>,
+    /// an i2c bus pin, shared with the other i2c claims
+    i2c: bool,
+}
+
+impl Claim {
+    fn host(key: &str, pin: u8) -> Claim {
+        Claim { key: key.to_string(), pin, plugin: None, i2c: false }
+    }
+
+    fn plugin(plugin: &str, key: String, pin: u8, i2c: bool) -> Claim {
+        Claim { key, pin, plugin: Some(plugin.to_string()), i2c }
+    }
+
+    /// true when both can't drive the pin
+    fn collides(&self, other: &Claim) -> bool {
+        self.pin == other.pin && !(self.i2c && other.i2c)
+    }
+}
+
+/// a claim that can't be honoured
+pub struct Conflict {
+    pub claim: Claim,
+    pub message: String,
+}
+
+/// every pin claim of the config: host hardware, then the enabled plugins by name
+pub fn claims(config: &HostConfig) -> Vec<Claim> {
+    // hardware this build has no driver for doesn't hold its pin
+    let mut claims: Vec<Claim> = [
+        (cfg!(feature = "leds"), Claim::host("leds.gpio_pin", config.leds.gpio_pin)),
+        (cfg!(feature = "buzzer"), Claim::host("buzzer.gpio_pin", config.buzzer.gpio_pin)),
+        (cfg!(feature = "fan"), Claim::host("fan.gpio_pin", config.fan.gpio_pin)),
+    ]
+    .into_iter()
+    .filter_map(|(built, claim)| built.then_some(claim))
+    .collect();
+    let mut plugins: Vec<(&String, &crate::config::PluginEntry)> = config.plugins.iter().filter(|(_, p)| p.enabled).collect();
+    plugins.sort_by_key(|(name, _)| *name);
+    for (name, entry) in plugins {
+        match name.as_str() {
+            "dht22" => claims.push(Claim::plugin(name, "sensors.dht22.gpio_pin".to_string(), config.sensors.dht22.gpio_pin, false)),
+            "bme680" => claims.extend(I2C_PINS.map(|pin| Claim::plugin(name, "sensors.bme680 (i2c bus)".to_string(), pin, true))),
+            _ => {}
+        }
+        for pin in &entry.pins {
+            claims.push(Claim::plugin(name, format!("plugins.{}.pins", name), *pin, false));
+        }
+    }
+    claims
+}
+
+/// claims off the header or on a pin an earlier claim holds. a plugin with a
+/// conflict gives up all of its pins, its other claims aren't checked.
+pub fn conflicts(claims: Vec<Claim>) -> Vec<Conflict> {
+    let mut taken: Vec<Claim> = Vec::new();
+    let mut conflicts: Vec<Conflict> = Vec::new();
+    for claim in claims {
+        if claim.plugin.is_some() && conflicts.iter().any(|c| c.claim.plugin == claim.plugin) {
+            continue;
+        }
+        let message = match taken.iter().find(|t| t.collides(&claim)) {
+            _ if claim.pin > MAX_GPIO => format!("gpio {} is not on the header (0-{})", claim.pin, MAX_GPIO),
+            Some(owner) if owner.i2c => format!("gpio {} is an i2c bus pin, needed by the {} plugin", claim.pin, owner.plugin.as_deref().unwrap_or("")),
+            Some(owner) => format!("gpio {} is also used by {}", claim.pin, owner.key),
+            None => {
+                taken.push(claim);
+                continue;
+            }
+        };
+        if claim.plugin.is_some() {
+            taken.retain(|t| t.plugin != claim.plugin);
+        }
+        conflicts.push(Conflict { claim, message });
+    }
+    conflicts
+}
+
+/// refuse to start on host hardware conflicts, disable plugins that claim a taken pin
+pub fn check(config: &mut HostConfig) -> anyhow::Result<()> {
+    let conflicts = conflicts(claims(config));
+    let host: Vec<String> = conflicts
+        .iter()
+        .filter(|c| c.claim.plugin.is_none())
+        .map(|c| format!("{}: {}", c.claim.key, c.message))
+        .collect();
+    if !host.is_empty() {
+        anyhow::bail!("gpio pin conflict: {}", host.join("; "));
+    }
+    for conflict in conflicts {
+        let Some(plugin) = conflict.claim.plugin else { continue };
+        if let Some(entry) = config.plugins.get_mut(&plugin).filter(|p| p.enabled) {
+            entry.enabled = false;
+            log_msg(&format!("❌ [GPIO] {}: {} - plugin '{}' disabled", conflict.claim.key, conflict.message, plugin));
+        }
+    }
+    Ok(())
+}
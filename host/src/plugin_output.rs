@@ -0,0 +1,143 @@
+//! ==============================================================================
+//! plugin_output.rs - recent stdout / stderr lines of each plugin
+//! ==============================================================================
+//!
+//! purpose:
+//!     a python plugin prints its traceback to stderr right before the
+//!     component traps, and that output only ever reached the terminal.
+//!     plugin stdout / stderr now go through a tee: written to the host's
+//!     stdout / stderr as before (inherit_stdio), and the last OUTPUT_LINES
+//!     lines are kept per plugin (stderr lines prefixed "stderr: ") so a
+//!     failure record can include what the plugin said before it died.
+//!
+//! relationships:
+//!     - used by: runtime.rs (wasi stdout / stderr of every plugin store),
+//!       plugin_stats.rs (last failure record)
+//!
+//! ==============================================================================
+
+use bytes::Bytes;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::sync::{Arc, Mutex};
+use wasmtime_wasi::{HostOutputStream, StdoutStream, StreamError, StreamResult, Subscribe};
+
+/// lines kept per plugin
+pub const OUTPUT_LINES: usize = 50;
+
+/// an unterminated line is cut here (a plugin printing without newlines)
+const MAX_LINE: usize = 4096;
+
+#[derive(Default)]
+struct Lines {
+    recent: VecDeque<String>,
+    /// unterminated tail of stdout / stderr
+    partial: [Vec<u8>; 2],
+}
+
+/// cheap handle, clones share the lines (kept across hot reloads)
+#[derive(Clone, Default)]
+pub struct PluginOutput {
+    lines: Arc<Mutex<Lines>>,
+}
+
+impl PluginOutput {
+    /// wasi stdout of a plugin store
+    pub fn stdout(&self) -> Tee {
+        Tee { output: self.clone(), stderr: false }
+    }
+
+    /// wasi stderr of a plugin store
+    pub fn stderr(&self) -> Tee {
+        Tee { output: self.clone(), stderr: true }
+    }
+
+    /// the last lines, oldest first (unterminated tails included)
+    pub fn recent(&self) -> Vec<String> {
+        let lines = self.lines.lock().unwrap();
+        let mut recent: Vec<String> = lines.recent.iter().cloned().collect();
+        for (stderr, partial) in lines.partial.iter().enumerate() {
+            if !partial.is_empty() {
+                recent.push(line(stderr == 1, partial));
+            }
+        }
+        recent
+    }
+
+    fn append(&self, stderr: bool, bytes: &[u8]) {
+        let mut lines = self.lines.lock().unwrap();
+        let Lines { recent, partial } = &mut *lines;
+        let partial = &mut partial[stderr as usize];
+        partial.extend_from_slice(bytes);
+        loop {
+            let (end, newline) = match partial.iter().position(|b| *b == b'\n') {
+                Some(pos) => (pos, 1),
+                None if partial.len() >= MAX_LINE => (MAX_LINE, 0),
+                None => break,
+            };
+            recent.push_back(line(stderr, &partial[..end]));
+            partial.drain(..end + newline);
+            if recent.len() > OUTPUT_LINES {
+                recent.pop_front();
+            }
+        }
+    }
+}
+
+fn line(stderr: bool, bytes: &[u8]) -> String {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_end_matches('\r');
+    match stderr {
+        true => format!("stderr: {}", text),
+        false => text.to_string(),
+    }
+}
+
+/// StdoutStream writing to the host's stdout / stderr and the plugin's lines
+pub struct Tee {
+    output: PluginOutput,
+    stderr: bool,
+}
+
+impl StdoutStream for Tee {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(TeeStream { output: self.output.clone(), stderr: self.stderr })
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+struct TeeStream {
+    output: PluginOutput,
+    stderr: bool,
+}
+
+#[wasmtime_wasi::async_trait]
+impl Subscribe for TeeStream {
+    async fn ready(&mut self) {}
+}
+
+impl HostOutputStream for TeeStream {
+    fn write(&mut self, bytes: Bytes) -> StreamResult<()> {
+        self.output.append(self.stderr, &bytes);
+        match self.stderr {
+            true => std::io::stderr().write_all(&bytes),
+            false => std::io::stdout().write_all(&bytes),
+        }
+        .map_err(|e| StreamError::LastOperationFailed(anyhow::anyhow!(e)))
+    }
+
+    fn flush(&mut self) -> StreamResult<()> {
+        match self.stderr {
+            true => std::io::stderr().flush(),
+            false => std::io::stdout().flush(),
+        }
+        .map_err(|e| StreamError::LastOperationFailed(anyhow::anyhow!(e)))
+    }
+
+    fn check_write(&mut self) -> StreamResult<usize> {
+        Ok(1024 * 1024)
+    }
+}
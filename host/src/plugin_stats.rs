@@ -0,0 +1,249 @@
+//! ==============================================================================
+//! plugin_stats.rs - per-plugin call counters and latencies
+//! ==============================================================================
+//!
+//! purpose:
+//!     a plugin that traps or slows down shows up nowhere but in missing
+//!     readings. every wasm export the runtime calls (poll, render) is
+//!     recorded per plugin:
+//!         calls        exports called
+//!         errors       calls that returned an error (traps included)
+//!         traps        calls that trapped (panic, unreachable, out of bounds)
+//!         misses       polls that didn't answer within the cycle's budget
+//!                      (polling.budget_ms) - counted in calls once they finish
+//!         latency      p50 / p99 / max over the last LATENCY_WINDOW calls
+//!         last_failure the most recent failed call (see below)
+//!
+//! last failure:
+//!     message, function and time of the call, and for a trap the trap
+//!     itself ("wasm `unreachable` instruction executed"), the wasm
+//!     backtrace (one frame per line, innermost first) and the plugin's
+//!     last stdout / stderr lines from plugin_output.rs - a python
+//!     plugin's traceback ends up there. a component instance that trapped
+//!     refuses all later calls until it is reloaded; those calls count as
+//!     errors but keep the original trap as the last failure.
+//!
+//! exposed:
+//!     GET /api/plugins/{name}/stats (json) and GET /metrics (prometheus
+//!     text format: wasi_plugin_calls_total, wasi_plugin_errors_total,
+//!     wasi_plugin_traps_total, wasi_plugin_misses_total and the
+//!     wasi_plugin_call_duration_seconds histogram, labelled by plugin).
+//!     counters start at zero on startup and survive hot reloads.
+//!
+//! relationships:
+//!     - used by: runtime.rs (WasmRuntime records every plugin call),
+//!       main.rs (stats / metrics endpoints)
+//!     - uses: plugin_output.rs (recent output of each plugin)
+//!
+//! ==============================================================================
+
+use crate::plugin_output::PluginOutput;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// call durations kept per plugin for the percentiles
+const LATENCY_WINDOW: usize = 1000;
+
+/// upper bounds of the /metrics histogram buckets, seconds
+const BUCKETS: [f64; 10] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PluginCallStats {
+    pub plugin: String,
+    pub calls: u64,
+    pub errors: u64,
+    pub traps: u64,
+    pub misses: u64,
+    pub latency_ms: Option<Latency>,
+    pub last_failure: Option<LastFailure>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Latency {
+    pub p50: f64,
+    pub p99: f64,
+    pub max: f64,
+    /// calls the percentiles are based on
+    pub samples: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LastFailure {
+    pub message: String,
+    pub function: String,
+    pub timestamp_ms: u64,
+    /// the trap, None for errors that were not traps
+    pub trap: Option<String>,
+    pub backtrace: Vec<String>,
+    /// the plugin's last stdout / stderr lines at the time
+    pub output: Vec<String>,
+}
+
+#[derive(Default)]
+struct Calls {
+    calls: u64,
+    errors: u64,
+    traps: u64,
+    misses: u64,
+    recent: VecDeque<f64>,
+    /// cumulative histogram: buckets[i] = calls <= BUCKETS[i]
+    buckets: [u64; BUCKETS.len()],
+    sum_seconds: f64,
+    last_failure: Option<LastFailure>,
+}
+
+/// cheap handle, clones share the counters
+#[derive(Clone, Default)]
+pub struct PluginStats {
+    plugins: Arc<Mutex<BTreeMap<String, Calls>>>,
+    outputs: Arc<Mutex<BTreeMap<String, PluginOutput>>>,
+}
+
+impl PluginStats {
+    /// stdout / stderr capture of a plugin, shared by all its stores
+    pub fn output(&self, plugin: &str) -> PluginOutput {
+        self.outputs.lock().unwrap().entry(plugin.to_string()).or_default().clone()
+    }
+
+    /// note one finished call of a plugin export
+    pub fn record(&self, plugin: &str, function: &str, elapsed: Duration, error: Option<&anyhow::Error>) {
+        let mut plugins = self.plugins.lock().unwrap();
+        let calls = plugins.entry(plugin.to_string()).or_default();
+        calls.calls += 1;
+
+        let seconds = elapsed.as_secs_f64();
+        calls.sum_seconds += seconds;
+        for (bound, count) in BUCKETS.iter().zip(calls.buckets.iter_mut()) {
+            if seconds <= *bound {
+                *count += 1;
+            }
+        }
+        calls.recent.push_back(seconds * 1000.0);
+        if calls.recent.len() > LATENCY_WINDOW {
+            calls.recent.pop_front();
+        }
+
+        if let Some(e) = error {
+            calls.errors += 1;
+            let trap = e.downcast_ref::<wasmtime::Trap>();
+            if trap == Some(&wasmtime::Trap::CannotEnterComponent) {
+                // an instance that trapped refuses every later call - keep the trap that caused it
+                return;
+            }
+            if trap.is_some() {
+                calls.traps += 1;
+            }
+            let backtrace = e.downcast_ref::<wasmtime::WasmBacktrace>().map(|bt| {
+                // drop the "error while executing at wasm backtrace:" header
+                let frames = bt.to_string();
+                frames.lines().map(str::trim).skip_while(|l| !l.starts_with(|c: char| c.is_ascii_digit())).map(String::from).collect()
+            });
+            calls.last_failure = Some(LastFailure {
+                // the backtrace is part of the error chain; keep the message short
+                message: match backtrace {
+                    Some(_) => e.root_cause().to_string(),
+                    None => format!("{:#}", e),
+                },
+                function: function.to_string(),
+                timestamp_ms: crate::domain::now_ms(),
+                trap: trap.map(|t| t.to_string()),
+                backtrace: backtrace.unwrap_or_default(),
+                output: self.output(plugin).recent(),
+            });
+        }
+    }
+
+    /// note a poll that missed the cycle's budget
+    pub fn miss(&self, plugin: &str) {
+        self.plugins.lock().unwrap().entry(plugin.to_string()).or_default().misses += 1;
+    }
+
+    /// counters of one plugin (zeros if it was never called)
+    pub fn get(&self, plugin: &str) -> PluginCallStats {
+        summarize(plugin, self.plugins.lock().unwrap().get(plugin))
+    }
+
+    /// like get, None while the counters are locked - the panic hook may run
+    /// on a thread that holds them
+    pub fn try_get(&self, plugin: &str) -> Option<PluginCallStats> {
+        let plugins = self.plugins.try_lock().ok()?;
+        Some(summarize(plugin, plugins.get(plugin)))
+    }
+
+    /// append the plugin metrics in prometheus text format
+    pub fn write_prometheus(&self, out: &mut String) {
+        let plugins = self.plugins.lock().unwrap();
+        let counters = [
+            ("wasi_plugin_calls_total", "Plugin exports called.", Counter::Calls),
+            ("wasi_plugin_errors_total", "Plugin calls that returned an error.", Counter::Errors),
+            ("wasi_plugin_traps_total", "Plugin calls that trapped.", Counter::Traps),
+            ("wasi_plugin_misses_total", "Plugin polls that missed the poll budget.", Counter::Misses),
+        ];
+        for (name, help, counter) in counters {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} counter", name, help, name);
+            for (plugin, calls) in plugins.iter() {
+                let _ = writeln!(out, "{}{{plugin=\"{}\"}} {}", name, plugin, counter.of(calls));
+            }
+        }
+
+        let name = "wasi_plugin_call_duration_seconds";
+        let _ = writeln!(out, "# HELP {} Duration of plugin calls.\n# TYPE {} histogram", name, name);
+        for (plugin, calls) in plugins.iter() {
+            for (bound, count) in BUCKETS.iter().zip(calls.buckets.iter()) {
+                let _ = writeln!(out, "{}_bucket{{plugin=\"{}\",le=\"{}\"}} {}", name, plugin, bound, count);
+            }
+            let _ = writeln!(out, "{}_bucket{{plugin=\"{}\",le=\"+Inf\"}} {}", name, plugin, calls.calls);
+            let _ = writeln!(out, "{}_sum{{plugin=\"{}\"}} {}", name, plugin, calls.sum_seconds);
+            let _ = writeln!(out, "{}_count{{plugin=\"{}\"}} {}", name, plugin, calls.calls);
+        }
+    }
+}
+
+enum Counter {
+    Calls,
+    Errors,
+    Traps,
+    Misses,
+}
+
+impl Counter {
+    fn of(&self, calls: &Calls) -> u64 {
+        match self {
+            Counter::Calls => calls.calls,
+            Counter::Errors => calls.errors,
+            Counter::Traps => calls.traps,
+            Counter::Misses => calls.misses,
+        }
+    }
+}
+
+/// the PluginCallStats of a plugin's counters (zeros if it was never called)
+fn summarize(plugin: &str, calls: Option<&Calls>) -> PluginCallStats {
+    let Some(calls) = calls else {
+        return PluginCallStats { plugin: plugin.to_string(), calls: 0, errors: 0, traps: 0, misses: 0, latency_ms: None, last_failure: None };
+    };
+    let mut sorted: Vec<f64> = calls.recent.iter().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    let percentile = |p: f64| sorted[((sorted.len() - 1) as f64 * p).round() as usize];
+    PluginCallStats {
+        plugin: plugin.to_string(),
+        calls: calls.calls,
+        errors: calls.errors,
+        traps: calls.traps,
+        misses: calls.misses,
+        latency_ms: (!sorted.is_empty()).then(|| Latency {
+            p50: round(percentile(0.5)),
+            p99: round(percentile(0.99)),
+            max: round(sorted[sorted.len() - 1]),
+            samples: sorted.len(),
+        }),
+        last_failure: calls.last_failure.clone(),
+    }
+}
+
+fn round(value: f64) -> f64 {
+    (value * 1000.0).round() / 1000.0
+}
@@ -0,0 +1,68 @@
+//! ==============================================================================
+//! plugin_worker.rs - run each plugin's calls on a thread of its own
+//! ==============================================================================
+//!
+//! purpose:
+//!     a wasm export doesn't yield until it returns, so a plugin call used
+//!     to hold one of the api's tokio threads for its whole run. a dashboard
+//!     render of a few hundred ms, with a couple of browsers refreshing,
+//!     stalled the poll loop and every other request behind it. each plugin
+//!     now has a worker: a thread with its own single-threaded runtime that
+//!     takes calls from a queue (QUEUE_DEPTH deep, callers wait when it is
+//!     full) and runs them one at a time, in order. the caller awaits the
+//!     result without blocking anything, so a slow render only delays the
+//!     next render - polling keeps its cadence and the api its latency.
+//!     hot reload / unload still swap the plugin slot directly, waiting for
+//!     a call that is running.
+//!
+//! relationships:
+//!     - used by: runtime.rs (every plugin export call)
+//!
+//! ==============================================================================
+
+use std::future::Future;
+use std::pin::Pin;
+use tokio::sync::{mpsc, oneshot};
+use tracing::Instrument;
+
+/// calls queued per plugin before callers wait
+const QUEUE_DEPTH: usize = 16;
+
+type Job = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// cheap handle, clones queue onto the same thread; it stops with the last one
+#[derive(Clone)]
+pub struct Worker {
+    plugin: &'static str,
+    queue: mpsc::Sender<Job>,
+}
+
+impl Worker {
+    /// start the worker thread of `plugin`
+    pub fn spawn(plugin: &'static str) -> std::io::Result<Worker> {
+        let (queue, mut jobs) = mpsc::channel::<Job>(QUEUE_DEPTH);
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        std::thread::Builder::new().name(format!("plugin-{}", plugin)).spawn(move || {
+            runtime.block_on(async move {
+                while let Some(job) = jobs.recv().await {
+                    // a call that panics fails that call, not the worker
+                    let _ = tokio::spawn(job).await;
+                }
+            })
+        })?;
+        Ok(Worker { plugin, queue })
+    }
+
+    /// run `call` on the worker thread (in the caller's span) and wait for it
+    pub async fn run<T: Send + 'static>(&self, call: impl Future<Output = T> + Send + 'static) -> anyhow::Result<T> {
+        let (done, result) = oneshot::channel();
+        let job = async move {
+            let _ = done.send(call.await);
+        };
+        self.queue
+            .send(Box::pin(job.instrument(tracing::Span::current())))
+            .await
+            .map_err(|_| anyhow::anyhow!("worker of '{}' has stopped", self.plugin))?;
+        result.await.map_err(|_| anyhow::anyhow!("'{}' call panicked", self.plugin))
+    }
+}
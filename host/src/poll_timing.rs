@@ -0,0 +1,239 @@
+//! ==============================================================================
+//! poll_timing.rs - poll loop ticks, duration, overrun and drift tracking
+//! ==============================================================================
+//!
+//! purpose:
+//!     the polling loop used to sleep polling.interval_seconds after each
+//!     cycle, so a cycle with slow plugins (python subprocess sensors, a hub
+//!     push that waits for its timeout) stretched the real cadence: the
+//!     "5 second" loop ran every 9+ seconds and nothing said so. it now
+//!     waits on a tokio interval - cycles start on a fixed schedule however
+//!     long the work took - and every cycle's work (heartbeat, hot reload,
+//!     poll, merge, push) is timed here:
+//!         overrun    a cycle took longer than the interval itself
+//!         drift      how far the cycles have fallen behind the schedule -
+//!                    the sum of (time between cycle starts - interval)
+//!         lateness   how long after its scheduled instant a tick fired
+//!         missed     ticks that went by while a cycle overran
+//!     cycles skipped for a maintenance window (maintenance.rs) are counted
+//!     apart and left out of the cycle durations.
+//!
+//! missed ticks (polling.missed_ticks):
+//!     skip    (default) drop the missed ticks, the next cycle starts on
+//!             the schedule's next slot - the cadence stays on the grid
+//!     delay   start the next cycle right away, and the schedule from there
+//!     burst   run the missed cycles back to back until caught up
+//!
+//! reporting:
+//!     the first overrun after on-time cycles is logged, and so is the
+//!     return to on-time cycles; every overrun is a tracing debug event. the
+//!     totals are in GET /api/cluster (hub.polling) and /metrics
+//!     (wasi_poll_*).
+//!
+//! relationships:
+//!     - used by: main.rs (polling loop, cluster / metrics handlers)
+//!
+//! ==============================================================================
+
+use crate::config::PollingConfig;
+use crate::log_msg;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::time::MissedTickBehavior;
+
+/// cycle starts averaged into effective_interval_ms
+const WINDOW: usize = 20;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PollTimingStats {
+    pub interval_ms: u64,
+    pub cycles: u64,
+    pub last_cycle_ms: Option<u64>,
+    pub max_cycle_ms: Option<u64>,
+    pub mean_cycle_ms: Option<u64>,
+    /// mean time between the recent cycle starts (what the interval really is)
+    pub effective_interval_ms: Option<u64>,
+    pub overruns: u64,
+    pub drift_ms: u64,
+    pub ticks: u64,
+    /// how late the last tick fired, against its slot in the schedule
+    pub last_tick_lateness_ms: Option<u64>,
+    pub max_tick_lateness_ms: Option<u64>,
+    pub missed_ticks: u64,
+    /// cycles skipped while a maintenance window ran
+    pub maintenance_cycles: u64,
+}
+
+/// the poll interval is at least a second (a zero period would stall
+/// the tokio interval) and the missed tick policy is known
+pub fn check_config(polling: &PollingConfig) -> anyhow::Result<()> {
+    if polling.interval_seconds == 0 {
+        anyhow::bail!("polling.interval_seconds must be at least 1");
+    }
+    missed_tick_behavior(&polling.missed_ticks).map(|_| ())
+}
+
+/// polling.missed_ticks as tokio's policy
+pub fn missed_tick_behavior(name: &str) -> anyhow::Result<MissedTickBehavior> {
+    match name {
+        "skip" => Ok(MissedTickBehavior::Skip),
+        "delay" => Ok(MissedTickBehavior::Delay),
+        "burst" => Ok(MissedTickBehavior::Burst),
+        other => anyhow::bail!("unknown missed tick policy '{}' (skip, delay or burst)", other),
+    }
+}
+
+#[derive(Default)]
+struct Timing {
+    interval: Duration,
+    cycles: u64,
+    overruns: u64,
+    overrunning: bool,
+    last_start: Option<Instant>,
+    last_cycle: Duration,
+    max_cycle: Duration,
+    total_cycle: Duration,
+    drift: Duration,
+    periods: VecDeque<Duration>,
+    ticks: u64,
+    last_scheduled: Option<Instant>,
+    last_lateness: Duration,
+    max_lateness: Duration,
+    missed: u64,
+    maintenance_cycles: u64,
+}
+
+pub struct PollTiming {
+    timing: Mutex<Timing>,
+}
+
+impl PollTiming {
+    pub fn new(interval: Duration) -> Self {
+        Self { timing: Mutex::new(Timing { interval, ..Default::default() }) }
+    }
+
+    /// polling.interval_seconds as the loop should use it now
+    pub fn interval(&self) -> Duration {
+        self.timing.lock().unwrap().interval
+    }
+
+    /// new poll interval (config reload); drift is measured against it from the next cycle on.
+    /// never below a second, check_config keeps 0 out of the config
+    pub fn set_interval(&self, interval: Duration) {
+        let mut timing = self.timing.lock().unwrap();
+        timing.interval = interval.max(Duration::from_secs(1));
+        timing.last_start = None;
+        timing.last_scheduled = None;
+        timing.periods.clear();
+    }
+
+    /// the loop's tick for the slot `scheduled` fired just now
+    pub fn ticked(&self, scheduled: Instant) {
+        let lateness = scheduled.elapsed();
+        let mut timing = self.timing.lock().unwrap();
+        let interval = timing.interval;
+        timing.ticks += 1;
+        timing.last_lateness = lateness;
+        timing.max_lateness = timing.max_lateness.max(lateness);
+        // slots between two ticks are the ones skip dropped
+        if let Some(previous) = timing.last_scheduled.replace(scheduled) {
+            let slots = scheduled.duration_since(previous).as_nanos() / interval.as_nanos().max(1);
+            timing.missed += (slots as u64).saturating_sub(1);
+        }
+        if lateness >= interval {
+            tracing::debug!("poll tick fired {:.1}s late", lateness.as_secs_f64());
+        }
+    }
+
+    /// a poll cycle that began at `started` just finished
+    pub fn cycle_done(&self, started: Instant) {
+        let took = started.elapsed();
+        let mut timing = self.timing.lock().unwrap();
+        let interval = timing.interval;
+        timing.cycles += 1;
+        timing.last_cycle = took;
+        timing.max_cycle = timing.max_cycle.max(took);
+        timing.total_cycle += took;
+
+        if let Some(previous) = timing.last_start.replace(started) {
+            let period = started.duration_since(previous);
+            timing.drift += period.saturating_sub(interval);
+            timing.periods.push_back(period);
+            if timing.periods.len() > WINDOW {
+                timing.periods.pop_front();
+            }
+        }
+
+        let overrun = took > interval;
+        if overrun {
+            timing.overruns += 1;
+            tracing::debug!("poll cycle took {:.1}s, interval is {}s", took.as_secs_f64(), interval.as_secs());
+        }
+        match (overrun, timing.overrunning) {
+            (true, false) => log_msg(&format!(
+                "⚠️ [POLL] Cycle took {:.1}s, longer than the {}s interval - polling falls behind",
+                took.as_secs_f64(),
+                interval.as_secs()
+            )),
+            (false, true) => log_msg(&format!(
+                "✅ [POLL] Cycles back within the {}s interval ({} overruns so far)",
+                interval.as_secs(),
+                timing.overruns
+            )),
+            _ => {}
+        }
+        timing.overrunning = overrun;
+    }
+
+    /// the poll cycle that began at `started` was skipped for maintenance
+    pub fn cycle_skipped(&self, started: Instant) {
+        let mut timing = self.timing.lock().unwrap();
+        timing.maintenance_cycles += 1;
+        // the next cycle's period counts from this slot, not the last polled one
+        timing.last_start = Some(started);
+    }
+
+    pub fn stats(&self) -> PollTimingStats {
+        let timing = self.timing.lock().unwrap();
+        let ran = timing.cycles > 0;
+        PollTimingStats {
+            interval_ms: timing.interval.as_millis() as u64,
+            cycles: timing.cycles,
+            last_cycle_ms: ran.then(|| timing.last_cycle.as_millis() as u64),
+            max_cycle_ms: ran.then(|| timing.max_cycle.as_millis() as u64),
+            mean_cycle_ms: ran.then(|| (timing.total_cycle / timing.cycles as u32).as_millis() as u64),
+            effective_interval_ms: (!timing.periods.is_empty())
+                .then(|| (timing.periods.iter().sum::<Duration>() / timing.periods.len() as u32).as_millis() as u64),
+            overruns: timing.overruns,
+            drift_ms: timing.drift.as_millis() as u64,
+            ticks: timing.ticks,
+            last_tick_lateness_ms: (timing.ticks > 0).then(|| timing.last_lateness.as_millis() as u64),
+            max_tick_lateness_ms: (timing.ticks > 0).then(|| timing.max_lateness.as_millis() as u64),
+            missed_ticks: timing.missed,
+            maintenance_cycles: timing.maintenance_cycles,
+        }
+    }
+
+    /// append the poll loop metrics in prometheus text format
+    pub fn write_prometheus(&self, out: &mut String) {
+        let timing = self.timing.lock().unwrap();
+        let metrics = [
+            ("wasi_poll_cycles_total", "counter", "Poll cycles run.", timing.cycles as f64),
+            ("wasi_poll_overruns_total", "counter", "Poll cycles that took longer than the interval.", timing.overruns as f64),
+            ("wasi_poll_drift_seconds_total", "counter", "Time the poll loop fell behind its schedule.", timing.drift.as_secs_f64()),
+            ("wasi_poll_cycle_seconds", "gauge", "Duration of the last poll cycle.", timing.last_cycle.as_secs_f64()),
+            ("wasi_poll_interval_seconds", "gauge", "Configured poll interval.", timing.interval.as_secs_f64()),
+            ("wasi_poll_ticks_total", "counter", "Poll loop ticks fired.", timing.ticks as f64),
+            ("wasi_poll_missed_ticks_total", "counter", "Poll loop ticks skipped because a cycle overran.", timing.missed as f64),
+            ("wasi_poll_tick_lateness_seconds", "gauge", "How late the last tick fired against its schedule.", timing.last_lateness.as_secs_f64()),
+            ("wasi_poll_tick_lateness_max_seconds", "gauge", "Latest any tick has fired against its schedule.", timing.max_lateness.as_secs_f64()),
+            ("wasi_poll_maintenance_cycles_total", "counter", "Poll cycles skipped during a maintenance window.", timing.maintenance_cycles as f64),
+        ];
+        for (name, kind, help, value) in metrics {
+            let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}\n{} {}", name, help, name, kind, name, value);
+        }
+    }
+}
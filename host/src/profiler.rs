@@ -0,0 +1,138 @@
+//! ==============================================================================
+//! profiler.rs - wasmtime's guest profiler for one plugin ([plugin_profiler])
+//! ==============================================================================
+//!
+//! purpose:
+//!     the plugin stats say a plugin is slow (p99 latency), not where its
+//!     time goes. with [plugin_profiler] on, the chosen plugin's calls are
+//!     sampled by wasmtime's GuestProfiler: every interval_ms the epoch
+//!     check compiled into the guest (function entries, loop headers)
+//!     records the wasm stack. GET /api/plugins/{name}/profile hands out
+//!     what was recorded since the previous download as firefox profiler
+//!     json (open it at https://profiler.firefox.com) and starts over.
+//!     a call shorter than the interval is only sampled when a tick happens
+//!     to fall into it, so a profile of quick calls needs many of them.
+//!
+//!     [plugin_profiler]
+//!     enabled = true
+//!     plugin = "revpi-monitor"
+//!     interval_ms = 10          # rounded to the 10ms epoch tick
+//!
+//!     frames are named from the core module the export runs in. for a
+//!     componentize-py plugin that is the interpreter holding the plugin's
+//!     code, so the stacks show cpython's eval loop and the functions it
+//!     calls (json, re, struct, ...) rather than python lines. time in host
+//!     functions isn't sampled. the profile lives in the plugin's store,
+//!     a reload or watchdog restart starts a new one.
+//!
+//!     sampling takes over the epoch deadline of the profiled plugin; the
+//!     watchdog's call deadline is counted down here, a call past it still
+//!     traps with "interrupt".
+//!
+//! relationships:
+//!     - used by: runtime.rs (store callback, call deadlines, profile
+//!       download), main.rs (GET /api/plugins/{name}/profile)
+//!     - reads: config.rs (PluginProfilerConfig), watchdog.rs (TICK)
+//!
+//! ==============================================================================
+
+use crate::config::PluginProfilerConfig;
+use crate::runtime::{HostState, KNOWN_PLUGINS};
+use crate::watchdog::TICK;
+use anyhow::Result;
+use std::time::Duration;
+use wasmtime::{AsContext, GuestProfiler, Store, StoreContextMut, Trap, UpdateDeadline, WasmBacktrace};
+
+/// sampling state of the profiled plugin's store
+pub struct Profile {
+    plugin: String,
+    interval: Duration,
+    interval_ticks: u64,
+    /// ticks until the current call's watchdog deadline
+    ticks_left: u64,
+    /// ticks until the next sample
+    armed: u64,
+    /// started at the first sample, its stack tells which module to name frames from
+    guest: Option<GuestProfiler>,
+    samples: u64,
+}
+
+impl Profile {
+    /// a profile for `plugin` when [plugin_profiler] picks it
+    pub fn for_plugin(config: &PluginProfilerConfig, plugin: &str) -> Option<Self> {
+        (config.enabled && config.plugin == plugin).then(|| {
+            let interval_ticks = (config.interval_ms / TICK.as_millis() as u64).max(1);
+            Self {
+                plugin: plugin.to_string(),
+                interval: TICK * interval_ticks as u32,
+                interval_ticks,
+                ticks_left: 0,
+                armed: 0,
+                guest: None,
+                samples: 0,
+            }
+        })
+    }
+
+    /// an epoch deadline passed inside the guest: sample, then the next deadline
+    fn sample(&mut self, store: impl AsContext) -> Result<UpdateDeadline> {
+        if self.guest.is_none() {
+            let backtrace = WasmBacktrace::capture(&store);
+            if let Some(outermost) = backtrace.frames().last() {
+                let module = outermost.module();
+                let name = module.name().unwrap_or(&self.plugin).to_string();
+                self.guest = Some(GuestProfiler::new(&self.plugin, self.interval, vec![(name, module.clone())]));
+            }
+        }
+        if let Some(guest) = &mut self.guest {
+            guest.sample(&store, TICK * self.armed as u32);
+            self.samples += 1;
+        }
+        self.ticks_left = self.ticks_left.saturating_sub(self.armed);
+        if self.ticks_left == 0 {
+            return Err(Trap::Interrupt.into());
+        }
+        self.armed = self.interval_ticks.min(self.ticks_left);
+        Ok(UpdateDeadline::Continue(self.armed))
+    }
+
+    /// what was sampled so far and its sample count, the next sample starts a new profile
+    pub fn take(&mut self) -> Option<(GuestProfiler, u64)> {
+        let samples = std::mem::take(&mut self.samples);
+        self.guest.take().filter(|_| samples > 0).map(|guest| (guest, samples))
+    }
+}
+
+/// sample the calls of a profiled plugin's store (others keep the plain deadline)
+pub fn attach(store: &mut Store<HostState>) {
+    if store.data().profile.is_none() {
+        return;
+    }
+    store.epoch_deadline_callback(|mut store: StoreContextMut<HostState>| {
+        let mut profile = store.data_mut().profile.take().expect("profiled store");
+        let next = profile.sample(&store);
+        store.data_mut().profile = Some(profile);
+        next
+    });
+}
+
+/// give the next call `deadline` epoch ticks (the watchdog's), sampling along the way
+pub fn set_deadline(store: &mut Store<HostState>, deadline: u64) {
+    let armed = match &mut store.data_mut().profile {
+        Some(profile) => {
+            profile.ticks_left = deadline;
+            profile.armed = profile.interval_ticks.min(deadline);
+            profile.armed
+        }
+        None => deadline,
+    };
+    store.set_epoch_deadline(armed);
+}
+
+/// [plugin_profiler] names a plugin the runtime knows
+pub fn check_config(config: &PluginProfilerConfig) -> Result<()> {
+    if config.enabled && !KNOWN_PLUGINS.contains(&config.plugin.as_str()) {
+        anyhow::bail!("plugin_profiler.plugin '{}' is not one of {}", config.plugin, KNOWN_PLUGINS.join(", "));
+    }
+    Ok(())
+}
@@ -0,0 +1,211 @@
+//! ==============================================================================
+//! provision.rs - first-run setup page when there is no host.toml yet
+//! ==============================================================================
+//!
+//! purpose:
+//!     a freshly flashed spoke used to start on the built-in defaults until
+//!     someone wrote host.toml over serial. without a config file (and not
+//!     --headless) the host now starts in setup mode instead: port 3000
+//!     serves a single form and its api, nothing else runs.
+//!         GET  /               - the setup form
+//!         GET  /api/provision  - roles and the plugins this host knows
+//!                                (with whether their .wasm is on disk)
+//!         POST /api/provision  - {"node_id": "pi4", "role": "spoke",
+//!                                 "hub_url": "http://192.168.7.10:3000/push",
+//!                                 "plugins": ["dht22", "pi4-monitor"]}
+//!     a valid request writes config/host.toml - every section with its
+//!     defaults and comments, as `wasi-host init-config` would - then the
+//!     setup server stops and the host starts normally on the new file.
+//!     the request is checked like `validate-config` (a spoke needs a hub
+//!     url, enabled plugins need their .wasm, ...); errors come back as 422
+//!     {"errors": [...]} and nothing is written.
+//!
+//! relationships:
+//!     - used by: main.rs (startup, before the config is loaded)
+//!     - uses: config_init.rs (renders host.toml), config_check.rs (checks it),
+//!       role.rs (role names), runtime.rs (KNOWN_PLUGINS, plugin paths)
+//!
+//! ==============================================================================
+
+use crate::config::{HostConfig, PluginEntry};
+use crate::log_msg;
+use axum::extract::State;
+use axum::response::{Html, IntoResponse, Response};
+use axum::routing::get;
+use axum::{Json, Router};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const BIND_ADDR: &str = "0.0.0.0:3000";
+
+/// body of POST /api/provision
+#[derive(Deserialize)]
+struct Provision {
+    node_id: String,
+    role: String,
+    /// where a spoke pushes its readings
+    #[serde(default)]
+    hub_url: String,
+    /// KNOWN_PLUGINS names to enable
+    #[serde(default)]
+    plugins: Vec<String>,
+}
+
+#[derive(Clone)]
+struct Setup {
+    /// where host.toml is written
+    path: PathBuf,
+    /// stops the setup server once it is written
+    done: tokio::sync::mpsc::Sender<()>,
+}
+
+/// serve the setup page until a config is written; returns its path
+pub async fn run() -> anyhow::Result<PathBuf> {
+    let path = target();
+    let (done, mut finished) = tokio::sync::mpsc::channel(1);
+    let app = Router::new()
+        .route("/", get(page_handler))
+        .route("/api/provision", get(options_handler).post(provision_handler))
+        .with_state(Setup { path: path.clone(), done });
+    let listener = tokio::net::TcpListener::bind(BIND_ADDR).await?;
+    log_msg(&format!("🛠️ [SETUP] No config file - setup page on http://{} writes {}", BIND_ADDR, path.display()));
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            finished.recv().await;
+        })
+        .await?;
+    log_msg(&format!("🛠️ [SETUP] Wrote {}, starting the host", path.display()));
+    Ok(path)
+}
+
+/// host.toml in config/ (or ../config/, where find_config_file looks next)
+fn target() -> PathBuf {
+    let dir = [PathBuf::from("config"), PathBuf::from("..").join("config")]
+        .into_iter()
+        .find(|dir| dir.is_dir())
+        .unwrap_or_else(|| PathBuf::from("config"));
+    dir.join("host.toml")
+}
+
+/// the config a request describes, the rest left at the defaults
+fn build(request: &Provision) -> anyhow::Result<HostConfig> {
+    let node_id = request.node_id.trim();
+    if node_id.is_empty() || !node_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        anyhow::bail!("node_id must be letters, digits, '-' or '_' (it prefixes every sensor id)");
+    }
+    let role = crate::role::Role::from_name(&request.role)?;
+    let hub_url = request.hub_url.trim();
+    if role == crate::role::Role::Spoke && hub_url.is_empty() {
+        anyhow::bail!("a spoke needs the hub_url it pushes to");
+    }
+    if !hub_url.is_empty() && !hub_url.starts_with("http://") && !hub_url.starts_with("https://") {
+        anyhow::bail!("hub_url must start with http:// or https://");
+    }
+    if let Some(unknown) = request.plugins.iter().find(|p| !crate::runtime::KNOWN_PLUGINS.contains(&p.as_str())) {
+        anyhow::bail!("unknown plugin '{}' (expected one of {})", unknown, crate::runtime::KNOWN_PLUGINS.join(", "));
+    }
+
+    let mut config = HostConfig::default();
+    config.cluster.node_id = node_id.to_string();
+    config.cluster.role = role.to_string();
+    config.cluster.hub_url = hub_url.to_string();
+    for name in crate::runtime::KNOWN_PLUGINS {
+        let enabled = request.plugins.iter().any(|p| p == name);
+        config.plugins.insert(name.replace('-', "_"), PluginEntry { enabled, ..Default::default() });
+    }
+    Ok(config)
+}
+
+/// roles and plugins for the form
+async fn options_handler() -> Json<serde_json::Value> {
+    let plugins: Vec<serde_json::Value> = crate::runtime::KNOWN_PLUGINS
+        .iter()
+        .map(|name| serde_json::json!({ "name": name, "available": crate::runtime::plugin_path(Path::new(".."), name).exists() }))
+        .collect();
+    Json(serde_json::json!({ "roles": ["hub", "spoke", "standalone"], "plugins": plugins }))
+}
+
+/// check the request, write host.toml and end setup mode
+async fn provision_handler(State(setup): State<Setup>, Json(request): Json<Provision>) -> Response {
+    let unprocessable = |errors: Vec<String>| (axum::http::StatusCode::UNPROCESSABLE_ENTITY, Json(serde_json::json!({ "errors": errors }))).into_response();
+    let config = match build(&request) {
+        Ok(config) => config,
+        Err(e) => return unprocessable(vec![format!("{:#}", e)]),
+    };
+    let errors = crate::config_check::errors(&config, Path::new(".."));
+    if !errors.is_empty() {
+        return unprocessable(errors);
+    }
+    if setup.path.exists() {
+        return (axum::http::StatusCode::CONFLICT, format!("{} exists already", setup.path.display())).into_response();
+    }
+    let written = crate::config_init::render(&config, "the first-run setup page").and_then(|text| {
+        if let Some(dir) = setup.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(std::fs::write(&setup.path, text)?)
+    });
+    if let Err(e) = written {
+        log_msg(&format!("❌ [SETUP] Could not write {}: {:#}", setup.path.display(), e));
+        return (axum::http::StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e)).into_response();
+    }
+    log_msg(&format!("🛠️ [SETUP] Provisioned '{}' as {}", config.cluster.node_id, config.cluster.role));
+    let _ = setup.done.try_send(());
+    Json(serde_json::json!({ "path": setup.path, "node_id": config.cluster.node_id, "role": config.cluster.role })).into_response()
+}
+
+async fn page_handler() -> Html<&'static str> {
+    Html(PAGE)
+}
+
+/// the setup form - plain html, it has to work before any plugin is loaded
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>WASI Host setup</title>
+<style>
+  body { font-family: sans-serif; max-width: 32em; margin: 2em auto; padding: 0 1em; }
+  label { display: block; margin-top: 1em; }
+  input[type=text], select { width: 100%; padding: .4em; }
+  .missing { color: #888; }
+  #result { margin-top: 1.5em; white-space: pre-wrap; }
+</style>
+</head>
+<body>
+<h1>WASI Host setup</h1>
+<p>This node has no config yet. The host starts as soon as one is written.</p>
+<form id="setup">
+  <label>Node id <input type="text" name="node_id" required placeholder="pi4"></label>
+  <label>Role <select name="role" id="role"></select></label>
+  <label>Hub url (spokes) <input type="text" name="hub_url" placeholder="http://192.168.7.10:3000/push"></label>
+  <fieldset style="margin-top: 1em"><legend>Plugins</legend><div id="plugins"></div></fieldset>
+  <p><button type="submit">Write host.toml and start</button></p>
+</form>
+<div id="result"></div>
+<script>
+fetch('/api/provision').then(r => r.json()).then(options => {
+  document.getElementById('role').innerHTML = options.roles.map(r => `<option>${r}</option>`).join('');
+  document.getElementById('plugins').innerHTML = options.plugins.map(p =>
+    `<label class="${p.available ? '' : 'missing'}"><input type="checkbox" name="plugins" value="${p.name}" ${p.available ? '' : 'disabled'}> ${p.name}${p.available ? '' : ' (no .wasm)'}</label>`
+  ).join('');
+});
+document.getElementById('setup').addEventListener('submit', async event => {
+  event.preventDefault();
+  const form = new FormData(event.target);
+  const body = { node_id: form.get('node_id'), role: form.get('role'), hub_url: form.get('hub_url'), plugins: form.getAll('plugins') };
+  const response = await fetch('/api/provision', { method: 'POST', headers: { 'content-type': 'application/json' }, body: JSON.stringify(body) });
+  const result = document.getElementById('result');
+  if (response.ok) {
+    const done = await response.json();
+    result.textContent = `Wrote ${done.path}. The host is starting, reload in a few seconds.`;
+  } else {
+    const text = await response.text();
+    try { result.textContent = JSON.parse(text).errors.join('\n'); } catch { result.textContent = text; }
+  }
+});
+</script>
+</body>
+</html>
+"#;
@@ -0,0 +1,106 @@
+//! ==============================================================================
+//! python_driver.rs - long-running python helpers for drivers rust lacks
+//! ==============================================================================
+//!
+//! purpose:
+//!     the led strip (rpi_ws281x, pwm + dma) and the dht22 (adafruit_dht,
+//!     timing-critical bit-banging) have no usable rust driver, so those two
+//!     stay in python. they used to start an interpreter per call: every
+//!     heartbeat strip write (`sudo python3`, once per cycle) and every dht22
+//!     poll paid for an interpreter start and its imports, most of a pi
+//!     zero's cpu time per cycle (see children_cpu_seconds in memory.rs).
+//!     a PythonDriver starts its interpreter once and keeps it: requests go
+//!     to its stdin as one json line each, the answer comes back as one
+//!     json line ({"error": "..."} when the handler raised). an interpreter
+//!     that died or answered garbage is killed and started again on the
+//!     next request; it ends with the host, when its stdin closes.
+//!     everything else (gpio, buzzer, fan, i2c) is rppal.
+//!
+//!     the script given to a driver defines the setup and
+//!     `handle(request) -> dict`; the request loop is appended here.
+//!
+//! relationships:
+//!     - used by: hal.rs (led strip writes, dht22 reads on feature="hardware")
+//!
+//! ==============================================================================
+
+use anyhow::{Context, Result};
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Mutex;
+
+/// reads requests from stdin, answers each with one json line
+const REQUEST_LOOP: &str = r#"
+import json, sys
+for line in sys.stdin:
+    try:
+        response = handle(json.loads(line))
+    except Exception as e:
+        response = {"error": str(e) or type(e).__name__}
+    print(json.dumps(response), flush=True)
+"#;
+
+struct Running {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+pub struct PythonDriver {
+    name: &'static str,
+    /// program and arguments before `-c script` ("sudo", "python3")
+    command: &'static [&'static str],
+    script: String,
+    running: Mutex<Option<Running>>,
+}
+
+impl PythonDriver {
+    pub fn new(name: &'static str, command: &'static [&'static str], script: &str) -> Self {
+        Self { name, command, script: format!("{}\n{}", script, REQUEST_LOOP), running: Mutex::new(None) }
+    }
+
+    /// send `request` and wait for the answer, starting the interpreter if needed
+    pub fn call(&self, request: &serde_json::Value) -> Result<serde_json::Value> {
+        let mut running = self.running.lock().unwrap();
+        if running.is_none() {
+            *running = Some(self.start()?);
+        }
+        let result = Self::exchange(running.as_mut().unwrap(), request);
+        if result.is_err() {
+            if let Some(mut dead) = running.take() {
+                let _ = dead.child.kill();
+                let _ = dead.child.wait();
+            }
+        }
+        let response = result.with_context(|| format!("{} driver", self.name))?;
+        if let Some(error) = response.get("error").and_then(|e| e.as_str()) {
+            anyhow::bail!("{} driver: {}", self.name, error);
+        }
+        Ok(response)
+    }
+
+    fn start(&self) -> Result<Running> {
+        let (program, args) = self.command.split_first().expect("driver command is empty");
+        let mut child = Command::new(program)
+            .args(args)
+            .args(["-u", "-c", &self.script])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("failed to start the {} driver", self.name))?;
+        tracing::debug!("[PYTHON] {} driver started (pid {})", self.name, child.id());
+        let stdin = child.stdin.take().expect("piped stdin");
+        let stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+        Ok(Running { child, stdin, stdout })
+    }
+
+    fn exchange(running: &mut Running, request: &serde_json::Value) -> Result<serde_json::Value> {
+        writeln!(running.stdin, "{}", request)?;
+        running.stdin.flush()?;
+        let mut line = String::new();
+        if running.stdout.read_line(&mut line)? == 0 {
+            anyhow::bail!("interpreter exited");
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+}
@@ -0,0 +1,71 @@
+//! ==============================================================================
+//! recent.rs - the last readings of every sensor, in memory
+//! ==============================================================================
+//!
+//! purpose:
+//!     AppState only holds the latest reading per sensor. plugins computing
+//!     trends or rolling averages need a few more, and keeping them in the
+//!     plugin loses them on every hot reload. merge_readings records each
+//!     merged reading here; plugins read them back through the
+//!     reading-history wit interface (get-recent-readings).
+//!
+//! bounds:
+//!     PER_SENSOR readings per sensor, oldest dropped first. readings are
+//!     kept in timestamp order; a re-delivered reading replaces the one with
+//!     the same timestamp. removing a node or evicting a sensor from the
+//!     latest view ([state]) forgets its readings here too.
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.recent, filled by merge_readings)
+//!     - used by: runtime.rs (HostState, reading-history implementation)
+//!
+//! ==============================================================================
+
+use crate::domain::SensorReading;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// readings kept per sensor
+pub const PER_SENSOR: usize = 120;
+
+/// cheap handle, clones share the buffer
+#[derive(Clone, Default)]
+pub struct RecentReadings {
+    sensors: Arc<Mutex<HashMap<String, VecDeque<SensorReading>>>>,
+}
+
+impl RecentReadings {
+    pub fn record(&self, readings: &[SensorReading]) {
+        let mut sensors = self.sensors.lock().unwrap();
+        for reading in readings {
+            let recent = sensors.entry(reading.sensor_id.clone()).or_default();
+            match recent.binary_search_by_key(&reading.timestamp_ms, |r| r.timestamp_ms) {
+                Ok(pos) => recent[pos] = reading.clone(),
+                Err(pos) => recent.insert(pos, reading.clone()),
+            }
+            if recent.len() > PER_SENSOR {
+                recent.pop_front();
+            }
+        }
+    }
+
+    /// up to `count` newest readings of a sensor, oldest first
+    pub fn get(&self, sensor_id: &str, count: usize) -> Vec<SensorReading> {
+        let sensors = self.sensors.lock().unwrap();
+        let Some(recent) = sensors.get(sensor_id) else {
+            return Vec::new();
+        };
+        recent.iter().skip(recent.len().saturating_sub(count)).cloned().collect()
+    }
+
+    /// drop one sensor
+    pub fn forget_sensor(&self, sensor_id: &str) {
+        self.sensors.lock().unwrap().remove(sensor_id);
+    }
+
+    /// drop every sensor of a node
+    pub fn forget_node(&self, node_id: &str) {
+        let prefix = format!("{}:", node_id);
+        self.sensors.lock().unwrap().retain(|sensor_id, _| !sensor_id.starts_with(&prefix));
+    }
+}
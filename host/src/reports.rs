@@ -0,0 +1,247 @@
+//! ==============================================================================
+//! reports.rs - scheduled summary reports
+//! ==============================================================================
+//!
+//! purpose:
+//!     nobody reads dashboards every day. once per reports.period_hours (at
+//!     reports.at, local time) the host summarizes the period that just
+//!     ended - min / max / avg of every numeric field of every sensor from
+//!     the sqlite history, plus the notable events it saw (alerts firing /
+//!     resolving, anomalies, nodes going offline / online).
+//!
+//! rendering:
+//!     the summary is passed as json to the report plugin's render export
+//!     (report-logic in plugin.wit, [plugins.report]), which returns html or
+//!     markdown. without the plugin, or if it fails, a markdown table is
+//!     built here.
+//!
+//! delivery:
+//!     the newest report is served at GET /reports/latest and, with
+//!     reports.deliver, sent through the [notify] channels.
+//!
+//! relationships:
+//!     - used by: main.rs (startup, /reports/latest)
+//!     - reads: config.rs (ReportsConfig), storage.rs (Store::summary), events.rs
+//!     - uses: runtime.rs (report plugin), notify.rs (delivery)
+//!
+//! ==============================================================================
+
+use crate::config::{HostConfig, ReportsConfig};
+use crate::events::{Event, EventBus};
+use crate::log_msg;
+use crate::storage::{FieldSummary, Store};
+use chrono::{Local, NaiveTime, TimeZone};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+
+/// events listed in one report, the rest are only counted
+const MAX_EVENTS: usize = 200;
+
+/// event kinds worth a line in the report
+const NOTABLE: [&str; 5] = ["alert_firing", "alert_resolved", "anomaly", "node_offline", "node_online"];
+
+/// a rendered report
+#[derive(Clone)]
+pub struct Report {
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+/// what the report plugin gets to render
+#[derive(Serialize)]
+struct ReportData<'a> {
+    node_id: &'a str,
+    from_ms: u64,
+    to_ms: u64,
+    sensors: BTreeMap<String, BTreeMap<String, FieldSummary>>,
+    events: &'a [Event],
+    events_dropped: usize,
+}
+
+/// holds the newest report for /reports/latest
+#[derive(Default)]
+pub struct Reports {
+    latest: RwLock<Option<Report>>,
+}
+
+/// reject a malformed reports.at at startup
+pub fn check_config(config: &ReportsConfig) -> anyhow::Result<()> {
+    if config.enabled {
+        parse_at(&config.at)?;
+        if config.period_hours == 0 {
+            anyhow::bail!("reports.period_hours must be at least 1");
+        }
+    }
+    Ok(())
+}
+
+fn parse_at(at: &str) -> anyhow::Result<NaiveTime> {
+    NaiveTime::parse_from_str(at, "%H:%M").map_err(|_| anyhow::anyhow!("reports.at must be \"HH:MM\", got '{}'", at))
+}
+
+impl Reports {
+    pub fn latest(&self) -> Option<Report> {
+        self.latest.read().unwrap().clone()
+    }
+
+    /// collect events and build a report every period
+    pub fn spawn(
+        self: Arc<Self>,
+        config: &HostConfig,
+        store: Option<Arc<Store>>,
+        events: &EventBus,
+        runtime: crate::runtime::WasmRuntime,
+        client: reqwest::Client,
+    ) {
+        let reports = config.reports.clone();
+        if !reports.enabled {
+            return;
+        }
+        if store.is_none() {
+            log_msg("⚠️ [REPORT] storage is disabled - reports will only list events");
+        }
+        let at = parse_at(&reports.at).unwrap_or_default();
+        let (notify, node_id) = (config.notify.clone(), config.cluster.node_id.clone());
+        log_msg(&format!("📰 [REPORT] Summary report every {}h at {}", reports.period_hours, reports.at));
+
+        let mut rx = events.subscribe();
+        tokio::spawn(async move {
+            let mut collected: Vec<Event> = Vec::new();
+            let mut dropped = 0;
+            let mut next = next_run(at);
+            loop {
+                let wait = Duration::from_millis(next.saturating_sub(crate::domain::now_ms()));
+                tokio::select! {
+                    event = rx.recv() => match event {
+                        Ok(event) if NOTABLE.contains(&event.kind.as_str()) => {
+                            if collected.len() < MAX_EVENTS {
+                                collected.push(event);
+                            } else {
+                                dropped += 1;
+                            }
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(missed)) => dropped += missed as usize,
+                        Err(RecvError::Closed) => return,
+                    },
+                    _ = tokio::time::sleep(wait) => {
+                        let to_ms = crate::domain::now_ms();
+                        let from_ms = to_ms.saturating_sub(reports.period_hours * 3600 * 1000);
+                        let events = std::mem::take(&mut collected);
+                        let data = build(store.clone(), &node_id, from_ms, to_ms, &events, std::mem::take(&mut dropped)).await;
+                        let report = render(&runtime, &data).await;
+                        log_msg(&format!("📰 [REPORT] Built report for the last {}h ({} events)", reports.period_hours, events.len()));
+                        if reports.deliver {
+                            let subject = format!("[{}] {}h summary report", node_id, reports.period_hours);
+                            crate::notify::deliver(&notify, &client, &subject, &report.body).await;
+                        }
+                        *self.latest.write().unwrap() = Some(report);
+                        next = next_run(at);
+                    }
+                }
+            }
+        });
+    }
+}
+
+/// unix ms of the next `at` (local time), today or tomorrow
+fn next_run(at: NaiveTime) -> u64 {
+    let now = Local::now();
+    let today = now.date_naive().and_time(at);
+    let when = match Local.from_local_datetime(&today).earliest() {
+        Some(t) if t > now => t,
+        _ => {
+            let tomorrow = today + chrono::Duration::days(1);
+            Local.from_local_datetime(&tomorrow).earliest().unwrap_or(now + chrono::Duration::days(1))
+        }
+    };
+    when.timestamp_millis() as u64
+}
+
+async fn build(
+    store: Option<Arc<Store>>,
+    node_id: &str,
+    from_ms: u64,
+    to_ms: u64,
+    events: &[Event],
+    events_dropped: usize,
+) -> serde_json::Value {
+    let sensors = match store {
+        Some(store) => {
+            let result = tokio::task::spawn_blocking(move || store.summary(from_ms, to_ms)).await;
+            result.map_err(anyhow::Error::from).and_then(|r| r).unwrap_or_else(|e| {
+                log_msg(&format!("❌ [REPORT] Failed to summarize history: {}", e));
+                BTreeMap::new()
+            })
+        }
+        None => BTreeMap::new(),
+    };
+    let data = ReportData { node_id, from_ms, to_ms, sensors, events, events_dropped };
+    serde_json::to_value(&data).unwrap_or_default()
+}
+
+async fn render(runtime: &crate::runtime::WasmRuntime, data: &serde_json::Value) -> Report {
+    let body = match runtime.render_report(&data.to_string()).await {
+        Ok(Some(body)) => body,
+        Ok(None) => markdown(data),
+        Err(e) => {
+            log_msg(&format!("❌ [REPORT] {:#}, falling back to markdown", e));
+            markdown(data)
+        }
+    };
+    let content_type = match body.trim_start().starts_with('<') {
+        true => "text/html; charset=utf-8",
+        false => "text/markdown; charset=utf-8",
+    };
+    Report { content_type, body }
+}
+
+/// built-in rendering when no report plugin is loaded
+fn markdown(data: &serde_json::Value) -> String {
+    let time = |ms: &serde_json::Value| {
+        Local.timestamp_millis_opt(ms.as_i64().unwrap_or_default()).single().map(|t| t.format("%Y-%m-%d %H:%M").to_string()).unwrap_or_default()
+    };
+    let mut out = format!(
+        "# Summary report - {}\n\n{} to {}\n\n## Sensors\n\n",
+        data["node_id"].as_str().unwrap_or_default(),
+        time(&data["from_ms"]),
+        time(&data["to_ms"])
+    );
+    match data["sensors"].as_object().filter(|s| !s.is_empty()) {
+        Some(sensors) => {
+            out.push_str("| sensor | field | min | avg | max | samples |\n|---|---|---|---|---|---|\n");
+            for (sensor_id, fields) in sensors {
+                for (field, s) in fields.as_object().into_iter().flatten() {
+                    let num = |key: &str| s[key].as_f64().unwrap_or_default();
+                    out.push_str(&format!(
+                        "| {} | {} | {:.2} | {:.2} | {:.2} | {} |\n",
+                        sensor_id, field, num("min"), num("avg"), num("max"), s["samples"]
+                    ));
+                }
+            }
+        }
+        None => out.push_str("No stored readings in this period.\n"),
+    }
+
+    let events = data["events"].as_array().cloned().unwrap_or_default();
+    out.push_str(&format!("\n## Events ({})\n\n", events.len()));
+    if events.is_empty() {
+        out.push_str("Nothing notable.\n");
+    }
+    for event in &events {
+        out.push_str(&format!(
+            "- {} {} {}: {}\n",
+            time(&event["timestamp_ms"]),
+            event["kind"].as_str().unwrap_or_default(),
+            event["source"].as_str().unwrap_or_default(),
+            event["message"].as_str().unwrap_or_default()
+        ));
+    }
+    if let Some(dropped) = data["events_dropped"].as_u64().filter(|d| *d > 0) {
+        out.push_str(&format!("- ... and {} more\n", dropped));
+    }
+    out
+}
@@ -0,0 +1,315 @@
+//! ==============================================================================
+//! role.rs - switch a node between hub, spoke and standalone while it runs
+//! ==============================================================================
+//!
+//! purpose:
+//!     promoting a spoke to stand in for its hub during maintenance used to
+//!     mean editing cluster.role and restarting it. the role can be switched
+//!     live instead, over the api or the command channel:
+//!         POST /api/role     {"role": "hub"}        (api.admin_token, admin.rs)
+//!         POST /api/command  {"node_id": "pi4", "type": "set-role", "role": "hub"}
+//!     the poll loop picks the new role up at the start of its next cycle,
+//!     stops the tasks of the old role and starts the ones of the new role:
+//!         spoke       push readings to the hub (http, websocket or nats),
+//!                     heartbeat, command receiver, failover probe, config
+//!                     sync (only when cluster.role is spoke)
+//!         hub         /push, /push/backfill, /ws and /heartbeat (503 on
+//!                     other roles), stale-node check, pull loop, nats consumer
+//!         standalone  neither - polls and keeps its readings to itself
+//!     every role flags readings older than cluster.stale_after_seconds as
+//!     stale.
+//!     alerts, notifications and reports start the first time the node is a
+//!     hub or standalone and keep running if it becomes a spoke again.
+//!     a switch is not written to host.toml; a restart goes back to
+//!     cluster.role.
+//!
+//! relationships:
+//!     - used by: main.rs (poll loop, /api/role, hub-only routes, /api/cluster),
+//!       commands.rs (set-role command)
+//!     - starts: cluster.rs (failover probe, pull loop), nodes.rs (heartbeat,
+//!       stale check), commands.rs (receiver), ws.rs (uplink), nats.rs,
+//!       node_config.rs (config sync), alerts.rs, notify.rs, reports.rs
+//!
+//! ==============================================================================
+
+use crate::alerts::AlertEngine;
+use crate::cluster::HubFailover;
+use crate::codec::Encoding;
+use crate::config::HostConfig;
+use crate::domain::AppState;
+use crate::events::EventBus;
+use crate::log_msg;
+use crate::nodes::NodeRegistry;
+use crate::reports::Reports;
+use crate::runtime::WasmRuntime;
+use crate::storage::Store;
+use axum::extract::Request;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Once, OnceLock};
+use tokio::sync::{watch, RwLock};
+use tokio::task::JoinHandle;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    Hub,
+    Spoke,
+    Standalone,
+}
+
+impl Role {
+    /// cluster.role / the role of a switch request
+    pub fn from_name(name: &str) -> anyhow::Result<Role> {
+        match name {
+            "hub" => Ok(Role::Hub),
+            "spoke" => Ok(Role::Spoke),
+            "standalone" => Ok(Role::Standalone),
+            other => anyhow::bail!("unknown role '{}' (expected hub, spoke or standalone)", other),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Hub => "hub",
+            Role::Spoke => "spoke",
+            Role::Standalone => "standalone",
+        }
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+struct Switch {
+    tx: watch::Sender<Role>,
+    /// a hub to push to (hub_url / hub_urls, or the nats transport)
+    can_push: bool,
+    /// the web server runs (not headless), spokes can reach a hub
+    serving: bool,
+}
+
+static SWITCH: OnceLock<Switch> = OnceLock::new();
+
+/// alerts / notify / reports run from the first hub or standalone role on
+static SERVICES: Once = Once::new();
+
+/// set the startup role (cluster.role); the poll loop watches the receiver
+pub fn init(role: Role, can_push: bool, serving: bool) -> watch::Receiver<Role> {
+    let (tx, rx) = watch::channel(role);
+    let _ = SWITCH.set(Switch { tx, can_push, serving });
+    rx
+}
+
+/// the role the node runs as now
+pub fn current() -> Role {
+    SWITCH.get().map_or(Role::Hub, |s| *s.tx.borrow())
+}
+
+/// switch to `role` from the next poll cycle on; returns the previous role
+pub fn set(role: Role, actor: &str) -> anyhow::Result<Role> {
+    let Some(switch) = SWITCH.get() else {
+        anyhow::bail!("the poll loop is not running yet");
+    };
+    if role == Role::Spoke && !switch.can_push {
+        anyhow::bail!("a spoke needs cluster.hub_url / hub_urls (or transport = \"nats\") to push to");
+    }
+    if role == Role::Hub && !switch.serving {
+        anyhow::bail!("a headless node has no web server for spokes to push to");
+    }
+    let previous = switch.tx.send_replace(role);
+    if previous != role {
+        log_msg(&format!("🔀 [ROLE] {} switched the role {} → {}, applied from the next poll cycle", actor, previous, role));
+    }
+    Ok(previous)
+}
+
+/// axum middleware for the endpoints spokes feed a hub through
+pub async fn hub_only(request: Request, next: Next) -> Response {
+    match current() {
+        Role::Hub => next.run(request).await,
+        role => (axum::http::StatusCode::SERVICE_UNAVAILABLE, format!("this node runs as {}, not as a hub", role)).into_response(),
+    }
+}
+
+// ==============================================================================
+// role tasks
+// ==============================================================================
+
+/// what the tasks of every role are started with
+pub struct Context {
+    pub config: HostConfig,
+    pub client: reqwest::Client,
+    /// influx / notifications / reports, outside the cluster
+    pub external_client: reqwest::Client,
+    pub hubs: Arc<HubFailover>,
+    pub runtime: WasmRuntime,
+    pub state: Arc<RwLock<AppState>>,
+    pub nodes: Arc<NodeRegistry>,
+    pub events: Arc<EventBus>,
+    pub alerts: Arc<AlertEngine>,
+    pub reports: Arc<Reports>,
+    pub store: Option<Arc<Store>>,
+    /// what pulled / nats readings are merged through (ingest_readings)
+    pub api: crate::ApiState,
+    pub encoding: Encoding,
+    /// cluster.transport = "nats" in a build that has it
+    #[cfg_attr(not(feature = "nats"), allow(dead_code))]
+    pub use_nats: bool,
+    /// hub overlay version applied at startup, None unless cluster.role is spoke
+    pub overlay_version: Option<String>,
+}
+
+/// the tasks of the role the node runs as; dropping it stops them
+pub struct Running {
+    pub role: Role,
+    tasks: Vec<JoinHandle<()>>,
+    /// spoke websocket (transport = "websocket")
+    pub uplink: Option<crate::ws::WsUplink>,
+    #[cfg(feature = "nats")]
+    pub nats: Option<crate::nats::NatsPublisher>,
+}
+
+impl Running {
+    fn idle(role: Role) -> Running {
+        Running {
+            role,
+            tasks: Vec::new(),
+            uplink: None,
+            #[cfg(feature = "nats")]
+            nats: None,
+        }
+    }
+}
+
+impl Drop for Running {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+impl Context {
+    /// start the tasks of `role`
+    pub async fn start(&self, role: Role) -> anyhow::Result<Running> {
+        let mut running = Running::idle(role);
+        // old readings are flagged stale whatever the node runs as
+        running.tasks.extend(crate::nodes::spawn_stale_readings(self.state.clone(), self.config.cluster.stale_after_seconds));
+        match role {
+            Role::Spoke => self.start_spoke(&mut running).await?,
+            Role::Hub => self.start_hub(&mut running),
+            Role::Standalone => {}
+        }
+        if role != Role::Spoke {
+            SERVICES.call_once(|| self.start_services());
+        }
+        Ok(running)
+    }
+
+    /// stop the tasks of the current role and start the ones of `role`
+    pub async fn switch(&self, running: &mut Running, role: Role) {
+        let from = running.role;
+        // the old tasks stop before the new ones start
+        *running = Running::idle(role);
+        match self.start(role).await {
+            Ok(started) => *running = started,
+            Err(e) => log_msg(&format!("❌ [ROLE] Tasks of the {} role not started: {:#}", role, e)),
+        }
+        log_msg(&format!("🔀 [ROLE] Now running as {} (was {})", role, from));
+    }
+
+    async fn start_spoke(&self, running: &mut Running) -> anyhow::Result<()> {
+        let config = &self.config;
+        let node_id = &config.cluster.node_id;
+        // failover - fall back to higher-priority hubs once they recover
+        running.tasks.extend(self.hubs.clone().spawn_probe(self.client.clone(), config.cluster.failover_probe_seconds));
+
+        #[cfg(feature = "nats")]
+        if self.use_nats {
+            running.nats = match crate::nats::NatsPublisher::connect(&config.cluster.nats, node_id).await {
+                Ok(publisher) => Some(publisher),
+                Err(e) => {
+                    log_msg(&format!("❌ [NATS] Publisher disabled: {:#}", e));
+                    None
+                }
+            };
+        }
+        if self.hubs.is_empty() {
+            return Ok(());
+        }
+
+        // heartbeat - cheap liveness signal for the hub's stale-node detection
+        running.tasks.extend(crate::nodes::spawn_heartbeat(
+            self.client.clone(),
+            self.hubs.clone(),
+            self.runtime.clone(),
+            node_id.clone(),
+            config.cluster.metadata.clone(),
+            config.cluster.heartbeat_seconds,
+        ));
+
+        // websocket - readings up and commands down over one connection,
+        // else long-poll the active hub for queued commands
+        if config.cluster.transport == "websocket" {
+            let hubs = self.hubs.clone();
+            running.uplink = Some(crate::ws::spawn_uplink(self.client.clone(), move || hubs.active_base(), config.clone(), self.runtime.clone())?);
+        } else {
+            let hubs = self.hubs.clone();
+            running.tasks.push(crate::commands::spawn_receiver(self.client.clone(), move || hubs.active_base(), config.clone(), self.runtime.clone()));
+        }
+
+        // config sync - restart when the hub's overlay for this node changes
+        if let Some(version) = &self.overlay_version {
+            let hubs = self.hubs.clone();
+            running.tasks.extend(crate::node_config::spawn_sync(
+                self.client.clone(),
+                move || hubs.active_base(),
+                node_id.clone(),
+                version.clone(),
+                config.cluster.config_sync_seconds,
+            ));
+        }
+        Ok(())
+    }
+
+    fn start_hub(&self, running: &mut Running) {
+        let config = &self.config;
+        running.tasks.extend(self.nodes.clone().spawn_stale_check(config.cluster.stale_after_seconds));
+
+        // pull mode - fetch readings from spokes that can't reach us
+        if !config.cluster.pull_spokes.is_empty() {
+            let pull_interval = match config.cluster.pull_interval_seconds {
+                0 => config.polling.interval_seconds,
+                n => n,
+            };
+            running.tasks.push(crate::cluster::spawn_pull_loop(
+                self.api.clone(),
+                self.client.clone(),
+                config.cluster.pull_spokes.clone(),
+                pull_interval,
+                self.encoding,
+            ));
+        }
+
+        #[cfg(feature = "nats")]
+        if self.use_nats {
+            running.tasks.push(crate::nats::spawn_consumer(self.api.clone(), config.cluster.clone()));
+        }
+    }
+
+    /// alerts, notifications and reports
+    fn start_services(&self) {
+        let config = &self.config;
+        self.alerts.clone().spawn(self.state.clone(), config.alerts.interval_seconds);
+        if let Some(store) = self.store.clone() {
+            crate::alerts::spawn_history(store, &self.events);
+        }
+        crate::notify::spawn(config.notify.clone(), &self.events, config.cluster.node_id.clone(), self.external_client.clone());
+        self.reports.clone().spawn(config, self.store.clone(), &self.events, self.runtime.clone(), self.external_client.clone());
+    }
+}
@@ -14,6 +14,7 @@
 //!     - bme680: Environmental sensor (temp, humidity, pressure, gas/IAQ), LED 2
 //!     - pi-monitor: System health (CPU temp, RAM, uptime), controls LED 0
 //!     - dashboard: HTML rendering (no hardware access)
+//!     - report: periodic summary report rendering (no hardware access, optional)
 //!
 //! phase 3 (generic hal):
 //!     - Implements i2c::Host trait for generic I2C access (uses hex strings)
@@ -22,14 +23,33 @@
 //! relationships:
 //!     - used by: main.rs (creates runtime, polling loop)
 //!     - reads: ../wit/plugin.wit (interface definitions)
-//!     - implements: gpio-provider, led-controller, buzzer-controller, i2c, system-info
+//!     - implements: gpio-provider, led-controller, buzzer-controller, i2c, system-info,
+//!       reading-history
+//!     - reads: recent.rs (recent readings served to plugins)
 //!     - uses: hal.rs (actual hardware access via rppal)
+//!     - writes: audit.rs (buzzer / fan / led actions of each plugin)
+//!     - uses: telemetry.rs (plugin.call spans)
+//!     - writes: plugin_stats.rs (call counters / latencies / last failure per plugin)
+//!     - uses: plugin_output.rs (captures plugin stdout / stderr)
+//!     - uses: plugin_worker.rs (every export call runs on its plugin's thread)
+//!     - writes: memory.rs (linear memory / table counts per store)
+//!     - uses: backoff.rs (skips failing sensor plugins, polling.backoff)
+//!     - uses: watchdog.rs (kills hung calls, restarts / quarantines plugins)
+//!     - uses: profiler.rs (guest profile of the [plugin_profiler] plugin)
 //!     - loads: ../plugins/{dht22,bme680,pi-monitor,dashboard}/*.wasm
 //!
 //! ==============================================================================
 
 // use crate::hal;
-use crate::domain::SensorReading;
+use crate::domain::{Quality, SensorReading};
+use crate::recent::RecentReadings;
+use crate::plugin_output::PluginOutput;
+use crate::plugin_stats::PluginStats;
+use crate::plugin_worker::Worker;
+use crate::backoff::Backoff;
+use crate::watchdog::{Verdict, Watchdog};
+use crate::memory::{Limiter, MemoryUsage};
+use crate::telemetry;
 
 use anyhow::{Result, Context};
 use crate::config::HostConfig;
@@ -38,7 +58,10 @@ use wasmtime::{
     Config, Engine, Store,
 };
 use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, WasiView};
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
 use std::time::SystemTime;
 use std::sync::Arc;
 use tokio::sync::Mutex;
@@ -65,6 +88,15 @@ mod dashboard_bindings {
 }
 use dashboard_bindings::DashboardPlugin;
 
+mod report_bindings {
+    wasmtime::component::bindgen!({
+        path: "../wit",
+        world: "report-plugin",
+        async: true,
+    });
+}
+use report_bindings::ReportPlugin;
+
 mod bme680_bindings {
     wasmtime::component::bindgen!({
         path: "../wit",
@@ -109,6 +141,15 @@ pub struct HostState {
     ctx: WasiCtx,
     table: ResourceTable,
     pub config: HostConfig,
+    recent: RecentReadings,
+    /// "plugin:<name>", the actor of its hardware actions in the audit log
+    actor: String,
+    /// counts the store's memory / table growth
+    limiter: Limiter,
+    /// the process-wide hal (hal::shared)
+    hal: Arc<crate::hal::Hal>,
+    /// guest profile when [plugin_profiler] picks this plugin (profiler.rs)
+    pub profile: Option<crate::profiler::Profile>,
 }
 
 impl WasiView for HostState {
@@ -121,6 +162,7 @@ impl WasiView for HostState {
 // ==============================================================================
 //
 // NOTE: We use `crate::hal::Hal` which handles cross-platform logic (mock vs real).
+// Every HostState holds the one process-wide instance (hal::shared).
 // All hardware access is performed safely via a non-blocking HAL.
 // As of the Standalone Harvester update, consensus logic is replaced by local 
 // aggregation on the Hub.
@@ -128,7 +170,7 @@ impl WasiView for HostState {
 impl dht22_bindings::demo::plugin::gpio_provider::Host for HostState {
     async fn read_dht22(&mut self, _pin: u8) -> Result<(f32, f32), String> {
         let pin = self.config.sensors.dht22.gpio_pin;
-        let hal = crate::hal::Hal::new();
+        let hal = self.hal.clone();
         tokio::task::spawn_blocking(move || {
             use crate::hal::HardwareProvider;
             hal.read_dht22(pin)
@@ -143,20 +185,20 @@ impl dht22_bindings::demo::plugin::gpio_provider::Host for HostState {
     }
     
     async fn get_cpu_temp(&mut self) -> f32 {
-         let hal = crate::hal::Hal::new();
+         let hal = &self.hal;
          use crate::hal::HardwareProvider;
          hal.get_cpu_temp()
     }
     
     async fn read_bme680(&mut self, _i2c_addr: u8) -> Result<(f32, f32, f32, f32), String> {
         let i2c_addr_str = &self.config.sensors.bme680.i2c_address;
-        let i2c_addr = if i2c_addr_str.starts_with("0x") {
-            u8::from_str_radix(&i2c_addr_str[2..], 16).unwrap_or(0x77)
+        let i2c_addr = if let Some(hex) = i2c_addr_str.strip_prefix("0x") {
+            u8::from_str_radix(hex, 16).unwrap_or(0x77)
         } else {
             i2c_addr_str.parse().unwrap_or(0x77)
         };
         
-        let hal = crate::hal::Hal::new();
+        let hal = self.hal.clone();
         tokio::task::spawn_blocking(move || {
             use crate::hal::HardwareProvider;
              // Dummy implementation for now via HAL
@@ -176,38 +218,40 @@ impl dht22_bindings::demo::plugin::gpio_provider::Host for HostState {
 impl dht22_bindings::demo::plugin::led_controller::Host for HostState {
     async fn set_led(&mut self, index: u8, r: u8, g: u8, b: u8) {
          use crate::hal::HardwareProvider;
-         let hal = crate::hal::Hal::new();
+         let hal = &self.hal;
          let _ = hal.set_led(index, r, g, b);
+         crate::audit::leds(&self.actor, &[(index, r, g, b)], "");
     }
     
     async fn set_all(&mut self, r: u8, g: u8, b: u8) {
         use crate::hal::HardwareProvider;
-        let hal = crate::hal::Hal::new();
+        let hal = &self.hal;
         for i in 0..11 {
             let _ = hal.set_led(i, r, g, b);
         }
+        crate::audit::leds(&self.actor, &(0..11).map(|i| (i, r, g, b)).collect::<Vec<_>>(), "");
     }
     
     async fn set_two(&mut self, r0: u8, g0: u8, b0: u8, r1: u8, g1: u8, b1: u8) {
         use crate::hal::HardwareProvider;
-        let hal = crate::hal::Hal::new();
+        let hal = &self.hal;
         let _ = hal.set_led(0, r0, g0, b0);
         let _ = hal.set_led(1, r1, g1, b1);
+        crate::audit::leds(&self.actor, &[(0, r0, g0, b0), (1, r1, g1, b1)], "");
     }
     
     async fn clear(&mut self) {
         use crate::hal::HardwareProvider;
-        let hal = crate::hal::Hal::new();
+        let hal = &self.hal;
         for i in 0..11 {
             let _ = hal.set_led(i, 0, 0, 0);
         }
+        crate::audit::leds(&self.actor, &(0..11).map(|i| (i, 0, 0, 0)).collect::<Vec<_>>(), "");
     }
 
-    async fn sync_leds(&mut self) {
-        use crate::hal::HardwareProvider;
-        let hal = crate::hal::Hal::new();
-        let _ = hal.sync_leds();
-    }
+    /// the frame is written once the poll cycle is done (hal::flush_leds),
+    /// so plugins syncing one after another cost a single strip update
+    async fn sync_leds(&mut self) {}
 }
 
 // ==============================================================================
@@ -217,29 +261,19 @@ impl dht22_bindings::demo::plugin::led_controller::Host for HostState {
 impl dht22_bindings::demo::plugin::buzzer_controller::Host for HostState {
     async fn buzz(&mut self, duration_ms: u32) {
         let pin = self.config.buzzer.gpio_pin;
-        let hal = crate::hal::Hal::new();
-        tokio::task::spawn_blocking(move || {
-            use crate::hal::HardwareProvider;
-            let _ = hal.set_gpio_mode(pin, "OUT");
-            let _ = hal.write_gpio(pin, false); // Relay on (Low)
-            std::thread::sleep(std::time::Duration::from_millis(duration_ms as u64));
-            let _ = hal.write_gpio(pin, true);  // Relay off (High)
-        }).await.ok();
+        crate::audit::record(&self.actor, "buzzer", format!("{}ms", duration_ms));
+        if let Err(e) = crate::actuators::buzz(pin, vec![(duration_ms as u64, 0)]).await {
+            tracing::debug!("[BUZZER] buzz from {} failed: {}", self.actor, e);
+        }
     }
     
     async fn beep(&mut self, count: u8, duration_ms: u32, interval_ms: u32) {
         let pin = self.config.buzzer.gpio_pin;
-        let hal = crate::hal::Hal::new();
-        tokio::task::spawn_blocking(move || {
-            use crate::hal::HardwareProvider;
-            let _ = hal.set_gpio_mode(pin, "OUT");
-            for _ in 0..count {
-                let _ = hal.write_gpio(pin, false);
-                std::thread::sleep(std::time::Duration::from_millis(duration_ms as u64));
-                let _ = hal.write_gpio(pin, true);
-                std::thread::sleep(std::time::Duration::from_millis(interval_ms as u64));
-            }
-        }).await.ok();
+        crate::audit::record(&self.actor, "buzzer", format!("{} x {}ms", count, duration_ms));
+        let steps = vec![(duration_ms as u64, interval_ms as u64); count as usize];
+        if let Err(e) = crate::actuators::buzz(pin, steps).await {
+            tracing::debug!("[BUZZER] beep from {} failed: {}", self.actor, e);
+        }
     }
 }
 
@@ -291,20 +325,17 @@ impl pi4_monitor_bindings::demo::plugin::buzzer_controller::Host for HostState {
 
 impl pi4_monitor_bindings::demo::plugin::fan_controller::Host for HostState {
     async fn set_fan(&mut self, on: bool) {
-        use std::sync::atomic::Ordering;
-        let pin = self.config.fan.gpio_pin;
-        let hal = crate::hal::Hal::new();
-        
-        // Update global fan state for tracking
-        crate::hal::GLOBAL_FAN_STATE.store(on, Ordering::SeqCst);
-        
-        // Use write_gpio like buzzer does - rppal maintains GPIO state
-        tokio::task::spawn_blocking(move || {
-            use crate::hal::HardwareProvider;
-            let _ = hal.set_gpio_mode(pin, "OUT");
-            // Active-low relay: write false = LOW = relay ON = fan running
-            let _ = hal.write_gpio(pin, !on);
-        }).await.ok();
+        let fan = &self.config.fan;
+        if !fan.auto {
+            // [fan] auto = false: plugins built before HARVESTER_FAN_AUTO still ask
+            tracing::debug!("[FAN] Ignoring set_fan({}) from {}, fan is in manual mode", on, self.actor);
+            return;
+        }
+        match crate::actuators::switch_fan(fan, on).await {
+            Ok(true) => crate::audit::record(&self.actor, "fan", if on { "on" } else { "off" }),
+            Ok(false) => {}
+            Err(e) => tracing::debug!("[FAN] set_fan({}) from {} failed: {}", on, self.actor, e),
+        }
     }
     
     async fn get_fan_state(&mut self) -> bool {
@@ -437,30 +468,184 @@ impl revpi_monitor_bindings::demo::plugin::system_info::Host for HostState {
     }
 }
 
+// ==============================================================================
+// reading-history implementation
+// ==============================================================================
+//
+// every world importing reading-history gets its own generated trait and
+// record type, so the impl is stamped out per bindings module.
+
+impl HostState {
+    /// recent readings of a full sensor id, or of this node's sensor
+    /// when a plugin passes its own id ("dht22" -> "pi4:dht22")
+    fn recent_readings(&self, sensor_id: &str, count: u32) -> Vec<SensorReading> {
+        let count = (count as usize).min(crate::recent::PER_SENSOR);
+        let readings = self.recent.get(sensor_id, count);
+        if !readings.is_empty() || sensor_id.contains(':') {
+            return readings;
+        }
+        self.recent.get(&format!("{}:{}", self.config.cluster.node_id, sensor_id), count)
+    }
+}
+
+macro_rules! impl_reading_history {
+    ($($bindings:ident),*) => {$(
+        impl $bindings::demo::plugin::reading_history::Host for HostState {
+            async fn get_recent_readings(
+                &mut self,
+                sensor_id: String,
+                count: u32,
+            ) -> Vec<$bindings::demo::plugin::reading_history::RecentReading> {
+                self.recent_readings(&sensor_id, count)
+                    .into_iter()
+                    .map(|r| $bindings::demo::plugin::reading_history::RecentReading {
+                        timestamp_ms: r.timestamp_ms,
+                        data: r.data.to_string(),
+                    })
+                    .collect()
+            }
+        }
+    )*};
+}
+
+impl_reading_history!(dht22_bindings, bme680_bindings, pi4_monitor_bindings, revpi_monitor_bindings, oled_bindings);
+
+// the quality field of the sensor records - one generated enum per world too.
+// reading-types only holds types, its host trait has nothing to implement.
+macro_rules! impl_reading_quality {
+    ($($bindings:ident),*) => {$(
+        impl $bindings::demo::plugin::reading_types::Host for HostState {}
+
+        impl From<$bindings::demo::plugin::reading_types::ReadingQuality> for Quality {
+            fn from(quality: $bindings::demo::plugin::reading_types::ReadingQuality) -> Quality {
+                use $bindings::demo::plugin::reading_types::ReadingQuality as Q;
+                match quality {
+                    Q::Ok => Quality::Ok,
+                    Q::Stale => Quality::Stale,
+                    Q::Estimated => Quality::Estimated,
+                    Q::SensorError => Quality::SensorError,
+                }
+            }
+        }
+    )*};
+}
+
+impl_reading_quality!(dht22_bindings, bme680_bindings, pi4_monitor_bindings, revpi_monitor_bindings);
+
 
 // ==============================================================================
 // plugin metadata 
 // ==============================================================================
 
 pub struct PluginState<T> {
-    #[allow(dead_code)]
     path: PathBuf,
-    #[allow(dead_code)]
     last_modified: SystemTime,
     store: Store<HostState>,
     instance: T,
 }
 
 impl<T> PluginState<T> {
-    #[allow(dead_code)]
     fn needs_reload(&self) -> bool {
-        std::fs::metadata(&self.path)
-            .and_then(|m| m.modified())
-            .map(|t| t > self.last_modified)
-            .unwrap_or(false)
+        modified(&self.path).is_some_and(|t| t > self.last_modified)
     }
 }
 
+/// mtime of a plugin's .wasm
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+type PluginSlot<T> = Arc<Mutex<Option<PluginState<T>>>>;
+
+/// true if the plugin is loaded and its .wasm changed on disk since load
+async fn is_stale<T>(slot: &PluginSlot<T>) -> bool {
+    slot.lock().await.as_ref().map(|p| p.needs_reload()).unwrap_or(false)
+}
+
+// ==============================================================================
+// plugin loaders
+// ==============================================================================
+//
+// each plugin world has its own generated type, so the compile/link/instantiate
+// sequence is stamped out per world. used both at startup and for hot reload.
+
+fn create_host_state(config: &HostConfig, recent: &RecentReadings, plugin: &str, output: PluginOutput) -> HostState {
+    let node_id = &config.cluster.node_id;
+    let mut builder = WasiCtxBuilder::new();
+    // stdout / stderr still reach the terminal; the last lines are kept for failure records
+    builder.inherit_stdin().stdout(output.stdout()).stderr(output.stderr());
+
+    // Set Environment Variables for Plugins
+    builder.env("HARVESTER_NODE_ID", node_id);
+    if node_id.contains("pizero") {
+        builder.env("HARVESTER_PASSIVE", "1");
+    }
+    // [fan]: the monitor plugins switch the fan themselves
+    builder.env("HARVESTER_FAN_AUTO", if config.fan.auto { "1" } else { "0" });
+    builder.env("HARVESTER_FAN_ON", config.fan.threshold_on.to_string());
+    builder.env("HARVESTER_FAN_OFF", config.fan.threshold_off.to_string());
+
+    let wasi = builder.build();
+    HostState {
+        ctx: wasi,
+        table: ResourceTable::new(),
+        config: config.clone(),
+        recent: recent.clone(),
+        actor: format!("plugin:{}", plugin),
+        limiter: Limiter::default(),
+        hal: crate::hal::shared(),
+        profile: crate::profiler::Profile::for_plugin(&config.plugin_profiler, plugin),
+    }
+}
+
+macro_rules! define_loader {
+    ($fn_name:ident, $world:ident, $label:literal, $link:expr) => {
+        async fn $fn_name(engine: &Engine, path: PathBuf, config: &HostConfig, recent: &RecentReadings, stats: &PluginStats, memory: &MemoryUsage) -> Result<PluginState<$world>> {
+            println!("[DEBUG] Loading {} plugin...", $label);
+            let last_modified = std::fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .unwrap_or_else(|_| SystemTime::now());
+            let component = Component::from_file(engine, &path)
+                .context(concat!("failed to load ", $label, ".wasm"))?;
+
+            let mut linker = Linker::new(engine);
+            wasmtime_wasi::add_to_linker_async(&mut linker)?;
+            $link(&mut linker)?;
+
+            let mut store = Store::new(engine, create_host_state(config, recent, $label, stats.output($label)));
+            store.limiter(|state| &mut state.limiter);
+            store.set_epoch_deadline(Watchdog::unlimited());
+            let instance = $world::instantiate_async(&mut store, &component, &linker).await
+                .context(concat!("failed to instantiate ", $label, " plugin"))?;
+            crate::profiler::attach(&mut store);
+            let code = component.image_range();
+            memory.register($label, &store.data().limiter, (code.end as usize - code.start as usize) as u64);
+
+            Ok(PluginState { path, last_modified, store, instance })
+        }
+    };
+}
+
+define_loader!(load_dht22, Dht22Plugin, "dht22", |l: &mut Linker<HostState>| {
+    Dht22Plugin::add_to_linker(l, |s: &mut HostState| s)
+});
+define_loader!(load_pi4_monitor, Pi4MonitorPlugin, "pi4-monitor", |l: &mut Linker<HostState>| {
+    Pi4MonitorPlugin::add_to_linker(l, |s: &mut HostState| s)
+});
+define_loader!(load_revpi_monitor, RevpiMonitorPlugin, "revpi-monitor", |l: &mut Linker<HostState>| {
+    RevpiMonitorPlugin::add_to_linker(l, |s: &mut HostState| s)
+});
+define_loader!(load_bme680, Bme680Plugin, "bme680", |l: &mut Linker<HostState>| {
+    Bme680Plugin::add_to_linker(l, |s: &mut HostState| s)
+});
+// Note: Dashboard only exports logic, no host imports needed in the linker
+define_loader!(load_dashboard, DashboardPlugin, "dashboard", |_l: &mut Linker<HostState>| {
+    anyhow::Ok(())
+});
+define_loader!(load_report, ReportPlugin, "report", |_l: &mut Linker<HostState>| {
+    anyhow::Ok(())
+});
+
 // ==============================================================================
 // Standalone Wasm Runtime
 // ==============================================================================
@@ -471,257 +656,530 @@ impl<T> PluginState<T> {
 
 #[derive(Clone)]
 pub struct WasmRuntime {
-    #[allow(dead_code)]
     engine: Engine,
-    #[allow(dead_code)]
     config: HostConfig,
-    dht22_plugin: Arc<Mutex<Option<PluginState<Dht22Plugin>>>>,
-    pi4_monitor_plugin: Arc<Mutex<Option<PluginState<Pi4MonitorPlugin>>>>,
-    revpi_monitor_plugin: Arc<Mutex<Option<PluginState<RevpiMonitorPlugin>>>>,
+    recent: RecentReadings,
+    base_path: PathBuf,
+    dht22_plugin: PluginSlot<Dht22Plugin>,
+    pi4_monitor_plugin: PluginSlot<Pi4MonitorPlugin>,
+    revpi_monitor_plugin: PluginSlot<RevpiMonitorPlugin>,
+    dashboard_plugin: PluginSlot<DashboardPlugin>,
+    bme680_plugin: PluginSlot<Bme680Plugin>,
+    report_plugin: PluginSlot<ReportPlugin>,
     #[allow(dead_code)]
-    dashboard_plugin: Arc<Mutex<Option<PluginState<DashboardPlugin>>>>,
-    bme680_plugin: Arc<Mutex<Option<PluginState<Bme680Plugin>>>>,
-    #[allow(dead_code)]
-    oled_plugin: Arc<Mutex<Option<PluginState<OledPlugin>>>>,
+    oled_plugin: PluginSlot<OledPlugin>,
+    stats: PluginStats,
+    memory: MemoryUsage,
+    backoff: Arc<Backoff>,
+    watchdog: Arc<Watchdog>,
+    /// plugins the watchdog quarantined, with the mtime of their .wasm then -
+    /// hot reload brings them back once it changes
+    quarantined: Arc<std::sync::Mutex<HashMap<&'static str, SystemTime>>>,
+    /// one per KNOWN_PLUGINS entry
+    workers: Arc<HashMap<&'static str, Worker>>,
+}
+
+/// {base}/plugins/{name}/{name}.wasm
+pub fn plugin_path(base: &Path, name: &str) -> PathBuf {
+    base.join("plugins").join(name).join(format!("{}.wasm", name))
 }
 
+/// plugin names the runtime knows how to load (also the reload-plugin targets)
+pub const KNOWN_PLUGINS: [&str; 6] = ["dht22", "pi4-monitor", "revpi-monitor", "bme680", "dashboard", "report"];
+
 impl WasmRuntime {
-    pub async fn new(path: PathBuf, config: &HostConfig) -> Result<Self> {
+    pub async fn new(path: PathBuf, config: &HostConfig, recent: RecentReadings) -> Result<Self> {
         let mut wasm_config = Config::new();
         wasm_config.wasm_component_model(true);
         wasm_config.async_support(true);
+        // the watchdog's call deadlines
+        wasm_config.epoch_interruption(true);
         let engine = Engine::new(&wasm_config)?;
+        let ticking = engine.weak();
+        std::thread::Builder::new().name("plugin-epoch".to_string()).spawn(move || {
+            while let Some(engine) = ticking.upgrade() {
+                engine.increment_epoch();
+                drop(engine);
+                std::thread::sleep(crate::watchdog::TICK);
+            }
+        })?;
+        let workers = KNOWN_PLUGINS
+            .into_iter()
+            .map(|name| Ok((name, Worker::spawn(name)?)))
+            .collect::<std::io::Result<HashMap<_, _>>>()
+            .context("failed to start the plugin workers")?;
 
-        let create_host_state = |conf: HostConfig, node_id: String| {
-             let mut builder = WasiCtxBuilder::new();
-             builder.inherit_stdio();
-             
-             // Set Environment Variables for Plugins
-             builder.env("HARVESTER_NODE_ID", &node_id);
-             if node_id.contains("pizero") {
-                 builder.env("HARVESTER_PASSIVE", "1");
-             }
-             
-             let wasi = builder.build();
-             HostState { ctx: wasi, table: ResourceTable::new(), config: conf }
+        let runtime = Self {
+            engine,
+            config: config.clone(),
+            recent,
+            base_path: path,
+            dht22_plugin: Arc::new(Mutex::new(None)),
+            pi4_monitor_plugin: Arc::new(Mutex::new(None)),
+            revpi_monitor_plugin: Arc::new(Mutex::new(None)),
+            dashboard_plugin: Arc::new(Mutex::new(None)),
+            bme680_plugin: Arc::new(Mutex::new(None)),
+            report_plugin: Arc::new(Mutex::new(None)),
+            oled_plugin: Arc::new(Mutex::new(None)),
+            stats: PluginStats::default(),
+            memory: MemoryUsage::default(),
+            backoff: Arc::new(Backoff::new(config.polling.backoff.clone())),
+            watchdog: Arc::new(Watchdog::new(config.plugin_watchdog.clone())),
+            quarantined: Arc::default(),
+            workers: Arc::new(workers),
         };
 
-        // 1. DHT22 Plugin
-        let dht22_plugin = if config.plugins.dht22.enabled {
-            println!("[DEBUG] Loading dht22 plugin...");
-            let dht22_path = path.join("plugins/dht22/dht22.wasm");
-            let dht22_component = Component::from_file(&engine, &dht22_path)
-                .context("failed to load dht22.wasm")?;
-            
-            let mut linker = Linker::new(&engine);
-            wasmtime_wasi::add_to_linker_async(&mut linker)?;
-            dht22_bindings::Dht22Plugin::add_to_linker(&mut linker, |s: &mut HostState| s)?;
-            
-            let mut store = Store::new(&engine, create_host_state(config.clone(), config.cluster.node_id.clone()));
-            let dht22_instance = Dht22Plugin::instantiate_async(&mut store, &dht22_component, &linker).await
-                .context("failed to instantiate dht22 plugin")?;
-            
-            Arc::new(Mutex::new(Some(PluginState {
-                last_modified: SystemTime::now(),
-                path: dht22_path,
-                store: store,
-                instance: dht22_instance,
-            })))
-        } else {
-            Arc::new(Mutex::new(None))
-        };
-        
-        // 2a. Pi 4 Monitor Plugin
-        let pi4_monitor_plugin = if config.plugins.pi4_monitor.enabled {
-            println!("[DEBUG] Loading pi4-monitor plugin...");
-            let path = path.join("plugins/pi4-monitor/pi4-monitor.wasm");
-            let comp = Component::from_file(&engine, &path).context("failed to load pi4-monitor.wasm")?;
-            let mut linker = Linker::new(&engine);
-            wasmtime_wasi::add_to_linker_async(&mut linker)?;
-            pi4_monitor_bindings::Pi4MonitorPlugin::add_to_linker(&mut linker, |s: &mut HostState| s)?;
-            let mut store = Store::new(&engine, create_host_state(config.clone(), config.cluster.node_id.clone()));
-            let inst = Pi4MonitorPlugin::instantiate_async(&mut store, &comp, &linker).await?;
-            Arc::new(Mutex::new(Some(PluginState { last_modified: SystemTime::now(), path, store, instance: inst })))
-        } else {
-            Arc::new(Mutex::new(None))
-        };
+        // 1. DHT22, 2a. Pi 4 Monitor, 2b. RevPi Monitor, 3. BME680, 4. Dashboard, 5. Report
+        for name in KNOWN_PLUGINS {
+            if config.plugin_enabled(name) {
+                runtime.reload_plugin(name).await?;
+            }
+        }
+        let mut unknown: Vec<&str> = config
+            .plugins
+            .iter()
+            .filter(|(key, entry)| entry.enabled && !KNOWN_PLUGINS.contains(&key.replace('_', "-").as_str()))
+            .map(|(key, _)| key.as_str())
+            .collect();
+        unknown.sort();
+        for key in unknown {
+            crate::log_msg(&format!("⚠️ [RUNTIME] plugins.{} is enabled but this host has no such plugin", key));
+        }
 
-        // 2b. RevPi Monitor Plugin
-        let revpi_monitor_plugin = if config.plugins.revpi_monitor.enabled {
-            println!("[DEBUG] Loading revpi-monitor plugin...");
-            let path = path.join("plugins/revpi-monitor/revpi-monitor.wasm");
-            let comp = Component::from_file(&engine, &path).context("failed to load revpi-monitor.wasm")?;
-            let mut linker = Linker::new(&engine);
-            wasmtime_wasi::add_to_linker_async(&mut linker)?;
-            revpi_monitor_bindings::RevpiMonitorPlugin::add_to_linker(&mut linker, |s: &mut HostState| s)?;
-            let mut store = Store::new(&engine, create_host_state(config.clone(), config.cluster.node_id.clone()));
-            let inst = RevpiMonitorPlugin::instantiate_async(&mut store, &comp, &linker).await?;
-            Arc::new(Mutex::new(Some(PluginState { last_modified: SystemTime::now(), path, store, instance: inst })))
-        } else {
-            Arc::new(Mutex::new(None))
-        };
+        Ok(runtime)
+    }
 
-        // 3. BME680 Plugin
-        let bme680_plugin = if config.plugins.bme680.enabled {
-            println!("[DEBUG] Loading bme680 plugin...");
-            let bme680_path = path.join("plugins/bme680/bme680.wasm");
-            let bme680_component = Component::from_file(&engine, &bme680_path)
-                .context("failed to load bme680.wasm")?;
-            
-            let mut linker = Linker::new(&engine);
-            wasmtime_wasi::add_to_linker_async(&mut linker)?;
-            bme680_bindings::Bme680Plugin::add_to_linker(&mut linker, |s: &mut HostState| s)?;
-            
-            let mut store = Store::new(&engine, create_host_state(config.clone(), config.cluster.node_id.clone()));
-            let bme680_instance = Bme680Plugin::instantiate_async(&mut store, &bme680_component, &linker).await
-                .context("failed to instantiate bme680 plugin")?;
-            
-            Arc::new(Mutex::new(Some(PluginState {
-                last_modified: SystemTime::now(),
-                path: bme680_path,
-                store: store,
-                instance: bme680_instance,
-            })))
-        } else {
-            Arc::new(Mutex::new(None))
+    /// path of a plugin's component: plugins/{name}/{name}.wasm
+    pub fn plugin_path(&self, name: &str) -> PathBuf {
+        plugin_path(&self.base_path, name)
+    }
+
+    /// (re)load a plugin from disk, replacing the running instance.
+    /// the old store is dropped only after the new one instantiated, so a
+    /// broken upload leaves the previous version running.
+    pub async fn reload_plugin(&self, name: &str) -> Result<()> {
+        self.instantiate(name).await?;
+        self.backoff.reset(name);
+        self.watchdog.reset(name);
+        self.quarantined.lock().unwrap().remove(name);
+        Ok(())
+    }
+
+    /// a fresh instance of a plugin from its .wasm
+    async fn instantiate(&self, name: &str) -> Result<()> {
+        let path = self.plugin_path(name);
+        match name {
+            "dht22" => *self.dht22_plugin.lock().await = Some(load_dht22(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            "pi4-monitor" => *self.pi4_monitor_plugin.lock().await = Some(load_pi4_monitor(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            "revpi-monitor" => *self.revpi_monitor_plugin.lock().await = Some(load_revpi_monitor(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            "bme680" => *self.bme680_plugin.lock().await = Some(load_bme680(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            "dashboard" => *self.dashboard_plugin.lock().await = Some(load_dashboard(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            "report" => *self.report_plugin.lock().await = Some(load_report(&self.engine, path, &self.config, &self.recent, &self.stats, &self.memory).await?),
+            other => anyhow::bail!("unknown plugin '{}'", other),
+        }
+        Ok(())
+    }
+
+    /// drop a plugin's running instance (plugins.{name}.enabled turned off,
+    /// quarantined, about to be re-instantiated). the store goes and with it the instance and
+    /// the component - the engine keeps no compiled code of its own, and
+    /// the linker only lived for the load - so its memory is unmapped.
+    pub async fn unload_plugin(&self, name: &str) -> Result<()> {
+        // a disabled plugin stays unloaded whatever happens to its .wasm
+        self.quarantined.lock().unwrap().remove(name);
+        let rss_before = crate::memory::process().rss_bytes;
+        let dropped = match name {
+            "dht22" => self.dht22_plugin.lock().await.take().is_some(),
+            "pi4-monitor" => self.pi4_monitor_plugin.lock().await.take().is_some(),
+            "revpi-monitor" => self.revpi_monitor_plugin.lock().await.take().is_some(),
+            "bme680" => self.bme680_plugin.lock().await.take().is_some(),
+            "dashboard" => self.dashboard_plugin.lock().await.take().is_some(),
+            "report" => self.report_plugin.lock().await.take().is_some(),
+            other => anyhow::bail!("unknown plugin '{}'", other),
         };
+        let released = self.memory.unregister(name);
+        if let (true, Some(released)) = (dropped, released) {
+            let mb = |bytes: u64| format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0));
+            let rss = match (rss_before, crate::memory::process().rss_bytes) {
+                (Some(before), Some(after)) => format!(", rss {} -> {}", mb(before), mb(after)),
+                _ => String::new(),
+            };
+            crate::log_msg(&format!(
+                "🧹 [RUNTIME] Unloaded '{}': released {} of linear memory and {} of code{}",
+                name, mb(released.linear_memory_bytes), mb(released.code_bytes), rss
+            ));
+        }
+        Ok(())
+    }
 
-        // 4. Dashboard Plugin
-        let dashboard_plugin = if config.plugins.dashboard.enabled {
-            println!("[DEBUG] Loading dashboard plugin...");
-            let path = path.join("plugins/dashboard/dashboard.wasm");
-            let comp = Component::from_file(&engine, &path).context("failed to load dashboard.wasm")?;
-            
-            let mut linker = Linker::new(&engine);
-            wasmtime_wasi::add_to_linker_async(&mut linker)?;
-            // Note: Dashboard only exports logic, no host imports needed in the linker
-            
-            let mut store = Store::new(&engine, create_host_state(config.clone(), config.cluster.node_id.clone()));
-            let inst = DashboardPlugin::instantiate_async(&mut store, &comp, &linker).await?;
-            Arc::new(Mutex::new(Some(PluginState { last_modified: SystemTime::now(), path, store, instance: inst })))
-        } else {
-            Arc::new(Mutex::new(None))
+    /// what the guest profiler recorded of a plugin since the last call, as
+    /// firefox profiler json (None: not profiled, not loaded or no samples yet)
+    pub async fn take_profile(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        async fn take<T>(slot: &PluginSlot<T>) -> Option<(wasmtime::GuestProfiler, u64)> {
+            slot.lock().await.as_mut()?.store.data_mut().profile.as_mut()?.take()
+        }
+        let taken = match name {
+            "dht22" => take(&self.dht22_plugin).await,
+            "pi4-monitor" => take(&self.pi4_monitor_plugin).await,
+            "revpi-monitor" => take(&self.revpi_monitor_plugin).await,
+            "bme680" => take(&self.bme680_plugin).await,
+            "dashboard" => take(&self.dashboard_plugin).await,
+            "report" => take(&self.report_plugin).await,
+            other => anyhow::bail!("unknown plugin '{}'", other),
         };
-        
-        Ok(Self {
-            engine,
-            config: config.clone(),
-            dht22_plugin,
-            pi4_monitor_plugin,
-            revpi_monitor_plugin,
-            dashboard_plugin,
-            bme680_plugin,
-            oled_plugin: Arc::new(Mutex::new(None)),
-        })
+        let Some((guest, samples)) = taken else {
+            return Ok(None);
+        };
+        let mut json = Vec::new();
+        guest.finish(&mut json)?;
+        crate::log_msg(&format!("🔬 [PROFILER] Handed out the profile of '{}' ({} samples), sampling a new one", name, samples));
+        Ok(Some(json))
     }
-    
-    pub async fn check_hot_reload(&self) {
-        // Since we have different types, we'll revert to individual checks to avoid type mismatch in a vector
-        self.check_plugin_reload("dht22", self.dht22_plugin.clone()).await;
-        self.check_plugin_reload_bme680("bme680", self.bme680_plugin.clone()).await;
-        // ... etc
+
+    /// install a new .wasm for a plugin and hot-reload it.
+    /// the previous file is kept as {name}.wasm.bak and restored if the new
+    /// component fails to instantiate, so disk always matches what's running.
+    pub async fn install_plugin(&self, name: &str, wasm: &[u8]) -> Result<()> {
+        if !KNOWN_PLUGINS.contains(&name) {
+            anyhow::bail!("unknown plugin '{}'", name);
+        }
+        if !wasm.starts_with(b"\0asm") {
+            anyhow::bail!("upload is not a wasm binary");
+        }
+
+        let path = self.plugin_path(name);
+        let tmp = path.with_extension("wasm.tmp");
+        let backup = path.with_extension("wasm.bak");
+        if let Some(dir) = path.parent() {
+            tokio::fs::create_dir_all(dir).await?;
+        }
+        tokio::fs::write(&tmp, wasm).await.context("failed to write plugin upload")?;
+        let had_previous = tokio::fs::rename(&path, &backup).await.is_ok();
+        tokio::fs::rename(&tmp, &path).await.context("failed to move plugin into place")?;
+
+        if let Err(e) = self.reload_plugin(name).await {
+            if had_previous {
+                let _ = tokio::fs::rename(&backup, &path).await;
+            }
+            return Err(e.context(format!("new {} plugin failed to load, previous version kept", name)));
+        }
+        Ok(())
     }
 
-    async fn check_plugin_reload<T>(&self, _name: &str, _plugin: Arc<Mutex<Option<PluginState<T>>>>) {
-        // Placeholder or implement generic reload logic if possible
+    /// names of the plugins currently loaded
+    pub async fn loaded_plugins(&self) -> Vec<String> {
+        let loaded = [
+            ("dht22", self.dht22_plugin.lock().await.is_some()),
+            ("pi4-monitor", self.pi4_monitor_plugin.lock().await.is_some()),
+            ("revpi-monitor", self.revpi_monitor_plugin.lock().await.is_some()),
+            ("bme680", self.bme680_plugin.lock().await.is_some()),
+            ("dashboard", self.dashboard_plugin.lock().await.is_some()),
+            ("report", self.report_plugin.lock().await.is_some()),
+        ];
+        loaded.into_iter().filter(|(_, is_loaded)| *is_loaded).map(|(name, _)| name.to_string()).collect()
     }
 
-    async fn check_plugin_reload_bme680(&self, _name: &str, _plugin: Arc<Mutex<Option<PluginState<Bme680Plugin>>>>) {
-        // ...
+    /// loaded state of every plugin without waiting for its lock
+    /// (None = busy, e.g. in the middle of a call). for the panic hook.
+    pub fn loaded_now(&self) -> Vec<(&'static str, Option<bool>)> {
+        vec![
+            ("dht22", self.dht22_plugin.try_lock().ok().map(|p| p.is_some())),
+            ("pi4-monitor", self.pi4_monitor_plugin.try_lock().ok().map(|p| p.is_some())),
+            ("revpi-monitor", self.revpi_monitor_plugin.try_lock().ok().map(|p| p.is_some())),
+            ("bme680", self.bme680_plugin.try_lock().ok().map(|p| p.is_some())),
+            ("dashboard", self.dashboard_plugin.try_lock().ok().map(|p| p.is_some())),
+            ("report", self.report_plugin.try_lock().ok().map(|p| p.is_some())),
+        ]
     }
-    
-    pub async fn poll_sensors(&self) -> Result<Vec<SensorReading>> {
-        let mut all_readings = Vec::new();
-
-        // 1. Poll DHT22
-        {
-            let mut guard = self.dht22_plugin.lock().await;
-            if let Some(plugin) = guard.as_mut() {
-                if let Ok(readings) = plugin.instance.demo_plugin_dht22_logic().call_poll(&mut plugin.store).await {
-                    all_readings.extend(readings.into_iter().map(|r| SensorReading {
-                        sensor_id: r.sensor_id,
-                        timestamp_ms: r.timestamp_ms,
-                        data: serde_json::json!({ "temperature": r.temperature, "humidity": r.humidity }),
-                    }));
-                }
+
+    /// reload any loaded plugin whose .wasm file changed on disk.
+    /// returns (plugin, result) for each reload attempted.
+    pub async fn check_hot_reload(&self) -> Vec<(&'static str, Result<()>)> {
+        let stale = [
+            ("dht22", is_stale(&self.dht22_plugin).await),
+            ("pi4-monitor", is_stale(&self.pi4_monitor_plugin).await),
+            ("revpi-monitor", is_stale(&self.revpi_monitor_plugin).await),
+            ("bme680", is_stale(&self.bme680_plugin).await),
+            ("dashboard", is_stale(&self.dashboard_plugin).await),
+            ("report", is_stale(&self.report_plugin).await),
+        ];
+        // quarantined plugins have no instance to compare with - the .wasm
+        // replaced since they were unloaded brings them back
+        let replaced: Vec<&'static str> = self
+            .quarantined
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(name, unloaded)| modified(&self.plugin_path(name)).is_some_and(|t| t > **unloaded))
+            .map(|(name, _)| *name)
+            .collect();
+        let mut results = Vec::new();
+        for (name, changed) in stale {
+            if changed || replaced.contains(&name) {
+                results.push((name, self.reload_plugin(name).await));
             }
         }
+        results
+    }
+    
+    /// strikes / quarantine of every plugin
+    pub fn watchdog(&self) -> &Watchdog {
+        &self.watchdog
+    }
 
-        // 2. Poll BME680
-        {
-            let mut guard = self.bme680_plugin.lock().await;
-            if let Some(plugin) = guard.as_mut() {
-                if let Ok(readings) = plugin.instance.demo_plugin_bme680_logic().call_poll(&mut plugin.store).await {
-                    all_readings.extend(readings.into_iter().map(|r| SensorReading {
-                        sensor_id: r.sensor_id,
-                        timestamp_ms: r.timestamp_ms,
-                        data: serde_json::json!({ 
-                            "temperature": r.temperature, 
-                            "humidity": r.humidity,
-                            "pressure": r.pressure,
-                            "gas_resistance": r.gas_resistance,
-                            "iaq_score": r.iaq_score
-                        }),
-                    }));
-                }
+    /// call counters, errors and latencies of every plugin
+    pub fn stats(&self) -> &PluginStats {
+        &self.stats
+    }
+
+    /// memory usage of every plugin store
+    pub fn memory(&self) -> &MemoryUsage {
+        &self.memory
+    }
+
+    /// run an export of a loaded plugin on its worker (None = not loaded):
+    /// plugin.call span, call counters and latency, the watchdog deadline
+    async fn call<T, R, F>(&self, plugin: &'static str, function: &'static str, slot: &PluginSlot<T>, call: F) -> Option<Result<R>>
+    where
+        T: Send + 'static,
+        R: Send + 'static,
+        F: for<'a> FnOnce(&'a mut PluginState<T>) -> Pin<Box<dyn Future<Output = Result<R>> + Send + 'a>> + Send + 'static,
+    {
+        let slot = slot.clone();
+        let stats = self.stats.clone();
+        let deadline = self.watchdog.deadline_ticks();
+        let runtime = self.clone();
+        let ran = self.workers[plugin].run(async move {
+            let result = {
+                let mut guard = slot.lock().await;
+                let loaded = guard.as_mut()?;
+                crate::profiler::set_deadline(&mut loaded.store, deadline);
+                let started = std::time::Instant::now();
+                let result = telemetry::plugin_call(plugin, function, call(loaded)).await;
+                stats.record(plugin, function, started.elapsed(), result.as_ref().err());
+                result
+            };
+            // here rather than in the caller: a poll past its budget has
+            // stopped waiting, the watchdog's verdict must not go with it
+            runtime.settle(plugin, function, result.as_ref().err()).await;
+            Some(result)
+        });
+        ran.await.unwrap_or_else(|e| Some(Err(e)))
+    }
+
+    /// what a call's outcome means for the instance, on the plugin's worker
+    /// right after the call
+    async fn settle(&self, plugin: &'static str, function: &str, error: Option<&anyhow::Error>) {
+        let verdict = match error.map(|e| (e, e.downcast_ref::<wasmtime::Trap>())) {
+            None => {
+                self.watchdog.returned(plugin);
+                Verdict::Fine
+            }
+            Some((_, Some(wasmtime::Trap::Interrupt))) => self.watchdog.killed(plugin),
+            // poisoned by a trap without a verdict - it can't be entered again
+            Some((_, Some(wasmtime::Trap::CannotEnterComponent))) => {
+                crate::log_msg(&format!("⚠️ [PLUGIN] '{}' can't be entered again, re-instantiating it", plugin));
+                Verdict::Restart
+            }
+            // a trapped instance can't be entered again - a fresh one, until
+            // it has trapped `strikes` times in a row
+            Some((e, Some(_))) => {
+                crate::log_msg(&format!(
+                    "❌ [PLUGIN] '{}' trapped in {}: {} (see /api/plugins/{}/stats)",
+                    plugin, function, e.root_cause(), plugin
+                ));
+                self.watchdog.trapped(plugin)
+            }
+            Some((_, None)) => Verdict::Fine,
+        };
+        match verdict {
+            Verdict::Fine => {}
+            // calls queued behind this one find the plugin unloaded rather
+            // than dead; compiling it again goes on the same worker after them
+            Verdict::Restart => {
+                let _ = self.unload_plugin(plugin).await;
+                let runtime = self.clone();
+                let worker = self.workers[plugin].clone();
+                tokio::spawn(async move {
+                    match worker.run(async move { runtime.instantiate(plugin).await }).await.and_then(|r| r) {
+                        Ok(()) => crate::log_msg(&format!("🔄 [WATCHDOG] Restarted '{}'", plugin)),
+                        Err(e) => crate::log_msg(&format!("❌ [WATCHDOG] Could not restart '{}': {:#}", plugin, e)),
+                    }
+                });
+            }
+            Verdict::Quarantine => {
+                let _ = self.unload_plugin(plugin).await;
+                let unloaded = modified(&self.plugin_path(plugin)).unwrap_or_else(SystemTime::now);
+                self.quarantined.lock().unwrap().insert(plugin, unloaded);
             }
         }
+    }
 
-        // 3. Poll Pi Monitor (Pi4)
-        {
-            let mut guard = self.pi4_monitor_plugin.lock().await;
-            if let Some(plugin) = guard.as_mut() {
-                if let Ok(stats) = plugin.instance.demo_plugin_pi_monitor_logic().call_poll(&mut plugin.store).await {
-                    all_readings.push(SensorReading {
-                        sensor_id: "pi4-monitor".to_string(),
-                        timestamp_ms: stats.timestamp_ms,
-                        data: serde_json::json!({
-                            "cpu_temp": stats.cpu_temp,
-                            "cpu_usage": stats.cpu_usage,
-                            "memory_used_mb": stats.memory_used_mb,
-                            "memory_total_mb": stats.memory_total_mb,
-                            "uptime_seconds": stats.uptime_seconds,
-                            "fan_on": stats.fan_on,
-                        }),
-                    });
-                }
+    /// sensor ids of the plugins backoff skipped this cycle (backoff.rs)
+    pub fn backed_off_sensors(&self) -> Vec<String> {
+        self.backoff.resting_sensors()
+    }
+
+    /// poll every sensor plugin at once. a plugin that hasn't answered when
+    /// `budget` is up misses this cycle - its readings are left out (the
+    /// previous ones stay in the state) and the rest go out without it.
+    pub async fn poll_sensors(&self, budget: std::time::Duration) -> Result<Vec<SensorReading>> {
+        let deadline = tokio::time::Instant::now() + budget;
+        let (dht22, bme680, pi4_monitor, revpi_monitor) = tokio::join!(
+            self.within(deadline, "dht22", self.poll_dht22()),
+            self.within(deadline, "bme680", self.poll_bme680()),
+            self.within(deadline, "pi4-monitor", self.poll_pi4_monitor()),
+            self.within(deadline, "revpi-monitor", self.poll_revpi_monitor()),
+        );
+        Ok([dht22, bme680, pi4_monitor, revpi_monitor].concat())
+    }
+
+    /// the readings of a plugin poll, or none when it misses `deadline`.
+    /// its worker finishes the call; the next poll queues behind it.
+    async fn within(&self, deadline: tokio::time::Instant, plugin: &'static str, poll: impl Future<Output = Vec<SensorReading>>) -> Vec<SensorReading> {
+        match tokio::time::timeout_at(deadline, poll).await {
+            Ok(readings) => {
+                self.backoff.reported(plugin, readings.iter().map(|r| r.sensor_id.clone()).collect());
+                readings
+            }
+            Err(_) => {
+                self.stats.miss(plugin);
+                self.backoff.record(plugin, false);
+                crate::log_msg(&format!("⏱️ [POLL] '{}' missed the poll budget, keeping its previous readings", plugin));
+                Vec::new()
             }
         }
+    }
 
-        // 4. Poll Pi Monitor (RevPi)
-        {
-            let mut guard = self.revpi_monitor_plugin.lock().await;
-            if let Some(plugin) = guard.as_mut() {
-                if let Ok(stats) = plugin.instance.demo_plugin_pi_monitor_logic().call_poll(&mut plugin.store).await {
-                    all_readings.push(SensorReading {
-                        sensor_id: "revpi-monitor".to_string(),
-                        timestamp_ms: stats.timestamp_ms,
-                        data: serde_json::json!({
-                            "cpu_temp": stats.cpu_temp,
-                            "cpu_usage": stats.cpu_usage,
-                            "memory_used_mb": stats.memory_used_mb,
-                            "memory_total_mb": stats.memory_total_mb,
-                            "uptime_seconds": stats.uptime_seconds,
-                            "fan_on": stats.fan_on,
-                        }),
-                    });
-                }
-            }
+    async fn poll_dht22(&self) -> Vec<SensorReading> {
+        if !self.backoff.due("dht22") {
+            return Vec::new();
         }
+        let polled = self.call("dht22", "poll", &self.dht22_plugin, |plugin| {
+            Box::pin(plugin.instance.demo_plugin_dht22_logic().call_poll(&mut plugin.store))
+        });
+        let Some(polled) = polled.await else { return Vec::new() };
+        // a sensor that answers with nothing (dht22 timeout) failed too
+        self.backoff.record("dht22", polled.as_ref().is_ok_and(|r| !r.is_empty()));
+        polled
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| SensorReading {
+                sensor_id: r.sensor_id,
+                metadata: None,
+                quality: Some(r.quality.into()),
+                timestamp_ms: r.timestamp_ms,
+                data: serde_json::json!({ "temperature": r.temperature, "humidity": r.humidity }),
+            })
+            .collect()
+    }
 
-        Ok(all_readings)
+    async fn poll_bme680(&self) -> Vec<SensorReading> {
+        if !self.backoff.due("bme680") {
+            return Vec::new();
+        }
+        let polled = self.call("bme680", "poll", &self.bme680_plugin, |plugin| {
+            Box::pin(plugin.instance.demo_plugin_bme680_logic().call_poll(&mut plugin.store))
+        });
+        let Some(polled) = polled.await else { return Vec::new() };
+        // a sensor that answers with nothing (dht22 timeout) failed too
+        self.backoff.record("bme680", polled.as_ref().is_ok_and(|r| !r.is_empty()));
+        polled
+            .unwrap_or_default()
+            .into_iter()
+            .map(|r| SensorReading {
+                sensor_id: r.sensor_id,
+                metadata: None,
+                quality: Some(r.quality.into()),
+                timestamp_ms: r.timestamp_ms,
+                data: serde_json::json!({ 
+                    "temperature": r.temperature, 
+                    "humidity": r.humidity,
+                    "pressure": r.pressure,
+                    "gas_resistance": r.gas_resistance,
+                    "iaq_score": r.iaq_score
+                }),
+            })
+            .collect()
     }
-    
+
+    async fn poll_pi4_monitor(&self) -> Vec<SensorReading> {
+        if !self.backoff.due("pi4-monitor") {
+            return Vec::new();
+        }
+        let polled = self.call("pi4-monitor", "poll", &self.pi4_monitor_plugin, |plugin| {
+            Box::pin(plugin.instance.demo_plugin_pi_monitor_logic().call_poll(&mut plugin.store))
+        });
+        let Some(polled) = polled.await else { return Vec::new() };
+        self.backoff.record("pi4-monitor", polled.is_ok());
+        polled
+            .map(|stats| SensorReading {
+                sensor_id: "pi4-monitor".to_string(),
+                metadata: None,
+                quality: Some(stats.quality.into()),
+                timestamp_ms: stats.timestamp_ms,
+                data: serde_json::json!({
+                    "cpu_temp": stats.cpu_temp,
+                    "cpu_usage": stats.cpu_usage,
+                    "memory_used_mb": stats.memory_used_mb,
+                    "memory_total_mb": stats.memory_total_mb,
+                    "uptime_seconds": stats.uptime_seconds,
+                    "fan_on": stats.fan_on,
+                }),
+            })
+            .into_iter()
+            .collect()
+    }
+
+    async fn poll_revpi_monitor(&self) -> Vec<SensorReading> {
+        if !self.backoff.due("revpi-monitor") {
+            return Vec::new();
+        }
+        let polled = self.call("revpi-monitor", "poll", &self.revpi_monitor_plugin, |plugin| {
+            Box::pin(plugin.instance.demo_plugin_pi_monitor_logic().call_poll(&mut plugin.store))
+        });
+        let Some(polled) = polled.await else { return Vec::new() };
+        self.backoff.record("revpi-monitor", polled.is_ok());
+        polled
+            .map(|stats| SensorReading {
+                sensor_id: "revpi-monitor".to_string(),
+                metadata: None,
+                quality: Some(stats.quality.into()),
+                timestamp_ms: stats.timestamp_ms,
+                data: serde_json::json!({
+                    "cpu_temp": stats.cpu_temp,
+                    "cpu_usage": stats.cpu_usage,
+                    "memory_used_mb": stats.memory_used_mb,
+                    "memory_total_mb": stats.memory_total_mb,
+                    "uptime_seconds": stats.uptime_seconds,
+                    "fan_on": stats.fan_on,
+                }),
+            })
+            .into_iter()
+            .collect()
+    }
+
     pub async fn render_dashboard(&self, json_data: String) -> Result<String> {
-        let mut guard = self.dashboard_plugin.lock().await;
-        if let Some(plugin) = guard.as_mut() {
-            plugin.instance.demo_plugin_dashboard_logic()
-                .call_render(&mut plugin.store, &json_data).await
-                .map_err(|e| anyhow::anyhow!("Dashboard render failed: {}", e))
-        } else {
-            Ok("<h1 style='color:red'>Dashboard Plugin Not Loaded</h1>".to_string())
+        let rendered = self.call("dashboard", "render", &self.dashboard_plugin, move |plugin| {
+            Box::pin(async move { plugin.instance.demo_plugin_dashboard_logic().call_render(&mut plugin.store, &json_data).await })
+        });
+        match rendered.await {
+            Some(html) => html.map_err(|e| anyhow::anyhow!("Dashboard render failed: {}", e)),
+            None => Ok("<h1 style='color:red'>Dashboard Plugin Not Loaded</h1>".to_string()),
         }
     }
+
+    /// render a summary report with the report plugin (None = not loaded)
+    pub async fn render_report(&self, json_data: &str) -> Result<Option<String>> {
+        let json_data = json_data.to_string();
+        let rendered = self.call("report", "render", &self.report_plugin, move |plugin| {
+            Box::pin(async move { plugin.instance.demo_plugin_report_logic().call_render(&mut plugin.store, &json_data).await })
+        });
+        rendered
+            .await
+            .transpose()
+            .map_err(|e| anyhow::anyhow!("Report render failed: {}", e))
+    }
 }
 
 
@@ -773,7 +1231,7 @@ impl bme680_bindings::demo::plugin::buzzer_controller::Host for HostState {
 
 impl bme680_bindings::demo::plugin::i2c::Host for HostState {
     async fn transfer(&mut self, addr: u8, write_data: String, read_len: u32) -> Result<String, String> {
-        let hal = crate::hal::Hal::new();
+        let hal = self.hal.clone();
         use crate::hal::HardwareProvider;
         let data = hex::decode(write_data).map_err(|e| e.to_string())?;
         
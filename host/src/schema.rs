@@ -0,0 +1,126 @@
+//! ==============================================================================
+//! schema.rs - sensor field metadata (unit, display name, valid range)
+//! ==============================================================================
+//!
+//! purpose:
+//!     readings are free-form json, so consumers had to guess that
+//!     "temperature" is celsius and "pressure" hectopascal. the registry
+//!     describes each field once and the readings api attaches the matching
+//!     entries to every reading:
+//!         {"sensor_id": "pi4:dht22", "data": {"temperature": 21.5, ...},
+//!          "schema": {"temperature": {"unit": "°C", "display_name": "Temperature",
+//!                                     "min": -40.0, "max": 80.0, "max_step": 10.0}}}
+//!
+//! lookup:
+//!     entries are keyed by field name ("temperature") or by sensor and field
+//!     ("bme680.temperature", sensor_id without the node prefix); the
+//!     sensor-specific entry wins. built-in entries cover the fields of the
+//!     bundled plugins; [schema.*] tables in host.toml add or refine them.
+//!
+//! relationships:
+//!     - used by: main.rs (/api/readings, /api/schema), validate.rs (ranges / steps), units.rs
+//!     - reads: config.rs (HostConfig.schema, FieldSchema)
+//!
+//! ==============================================================================
+
+use crate::config::FieldSchema;
+use crate::domain::SensorReading;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+pub struct SchemaRegistry {
+    fields: BTreeMap<String, FieldSchema>,
+}
+
+/// a reading plus the schema of its fields, as served by /api/readings
+#[derive(Serialize)]
+pub struct AnnotatedReading<'a> {
+    #[serde(flatten)]
+    pub reading: &'a SensorReading,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub schema: BTreeMap<&'a str, &'a FieldSchema>,
+}
+
+impl SchemaRegistry {
+    /// built-in entries overlaid with the configured ones. a configured
+    /// entry only replaces the attributes it sets, so `[schema.humidity]
+    /// unit = "%RH"` keeps the built-in display name and range.
+    pub fn new(configured: &BTreeMap<String, FieldSchema>) -> Self {
+        let mut fields = builtin();
+        for (key, schema) in configured {
+            let entry = fields.entry(key.clone()).or_insert_with(|| schema.clone());
+            if !schema.unit.is_empty() {
+                entry.unit = schema.unit.clone();
+            }
+            if !schema.display_name.is_empty() {
+                entry.display_name = schema.display_name.clone();
+            }
+            entry.min = schema.min.or(entry.min);
+            entry.max = schema.max.or(entry.max);
+            entry.max_step = schema.max_step.or(entry.max_step);
+        }
+        Self { fields }
+    }
+
+    /// the registry with every entry passed through `f` (units.rs display copy)
+    pub fn map(&self, f: impl Fn(&FieldSchema) -> FieldSchema) -> Self {
+        Self { fields: self.fields.iter().map(|(key, schema)| (key.clone(), f(schema))).collect() }
+    }
+
+    pub fn all(&self) -> &BTreeMap<String, FieldSchema> {
+        &self.fields
+    }
+
+    /// schema of one field of a sensor ("node:sensor" or plain "sensor")
+    pub fn lookup(&self, sensor_id: &str, field: &str) -> Option<&FieldSchema> {
+        let sensor = sensor_id.split_once(':').map_or(sensor_id, |(_, sensor)| sensor);
+        self.fields.get(&format!("{}.{}", sensor, field)).or_else(|| self.fields.get(field))
+    }
+
+    pub fn annotate<'a>(&'a self, readings: &'a [SensorReading]) -> Vec<AnnotatedReading<'a>> {
+        readings
+            .iter()
+            .map(|reading| {
+                let schema = reading
+                    .data
+                    .as_object()
+                    .into_iter()
+                    .flat_map(|fields| fields.keys())
+                    .filter_map(|field| Some((field.as_str(), self.lookup(&reading.sensor_id, field)?)))
+                    .collect();
+                AnnotatedReading { reading, schema }
+            })
+            .collect()
+    }
+}
+
+/// fields produced by the bundled plugins (dht22, bme680, pi/revpi monitor)
+/// and by derived.rs
+fn builtin() -> BTreeMap<String, FieldSchema> {
+    let entry = |unit: &str, name: &str, min: Option<f64>, max: Option<f64>| FieldSchema {
+        unit: unit.to_string(),
+        display_name: name.to_string(),
+        min,
+        max,
+        max_step: None,
+    };
+    [
+        ("temperature", FieldSchema { max_step: Some(10.0), ..entry("°C", "Temperature", Some(-40.0), Some(85.0)) }),
+        ("humidity", entry("%", "Relative humidity", Some(0.0), Some(100.0))),
+        ("pressure", entry("hPa", "Pressure", Some(300.0), Some(1100.0))),
+        ("gas_resistance", entry("Ω", "Gas resistance", Some(0.0), None)),
+        ("iaq_score", entry("", "Air quality index", Some(0.0), Some(500.0))),
+        ("cpu_temp", entry("°C", "CPU temperature", Some(0.0), Some(110.0))),
+        ("cpu_usage", entry("%", "CPU usage", Some(0.0), Some(100.0))),
+        ("memory_used_mb", entry("MB", "Memory used", Some(0.0), None)),
+        ("memory_total_mb", entry("MB", "Memory total", Some(0.0), None)),
+        ("uptime_seconds", entry("s", "Uptime", Some(0.0), None)),
+        ("fan_on", entry("", "Fan running", None, None)),
+        ("dew_point", entry("°C", "Dew point", None, None)),
+        ("heat_index", entry("°C", "Heat index", None, None)),
+        ("absolute_humidity", entry("g/m³", "Absolute humidity", Some(0.0), None)),
+    ]
+    .into_iter()
+    .map(|(key, schema)| (key.to_string(), schema))
+    .collect()
+}
@@ -0,0 +1,146 @@
+//! ==============================================================================
+//! secrets.rs - ${NAME} references in the config, resolved at load time
+//! ==============================================================================
+//!
+//! purpose:
+//!     hub push tokens, mqtt / kafka passwords and webhook urls don't belong
+//!     in a host.toml that is committed to git. any string value in the
+//!     config may reference a secret instead:
+//!         [cluster]
+//!         auth_token = "${HUB_TOKEN}"
+//!         [[sinks]]
+//!         url = "https://hooks.example.com/${SINK_PATH}"
+//!     a name is looked up in the environment first, then in the secrets
+//!     file. a reference that resolves nowhere fails the load, naming the
+//!     key. "$${" is a literal "${".
+//!
+//! secrets file:
+//!     a flat toml table of strings (HUB_TOKEN = "..."), by default
+//!     secrets.toml next to host.toml and only read if it exists; another
+//!     path can be set with [secrets] file = "..." (relative to host.toml,
+//!     must exist then). on unix the file must not be readable by group or
+//!     others - a secrets file anyone can read is refused (chmod 600).
+//!
+//! relationships:
+//!     - used by: config.rs (load_toml, after the overlay and HOST__ overrides)
+//!     - used by: config_reload.rs (changes are diffed before resolving, so
+//!       resolved secrets never show up in the reload log)
+//!
+//! ==============================================================================
+
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// secrets.toml next to the config file
+const DEFAULT_FILE: &str = "secrets.toml";
+
+/// replace every ${NAME} in the string values of `config`
+pub fn resolve(config: &mut toml::Value, config_path: &Path) -> anyhow::Result<()> {
+    let (path, required) = secrets_path(config, config_path);
+    let secrets = match path.exists() || required {
+        true => load(&path)?,
+        false => BTreeMap::new(),
+    };
+    let lookup = |name: &str| std::env::var(name).ok().or_else(|| secrets.get(name).cloned());
+    let missing = |key: &str, name: &str| {
+        anyhow::anyhow!("{}: ${{{}}} is not set in the environment or {}", key, name, path.display())
+    };
+    walk(config, "", &lookup, &missing)
+}
+
+/// [secrets] file, or the default; true when it was named explicitly
+fn secrets_path(config: &toml::Value, config_path: &Path) -> (PathBuf, bool) {
+    let dir = config_path.parent().unwrap_or(Path::new("."));
+    match config.get("secrets").and_then(|s| s.get("file")).and_then(|f| f.as_str()) {
+        Some(file) if !file.is_empty() => (dir.join(file), true),
+        _ => (dir.join(DEFAULT_FILE), false),
+    }
+}
+
+/// NAME -> value from the secrets file, refusing one others can read
+fn load(path: &Path) -> anyhow::Result<BTreeMap<String, String>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)
+            .with_context(|| format!("secrets file {}", path.display()))?
+            .permissions()
+            .mode();
+        if mode & 0o077 != 0 {
+            anyhow::bail!(
+                "secrets file {} is accessible by group / others (mode {:o}), restrict it with chmod 600",
+                path.display(),
+                mode & 0o777
+            );
+        }
+    }
+    let content = std::fs::read_to_string(path).with_context(|| format!("secrets file {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&content).with_context(|| format!("secrets file {}", path.display()))?;
+    table
+        .into_iter()
+        .map(|(name, value)| match value {
+            toml::Value::String(s) => Ok((name, s)),
+            _ => anyhow::bail!("secrets file {}: {} is not a string", path.display(), name),
+        })
+        .collect()
+}
+
+/// resolve the strings of one value, `key` is its path for error messages
+fn walk(
+    value: &mut toml::Value,
+    key: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    missing: &dyn Fn(&str, &str) -> anyhow::Error,
+) -> anyhow::Result<()> {
+    match value {
+        toml::Value::String(s) if s.contains("${") => *s = interpolate(s, key, lookup, missing)?,
+        toml::Value::Table(table) => {
+            for (name, value) in table.iter_mut() {
+                let path = match key {
+                    "" => name.clone(),
+                    _ => format!("{}.{}", key, name),
+                };
+                walk(value, &path, lookup, missing)?;
+            }
+        }
+        toml::Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                walk(item, &format!("{}[{}]", key, i), lookup, missing)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// "Bearer ${TOKEN}" -> "Bearer abc"; "$${" stays a literal "${"
+fn interpolate(
+    template: &str,
+    key: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    missing: &dyn Fn(&str, &str) -> anyhow::Error,
+) -> anyhow::Result<String> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("${") {
+        if let Some(before) = rest[..start].strip_suffix('$') {
+            out.push_str(before);
+            out.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('}') else {
+            anyhow::bail!("{}: unterminated ${{...}} in '{}'", key, template);
+        };
+        let name = &rest[start + 2..start + end];
+        if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("{}: '{}' is not a secret name (letters, digits and _)", key, name);
+        }
+        out.push_str(&lookup(name).ok_or_else(|| missing(key, name))?);
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
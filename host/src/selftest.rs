@@ -0,0 +1,230 @@
+//! ==============================================================================
+//! selftest.rs - startup hardware self-test (GET /api/selftest)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a sensor on a loose wire or a missing python library doesn't stop
+//!     the host - the plugin just reports errors or zeros, and finding out
+//!     why takes an ssh session. before polling starts, everything the
+//!     config says this node drives is probed once:
+//!         i2c      the device at an address answers a one byte read
+//!                  (bme680 plugin, sensors.bme680.i2c_address)
+//!         gpio     the pin can be claimed through /dev/gpiomem
+//!                  (dht22 plugin, buzzer, fan)
+//!         python   the modules the python drivers import are installed
+//!                  (adafruit_dht + board for the dht22, RPi.GPIO for the
+//!                  buzzer, rpi_ws281x for the led strip)
+//!         leds     the strip driver loads with the privileges sync_leds
+//!                  uses (sudo -n python3). a ws2812b strip has no return
+//!                  line, so this is as close to "reachable" as it gets.
+//!     the results are logged as a pass / fail table and served at
+//!     GET /api/selftest. a failing check is only reported, the host
+//!     starts anyway.
+//!
+//! mock hal:
+//!     without the "hardware" feature nothing touches real hardware, so
+//!     every check is listed as skipped.
+//!
+//! relationships:
+//!     - used by: main.rs (startup, /api/selftest)
+//!     - reads: config.rs (SelfTestConfig, sensors / buzzer / fan / plugins)
+//!     - mirrors: hal.rs (what the real HAL needs to work)
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use crate::log_msg;
+use serde::Serialize;
+#[cfg(feature = "hardware")]
+use std::time::Duration;
+
+/// how long a python import check may take (a cold pi zero is slow)
+#[cfg(feature = "hardware")]
+const PYTHON_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Pass,
+    Fail,
+    Skip,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Check {
+    /// "i2c", "gpio", "python" or "leds"
+    pub kind: &'static str,
+    /// what was probed ("0x77", "pin 17", "RPi.GPIO")
+    pub target: String,
+    /// what needs it ("bme680 plugin", "buzzer")
+    pub used_by: String,
+    pub status: Status,
+    pub detail: String,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SelfTestReport {
+    pub timestamp_ms: u64,
+    /// false on the mock hal (everything skipped)
+    pub hardware: bool,
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    pub checks: Vec<Check>,
+}
+
+/// something this node's config says it drives
+struct Probe {
+    kind: &'static str,
+    target: String,
+    used_by: String,
+    #[cfg_attr(not(feature = "hardware"), allow(dead_code))]
+    how: How,
+}
+
+#[cfg_attr(not(feature = "hardware"), allow(dead_code))]
+enum How {
+    I2c(u8),
+    Gpio(u8),
+    Python(&'static [&'static str]),
+    LedStrip,
+}
+
+/// run every check that applies to this config and log the table
+pub async fn run(config: &HostConfig) -> SelfTestReport {
+    let mut checks = Vec::new();
+    for probe in probes(config) {
+        let (status, detail) = check(&probe.how).await;
+        checks.push(Check { kind: probe.kind, target: probe.target, used_by: probe.used_by, status, detail });
+    }
+    let count = |status| checks.iter().filter(|c| c.status == status).count();
+    let report = SelfTestReport {
+        timestamp_ms: crate::domain::now_ms(),
+        hardware: cfg!(feature = "hardware"),
+        passed: count(Status::Pass),
+        failed: count(Status::Fail),
+        skipped: count(Status::Skip),
+        checks,
+    };
+    log_table(&report);
+    report
+}
+
+fn probes(config: &HostConfig) -> Vec<Probe> {
+    let probe = |kind, target: String, used_by: &str, how| Probe { kind, target, used_by: used_by.to_string(), how };
+    let mut probes = Vec::new();
+    if config.plugin_enabled("dht22") {
+        let pin = config.sensors.dht22.gpio_pin;
+        probes.push(probe("gpio", format!("pin {}", pin), "dht22 plugin", How::Gpio(pin)));
+        probes.push(probe("python", "adafruit_dht, board".to_string(), "dht22 plugin", How::Python(&["adafruit_dht", "board"])));
+    }
+    if config.plugin_enabled("bme680") {
+        let address = &config.sensors.bme680.i2c_address;
+        let parsed = match address.strip_prefix("0x") {
+            Some(hex) => u8::from_str_radix(hex, 16).ok(),
+            None => address.parse().ok(),
+        };
+        probes.push(probe("i2c", address.clone(), "bme680 plugin", How::I2c(parsed.unwrap_or(0x77))));
+    }
+    // host hardware this build has no driver for isn't probed
+    if cfg!(feature = "leds") {
+        probes.push(probe("leds", format!("{} leds", config.leds.count), "led strip", How::LedStrip));
+    }
+    if cfg!(feature = "buzzer") {
+        probes.push(probe("gpio", format!("pin {}", config.buzzer.gpio_pin), "buzzer", How::Gpio(config.buzzer.gpio_pin)));
+        probes.push(probe("python", "RPi.GPIO".to_string(), "buzzer", How::Python(&["RPi.GPIO"])));
+    }
+    if cfg!(feature = "fan") {
+        probes.push(probe("gpio", format!("pin {}", config.fan.gpio_pin), "fan", How::Gpio(config.fan.gpio_pin)));
+    }
+    probes
+}
+
+#[cfg(not(feature = "hardware"))]
+async fn check(_how: &How) -> (Status, String) {
+    (Status::Skip, "mock HAL (built without the hardware feature)".to_string())
+}
+
+#[cfg(feature = "hardware")]
+async fn check(how: &How) -> (Status, String) {
+    let result = match how {
+        How::I2c(address) => {
+            let address = *address;
+            tokio::task::spawn_blocking(move || probe_i2c(address)).await.unwrap_or_else(|e| Err(e.into()))
+        }
+        How::Gpio(pin) => probe_gpio(*pin),
+        How::Python(modules) => python_imports(false, modules).await,
+        How::LedStrip => python_imports(true, &["rpi_ws281x"]).await,
+    };
+    match result {
+        Ok(detail) => (Status::Pass, detail),
+        Err(e) => (Status::Fail, format!("{:#}", e)),
+    }
+}
+
+#[cfg(feature = "hardware")]
+fn probe_i2c(address: u8) -> anyhow::Result<String> {
+    use rppal::i2c::I2c;
+    let mut i2c = I2c::new().map_err(|e| anyhow::anyhow!("cannot open the I2C bus: {}", e))?;
+    i2c.set_slave_address(address as u16)?;
+    let mut byte = [0u8; 1];
+    i2c.read(&mut byte).map_err(|e| anyhow::anyhow!("no answer from 0x{:02x}: {}", address, e))?;
+    Ok(format!("bus {} answered", i2c.bus()))
+}
+
+#[cfg(feature = "hardware")]
+fn probe_gpio(pin: u8) -> anyhow::Result<String> {
+    use rppal::gpio::Gpio;
+    let gpio = Gpio::new().map_err(|e| anyhow::anyhow!("cannot open GPIO: {}", e))?;
+    // dropping the unconfigured pin releases it again
+    gpio.get(pin)?;
+    Ok("claimable".to_string())
+}
+
+/// import the modules in a python3 subprocess (as root via sudo -n if `sudo`)
+#[cfg(feature = "hardware")]
+async fn python_imports(sudo: bool, modules: &[&str]) -> anyhow::Result<String> {
+    let script = format!("import {}", modules.join(", "));
+    let mut command = match sudo {
+        true => {
+            let mut command = tokio::process::Command::new("sudo");
+            command.args(["-n", "python3"]);
+            command
+        }
+        false => tokio::process::Command::new("python3"),
+    };
+    command.args(["-c", &script]).kill_on_drop(true);
+    let output = tokio::time::timeout(PYTHON_TIMEOUT, command.output())
+        .await
+        .map_err(|_| anyhow::anyhow!("import timed out after {}s", PYTHON_TIMEOUT.as_secs()))?
+        .map_err(|e| anyhow::anyhow!("cannot run {}: {}", if sudo { "sudo" } else { "python3" }, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        // "ModuleNotFoundError: No module named 'board'" is the last line of the traceback
+        let reason = stderr.lines().rev().find(|l| !l.trim().is_empty()).unwrap_or("exit status").trim();
+        anyhow::bail!("{}", reason);
+    }
+    Ok(match sudo {
+        true => "driver loads as root".to_string(),
+        false => "installed".to_string(),
+    })
+}
+
+fn log_table(report: &SelfTestReport) {
+    if !report.hardware {
+        log_msg(&format!("[SELFTEST] Mock HAL, {} hardware checks skipped", report.skipped));
+        return;
+    }
+    log_msg(&format!("[SELFTEST] {} passed, {} failed", report.passed, report.failed));
+    for check in &report.checks {
+        let mark = match check.status {
+            Status::Pass => "✅",
+            Status::Fail => "❌",
+            Status::Skip => "➖",
+        };
+        log_msg(&format!(
+            "{} [SELFTEST] {:<6} {:<20} {:<14} {}",
+            mark, check.kind, check.target, check.used_by, check.detail
+        ));
+    }
+}
@@ -0,0 +1,224 @@
+//! ==============================================================================
+//! sink.rs - generic http forwarding of readings ([[sinks]])
+//! ==============================================================================
+//!
+//! purpose:
+//!     a custom cloud ingest shouldn't need to pretend to be a hub. every
+//!     [[sinks]] entry POSTs the merged readings (the hub sees the whole
+//!     cluster) to an arbitrary http endpoint as json:
+//!         {"node_id": "pi4", "sink": "cloud", "readings": [{sensor_id, timestamp_ms, data, ...}]}
+//!     readings can be narrowed with a sensor_id glob (sensors = "*:bme680").
+//!
+//! templates:
+//!     url and header values may contain
+//!         {node_id}    this node's cluster.node_id
+//!         {name}       the sink's name
+//!         {date}       the utc date of the request (YYYY-MM-DD)
+//!         {env:VAR}    an environment variable, so tokens stay out of host.toml
+//!     a missing variable disables the sink at startup.
+//!
+//! batching and retry:
+//!     readings are buffered and posted every flush_seconds or once
+//!     batch_size are waiting. a failed post (network error or non-2xx)
+//!     is retried with exponential backoff from retry.initial_backoff_ms
+//!     up to retry.max_backoff_seconds; after retry.max_attempts tries the
+//!     batch is dropped (0 = keep it). past max_buffer readings the oldest
+//!     are dropped so a dead endpoint can't exhaust memory.
+//!
+//! relationships:
+//!     - used by: domain.rs (AppState.sinks), main.rs (startup)
+//!     - reads: config.rs (HttpSinkConfig), aggregate.rs (glob_match)
+//!
+//! ==============================================================================
+
+use crate::aggregate::glob_match;
+use crate::config::HttpSinkConfig;
+use crate::domain::SensorReading;
+use crate::log_msg;
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// cheap handle that queues readings for one sink's sender task
+#[derive(Clone)]
+pub struct HttpSink {
+    tx: mpsc::UnboundedSender<Vec<SensorReading>>,
+    sensors: String,
+}
+
+impl HttpSink {
+    /// resolve the templates and spawn the sender task
+    pub fn start(config: &HttpSinkConfig, node_id: &str, client: reqwest::Client) -> anyhow::Result<Self> {
+        if config.name.is_empty() {
+            anyhow::bail!("sink without a name");
+        }
+        let url = expand(&config.url, node_id, &config.name)?;
+        let probe = url.replace("{date}", "1970-01-01");
+        match reqwest::Url::parse(&probe) {
+            Ok(parsed) if matches!(parsed.scheme(), "http" | "https") => {}
+            _ => anyhow::bail!("sink '{}': invalid url '{}'", config.name, config.url),
+        }
+        let mut headers = Vec::new();
+        for (name, value) in &config.headers {
+            let value = expand(value, node_id, &config.name)?;
+            reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| anyhow::anyhow!("sink '{}': invalid header name '{}'", config.name, name))?;
+            headers.push((name.clone(), value));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let target = Target { url, headers, body_node: node_id.to_string() };
+        log_msg(&format!("📤 [SINK] Forwarding readings to '{}' ({})", config.name, redact(&config.url)));
+        tokio::spawn(send_loop(client, target, config.clone(), rx));
+        Ok(Self { tx, sensors: config.sensors.clone() })
+    }
+
+    pub fn forward(&self, readings: &[SensorReading]) {
+        let batch: Vec<SensorReading> = readings.iter().filter(|r| glob_match(&self.sensors, &r.sensor_id)).cloned().collect();
+        if !batch.is_empty() {
+            let _ = self.tx.send(batch);
+        }
+    }
+}
+
+/// start every configured sink, skipping (and logging) broken ones
+pub fn start_all(configs: &[HttpSinkConfig], node_id: &str, client: &reqwest::Client) -> Vec<HttpSink> {
+    configs
+        .iter()
+        .filter_map(|config| match HttpSink::start(config, node_id, client.clone()) {
+            Ok(sink) => Some(sink),
+            Err(e) => {
+                log_msg(&format!("❌ [SINK] '{}' disabled: {:#}", config.name, e));
+                None
+            }
+        })
+        .collect()
+}
+
+/// url and headers with the static placeholders filled in ({date} stays)
+struct Target {
+    url: String,
+    headers: Vec<(String, String)>,
+    body_node: String,
+}
+
+async fn send_loop(
+    client: reqwest::Client,
+    target: Target,
+    config: HttpSinkConfig,
+    mut rx: mpsc::UnboundedReceiver<Vec<SensorReading>>,
+) {
+    let flush_every = Duration::from_secs(config.flush_seconds.max(1));
+    let initial_backoff = Duration::from_millis(config.retry.initial_backoff_ms.max(1));
+    let max_backoff = Duration::from_secs(config.retry.max_backoff_seconds.max(1));
+    let mut buffer: VecDeque<SensorReading> = VecDeque::new();
+    let mut backoff = Duration::ZERO;
+    let mut attempts = 0u32;
+    let mut next_flush = tokio::time::Instant::now() + flush_every;
+
+    loop {
+        tokio::select! {
+            readings = rx.recv() => match readings {
+                Some(readings) => buffer.extend(readings),
+                None => return,
+            },
+            _ = tokio::time::sleep_until(next_flush) => {}
+        }
+        let overflow = buffer.len().saturating_sub(config.max_buffer);
+        if overflow > 0 {
+            buffer.drain(..overflow);
+            tracing::warn!("sink '{}' buffer full, dropped {} oldest readings", config.name, overflow);
+        }
+        let now = tokio::time::Instant::now();
+        let due = now >= next_flush;
+        if buffer.is_empty() || (!due && (buffer.len() < config.batch_size || !backoff.is_zero())) {
+            if due {
+                next_flush = now + flush_every;
+            }
+            continue;
+        }
+
+        while !buffer.is_empty() {
+            let take = buffer.len().min(config.batch_size.max(1));
+            let batch: Vec<&SensorReading> = buffer.iter().take(take).collect();
+            match send(&client, &target, &config, &batch).await {
+                Ok(()) => {
+                    buffer.drain(..take);
+                    if attempts > 0 {
+                        log_msg(&format!("📤 [SINK] '{}' recovered", config.name));
+                    }
+                    backoff = Duration::ZERO;
+                    attempts = 0;
+                }
+                Err(e) => {
+                    attempts += 1;
+                    if config.retry.max_attempts > 0 && attempts >= config.retry.max_attempts {
+                        buffer.drain(..take);
+                        log_msg(&format!(
+                            "❌ [SINK] '{}' dropped {} readings after {} attempts: {}",
+                            config.name, take, attempts, e
+                        ));
+                        backoff = Duration::ZERO;
+                        attempts = 0;
+                        continue;
+                    }
+                    backoff = (backoff * 2).clamp(initial_backoff, max_backoff);
+                    log_msg(&format!(
+                        "❌ [SINK] '{}' post failed ({} readings buffered, retry in {}ms): {}",
+                        config.name,
+                        buffer.len(),
+                        backoff.as_millis(),
+                        e
+                    ));
+                    break;
+                }
+            }
+        }
+        next_flush = tokio::time::Instant::now() + if backoff.is_zero() { flush_every } else { backoff };
+    }
+}
+
+async fn send(client: &reqwest::Client, target: &Target, config: &HttpSinkConfig, batch: &[&SensorReading]) -> anyhow::Result<()> {
+    let date = chrono::Utc::now().format("%Y-%m-%d").to_string();
+    let body = serde_json::json!({ "node_id": target.body_node, "sink": config.name, "readings": batch });
+    let mut request = client
+        .post(target.url.replace("{date}", &date))
+        .timeout(Duration::from_secs(config.timeout_seconds.max(1)))
+        .json(&body);
+    for (name, value) in &target.headers {
+        request = request.header(name.as_str(), value.replace("{date}", &date));
+    }
+    let response = request.send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        anyhow::bail!("{} {}", status, response.text().await.unwrap_or_default().trim());
+    }
+    Ok(())
+}
+
+/// fill in {node_id}, {name} and {env:VAR}
+fn expand(template: &str, node_id: &str, name: &str) -> anyhow::Result<String> {
+    let mut rest = template.replace("{node_id}", node_id).replace("{name}", name);
+    let mut out = String::new();
+    while let Some(start) = rest.find("{env:") {
+        let Some(len) = rest[start..].find('}') else {
+            anyhow::bail!("unterminated {{env:...}} in '{}'", template);
+        };
+        let var = &rest[start + 5..start + len];
+        let value = std::env::var(var).map_err(|_| anyhow::anyhow!("environment variable {} is not set", var))?;
+        out.push_str(&rest[..start]);
+        out.push_str(&value);
+        rest = rest.split_off(start + len + 1);
+    }
+    out.push_str(&rest);
+    Ok(out)
+}
+
+/// the url for the log, without credentials and query
+fn redact(url: &str) -> &str {
+    let url = url.split(['?', '#']).next().unwrap_or(url);
+    match url.split_once("://") {
+        Some((_, rest)) if rest.split('/').next().is_some_and(|host| host.contains('@')) => "<url with credentials>",
+        _ => url,
+    }
+}
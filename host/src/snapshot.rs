@@ -0,0 +1,417 @@
+//! ==============================================================================
+//! snapshot.rs - full node snapshot and restore (/api/snapshot, /api/restore)
+//! ==============================================================================
+//!
+//! purpose:
+//!     a hub's SD card dies eventually. GET /api/snapshot returns everything
+//!     needed to bring a fresh card back to where the old one was, as one
+//!     .tar.gz:
+//!         manifest.json          node id, role, host version, plugin versions
+//!         state.json             the latest readings view
+//!         config/...             host.toml and everything next to it
+//!                                (node overlays, retired_nodes.json)
+//!         data/readings.db       the sqlite history (when storage is on)
+//!         plugins/{n}/{n}.wasm   the loaded plugins (skipped with ?plugins=false)
+//!     pull it nightly from another machine, e.g. with cron:
+//!         curl -H "Authorization: Bearer $ADMIN_TOKEN" -o hub-$(date +%F).tar.gz http://hub:3000/api/snapshot
+//!
+//! restore:
+//!     POST /api/restore with the archive as body on the new node. readings,
+//!     history and plugins take effect immediately; config files are written
+//!     (the replaced ones kept as .bak) and apply on the next restart, the
+//!     reply says whether one is needed. an archive of a different node id
+//!     is refused unless ?force=true.
+//!     the upload is spooled to a temp file (at most RESTORE_LIMIT), then
+//!     every entry is checked and unpacked to a temp dir (at most
+//!     UNPACK_LIMIT in all) before anything on the node changes: an entry
+//!     create wouldn't write, a config path leaving the config dir, a
+//!     plugin that isn't wasm or an invalid manifest / state refuse the
+//!     whole archive.
+//!
+//! access:
+//!     both routes need api.admin_token (admin.rs) - the archive holds the
+//!     config with its secrets, and a restore installs plugin code.
+//!
+//! relationships:
+//!     - used by: main.rs (/api/snapshot, /api/restore)
+//!     - reads: config.rs (config directory), storage.rs (backup_to / restore_from),
+//!              runtime.rs (plugin files, install_plugin), domain.rs (AppState)
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use crate::domain::AppState;
+use crate::runtime::{WasmRuntime, KNOWN_PLUGINS};
+use crate::storage::Store;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+/// bumped when the archive layout changes incompatibly
+const FORMAT: u32 = 1;
+
+const DB_ENTRY: &str = "data/readings.db";
+
+#[derive(Serialize, Deserialize)]
+pub struct Manifest {
+    pub format: u32,
+    pub node_id: String,
+    pub role: String,
+    pub host_version: String,
+    pub created_ms: u64,
+    pub plugins: Vec<PluginVersion>,
+}
+
+/// what identifies a plugin build: its size and modification time
+#[derive(Serialize, Deserialize)]
+pub struct PluginVersion {
+    pub name: String,
+    pub loaded: bool,
+    pub size: u64,
+    pub modified_ms: u64,
+    /// the .wasm is in the archive
+    pub included: bool,
+}
+
+/// reply of POST /api/restore
+#[derive(Serialize)]
+pub struct RestoreSummary {
+    pub node_id: String,
+    pub created_ms: u64,
+    pub sensors: usize,
+    pub stored_readings: Option<u64>,
+    pub config_files: Vec<String>,
+    pub plugins: Vec<String>,
+    /// config files changed - they apply on the next start
+    pub restart_required: bool,
+}
+
+/// directory holding host.toml
+fn config_dir() -> PathBuf {
+    HostConfig::find_config_file()
+        .and_then(|p| p.parent().map(Path::to_path_buf))
+        .unwrap_or_else(|| PathBuf::from("config"))
+}
+
+/// build the .tar.gz of this node
+pub async fn create(
+    state: &Arc<RwLock<AppState>>,
+    store: Option<Arc<Store>>,
+    runtime: &WasmRuntime,
+    config: &HostConfig,
+    include_plugins: bool,
+) -> anyhow::Result<Vec<u8>> {
+    let state_json = serde_json::to_vec_pretty(&*state.read().await)?;
+    let loaded = runtime.loaded_plugins().await;
+    let plugins: Vec<(String, PathBuf, bool)> = KNOWN_PLUGINS
+        .iter()
+        .map(|name| (name.to_string(), runtime.plugin_path(name), loaded.iter().any(|l| l == name)))
+        .collect();
+    let (node_id, role) = (config.cluster.node_id.clone(), config.cluster.role.clone());
+
+    tokio::task::spawn_blocking(move || {
+        let mut archive = tar::Builder::new(flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default()));
+
+        let mut versions = Vec::new();
+        for (name, path, loaded) in plugins {
+            let Ok(meta) = std::fs::metadata(&path) else { continue };
+            let included = include_plugins && loaded;
+            if included {
+                archive.append_path_with_name(&path, format!("plugins/{0}/{0}.wasm", name))?;
+            }
+            let modified_ms = meta
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_millis() as u64);
+            versions.push(PluginVersion { name, loaded, size: meta.len(), modified_ms, included });
+        }
+
+        let manifest = Manifest {
+            format: FORMAT,
+            node_id,
+            role,
+            host_version: env!("CARGO_PKG_VERSION").to_string(),
+            created_ms: crate::domain::now_ms(),
+            plugins: versions,
+        };
+        append(&mut archive, "manifest.json", &serde_json::to_vec_pretty(&manifest)?)?;
+        append(&mut archive, "state.json", &state_json)?;
+
+        let dir = config_dir();
+        for file in config_files(&dir)? {
+            let name = file.strip_prefix(&dir)?.to_string_lossy().replace('\\', "/");
+            archive.append_path_with_name(&file, format!("config/{}", name))?;
+        }
+
+        if let Some(store) = store {
+            let tmp = std::env::temp_dir().join(format!("wasi-host-snapshot-{}.db", uuid::Uuid::new_v4()));
+            let result = store.backup_to(&tmp).and_then(|()| Ok(archive.append_path_with_name(&tmp, DB_ENTRY)?));
+            let _ = std::fs::remove_file(&tmp);
+            result.context("failed to copy the readings database")?;
+        }
+
+        Ok(archive.into_inner()?.finish()?)
+    })
+    .await?
+}
+
+fn append<W: std::io::Write>(archive: &mut tar::Builder<W>, name: &str, data: &[u8]) -> std::io::Result<()> {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_mtime(crate::domain::now_ms() / 1000);
+    header.set_cksum();
+    archive.append_data(&mut header, name, data)
+}
+
+/// files below the config directory, without backups and temp files
+fn config_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Ok(files);
+    };
+    for entry in entries {
+        let path = entry?.path();
+        if path.is_dir() {
+            files.extend(config_files(&path)?);
+        } else if !path.extension().is_some_and(|ext| ext == "bak" || ext == "tmp") {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// largest archive POST /api/restore accepts (history + plugins)
+pub const RESTORE_LIMIT: u64 = 1024 * 1024 * 1024;
+
+/// most bytes the archive may unpack to - a gzip bomb stops here
+const UNPACK_LIMIT: u64 = 4 * RESTORE_LIMIT;
+
+/// manifest.json and state.json are parsed in memory, they stay small
+const JSON_LIMIT: u64 = 64 * 1024 * 1024;
+
+/// an upload past RESTORE_LIMIT
+#[derive(Debug)]
+pub struct TooLarge;
+
+impl std::fmt::Display for TooLarge {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "archive is larger than {} bytes", RESTORE_LIMIT)
+    }
+}
+
+impl std::error::Error for TooLarge {}
+
+/// a file below the temp dir, removed when dropped
+pub struct TempPath(PathBuf);
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let _ = if self.0.is_dir() { std::fs::remove_dir_all(&self.0) } else { std::fs::remove_file(&self.0) };
+    }
+}
+
+/// an uploaded archive, on disk
+pub struct Spooled {
+    path: TempPath,
+    pub size: u64,
+}
+
+/// write the request body to a temp file, refusing it past RESTORE_LIMIT
+pub async fn spool(body: axum::body::Body) -> anyhow::Result<Spooled> {
+    use futures::StreamExt;
+    use tokio::io::AsyncWriteExt;
+
+    let path = TempPath(std::env::temp_dir().join(format!("wasi-host-restore-{}.tar.gz", uuid::Uuid::new_v4())));
+    let mut file = tokio::fs::File::create(&path.0).await?;
+    let mut size = 0u64;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.context("upload interrupted")?;
+        size += chunk.len() as u64;
+        if size > RESTORE_LIMIT {
+            return Err(TooLarge.into());
+        }
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(Spooled { path, size })
+}
+
+/// unpack `archive` over this node. every entry is checked and staged on
+/// disk before the first file of the node is touched.
+pub async fn restore(
+    archive: &Spooled,
+    state: &Arc<RwLock<AppState>>,
+    store: Option<Arc<Store>>,
+    runtime: &WasmRuntime,
+    config: &HostConfig,
+    force: bool,
+) -> anyhow::Result<RestoreSummary> {
+    let staged = tokio::task::spawn_blocking({
+        let archive = archive.path.0.clone();
+        move || stage(&archive)
+    })
+    .await??;
+    let manifest: Manifest = serde_json::from_slice(&staged.read("manifest.json")?.context("archive has no manifest.json")?)
+        .context("invalid manifest.json")?;
+    if manifest.format != FORMAT {
+        anyhow::bail!("unsupported snapshot format {} (expected {})", manifest.format, FORMAT);
+    }
+    if manifest.node_id != config.cluster.node_id && !force {
+        anyhow::bail!(
+            "snapshot is of node '{}', this is '{}' - add ?force=true to restore it anyway",
+            manifest.node_id,
+            config.cluster.node_id
+        );
+    }
+    let snapshot: Option<AppState> = match staged.read("state.json")? {
+        Some(json) => Some(serde_json::from_slice(&json).context("invalid state.json")?),
+        None => None,
+    };
+    let mut wasm = Vec::new();
+    for name in KNOWN_PLUGINS {
+        if let Some(bytes) = staged.read(&format!("plugins/{0}/{0}.wasm", name))? {
+            if !bytes.starts_with(b"\0asm") {
+                anyhow::bail!("plugins/{0}/{0}.wasm is not a wasm binary", name);
+            }
+            wasm.push((name, bytes));
+        }
+    }
+
+    // config files, applied on the next start
+    let dir = config_dir();
+    let mut config_files = Vec::new();
+    for name in staged.names.iter().filter_map(|n| n.strip_prefix("config/")) {
+        let data = std::fs::read(staged.path(&format!("config/{}", name)))?;
+        let path = dir.join(name);
+        if std::fs::read(&path).is_ok_and(|current| current == data) {
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let _ = std::fs::copy(&path, format!("{}.bak", path.display()));
+        std::fs::write(&path, data).with_context(|| format!("failed to write {}", path.display()))?;
+        config_files.push(name.to_string());
+    }
+
+    // sqlite history, restored from the staged copy
+    let stored_readings = match (staged.names.contains(DB_ENTRY), store) {
+        (true, Some(store)) => {
+            let db = staged.path(DB_ENTRY);
+            let result = tokio::task::spawn_blocking(move || store.restore_from(&db)).await;
+            Some(result?.context("failed to restore the readings database")?)
+        }
+        _ => None,
+    };
+
+    // latest readings view
+    let sensors = match snapshot {
+        Some(snapshot) => {
+            let mut s = state.write().await;
+            s.readings = snapshot.readings;
+            s.last_update = snapshot.last_update;
+            s.clock_skew_ms = snapshot.clock_skew_ms;
+            s.evict(crate::domain::now_ms());
+            s.readings.len()
+        }
+        None => 0,
+    };
+
+    // plugins, hot-reloaded
+    let mut plugins = Vec::new();
+    for (name, bytes) in wasm {
+        runtime.install_plugin(name, &bytes).await?;
+        plugins.push(name.to_string());
+    }
+
+    Ok(RestoreSummary {
+        node_id: manifest.node_id,
+        created_ms: manifest.created_ms,
+        sensors,
+        stored_readings,
+        restart_required: !config_files.is_empty(),
+        config_files,
+        plugins,
+    })
+}
+
+/// the archive's entries, unpacked to a temp dir
+struct Staged {
+    dir: TempPath,
+    names: BTreeSet<String>,
+}
+
+impl Staged {
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.0.join(name)
+    }
+
+    /// a small entry's bytes, None when the archive doesn't have it
+    fn read(&self, name: &str) -> anyhow::Result<Option<Vec<u8>>> {
+        if !self.names.contains(name) {
+            return Ok(None);
+        }
+        let path = self.path(name);
+        if name.ends_with(".json") && std::fs::metadata(&path)?.len() > JSON_LIMIT {
+            anyhow::bail!("{} is larger than {} bytes", name, JSON_LIMIT);
+        }
+        Ok(Some(std::fs::read(path)?))
+    }
+}
+
+/// an entry a snapshot can have: what create writes, nothing else
+fn allowed(name: &str) -> bool {
+    match name {
+        "manifest.json" | "state.json" | DB_ENTRY => true,
+        _ => match name.strip_prefix("config/") {
+            Some(relative) => !relative.is_empty() && Path::new(relative).components().all(|c| matches!(c, Component::Normal(_))),
+            None => KNOWN_PLUGINS.iter().any(|p| name == format!("plugins/{0}/{0}.wasm", p)),
+        },
+    }
+}
+
+/// check every entry of the .tar.gz and unpack it to a temp dir, at most
+/// UNPACK_LIMIT bytes in all
+fn stage(archive: &Path) -> anyhow::Result<Staged> {
+    let dir = TempPath(std::env::temp_dir().join(format!("wasi-host-restore-{}", uuid::Uuid::new_v4())));
+    std::fs::create_dir_all(&dir.0)?;
+    let mut names = BTreeSet::new();
+    let mut unpacked = 0u64;
+    let mut tar = tar::Archive::new(flate2::read::GzDecoder::new(std::fs::File::open(archive)?));
+    for entry in tar.entries().context("body is not a .tar.gz snapshot")? {
+        let entry = entry?;
+        let kind = entry.header().entry_type();
+        if kind.is_dir() {
+            continue;
+        }
+        let path = entry.path()?;
+        // tar -C dir . writes "./config/host.toml"
+        let name = path.strip_prefix(".").unwrap_or(&path).to_string_lossy().into_owned();
+        if !kind.is_file() || !allowed(&name) {
+            anyhow::bail!("refusing archive entry '{}'", name);
+        }
+        if !names.insert(name.clone()) {
+            anyhow::bail!("archive entry '{}' appears twice", name);
+        }
+        let path = dir.0.join(&name);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        // one byte past the budget tells an entry that doesn't fit
+        let budget = UNPACK_LIMIT - unpacked;
+        let written = std::io::copy(&mut entry.take(budget + 1), &mut std::fs::File::create(&path)?)?;
+        if written > budget {
+            anyhow::bail!("archive unpacks to more than {} bytes", UNPACK_LIMIT);
+        }
+        unpacked += written;
+    }
+    Ok(Staged { dir, names })
+}
@@ -0,0 +1,938 @@
+//! ==============================================================================
+//! storage.rs - sqlite time-series persistence for readings
+//! ==============================================================================
+//!
+//! purpose:
+//!     AppState only holds the newest reading per sensor and is lost on
+//!     restart. every merged reading is also appended to a local sqlite
+//!     database so the history api has something to serve and the dashboard
+//!     comes back with its last values after a reboot.
+//!
+//! design:
+//!     - AppState::merge_readings hands batches to a Recorder (an mpsc
+//!       sender) as rows - each reading's data already as json text, not a
+//!       copy of its value tree; a dedicated writer thread collects them for
+//!       storage.flush_interval_ms and inserts them in one transaction, so
+//!       request handlers and the polling loop never block on disk i/o and
+//!       the sd card sees few, larger writes.
+//!     - WAL mode lets the api read while the writer appends.
+//!     - (sensor_id, timestamp_ms) is unique: the same reading merged twice
+//!       (pull mode, replayed batches) is stored once.
+//!
+//! crash safety:
+//!     sd cards on nodes that lose power are the main risk. WAL keeps the
+//!     database consistent across a power cut: committed transactions
+//!     survive or roll back whole (storage.sync = "normal" may lose the
+//!     last flush interval, "full" fsyncs every commit). at startup the file
+//!     gets a quick_check (storage.integrity_check); a damaged one is moved
+//!     aside as {path}.corrupt-{ms}, a fresh database is created and every
+//!     row that can still be read is copied over.
+//!
+//! schema:
+//!     readings(sensor_id TEXT, timestamp_ms INTEGER, data TEXT, metadata TEXT)
+//!     readings_rollup(sensor_id TEXT, bucket_ms INTEGER, data TEXT, samples INTEGER)
+//!     alert_history(timestamp_ms INTEGER, transition TEXT, alert_id TEXT, rule TEXT,
+//!                   severity TEXT, sensor_id TEXT, value REAL, data TEXT)
+//!
+//! retention:
+//!     a background task (storage.retention.compact_interval_minutes) folds
+//!     raw readings older than raw_days into downsample_minutes buckets -
+//!     numeric fields averaged, other fields keep their last value - and
+//!     deletes buckets older than downsampled_days. the history api reads
+//!     both tables, so old ranges come back at the coarser resolution.
+//!     alert history is kept for downsampled_days as well.
+//!
+//! cargo feature:
+//!     the sqlite part needs the "storage" feature (a default one). a build
+//!     without it (the minimal spoke, see Cargo.toml) has no history:
+//!     storage.enabled is ignored with a warning and the stand-ins at the
+//!     end of this file take the place of Store / Recorder.
+//!
+//! relationships:
+//!     - used by: main.rs (startup restore, /api/history, /api/aggregate, /api/chart, node purge)
+//!     - used by: export.rs (daily parquet snapshots)
+//!     - used by: domain.rs (AppState.recorder)
+//!     - used by: alerts.rs (alert transition history)
+//!     - used by: reports.rs (daily min / max / avg summary)
+//!     - used by: snapshot.rs (database copy in /api/snapshot, /api/restore)
+//!     - reads: config.rs (StorageConfig, RetentionConfig)
+//!
+//! ==============================================================================
+
+use std::collections::BTreeMap;
+#[cfg(feature = "storage")]
+use {
+    crate::config::{RetentionConfig, StorageConfig},
+    crate::domain::SensorReading,
+    crate::log_msg,
+    rusqlite::{params, Connection},
+    serde_json::value::RawValue,
+    std::path::{Path, PathBuf},
+    std::sync::{mpsc, Arc, Mutex},
+    std::time::{Duration, Instant},
+};
+
+/// readings that end a flush interval early
+#[cfg(feature = "storage")]
+const WRITE_BATCH: usize = 500;
+
+/// unstored readings kept while inserts fail
+#[cfg(feature = "storage")]
+const MAX_PENDING: usize = 50_000;
+
+/// wal bytes kept after a checkpoint
+#[cfg(feature = "storage")]
+const WAL_SIZE_LIMIT: i64 = 16 * 1024 * 1024;
+
+const DAY_MS: u64 = 24 * 3600 * 1000;
+
+/// a reading as it goes into the readings table
+#[cfg(feature = "storage")]
+struct Row {
+    sensor_id: String,
+    timestamp_ms: u64,
+    data: Box<RawValue>,
+    metadata: Option<String>,
+}
+
+#[cfg(feature = "storage")]
+impl Row {
+    fn of(reading: &SensorReading) -> Option<Row> {
+        Some(Row {
+            sensor_id: reading.sensor_id.clone(),
+            timestamp_ms: reading.timestamp_ms,
+            data: serde_json::value::to_raw_value(&reading.data).ok()?,
+            metadata: reading.metadata.as_ref().and_then(|m| serde_json::to_string(m).ok()),
+        })
+    }
+}
+
+/// cheap handle that queues readings for the writer thread
+#[cfg(feature = "storage")]
+#[derive(Clone)]
+pub struct Recorder {
+    tx: mpsc::Sender<Vec<Row>>,
+}
+
+#[cfg(feature = "storage")]
+impl Recorder {
+    pub fn record(&self, readings: &[SensorReading]) {
+        if !readings.is_empty() {
+            let _ = self.tx.send(readings.iter().filter_map(Row::of).collect());
+        }
+    }
+}
+
+/// one row of GET /api/alerts/history
+#[derive(serde::Serialize, Debug)]
+pub struct AlertRecord {
+    pub timestamp_ms: u64,
+    /// pending | firing | resolved | acked | silenced
+    pub transition: String,
+    pub alert_id: String,
+    pub rule: String,
+    pub severity: String,
+    pub sensor_id: String,
+    pub value: Option<f64>,
+    /// the alert as it was at the transition
+    pub alert: serde_json::Value,
+}
+
+/// min / max / avg of one field over a period (summary reports, chart bins)
+#[derive(serde::Serialize, Debug, Clone)]
+pub struct FieldSummary {
+    pub min: f64,
+    pub max: f64,
+    pub avg: f64,
+    pub samples: u64,
+}
+
+/// one window of GET /api/aggregate
+#[derive(serde::Serialize, Debug)]
+pub struct AggregatePoint {
+    /// window start (unix ms)
+    pub timestamp_ms: u64,
+    /// readings in the window (downsampled buckets count their samples)
+    pub samples: u64,
+    /// aggregated value of every numeric data field
+    pub values: BTreeMap<String, f64>,
+}
+
+/// one window of GET /api/chart
+#[derive(serde::Serialize, Debug)]
+pub struct ChartBin {
+    /// window start (unix ms)
+    pub timestamp_ms: u64,
+    pub samples: u64,
+    pub values: BTreeMap<String, FieldSummary>,
+}
+
+/// parse a duration like "30s", "15m", "1h", "7d" into milliseconds
+pub fn parse_duration_ms(text: &str) -> Option<u64> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let value: u64 = text[..split].parse().ok()?;
+    let unit_ms = match &text[split..] {
+        "ms" => 1,
+        "s" | "" => 1000,
+        "m" => 60 * 1000,
+        "h" => 3600 * 1000,
+        "d" => DAY_MS,
+        _ => return None,
+    };
+    value.checked_mul(unit_ms).filter(|&ms| ms > 0)
+}
+
+/// per field of the current window: (weighted sum, weight, min, max)
+#[cfg(feature = "storage")]
+type FieldStats = BTreeMap<String, (f64, f64, f64, f64)>;
+
+/// read side of the store (the writer thread has its own connection)
+#[cfg(feature = "storage")]
+pub struct Store {
+    conn: Mutex<Connection>,
+}
+
+/// open (or create) the database and start the writer thread. a database
+/// that fails the startup integrity check is moved aside and salvaged.
+#[cfg(feature = "storage")]
+pub fn open(config: &StorageConfig) -> anyhow::Result<(Store, Recorder)> {
+    let path = Path::new(&config.path);
+    let sync = match config.sync.as_str() {
+        "normal" => "NORMAL",
+        "full" => "FULL",
+        other => anyhow::bail!("unknown storage.sync '{}' (expected normal or full)", other),
+    };
+    let check = match config.integrity_check.as_str() {
+        "quick" => Some("quick_check"),
+        "full" => Some("integrity_check"),
+        "off" => None,
+        other => anyhow::bail!("unknown storage.integrity_check '{}' (expected quick, full or off)", other),
+    };
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    if let (Some(pragma), true) = (check, path.exists()) {
+        if let Err(problem) = verify(path, pragma) {
+            log_msg(&format!("❌ [STORAGE] {} is damaged: {}", path.display(), problem));
+            recover(path, sync)?;
+        }
+    }
+    let writer = connect(path, sync)?;
+    create_schema(&writer)?;
+    let store = Store { conn: Mutex::new(connect(path, sync)?) };
+
+    let (tx, rx) = mpsc::channel::<Vec<Row>>();
+    let flush_interval = Duration::from_millis(config.flush_interval_ms);
+    std::thread::Builder::new()
+        .name("storage-writer".into())
+        .spawn(move || write_loop(writer, rx, flush_interval))?;
+
+    log_msg(&format!("💾 [STORAGE] Recording readings to {}", path.display()));
+    Ok((store, Recorder { tx }))
+}
+
+#[cfg(feature = "storage")]
+fn create_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS readings (
+             sensor_id    TEXT    NOT NULL,
+             timestamp_ms INTEGER NOT NULL,
+             data         TEXT    NOT NULL,
+             metadata     TEXT
+         );
+         CREATE UNIQUE INDEX IF NOT EXISTS readings_sensor_ts ON readings (sensor_id, timestamp_ms);
+         CREATE TABLE IF NOT EXISTS readings_rollup (
+             sensor_id TEXT    NOT NULL,
+             bucket_ms INTEGER NOT NULL,
+             data      TEXT    NOT NULL,
+             samples   INTEGER NOT NULL,
+             PRIMARY KEY (sensor_id, bucket_ms)
+         );
+         CREATE TABLE IF NOT EXISTS alert_history (
+             timestamp_ms INTEGER NOT NULL,
+             transition   TEXT    NOT NULL,
+             alert_id     TEXT    NOT NULL,
+             rule         TEXT    NOT NULL,
+             severity     TEXT    NOT NULL,
+             sensor_id    TEXT    NOT NULL,
+             value        REAL,
+             data         TEXT    NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS alert_history_ts ON alert_history (timestamp_ms);",
+    )
+}
+
+#[cfg(feature = "storage")]
+fn connect(path: &Path, sync: &str) -> rusqlite::Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    // WAL + NORMAL only syncs at checkpoints - gentle on sd cards. a power
+    // cut can lose the last commits but never leaves a torn database;
+    // FULL also syncs every commit.
+    conn.pragma_update(None, "synchronous", sync)?;
+    // truncate the wal back to this size after checkpoints instead of
+    // leaving a large file behind
+    conn.pragma_update(None, "journal_size_limit", WAL_SIZE_LIMIT)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    Ok(conn)
+}
+
+/// run `PRAGMA {pragma}` (quick_check | integrity_check) on an existing database
+#[cfg(feature = "storage")]
+fn verify(path: &Path, pragma: &str) -> Result<(), String> {
+    let conn = Connection::open(path).map_err(|e| e.to_string())?;
+    let problems: Vec<String> = (|| {
+        let mut stmt = conn.prepare(&format!("PRAGMA {}", pragma))?;
+        let rows = stmt.query_map([], |r| r.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<String>>>()
+    })()
+    .map_err(|e| e.to_string())?;
+    match problems.as_slice() {
+        [ok] if ok == "ok" => Ok(()),
+        [] => Err("integrity check returned nothing".to_string()),
+        _ => Err(problems.into_iter().take(3).collect::<Vec<_>>().join("; ")),
+    }
+}
+
+/// move a damaged database (and its wal) aside as {path}.corrupt-{ms},
+/// create a fresh one and copy over every row that can still be read
+#[cfg(feature = "storage")]
+fn recover(path: &Path, sync: &str) -> anyhow::Result<()> {
+    let aside = PathBuf::from(format!("{}.corrupt-{}", path.display(), crate::domain::now_ms()));
+    for suffix in ["", "-wal", "-shm"] {
+        let from = PathBuf::from(format!("{}{}", path.display(), suffix));
+        if from.exists() {
+            std::fs::rename(&from, format!("{}{}", aside.display(), suffix))?;
+        }
+    }
+    let mut fresh = connect(path, sync)?;
+    create_schema(&fresh)?;
+
+    let salvaged = match Connection::open_with_flags(&aside, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY) {
+        Ok(damaged) => ["readings", "readings_rollup", "alert_history"]
+            .iter()
+            .map(|table| salvage(&damaged, &mut fresh, table))
+            .sum(),
+        Err(e) => {
+            log_msg(&format!("❌ [STORAGE] Nothing salvageable in {}: {}", aside.display(), e));
+            0
+        }
+    };
+    log_msg(&format!(
+        "🩹 [STORAGE] Started a fresh {} with {} salvaged rows, damaged copy kept as {}",
+        path.display(),
+        salvaged,
+        aside.display()
+    ));
+    Ok(())
+}
+
+/// copy the rows of `table` until the first unreadable page
+#[cfg(feature = "storage")]
+fn salvage(damaged: &Connection, fresh: &mut Connection, table: &str) -> usize {
+    let mut copied = 0;
+    let result = (|| -> rusqlite::Result<()> {
+        let mut select = damaged.prepare(&format!("SELECT * FROM {}", table))?;
+        let columns = select.column_count();
+        let tx = fresh.transaction()?;
+        {
+            let placeholders = vec!["?"; columns].join(", ");
+            let mut insert = tx.prepare(&format!("INSERT OR IGNORE INTO {} VALUES ({})", table, placeholders))?;
+            let mut rows = select.query([])?;
+            // a corrupt page ends the scan with an error - keep what came before it
+            while let Ok(Some(row)) = rows.next() {
+                let values = (0..columns)
+                    .map(|i| row.get::<_, rusqlite::types::Value>(i))
+                    .collect::<rusqlite::Result<Vec<_>>>()?;
+                copied += insert.execute(rusqlite::params_from_iter(values))?;
+            }
+        }
+        tx.commit()
+    })();
+    if let Err(e) = result {
+        log_msg(&format!("⚠️ [STORAGE] Salvaging {} stopped: {}", table, e));
+    }
+    copied
+}
+
+/// insert queued readings in one transaction per flush interval (or per
+/// WRITE_BATCH readings), so the sd card sees few, larger writes. a failed
+/// transaction keeps its readings for the next flush (up to MAX_PENDING).
+#[cfg(feature = "storage")]
+fn write_loop(mut conn: Connection, rx: mpsc::Receiver<Vec<Row>>, flush_interval: Duration) {
+    let mut pending: Vec<Row> = Vec::new();
+    let mut open = true;
+    while open {
+        match rx.recv() {
+            Ok(batch) => pending.extend(batch),
+            Err(_) => open = false,
+        }
+        // coalesce whatever arrives until the flush is due
+        let deadline = Instant::now() + flush_interval;
+        while open && pending.len() < WRITE_BATCH {
+            let wait = deadline.saturating_duration_since(Instant::now());
+            match rx.recv_timeout(wait) {
+                Ok(more) => pending.extend(more),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => open = false,
+            }
+        }
+        if pending.is_empty() {
+            continue;
+        }
+        match insert(&mut conn, &pending) {
+            Ok(()) => pending.clear(),
+            Err(e) => {
+                log_msg(&format!("❌ [STORAGE] Failed to store {} readings: {}", pending.len(), e));
+                if pending.len() > MAX_PENDING {
+                    let dropped = pending.len() - MAX_PENDING;
+                    pending.drain(..dropped);
+                    log_msg(&format!("⚠️ [STORAGE] Dropped {} oldest unstored readings", dropped));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "storage")]
+fn insert(conn: &mut Connection, readings: &[Row]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT OR IGNORE INTO readings (sensor_id, timestamp_ms, data, metadata) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        for r in readings {
+            stmt.execute(params![r.sensor_id, r.timestamp_ms as i64, r.data.get(), r.metadata])?;
+        }
+    }
+    tx.commit()
+}
+
+#[cfg(feature = "storage")]
+fn row_to_reading(row: &rusqlite::Row) -> rusqlite::Result<SensorReading> {
+    let data: String = row.get(2)?;
+    let metadata: Option<String> = row.get(3)?;
+    Ok(SensorReading {
+        sensor_id: row.get(0)?,
+        timestamp_ms: row.get::<_, i64>(1)? as u64,
+        data: serde_json::from_str(&data).unwrap_or(serde_json::Value::Null),
+        metadata: metadata.and_then(|m| serde_json::from_str(&m).ok()),
+        quality: None,
+    })
+}
+
+#[cfg(feature = "storage")]
+impl Store {
+    /// newest reading of every sensor - used to repopulate AppState at startup
+    pub fn latest(&self) -> anyhow::Result<Vec<SensorReading>> {
+        let conn = self.conn.lock().unwrap();
+        // sqlite returns the bare columns of the row that holds MAX()
+        let mut stmt = conn.prepare(
+            "SELECT sensor_id, MAX(timestamp_ms), data, metadata FROM readings GROUP BY sensor_id",
+        )?;
+        let rows = stmt.query_map([], row_to_reading)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// readings of one sensor in [from_ms, to_ms], oldest first.
+    /// ranges older than the raw retention come from the downsampled buckets.
+    pub fn history(&self, sensor_id: &str, from_ms: u64, to_ms: u64, limit: usize) -> anyhow::Result<Vec<SensorReading>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT sensor_id, timestamp_ms, data, metadata FROM readings
+             WHERE sensor_id = ?1 AND timestamp_ms BETWEEN ?2 AND ?3
+             UNION ALL
+             SELECT sensor_id, bucket_ms, data, NULL FROM readings_rollup
+             WHERE sensor_id = ?1 AND bucket_ms BETWEEN ?2 AND ?3
+             ORDER BY 2 LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            params![sensor_id, from_ms as i64, to_ms.min(i64::MAX as u64) as i64, limit as i64],
+            row_to_reading,
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// every stored reading of every sensor in [from_ms, to_ms], oldest first
+    #[cfg_attr(not(feature = "parquet"), allow(dead_code))]
+    pub fn range(&self, from_ms: u64, to_ms: u64) -> anyhow::Result<Vec<SensorReading>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT sensor_id, timestamp_ms, data, metadata FROM readings
+             WHERE timestamp_ms BETWEEN ?1 AND ?2
+             UNION ALL
+             SELECT sensor_id, bucket_ms, data, NULL FROM readings_rollup
+             WHERE bucket_ms BETWEEN ?1 AND ?2
+             ORDER BY 2",
+        )?;
+        let rows = stmt.query_map(params![from_ms as i64, to_ms as i64], row_to_reading)?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// bucket one sensor's stored readings into `window_ms` windows over
+    /// [from_ms, to_ms] and apply `func` (avg | min | max | sum | count) to
+    /// every numeric field. downsampled buckets count with their sample
+    /// weight for avg/sum/count; min/max over them are min/max of averages.
+    pub fn aggregate(&self, sensor_id: &str, func: &str, window_ms: u64, from_ms: u64, to_ms: u64) -> anyhow::Result<Vec<AggregatePoint>> {
+        let mut points = Vec::new();
+        self.windows(sensor_id, window_ms, from_ms, to_ms, |timestamp_ms, samples, stats| {
+            let values = stats
+                .into_iter()
+                .map(|(field, (sum, weight, min, max))| {
+                    let value = match func {
+                        "avg" => sum / weight,
+                        "min" => min,
+                        "max" => max,
+                        "sum" => sum,
+                        _ => weight, // count
+                    };
+                    (field, value)
+                })
+                .collect();
+            points.push(AggregatePoint { timestamp_ms, samples, values });
+        })?;
+        Ok(points)
+    }
+
+    /// like aggregate, with min / max / avg of every field per window in
+    /// one pass (GET /api/chart draws the avg line inside a min-max band)
+    pub fn chart(&self, sensor_id: &str, window_ms: u64, from_ms: u64, to_ms: u64) -> anyhow::Result<Vec<ChartBin>> {
+        let mut bins = Vec::new();
+        self.windows(sensor_id, window_ms, from_ms, to_ms, |timestamp_ms, samples, stats| {
+            let values = stats
+                .into_iter()
+                .map(|(field, (sum, weight, min, max))| (field, FieldSummary { min, max, avg: sum / weight, samples: weight as u64 }))
+                .collect();
+            bins.push(ChartBin { timestamp_ms, samples, values });
+        })?;
+        Ok(bins)
+    }
+
+    /// stream one sensor's readings and hand each non-empty window to
+    /// `finish` as (window start, samples, per-field stats), oldest first
+    fn windows(
+        &self,
+        sensor_id: &str,
+        window_ms: u64,
+        from_ms: u64,
+        to_ms: u64,
+        mut finish: impl FnMut(u64, u64, FieldStats),
+    ) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT timestamp_ms, data, 1 FROM readings
+             WHERE sensor_id = ?1 AND timestamp_ms BETWEEN ?2 AND ?3
+             UNION ALL
+             SELECT bucket_ms, data, samples FROM readings_rollup
+             WHERE sensor_id = ?1 AND bucket_ms BETWEEN ?2 AND ?3
+             ORDER BY 1",
+        )?;
+        let mut rows = stmt.query(params![sensor_id, from_ms as i64, to_ms.min(i64::MAX as u64) as i64])?;
+
+        let mut current: Option<(u64, u64)> = None;
+        let mut acc = FieldStats::new();
+        while let Some(row) = rows.next()? {
+            let ts = row.get::<_, i64>(0)? as u64;
+            let data: String = row.get(1)?;
+            let weight = row.get::<_, i64>(2)? as f64;
+            let window = ts / window_ms * window_ms;
+            if current.map(|(start, _)| start) != Some(window) {
+                if let Some((start, samples)) = current {
+                    finish(start, samples, std::mem::take(&mut acc));
+                }
+                current = Some((window, 0));
+            }
+            if let Some((_, samples)) = current.as_mut() {
+                *samples += weight as u64;
+            }
+            let Ok(serde_json::Value::Object(fields)) = serde_json::from_str::<serde_json::Value>(&data) else { continue };
+            for (field, value) in fields {
+                let Some(n) = value.as_f64() else { continue };
+                let entry = acc.entry(field).or_insert((0.0, 0.0, f64::INFINITY, f64::NEG_INFINITY));
+                entry.0 += n * weight;
+                entry.1 += weight;
+                entry.2 = entry.2.min(n);
+                entry.3 = entry.3.max(n);
+            }
+        }
+        if let Some((start, samples)) = current {
+            finish(start, samples, acc);
+        }
+        Ok(())
+    }
+
+    /// min / max / avg of every numeric field of every sensor over
+    /// [from_ms, to_ms], streamed so a day of readings isn't held in memory.
+    /// downsampled buckets count with their sample weight.
+    pub fn summary(&self, from_ms: u64, to_ms: u64) -> anyhow::Result<BTreeMap<String, BTreeMap<String, FieldSummary>>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT sensor_id, data, 1 FROM readings
+             WHERE timestamp_ms BETWEEN ?1 AND ?2
+             UNION ALL
+             SELECT sensor_id, data, samples FROM readings_rollup
+             WHERE bucket_ms BETWEEN ?1 AND ?2",
+        )?;
+        let mut rows = stmt.query(params![from_ms as i64, to_ms.min(i64::MAX as u64) as i64])?;
+
+        // (weighted sum, weight, min, max) per sensor and field
+        let mut acc: BTreeMap<String, FieldStats> = BTreeMap::new();
+        while let Some(row) = rows.next()? {
+            let sensor_id: String = row.get(0)?;
+            let data: String = row.get(1)?;
+            let weight = row.get::<_, i64>(2)? as f64;
+            let Ok(serde_json::Value::Object(fields)) = serde_json::from_str::<serde_json::Value>(&data) else { continue };
+            let stats = acc.entry(sensor_id).or_default();
+            for (field, value) in fields {
+                let Some(n) = value.as_f64() else { continue };
+                let entry = stats.entry(field).or_insert((0.0, 0.0, f64::INFINITY, f64::NEG_INFINITY));
+                entry.0 += n * weight;
+                entry.1 += weight;
+                entry.2 = entry.2.min(n);
+                entry.3 = entry.3.max(n);
+            }
+        }
+        Ok(acc
+            .into_iter()
+            .map(|(sensor_id, stats)| {
+                let fields = stats
+                    .into_iter()
+                    .map(|(field, (sum, weight, min, max))| {
+                        (field, FieldSummary { min, max, avg: sum / weight.max(1.0), samples: weight as u64 })
+                    })
+                    .collect();
+                (sensor_id, fields)
+            })
+            .collect())
+    }
+
+    /// delete every stored reading of one node. returns the number of rows removed.
+    pub fn purge_node(&self, node_id: &str) -> anyhow::Result<usize> {
+        let prefix = format!("{}:", node_id);
+        let conn = self.conn.lock().unwrap();
+        let raw = conn.execute("DELETE FROM readings WHERE substr(sensor_id, 1, length(?1)) = ?1", params![prefix])?;
+        let rollup = conn.execute("DELETE FROM readings_rollup WHERE substr(sensor_id, 1, length(?1)) = ?1", params![prefix])?;
+        Ok(raw + rollup)
+    }
+
+    /// append one alert transition
+    pub fn record_alert(&self, record: &AlertRecord) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.prepare_cached(
+            "INSERT INTO alert_history (timestamp_ms, transition, alert_id, rule, severity, sensor_id, value, data)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        )?
+        .execute(params![
+            record.timestamp_ms as i64,
+            record.transition,
+            record.alert_id,
+            record.rule,
+            record.severity,
+            record.sensor_id,
+            record.value,
+            record.alert.to_string()
+        ])?;
+        Ok(())
+    }
+
+    /// alert transitions in [from_ms, to_ms], newest first, optionally for one
+    /// alert id and / or rule
+    pub fn alert_history(
+        &self,
+        from_ms: u64,
+        to_ms: u64,
+        alert_id: Option<&str>,
+        rule: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<AlertRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT timestamp_ms, transition, alert_id, rule, severity, sensor_id, value, data FROM alert_history
+             WHERE timestamp_ms BETWEEN ?1 AND ?2 AND (?3 IS NULL OR alert_id = ?3) AND (?4 IS NULL OR rule = ?4)
+             ORDER BY timestamp_ms DESC LIMIT ?5",
+        )?;
+        let rows = stmt.query_map(
+            params![from_ms as i64, to_ms.min(i64::MAX as u64) as i64, alert_id, rule, limit as i64],
+            |row| {
+                let data: String = row.get(7)?;
+                Ok(AlertRecord {
+                    timestamp_ms: row.get::<_, i64>(0)? as u64,
+                    transition: row.get(1)?,
+                    alert_id: row.get(2)?,
+                    rule: row.get(3)?,
+                    severity: row.get(4)?,
+                    sensor_id: row.get(5)?,
+                    value: row.get(6)?,
+                    alert: serde_json::from_str(&data).unwrap_or(serde_json::Value::Null),
+                })
+            },
+        )?;
+        Ok(rows.collect::<Result<_, _>>()?)
+    }
+
+    /// total rows stored (startup log)
+    pub fn count(&self) -> anyhow::Result<u64> {
+        let conn = self.conn.lock().unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM readings", [], |r| r.get(0))?;
+        Ok(count as u64)
+    }
+
+    /// consistent copy of the whole database into a new file (snapshot.rs).
+    /// VACUUM INTO reads one snapshot, so the writer can keep appending.
+    pub fn backup_to(&self, path: &Path) -> anyhow::Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("VACUUM INTO ?1", params![path.to_string_lossy()])?;
+        Ok(())
+    }
+
+    /// replace the rows of every table with those of the database at `path`
+    /// (a snapshot made by backup_to). tables the file lacks keep their rows.
+    /// returns the number of readings restored.
+    pub fn restore_from(&self, path: &Path) -> anyhow::Result<u64> {
+        let mut conn = self.conn.lock().unwrap();
+        conn.execute("ATTACH DATABASE ?1 AS snapshot", params![path.to_string_lossy()])?;
+        let result = (|| -> anyhow::Result<u64> {
+            let tx = conn.transaction()?;
+            for table in ["readings", "readings_rollup", "alert_history"] {
+                let present: bool = tx.query_row(
+                    "SELECT COUNT(*) > 0 FROM snapshot.sqlite_master WHERE type = 'table' AND name = ?1",
+                    params![table],
+                    |r| r.get(0),
+                )?;
+                if present {
+                    tx.execute_batch(&format!("DELETE FROM main.{0}; INSERT INTO main.{0} SELECT * FROM snapshot.{0};", table))?;
+                }
+            }
+            let count: i64 = tx.query_row("SELECT COUNT(*) FROM main.readings", [], |r| r.get(0))?;
+            tx.commit()?;
+            Ok(count as u64)
+        })();
+        conn.execute("DETACH DATABASE snapshot", [])?;
+        result
+    }
+
+    /// apply the retention policy once: downsample expired raw readings,
+    /// drop expired buckets. returns (raw rows folded, buckets dropped).
+    pub fn compact(&self, retention: &RetentionConfig, now_ms: u64) -> anyhow::Result<(usize, usize)> {
+        let mut conn = self.conn.lock().unwrap();
+        let mut folded = 0;
+        if retention.raw_days > 0 {
+            let bucket_ms = retention.downsample_minutes.max(1) * 60 * 1000;
+            // align to a bucket boundary so no bucket is split between tables
+            let cutoff = now_ms.saturating_sub(retention.raw_days * DAY_MS) / bucket_ms * bucket_ms;
+            folded = downsample(&mut conn, cutoff, bucket_ms)?;
+        }
+        let mut dropped = 0;
+        if retention.downsampled_days > 0 {
+            let cutoff = now_ms.saturating_sub(retention.downsampled_days * DAY_MS);
+            dropped = conn.execute("DELETE FROM readings_rollup WHERE bucket_ms < ?1", params![cutoff as i64])?;
+            conn.execute("DELETE FROM alert_history WHERE timestamp_ms < ?1", params![cutoff as i64])?;
+        }
+        if folded + dropped > 0 {
+            // hand the freed wal space back to the filesystem
+            conn.execute_batch("PRAGMA wal_checkpoint(TRUNCATE);")?;
+        }
+        Ok((folded, dropped))
+    }
+}
+
+/// fold raw readings older than `cutoff` into `bucket_ms` averages and delete them
+#[cfg(feature = "storage")]
+fn downsample(conn: &mut Connection, cutoff: u64, bucket_ms: u64) -> rusqlite::Result<usize> {
+    let tx = conn.transaction()?;
+    let mut folded = 0;
+    {
+        let mut select = tx.prepare(
+            "SELECT sensor_id, timestamp_ms, data FROM readings WHERE timestamp_ms < ?1 ORDER BY sensor_id, timestamp_ms",
+        )?;
+        let mut upsert = tx.prepare(
+            "INSERT OR REPLACE INTO readings_rollup (sensor_id, bucket_ms, data, samples) VALUES (?1, ?2, ?3, ?4)",
+        )?;
+        let mut rows = select.query(params![cutoff as i64])?;
+        let mut current: Option<(String, u64, Bucket)> = None;
+        while let Some(row) = rows.next()? {
+            let sensor_id: String = row.get(0)?;
+            let bucket = row.get::<_, i64>(1)? as u64 / bucket_ms * bucket_ms;
+            let data: String = row.get(2)?;
+            let data = serde_json::from_str(&data).unwrap_or(serde_json::Value::Null);
+            match &mut current {
+                Some((id, start, acc)) if *id == sensor_id && *start == bucket => acc.add(&data),
+                _ => {
+                    if let Some((id, start, acc)) = current.take() {
+                        upsert.execute(params![id, start as i64, acc.average().to_string(), acc.samples as i64])?;
+                    }
+                    let mut acc = Bucket::default();
+                    acc.add(&data);
+                    current = Some((sensor_id, bucket, acc));
+                }
+            }
+            folded += 1;
+        }
+        if let Some((id, start, acc)) = current {
+            upsert.execute(params![id, start as i64, acc.average().to_string(), acc.samples as i64])?;
+        }
+        tx.execute("DELETE FROM readings WHERE timestamp_ms < ?1", params![cutoff as i64])?;
+    }
+    tx.commit()?;
+    Ok(folded)
+}
+
+/// running per-field average of one downsampling bucket
+#[cfg(feature = "storage")]
+#[derive(Default)]
+struct Bucket {
+    samples: u64,
+    sums: serde_json::Map<String, serde_json::Value>,
+    counts: std::collections::HashMap<String, u64>,
+}
+
+#[cfg(feature = "storage")]
+impl Bucket {
+    fn add(&mut self, data: &serde_json::Value) {
+        self.samples += 1;
+        let Some(fields) = data.as_object() else { return };
+        for (key, value) in fields {
+            match value.as_f64() {
+                Some(n) => {
+                    let count = self.counts.entry(key.clone()).or_default();
+                    let sum = self.sums.get(key).and_then(|v| v.as_f64()).filter(|_| *count > 0).unwrap_or(0.0);
+                    self.sums.insert(key.clone(), (sum + n).into());
+                    *count += 1;
+                }
+                // strings, bools, nested objects: keep the last value
+                None => {
+                    self.counts.remove(key);
+                    self.sums.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+
+    fn average(&self) -> serde_json::Value {
+        let mut out = self.sums.clone();
+        for (key, count) in &self.counts {
+            if let Some(sum) = out.get(key).and_then(|v| v.as_f64()) {
+                out.insert(key.clone(), (sum / *count as f64).into());
+            }
+        }
+        serde_json::Value::Object(out)
+    }
+}
+
+/// run the retention policy in the background
+#[cfg(feature = "storage")]
+pub fn spawn_compaction(store: Arc<Store>, retention: RetentionConfig) {
+    if retention.compact_interval_minutes == 0 || (retention.raw_days == 0 && retention.downsampled_days == 0) {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(std::time::Duration::from_secs(retention.compact_interval_minutes * 60));
+        loop {
+            ticker.tick().await;
+            let (store, retention) = (store.clone(), retention.clone());
+            let result = tokio::task::spawn_blocking(move || store.compact(&retention, crate::domain::now_ms())).await;
+            match result.map_err(anyhow::Error::from).and_then(|r| r) {
+                Ok((0, 0)) => {}
+                Ok((folded, dropped)) => log_msg(&format!(
+                    "🧹 [STORAGE] Downsampled {} raw readings, dropped {} expired buckets", folded, dropped
+                )),
+                Err(e) => log_msg(&format!("❌ [STORAGE] Compaction failed: {}", e)),
+            }
+        }
+    });
+}
+
+// ==============================================================================
+// built without the "storage" feature
+// ==============================================================================
+//
+// no sqlite in the binary (a minimal spoke build): main.rs doesn't open a
+// store, AppState gets no recorder and ApiState no store, so the history
+// endpoints answer as with storage.enabled = false. Store and Recorder
+// have no values here, their methods only let the callers compile.
+
+#[cfg(not(feature = "storage"))]
+pub use disabled::*;
+
+#[cfg(not(feature = "storage"))]
+mod disabled {
+    use super::{AggregatePoint, AlertRecord, ChartBin, FieldSummary};
+    use crate::config::{RetentionConfig, StorageConfig};
+    use crate::domain::SensorReading;
+    use std::collections::BTreeMap;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    pub enum Store {}
+
+    #[derive(Clone)]
+    pub enum Recorder {}
+
+    impl Recorder {
+        pub fn record(&self, _readings: &[SensorReading]) {
+            match *self {}
+        }
+    }
+
+    pub fn open(_config: &StorageConfig) -> anyhow::Result<(Store, Recorder)> {
+        anyhow::bail!("this build lacks the 'storage' feature")
+    }
+
+    pub fn spawn_compaction(store: Arc<Store>, _retention: RetentionConfig) {
+        match *store {}
+    }
+
+    impl Store {
+        pub fn latest(&self) -> anyhow::Result<Vec<SensorReading>> {
+            match *self {}
+        }
+
+        pub fn history(&self, _sensor_id: &str, _from_ms: u64, _to_ms: u64, _limit: usize) -> anyhow::Result<Vec<SensorReading>> {
+            match *self {}
+        }
+
+        pub fn aggregate(&self, _sensor_id: &str, _func: &str, _window_ms: u64, _from_ms: u64, _to_ms: u64) -> anyhow::Result<Vec<AggregatePoint>> {
+            match *self {}
+        }
+
+        pub fn chart(&self, _sensor_id: &str, _window_ms: u64, _from_ms: u64, _to_ms: u64) -> anyhow::Result<Vec<ChartBin>> {
+            match *self {}
+        }
+
+        pub fn summary(&self, _from_ms: u64, _to_ms: u64) -> anyhow::Result<BTreeMap<String, BTreeMap<String, FieldSummary>>> {
+            match *self {}
+        }
+
+        pub fn purge_node(&self, _node_id: &str) -> anyhow::Result<usize> {
+            match *self {}
+        }
+
+        pub fn record_alert(&self, _record: &AlertRecord) -> anyhow::Result<()> {
+            match *self {}
+        }
+
+        pub fn alert_history(
+            &self,
+            _from_ms: u64,
+            _to_ms: u64,
+            _alert_id: Option<&str>,
+            _rule: Option<&str>,
+            _limit: usize,
+        ) -> anyhow::Result<Vec<AlertRecord>> {
+            match *self {}
+        }
+
+        pub fn count(&self) -> anyhow::Result<u64> {
+            match *self {}
+        }
+
+        pub fn backup_to(&self, _path: &Path) -> anyhow::Result<()> {
+            match *self {}
+        }
+
+        pub fn restore_from(&self, _path: &Path) -> anyhow::Result<u64> {
+            match *self {}
+        }
+    }
+}
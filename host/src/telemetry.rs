@@ -0,0 +1,170 @@
+//! ==============================================================================
+//! telemetry.rs - opentelemetry (otlp) traces and metrics ([telemetry])
+//! ==============================================================================
+//!
+//! purpose:
+//!     puts the edge fleet into the same observability stack as the
+//!     backend. the host describes its work as tracing spans and metric
+//!     events; with the "otel" feature and telemetry.enabled they are
+//!     exported over otlp/http (protobuf) to telemetry.endpoint, e.g. an
+//!     opentelemetry collector on :4318.
+//!
+//! spans:
+//!     http.request    every api / push request (method, route, status)
+//!     poll_cycle      one pass of the polling loop
+//!     plugin.call     a wasm plugin export (plugin, function)
+//!     hub.push        a spoke sending a batch to its hub
+//!
+//! metrics:
+//!     http_requests (counter), http_duration_ms, poll_duration_ms,
+//!     readings_polled (counter), plugin_call_ms, hub_push_ms (histograms).
+//!     they are tracing events with monotonic_counter.* / histogram.*
+//!     fields, turned into otel instruments by tracing-opentelemetry.
+//!
+//! cost:
+//!     without the feature (or with telemetry.enabled = false) the spans
+//!     and metric events have no subscriber interested in them and are
+//!     close to free. resource attributes: service.name =
+//!     telemetry.service_name, service.instance.id = cluster.node_id,
+//!     edge.role = cluster.role.
+//!
+//! relationships:
+//!     - used by: main.rs (subscriber, http middleware, poll loop, hub push),
+//!       runtime.rs (plugin calls)
+//!     - reads: config.rs (TelemetryConfig)
+//!
+//! ==============================================================================
+
+use crate::config::TelemetryConfig;
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use std::future::Future;
+use std::time::Instant;
+use tracing::Instrument;
+
+/// the otel part of the tracing subscriber
+pub type OtelLayer = Box<dyn tracing_subscriber::Layer<tracing_subscriber::Registry> + Send + Sync>;
+
+/// keeps the export pipelines alive for the lifetime of the process
+#[derive(Default)]
+pub struct Guard {
+    #[cfg(feature = "otel")]
+    _providers: Option<(opentelemetry_sdk::trace::SdkTracerProvider, opentelemetry_sdk::metrics::SdkMeterProvider)>,
+}
+
+/// build the otlp exporters (None when telemetry is off or not compiled in)
+pub async fn layer(config: &TelemetryConfig, node_id: &str, role: &str) -> anyhow::Result<(Option<OtelLayer>, Guard)> {
+    if !config.enabled {
+        return Ok((None, Guard::default()));
+    }
+    #[cfg(not(feature = "otel"))]
+    {
+        let _ = (node_id, role);
+        crate::log_msg("⚠️ [OTEL] telemetry.enabled is set but this build lacks the 'otel' feature");
+        Ok((None, Guard::default()))
+    }
+    #[cfg(feature = "otel")]
+    {
+        // the blocking http client may not be created on a runtime thread
+        let (config, node_id, role) = (config.clone(), node_id.to_string(), role.to_string());
+        tokio::task::spawn_blocking(move || otel::build(&config, &node_id, &role)).await?
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otel {
+    use super::{Guard, OtelLayer};
+    use crate::config::TelemetryConfig;
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{WithExportConfig, WithHttpConfig};
+    use opentelemetry_sdk::metrics::{PeriodicReader, SdkMeterProvider};
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+    use tracing_subscriber::filter::{LevelFilter, Targets};
+    use tracing_subscriber::Layer;
+
+    pub fn build(config: &TelemetryConfig, node_id: &str, role: &str) -> anyhow::Result<(Option<OtelLayer>, Guard)> {
+        let endpoint = config.endpoint.trim_end_matches('/');
+        if endpoint.is_empty() {
+            anyhow::bail!("telemetry.endpoint is not set");
+        }
+        if !(0.0..=1.0).contains(&config.sample_ratio) {
+            anyhow::bail!("telemetry.sample_ratio must be between 0 and 1");
+        }
+        let headers: std::collections::HashMap<String, String> = config.headers.clone().into_iter().collect();
+        let resource = Resource::builder()
+            .with_service_name(config.service_name.clone())
+            .with_attributes([
+                KeyValue::new("service.instance.id", node_id.to_string()),
+                KeyValue::new("edge.role", role.to_string()),
+            ])
+            .build();
+
+        let spans = opentelemetry_otlp::SpanExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/traces", endpoint))
+            .with_headers(headers.clone())
+            .build()?;
+        let tracer_provider = SdkTracerProvider::builder()
+            .with_batch_exporter(spans)
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+            .with_resource(resource.clone())
+            .build();
+
+        let metrics = opentelemetry_otlp::MetricExporter::builder()
+            .with_http()
+            .with_endpoint(format!("{}/v1/metrics", endpoint))
+            .with_headers(headers)
+            .build()?;
+        let reader = PeriodicReader::builder(metrics)
+            .with_interval(std::time::Duration::from_secs(config.metrics_interval_seconds.max(1)))
+            .build();
+        let meter_provider = SdkMeterProvider::builder().with_reader(reader).with_resource(resource).build();
+
+        // only the host's own spans - not the exporter's http client
+        let layer = tracing_opentelemetry::layer()
+            .with_tracer(tracer_provider.tracer("wasi-host"))
+            .and_then(tracing_opentelemetry::MetricsLayer::new(meter_provider.clone()))
+            .with_filter(Targets::new().with_target("wasi_host", LevelFilter::TRACE));
+
+        crate::log_msg(&format!("🔭 [OTEL] Exporting traces and metrics to {}", endpoint));
+        Ok((Some(Box::new(layer)), Guard { _providers: Some((tracer_provider, meter_provider)) }))
+    }
+}
+
+/// axum middleware: an http.request span per request, plus request count
+/// and duration metrics by route
+pub async fn http(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = match request.extensions().get::<MatchedPath>() {
+        Some(path) => path.as_str().to_string(),
+        None => request.uri().path().to_string(),
+    };
+    let span = tracing::info_span!(
+        "http.request",
+        http.method = %method,
+        http.route = %route,
+        http.status_code = tracing::field::Empty
+    );
+    let started = Instant::now();
+    let response = next.run(request).instrument(span.clone()).await;
+    let status = response.status().as_u16();
+    span.record("http.status_code", status);
+    tracing::trace!(monotonic_counter.http_requests = 1u64, method = %method, route = %route, status);
+    tracing::trace!(histogram.http_duration_ms = elapsed_ms(started), method = %method, route = %route);
+    response
+}
+
+/// run a wasm plugin export inside a plugin.call span and time it
+pub async fn plugin_call<T>(plugin: &'static str, function: &'static str, call: impl Future<Output = T>) -> T {
+    let started = Instant::now();
+    let result = call.instrument(tracing::info_span!("plugin.call", plugin, function)).await;
+    tracing::trace!(histogram.plugin_call_ms = elapsed_ms(started), plugin, function);
+    result
+}
+
+pub fn elapsed_ms(started: Instant) -> f64 {
+    started.elapsed().as_secs_f64() * 1000.0
+}
@@ -0,0 +1,220 @@
+//! ==============================================================================
+//! tls.rs - Mutual TLS for the hub/spoke channel
+//! ==============================================================================
+//!
+//! purpose:
+//!     builds the rustls configs used when `[cluster.tls] enabled = true`:
+//!     - server side: the http listener presents this node's cert and
+//!       verifies client certs against the cluster ca (hub verifies spokes).
+//!     - client side: the shared reqwest client presents this node's cert
+//!       and either pins the hub cert exactly or trusts the cluster ca.
+//!
+//! why pin instead of hostname checks?
+//!     nodes are addressed by ip on the lan (192.168.7.x), so hostname
+//!     verification is meaningless. pinning compares the exact der bytes of
+//!     the hub certificate, so a rogue device on a shared building network
+//!     can't impersonate the hub even with a cert from the same ca.
+//!
+//! relationships:
+//!     - used by: main.rs (listener + the process-wide http clients),
+//!       admin.rs (whether a connection presented a client cert)
+//!     - reads: config.rs (ClusterConfig.tls, HttpClientConfig)
+//!
+//! ==============================================================================
+
+use anyhow::{Context, Result};
+use crate::config::{HttpClientConfig, TlsConfig};
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::server::{AllowAnyAnonymousOrAuthenticatedClient, AllowAnyAuthenticatedClient};
+use rustls::{Certificate, PrivateKey, RootCertStore, ServerName};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+// ==============================================================================
+// pem helpers
+// ==============================================================================
+
+fn load_certs(path: &str) -> Result<Vec<Certificate>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("failed to open certificate '{}'", path))?;
+    let mut reader = std::io::BufReader::new(file);
+    let certs = rustls_pemfile::certs(&mut reader)
+        .map(|c| c.map(|der| Certificate(der.to_vec())))
+        .collect::<std::io::Result<Vec<_>>>()
+        .with_context(|| format!("failed to parse certificate '{}'", path))?;
+    if certs.is_empty() {
+        anyhow::bail!("no certificates found in '{}'", path);
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKey> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("failed to open private key '{}'", path))?;
+    let mut reader = std::io::BufReader::new(file);
+    let key = rustls_pemfile::private_key(&mut reader)
+        .with_context(|| format!("failed to parse private key '{}'", path))?
+        .ok_or_else(|| anyhow::anyhow!("no private key found in '{}'", path))?;
+    Ok(PrivateKey(key.secret_der().to_vec()))
+}
+
+fn load_roots(path: &str) -> Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in load_certs(path)? {
+        roots.add(&cert).with_context(|| format!("invalid ca certificate in '{}'", path))?;
+    }
+    Ok(roots)
+}
+
+// ==============================================================================
+// server side - listener config
+// ==============================================================================
+
+/// build the rustls server config for the http listener.
+/// client certs are verified against `ca_cert` (required unless
+/// `require_client_cert = false`, which still verifies any cert presented).
+/// without a `ca_cert` nothing could be verified - refuse to start rather
+/// than quietly serve tls without client auth.
+pub fn server_config(tls: &TlsConfig) -> Result<Arc<rustls::ServerConfig>> {
+    if tls.ca_cert.is_empty() {
+        anyhow::bail!("cluster.tls.ca_cert is required to verify spoke certificates");
+    }
+    let certs = load_certs(&tls.cert)?;
+    let key = load_key(&tls.key)?;
+    let roots = load_roots(&tls.ca_cert)?;
+
+    let builder = rustls::ServerConfig::builder().with_safe_defaults();
+    let builder = if tls.require_client_cert {
+        builder.with_client_cert_verifier(AllowAnyAuthenticatedClient::new(roots).boxed())
+    } else {
+        builder.with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+    };
+
+    let config = builder
+        .with_single_cert(certs, key)
+        .context("node certificate and key do not match")?;
+    Ok(Arc::new(config))
+}
+
+// ==============================================================================
+// client side - pinned hub verifier
+// ==============================================================================
+
+/// accepts exactly one server certificate, compared byte-for-byte.
+struct PinnedCertVerifier {
+    pinned: Certificate,
+}
+
+impl ServerCertVerifier for PinnedCertVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if end_entity.0 == self.pinned.0 {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General("server certificate does not match pinned hub cert".into()))
+        }
+    }
+}
+
+/// build the rustls client config used for outbound cluster requests.
+pub fn client_config(tls: &TlsConfig) -> Result<rustls::ClientConfig> {
+    let builder = rustls::ClientConfig::builder().with_safe_defaults();
+
+    let builder = if !tls.pinned_hub_cert.is_empty() {
+        let pinned = load_certs(&tls.pinned_hub_cert)?.remove(0);
+        builder.with_custom_certificate_verifier(Arc::new(PinnedCertVerifier { pinned }))
+    } else if !tls.ca_cert.is_empty() {
+        let verifier = WebPkiVerifier::new(load_roots(&tls.ca_cert)?, None);
+        builder.with_custom_certificate_verifier(Arc::new(verifier))
+    } else {
+        anyhow::bail!("cluster.tls needs either pinned_hub_cert or ca_cert to verify peers");
+    };
+
+    if tls.cert.is_empty() || tls.key.is_empty() {
+        Ok(builder.with_no_client_auth())
+    } else {
+        builder
+            .with_client_auth_cert(load_certs(&tls.cert)?, load_key(&tls.key)?)
+            .context("node certificate and key do not match")
+    }
+}
+
+/// a pooled client builder with the [http_client] timeouts
+fn client_builder(http: &HttpClientConfig) -> reqwest::ClientBuilder {
+    let builder = reqwest::Client::builder()
+        .connect_timeout(Duration::from_secs(http.connect_timeout_seconds.max(1)))
+        .pool_idle_timeout(Duration::from_secs(http.pool_idle_seconds))
+        .pool_max_idle_per_host(http.pool_max_idle_per_host);
+    match http.timeout_seconds {
+        0 => builder,
+        secs => builder.timeout(Duration::from_secs(secs)),
+    }
+}
+
+/// build the reqwest client for hub/spoke traffic.
+/// falls back to a plain client when tls is disabled.
+pub fn build_client(tls: &TlsConfig, http: &HttpClientConfig) -> Result<reqwest::Client> {
+    if !tls.enabled {
+        return plain_client(http);
+    }
+    let client = client_builder(http)
+        .use_preconfigured_tls(client_config(tls)?)
+        .build()?;
+    Ok(client)
+}
+
+/// the client for services outside the cluster (influx, sinks, notifications,
+/// webhooks, s3): the system roots, not the cluster ca or the pinned hub cert
+pub fn plain_client(http: &HttpClientConfig) -> Result<reqwest::Client> {
+    Ok(client_builder(http).build()?)
+}
+
+// ==============================================================================
+// tls listener
+// ==============================================================================
+
+/// accept loop for the https listener. each connection is handshaken with
+/// rustls (client cert verified there) and then handed to hyper.
+pub async fn serve(listener: tokio::net::TcpListener, app: axum::Router, config: Arc<rustls::ServerConfig>) {
+    use hyper_util::rt::{TokioExecutor, TokioIo};
+    use hyper_util::service::TowerToHyperService;
+
+    let acceptor = tokio_rustls::TlsAcceptor::from(config);
+    loop {
+        let (tcp, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                tracing::warn!("TLS accept failed: {}", e);
+                continue;
+            }
+        };
+        let acceptor = acceptor.clone();
+        let app = app.clone();
+        tokio::spawn(async move {
+            let stream = match acceptor.accept(tcp).await {
+                Ok(s) => s,
+                Err(e) => {
+                    tracing::warn!("TLS handshake with {} failed: {}", peer, e);
+                    return;
+                }
+            };
+            // same ConnectInfo the plain listener provides (audit log callers),
+            // and whether the peer proved itself with a cluster cert (admin::cluster_peer)
+            let verified = stream.get_ref().1.peer_certificates().is_some_and(|certs| !certs.is_empty());
+            let app = app
+                .layer(axum::Extension(axum::extract::ConnectInfo(peer)))
+                .layer(axum::Extension(crate::admin::ClientCert(verified)));
+            let service = TowerToHyperService::new(app);
+            let _ = hyper_util::server::conn::auto::Builder::new(TokioExecutor::new())
+                .serve_connection_with_upgrades(TokioIo::new(stream), service)
+                .await;
+        });
+    }
+}
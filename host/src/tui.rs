@@ -0,0 +1,346 @@
+//! ==============================================================================
+//! tui.rs - live terminal dashboard of a running host (--tui, "tui" feature)
+//! ==============================================================================
+//!
+//! purpose:
+//!     the dashboard plugin needs a browser, and on a spoke behind a
+//!     cellular router all there is is an ssh session. `wasi-host --tui`
+//!     is a second process next to the running host that polls its api
+//!     and draws:
+//!         header    node, role, version, uptime, poll loop timing
+//!         sensors   every reading: age, quality and its values
+//!         plugins   calls, errors, traps, p50 latency, linear memory and
+//!                   the last failure of each loaded plugin
+//!         push      spoke: the link to the active hub (latency, last
+//!                   success, failures). hub: every spoke, last seen and
+//!                   push count, stale ones in red
+//!         errors    the newest ❌ / ⚠️ lines of the host log
+//!     keys: q / esc quit, r refreshes now.
+//!
+//! usage:
+//!     wasi-host --tui                           (host on this machine)
+//!     wasi-host --tui --url https://hub:3000    (another node)
+//!     with cluster.tls enabled the client certs of ../config/host.toml
+//!     are used, same as the hub / spoke channel.
+//!
+//! relationships:
+//!     - used by: main.rs (--tui, before any host startup)
+//!     - reads: /api/cluster, /api/readings, /api/plugins/{name}/stats,
+//!       /api/system, /api/logs of the watched host
+//!     - uses: tls.rs (client for an mtls host)
+//!
+//! ==============================================================================
+
+use crate::config::HostConfig;
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Cell, Paragraph, Row, Table, Wrap};
+use ratatui::Frame;
+use serde_json::Value;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// how often the host api is polled
+const REFRESH: Duration = Duration::from_secs(2);
+/// per-request timeout, a hung host shouldn't freeze the screen
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+/// log lines shown in the errors panel
+const ERROR_LINES: usize = 8;
+
+/// the latest view of the watched host
+#[derive(Default)]
+struct Snapshot {
+    /// when the last fetch finished (0 = never)
+    fetched_ms: u64,
+    /// why the last fetch failed (the previous data stays on screen)
+    error: Option<String>,
+    cluster: Value,
+    readings: Vec<Value>,
+    /// /api/plugins/{name}/stats of every loaded plugin
+    plugins: Vec<Value>,
+    /// /api/system "plugins": memory per plugin store
+    memory: Value,
+    errors: Vec<String>,
+}
+
+/// run the dashboard until q / esc
+pub async fn run(url: &str) -> Result<()> {
+    let base = url.trim_end_matches('/').to_string();
+    let client = match HostConfig::find_config_file().and_then(|path| HostConfig::load(&path).ok()) {
+        Some(config) => crate::tls::build_client(&config.cluster.tls, &config.http_client)?,
+        None => reqwest::Client::new(),
+    };
+
+    let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+    let refresh_now = Arc::new(tokio::sync::Notify::new());
+    let poller = {
+        let (snapshot, refresh_now, base) = (snapshot.clone(), refresh_now.clone(), base.clone());
+        tokio::spawn(async move {
+            loop {
+                let result = fetch(&client, &base).await;
+                {
+                    let mut snapshot = snapshot.lock().unwrap();
+                    match result {
+                        Ok(fresh) => *snapshot = fresh,
+                        Err(e) => snapshot.error = Some(format!("{:#}", e)),
+                    }
+                    snapshot.fetched_ms = crate::domain::now_ms();
+                }
+                tokio::select! {
+                    _ = tokio::time::sleep(REFRESH) => {}
+                    _ = refresh_now.notified() => {}
+                }
+            }
+        })
+    };
+
+    // terminal input blocks, so the draw loop lives on its own thread
+    let result = tokio::task::spawn_blocking(move || {
+        let mut terminal = ratatui::init();
+        let result = (|| -> Result<()> {
+            loop {
+                terminal.draw(|frame| draw(frame, &base, &snapshot.lock().unwrap()))?;
+                if !event::poll(Duration::from_millis(250))? {
+                    continue;
+                }
+                if let Event::Key(key) = event::read()? {
+                    match key.code {
+                        _ if key.kind != KeyEventKind::Press => {}
+                        KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                        KeyCode::Char('r') => refresh_now.notify_one(),
+                        _ => {}
+                    }
+                }
+            }
+        })();
+        ratatui::restore();
+        result
+    })
+    .await?;
+    poller.abort();
+    result
+}
+
+/// one round of api calls
+async fn fetch(client: &reqwest::Client, base: &str) -> Result<Snapshot> {
+    let get = |path: String| async move {
+        let response = client.get(format!("{}{}", base, path)).timeout(REQUEST_TIMEOUT).send().await?;
+        anyhow::Ok(response.error_for_status()?.json::<Value>().await?)
+    };
+    let cluster = get("/api/cluster".to_string()).await?;
+    let readings = get("/api/readings".to_string()).await?;
+    let mut plugins = Vec::new();
+    for name in cluster["hub"]["plugins"].as_array().into_iter().flatten().filter_map(|p| p.as_str()) {
+        plugins.push(get(format!("/api/plugins/{}/stats", name)).await?);
+    }
+    // older hosts lack /api/system - the memory column just stays empty
+    let memory = get("/api/system".to_string()).await.map(|s| s["plugins"].clone()).unwrap_or_default();
+    let logs = get("/api/logs".to_string()).await?;
+    let errors: Vec<String> = logs["logs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|line| line.as_str())
+        .filter(|line| line.contains('❌') || line.contains("⚠️"))
+        .map(str::to_string)
+        .collect();
+
+    Ok(Snapshot {
+        fetched_ms: 0,
+        error: None,
+        readings: readings["readings"].as_array().cloned().unwrap_or_default(),
+        cluster,
+        plugins,
+        memory,
+        errors: errors[errors.len().saturating_sub(ERROR_LINES)..].to_vec(),
+    })
+}
+
+fn draw(frame: &mut Frame, base: &str, snapshot: &Snapshot) {
+    let now = crate::domain::now_ms();
+    let [header, sensors, middle, errors, footer] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(6),
+        Constraint::Length(9),
+        Constraint::Length(ERROR_LINES as u16 + 2),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [plugins, push] = Layout::horizontal([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(middle);
+
+    frame.render_widget(header_widget(base, snapshot, now), header);
+    frame.render_widget(sensors_widget(snapshot, now), sensors);
+    frame.render_widget(plugins_widget(snapshot), plugins);
+    frame.render_widget(push_widget(snapshot, now), push);
+    let lines: Vec<Line> = snapshot.errors.iter().map(|line| Line::from(line.as_str())).collect();
+    frame.render_widget(
+        Paragraph::new(lines).wrap(Wrap { trim: false }).block(Block::default().borders(Borders::ALL).title(" recent errors ")),
+        errors,
+    );
+    frame.render_widget(Paragraph::new(" q quit · r refresh").style(Style::default().fg(Color::DarkGray)), footer);
+}
+
+fn header_widget<'a>(base: &'a str, snapshot: &'a Snapshot, now: u64) -> Paragraph<'a> {
+    let hub = &snapshot.cluster["hub"];
+    let mut line = vec![
+        Span::styled(format!(" {} ", text(&hub["node_id"])), Style::default().add_modifier(Modifier::BOLD)),
+        Span::raw(format!(
+            "{} · v{} · up {} · poll cycle {}ms (mean), {} overruns",
+            text(&hub["role"]),
+            text(&hub["version"]),
+            duration(hub["uptime_secs"].as_u64().unwrap_or(0) * 1000),
+            number(&hub["polling"]["mean_cycle_ms"]),
+            number(&hub["polling"]["overruns"]),
+        )),
+    ];
+    let status = match (&snapshot.error, snapshot.fetched_ms) {
+        (_, 0) => Span::styled(format!("connecting to {}", base), Style::default().fg(Color::Yellow)),
+        (Some(e), _) => Span::styled(format!("{}: {}", base, e), Style::default().fg(Color::Red)),
+        (None, fetched) => Span::styled(
+            format!("{} · updated {} ago", base, duration(now.saturating_sub(fetched))),
+            Style::default().fg(Color::DarkGray),
+        ),
+    };
+    line.push(Span::raw("  "));
+    line.push(status);
+    Paragraph::new(Line::from(line)).block(Block::default().borders(Borders::ALL).title(" wasi-host "))
+}
+
+fn sensors_widget(snapshot: &Snapshot, now: u64) -> Table<'_> {
+    let rows = snapshot.readings.iter().map(|reading| {
+        let quality = reading["quality"].as_str().unwrap_or("-");
+        let style = match quality {
+            "ok" | "-" => Style::default(),
+            "stale" => Style::default().fg(Color::DarkGray),
+            _ => Style::default().fg(Color::Yellow),
+        };
+        let age = now.saturating_sub(reading["timestamp_ms"].as_u64().unwrap_or(now));
+        Row::new([
+            Cell::from(text(&reading["sensor_id"])),
+            Cell::from(duration(age)),
+            Cell::from(quality.to_string()),
+            Cell::from(values(&reading["data"])),
+        ])
+        .style(style)
+    });
+    Table::new(rows, [Constraint::Percentage(30), Constraint::Length(6), Constraint::Length(12), Constraint::Fill(1)])
+        .header(heading(["sensor", "age", "quality", "values"]))
+        .block(Block::default().borders(Borders::ALL).title(format!(" sensors ({}) ", snapshot.readings.len())))
+}
+
+fn plugins_widget(snapshot: &Snapshot) -> Table<'_> {
+    let rows = snapshot.plugins.iter().map(|stats| {
+        let name = text(&stats["plugin"]);
+        let memory = snapshot.memory[&name]["linear_memory_bytes"]
+            .as_u64()
+            .map(|bytes| format!("{:.1}M", bytes as f64 / (1024.0 * 1024.0)))
+            .unwrap_or_default();
+        let failed = stats["errors"].as_u64().unwrap_or(0) + stats["traps"].as_u64().unwrap_or(0) > 0;
+        Row::new([
+            Cell::from(name),
+            Cell::from(number(&stats["calls"])),
+            Cell::from(number(&stats["errors"])),
+            Cell::from(number(&stats["traps"])),
+            Cell::from(stats["latency_ms"]["p50"].as_f64().map(|ms| format!("{:.1}", ms)).unwrap_or_default()),
+            Cell::from(memory),
+            Cell::from(stats["last_failure"]["message"].as_str().unwrap_or("").to_string()),
+        ])
+        .style(if failed { Style::default().fg(Color::Yellow) } else { Style::default() })
+    });
+    let widths = [
+        Constraint::Length(14),
+        Constraint::Length(7),
+        Constraint::Length(6),
+        Constraint::Length(6),
+        Constraint::Length(7),
+        Constraint::Length(7),
+        Constraint::Fill(1),
+    ];
+    Table::new(rows, widths)
+        .header(heading(["plugin", "calls", "errors", "traps", "p50 ms", "memory", "last failure"]))
+        .block(Block::default().borders(Borders::ALL).title(" plugins "))
+}
+
+fn push_widget(snapshot: &Snapshot, now: u64) -> Paragraph<'_> {
+    let hub = &snapshot.cluster["hub"];
+    let mut lines = Vec::new();
+    if hub["role"] == "spoke" {
+        let link = &hub["link"];
+        let failures = link["consecutive_failures"].as_u64().unwrap_or(0);
+        let last_success = link["last_success_ms"].as_u64().unwrap_or(0);
+        lines.push(Line::from(format!("hub      {}", text(&link["hub"]))));
+        lines.push(Line::from(format!("latency  {}ms", number(&link["last_latency_ms"]))));
+        lines.push(Line::from(match last_success {
+            0 => "last ok  never".to_string(),
+            ms => format!("last ok  {} ago", duration(now.saturating_sub(ms))),
+        }));
+        lines.push(Line::styled(
+            format!("failing  {} in a row, {} total", failures, number(&link["total_failures"])),
+            if failures > 0 { Style::default().fg(Color::Red) } else { Style::default() },
+        ));
+    } else {
+        for spoke in snapshot.cluster["spokes"].as_array().into_iter().flatten() {
+            let seen = now.saturating_sub(spoke["last_seen_ms"].as_u64().unwrap_or(0));
+            let line = format!("{:<16} {:>6} ago  {} pushes", text(&spoke["node_id"]), duration(seen), number(&spoke["pushes"]));
+            match spoke["stale"].as_bool().unwrap_or(false) {
+                true => lines.push(Line::styled(format!("{}  stale", line), Style::default().fg(Color::Red))),
+                false => lines.push(Line::from(line)),
+            }
+        }
+        if lines.is_empty() {
+            lines.push(Line::styled("no spokes have pushed yet", Style::default().fg(Color::DarkGray)));
+        }
+    }
+    let title = match hub["role"] == "spoke" {
+        true => " push to hub ",
+        false => " spokes ",
+    };
+    Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(title))
+}
+
+fn heading<const N: usize>(columns: [&'static str; N]) -> Row<'static> {
+    Row::new(columns).style(Style::default().add_modifier(Modifier::BOLD))
+}
+
+/// a json string without quotes ("" for anything else)
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+/// a json number as written ("-" if missing)
+fn number(value: &Value) -> String {
+    match value {
+        Value::Number(n) => match n.as_f64() {
+            Some(f) if n.is_f64() => format!("{:.1}", f),
+            _ => n.to_string(),
+        },
+        _ => "-".to_string(),
+    }
+}
+
+/// "temperature=21.4 humidity=40.2 ..."
+fn values(data: &Value) -> String {
+    let Some(fields) = data.as_object() else { return data.to_string() };
+    fields
+        .iter()
+        .filter(|(_, value)| !value.is_object() && !value.is_array())
+        .map(|(key, value)| match value {
+            Value::String(s) => format!("{}={}", key, s),
+            other => format!("{}={}", key, number(other)),
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 42s, 5m, 3h, 2d
+fn duration(ms: u64) -> String {
+    match ms / 1000 {
+        s if s < 60 => format!("{}s", s),
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s => format!("{}d", s / 86400),
+    }
+}
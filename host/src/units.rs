@@ -0,0 +1,171 @@
+//! ==============================================================================
+//! units.rs - display unit conversion (metric / imperial)
+//! ==============================================================================
+//!
+//! purpose:
+//!     plugins report SI-ish units (°C, hPa) and storage, alerts, exports and
+//!     the cluster push keep them. with `units = "imperial"` in host.toml the
+//!     readings served by the api and handed to the dashboard plugin are
+//!     converted on the way out:
+//!         °C   -> °F    (temperature, cpu_temp, dew_point, heat_index)
+//!         hPa  -> inHg  (pressure)
+//!         mm   -> in, m -> ft, km/h -> mph, m/s -> mph
+//!     the field's schema unit decides the conversion, so a [schema.*] entry
+//!     with unit = "°C" makes a custom field convert too. the schema served
+//!     alongside the readings carries the converted unit and range.
+//!     GET /api/readings?units=raw skips the conversion - a hub pulling a
+//!     spoke (cluster.rs) stores what the spoke stores, whatever either
+//!     node displays.
+//!
+//! relationships:
+//!     - used by: main.rs (/api/readings, /api/schema, /api/history, /api/aggregate, /api/chart, dashboard)
+//!     - reads: config.rs (HostConfig.units), schema.rs (field units)
+//!
+//! ==============================================================================
+
+use crate::config::FieldSchema;
+use crate::domain::SensorReading;
+use crate::schema::SchemaRegistry;
+use crate::storage::{AggregatePoint, ChartBin};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+/// si unit -> (display unit, scale, offset)
+const IMPERIAL: [(&str, &str, f64, f64); 6] = [
+    ("°C", "°F", 1.8, 32.0),
+    ("hPa", "inHg", 0.029_529_983_071_4, 0.0),
+    ("mm", "in", 1.0 / 25.4, 0.0),
+    ("m", "ft", 1.0 / 0.3048, 0.0),
+    ("km/h", "mph", 1.0 / 1.609_344, 0.0),
+    ("m/s", "mph", 3600.0 / 1609.344, 0.0),
+];
+
+/// reject an unknown unit system at startup
+pub fn check_config(units: &str) -> anyhow::Result<()> {
+    if !matches!(units, "metric" | "imperial") {
+        anyhow::bail!("units must be \"metric\" or \"imperial\", got '{}'", units);
+    }
+    Ok(())
+}
+
+pub struct Units {
+    imperial: bool,
+    /// registry holding the stored units, used to look up what to convert
+    schema: Arc<SchemaRegistry>,
+    /// the same registry as displayed
+    display: SchemaRegistry,
+}
+
+impl Units {
+    pub fn new(units: &str, schema: Arc<SchemaRegistry>) -> Self {
+        let imperial = units == "imperial";
+        let display = schema.map(|field| display_schema(imperial, field));
+        Self { imperial, schema, display }
+    }
+
+    /// field metadata with the displayed units
+    pub fn schema(&self) -> &SchemaRegistry {
+        &self.display
+    }
+
+    /// field metadata with the stored units
+    pub fn stored_schema(&self) -> &SchemaRegistry {
+        &self.schema
+    }
+
+    /// (scale, offset) applied to `field` of `sensor_id`
+    fn field_conversion(&self, sensor_id: &str, field: &str) -> Option<(f64, f64)> {
+        let (_, scale, offset) = conversion(self.imperial, &self.schema.lookup(sensor_id, field)?.unit)?;
+        Some((scale, offset))
+    }
+
+    /// convert every field of `readings` that has a convertible unit
+    pub fn convert(&self, readings: &mut [SensorReading]) {
+        if !self.imperial {
+            return;
+        }
+        for reading in readings.iter_mut() {
+            let Some(data) = reading.data.as_object_mut() else {
+                continue;
+            };
+            for (field, value) in data.iter_mut() {
+                let (Some((scale, offset)), Some(raw)) = (self.field_conversion(&reading.sensor_id, field), value.as_f64()) else {
+                    continue;
+                };
+                *value = serde_json::json!(round(raw * scale + offset));
+            }
+        }
+    }
+
+    /// `readings` as displayed, borrowed when nothing needs converting
+    pub fn readings<'a>(&self, readings: &'a [SensorReading]) -> Cow<'a, [SensorReading]> {
+        if !self.imperial {
+            return Cow::Borrowed(readings);
+        }
+        let mut converted = readings.to_vec();
+        self.convert(&mut converted);
+        Cow::Owned(converted)
+    }
+
+    /// convert windowed stats of `sensor_id`. min / max / avg convert like
+    /// values, a sum gets the offset once per sample, counts stay as they are
+    pub fn convert_points(&self, sensor_id: &str, func: &str, points: &mut [AggregatePoint]) {
+        if !self.imperial || func == "count" {
+            return;
+        }
+        for point in points.iter_mut() {
+            for (field, value) in point.values.iter_mut() {
+                let Some((scale, offset)) = self.field_conversion(sensor_id, field) else {
+                    continue;
+                };
+                let offset = if func == "sum" { offset * point.samples as f64 } else { offset };
+                *value = round(*value * scale + offset);
+            }
+        }
+    }
+
+    /// convert the min / max / avg of chart bins of `sensor_id`
+    pub fn convert_bins(&self, sensor_id: &str, bins: &mut [ChartBin]) {
+        if !self.imperial {
+            return;
+        }
+        for bin in bins.iter_mut() {
+            for (field, summary) in bin.values.iter_mut() {
+                let Some((scale, offset)) = self.field_conversion(sensor_id, field) else {
+                    continue;
+                };
+                summary.min = round(summary.min * scale + offset);
+                summary.max = round(summary.max * scale + offset);
+                summary.avg = round(summary.avg * scale + offset);
+            }
+        }
+    }
+}
+
+fn conversion(imperial: bool, unit: &str) -> Option<(&'static str, f64, f64)> {
+    if !imperial {
+        return None;
+    }
+    IMPERIAL.iter().find(|(si, ..)| *si == unit).map(|(_, display, scale, offset)| (*display, *scale, *offset))
+}
+
+/// a schema entry with the displayed unit, range and step
+fn display_schema(imperial: bool, schema: &FieldSchema) -> FieldSchema {
+    let Some((display, scale, offset)) = conversion(imperial, &schema.unit) else {
+        return schema.clone();
+    };
+    let value = |v: Option<f64>| v.map(|v| round(v * scale + offset));
+    FieldSchema {
+        unit: display.to_string(),
+        display_name: schema.display_name.clone(),
+        min: value(schema.min),
+        max: value(schema.max),
+        // a step is a difference, the offset cancels out
+        max_step: schema.max_step.map(|step| round(step * scale)),
+    }
+}
+
+/// drop float noise like 70.69999999999999
+fn round(value: f64) -> f64 {
+    (value * 1e6).round() / 1e6
+}
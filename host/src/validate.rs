@@ -0,0 +1,173 @@
+//! ==============================================================================
+//! validate.rs - sanity checks on incoming readings
+//! ==============================================================================
+//!
+//! purpose:
+//!     a glitching DHT22 reports 3276.8°C or 0% humidity every few hundred
+//!     reads. one such value is enough to flatten a chart and fire an alert.
+//!     every polled and pushed reading is checked before it is merged:
+//!         - numeric fields must be numbers (NaN arrives as null or "nan")
+//!         - values must lie within the field's schema min / max
+//!         - a value may not move more than the field's max_step since the
+//!           last accepted value of the same sensor
+//!     ranges and steps come from the schema registry (schema.rs), so a
+//!     sensor type can have its own limits via [schema."dht22.humidity"].
+//!
+//! actions (validation.action):
+//!     drop  - the whole reading is discarded (default)
+//!     mark  - the bad fields are moved out of the data into an "invalid"
+//!             object with the reason, the rest of the reading is kept with
+//!             quality "sensor-error":
+//!             {"temperature": 21.4, "invalid": {"humidity": "140 outside 0..100"}}
+//!
+//! jumps:
+//!     a rejected jump doesn't move the reference value, so the glitch and
+//!     the return to normal aren't both flagged. a real step change is
+//!     accepted once STEP_CONFIRM readings in a row agree with each other.
+//!
+//! relationships:
+//!     - used by: main.rs (poll loop, accept_readings for every hub ingest route)
+//!     - reads: config.rs (ValidationConfig), schema.rs (min / max / max_step)
+//!
+//! ==============================================================================
+
+use crate::aggregate::{glob_match, CLUSTER_NODE};
+use crate::config::ValidationConfig;
+use crate::domain::SensorReading;
+use crate::log_msg;
+use crate::schema::SchemaRegistry;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// data key holding the rejected fields in "mark" mode
+pub const INVALID_KEY: &str = "invalid";
+
+/// consecutive out-of-step readings that establish a new level
+const STEP_CONFIRM: u32 = 3;
+
+struct Reference {
+    value: f64,
+    timestamp_ms: u64,
+    /// last rejected value and how many rejections in a row agreed with it
+    candidate: Option<(f64, u32)>,
+}
+
+pub struct Validator {
+    config: ValidationConfig,
+    schema: Arc<SchemaRegistry>,
+    last: Mutex<HashMap<(String, String), Reference>>,
+}
+
+/// reject an unknown action at startup
+pub fn check_config(config: &ValidationConfig) -> anyhow::Result<()> {
+    if !matches!(config.action.as_str(), "drop" | "mark") {
+        anyhow::bail!("validation.action must be \"drop\" or \"mark\", got '{}'", config.action);
+    }
+    Ok(())
+}
+
+impl Validator {
+    pub fn new(config: ValidationConfig, schema: Arc<SchemaRegistry>) -> Self {
+        Self { config, schema, last: Mutex::new(HashMap::new()) }
+    }
+
+    /// check `readings` in place, dropping or marking the bad ones
+    pub fn apply(&self, readings: &mut Vec<SensorReading>, via: &str) {
+        if !self.config.enabled {
+            return;
+        }
+        let drop = self.config.action != "mark";
+        readings.retain_mut(|reading| {
+            let problems = self.check(reading);
+            if problems.is_empty() {
+                return true;
+            }
+            let reasons: Vec<String> = problems.iter().map(|(field, reason)| format!("{} {}", field, reason)).collect();
+            log_msg(&format!(
+                "🧹 [{}] {} {}: {}",
+                via,
+                if drop { "Dropped" } else { "Marked" },
+                reading.sensor_id,
+                reasons.join(", ")
+            ));
+            if drop {
+                return false;
+            }
+            if let Some(data) = reading.data.as_object_mut() {
+                let mut invalid = serde_json::Map::new();
+                for (field, reason) in problems {
+                    data.remove(&field);
+                    invalid.insert(field, serde_json::Value::String(reason));
+                }
+                data.insert(INVALID_KEY.to_string(), serde_json::Value::Object(invalid));
+            }
+            reading.quality = Some(crate::domain::Quality::SensorError);
+            true
+        });
+    }
+
+    /// (field, reason) of every failed check
+    fn check(&self, reading: &SensorReading) -> Vec<(String, String)> {
+        let mut problems = Vec::new();
+        if reading.sensor_id.starts_with(&format!("{}:", CLUSTER_NODE))
+            || !glob_match(&self.config.sensors, &reading.sensor_id)
+        {
+            return problems;
+        }
+        let Some(data) = reading.data.as_object() else {
+            return problems;
+        };
+        let mut last = self.last.lock().unwrap();
+        for (field, value) in data {
+            let Some(schema) = self.schema.lookup(&reading.sensor_id, field) else {
+                continue;
+            };
+            let value = match value {
+                serde_json::Value::Number(n) => n.as_f64().unwrap_or(f64::NAN),
+                serde_json::Value::Null => f64::NAN,
+                serde_json::Value::String(s) => match s.trim().parse::<f64>() {
+                    Ok(v) if !v.is_finite() => v,
+                    _ => continue,
+                },
+                _ => continue,
+            };
+            if !value.is_finite() {
+                problems.push((field.clone(), "is not a number".to_string()));
+                continue;
+            }
+            if schema.min.is_some_and(|min| value < min) || schema.max.is_some_and(|max| value > max) {
+                let bound = |b: Option<f64>| b.map(|b| b.to_string()).unwrap_or_default();
+                problems.push((field.clone(), format!("{} outside {}..{}", value, bound(schema.min), bound(schema.max))));
+                continue;
+            }
+            let Some(max_step) = schema.max_step else {
+                continue;
+            };
+            let key = (reading.sensor_id.clone(), field.clone());
+            let Some(reference) = last.get_mut(&key) else {
+                last.insert(key, Reference { value, timestamp_ms: reading.timestamp_ms, candidate: None });
+                continue;
+            };
+            // an older or repeated reading says nothing about the step
+            if reading.timestamp_ms <= reference.timestamp_ms {
+                continue;
+            }
+            let step = (value - reference.value).abs();
+            if step <= max_step {
+                *reference = Reference { value, timestamp_ms: reading.timestamp_ms, candidate: None };
+                continue;
+            }
+            let agreeing = match reference.candidate {
+                Some((candidate, count)) if (value - candidate).abs() <= max_step => count + 1,
+                _ => 1,
+            };
+            if agreeing >= STEP_CONFIRM {
+                *reference = Reference { value, timestamp_ms: reading.timestamp_ms, candidate: None };
+                continue;
+            }
+            reference.candidate = Some((value, agreeing));
+            problems.push((field.clone(), format!("jumped {:.1} from {} (max {})", step, reference.value, max_step)));
+        }
+        problems
+    }
+}
@@ -0,0 +1,196 @@
+//! ==============================================================================
+//! watchdog.rs - kill plugin calls that hang ([plugin_watchdog])
+//! ==============================================================================
+//!
+//! purpose:
+//!     a guest stuck in a loop never returns from its export: the poll
+//!     budget (polling.budget_ms) only stops waiting for it, the call keeps
+//!     running on the plugin's worker and every later call queues behind it.
+//!     plugin code is compiled with epoch checks and every call gets a
+//!     deadline of `call_timeout_ms`; a call still running then traps
+//!     ("interrupt") wherever it is. a killed instance can't be entered
+//!     again, so the plugin is re-instantiated from its .wasm in the
+//!     background. a call that traps on its own (a python exception, out of
+//!     bounds) is a strike too and restarts the plugin the same way. after
+//!     `strikes` kills or traps in a row it is quarantined instead:
+//!     unloaded, shown in GET /api/plugins/{name}/stats and /api/cluster,
+//!     until it is reloaded (reload-plugin command, upload, a changed .wasm
+//!     picked up by hot reload, plugins.{name} turned off and on) or the
+//!     host restarts. a call that returns resets the count.
+//!
+//!     [plugin_watchdog]
+//!     enabled = true
+//!     call_timeout_ms = 10000   # a poll / render running longer is killed
+//!     strikes = 3               # kills / traps in a row before quarantine
+//!
+//!     with enabled = false calls have no deadline and nothing is
+//!     quarantined - a plugin that traps is still restarted, every time.
+//!
+//!     time spent in host functions (a dht22 read) counts towards the
+//!     deadline, but only guest code can be interrupted.
+//!
+//!     the verdict is taken on the plugin's worker as the call ends, not by
+//!     the caller - a poll past its budget has stopped waiting by the time
+//!     a hung call is killed. a killed plugin is unloaded at once, so calls
+//!     queued behind it find it missing rather than dead, and re-instantiated
+//!     after them. a call that finds the instance poisoned anyway
+//!     (CannotEnterComponent) has it re-instantiated too, without a strike.
+//!
+//! relationships:
+//!     - used by: runtime.rs (deadline on every call, verdict on the worker after it),
+//!       main.rs (plugin stats, /api/cluster)
+//!     - reads: config.rs (PluginWatchdogConfig)
+//!
+//! ==============================================================================
+
+use crate::config::PluginWatchdogConfig;
+use crate::domain::now_ms;
+use crate::log_msg;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// how often the engine epoch advances, the deadline's resolution
+pub const TICK: Duration = Duration::from_millis(10);
+
+/// a deadline that never comes (instantiation, watchdog off)
+const NEVER: u64 = u64::MAX / 2;
+
+/// what to do with a plugin after a call
+pub enum Verdict {
+    Fine,
+    /// killed or trapped, re-instantiate it
+    Restart,
+    /// killed or trapped `strikes` times in a row, unload it
+    Quarantine,
+}
+
+/// watchdog state of a plugin (plugin stats)
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct Status {
+    /// kills and traps in a row
+    pub strikes: u32,
+    pub kills: u64,
+    pub traps: u64,
+    pub quarantined_since_ms: Option<u64>,
+}
+
+pub struct Watchdog {
+    config: PluginWatchdogConfig,
+    plugins: Mutex<HashMap<&'static str, Status>>,
+}
+
+impl Watchdog {
+    pub fn new(config: PluginWatchdogConfig) -> Self {
+        Self { config, plugins: Mutex::new(HashMap::new()) }
+    }
+
+    /// epoch ticks a call may run before it is killed
+    pub fn deadline_ticks(&self) -> u64 {
+        if !self.config.enabled {
+            return NEVER;
+        }
+        (self.config.call_timeout_ms / TICK.as_millis() as u64).max(1)
+    }
+
+    /// ticks for instantiation, which is never killed
+    pub fn unlimited() -> u64 {
+        NEVER
+    }
+
+    /// a call returned in time
+    pub fn returned(&self, plugin: &'static str) {
+        if let Some(status) = self.plugins.lock().unwrap().get_mut(plugin) {
+            status.strikes = 0;
+        }
+    }
+
+    /// a call trapped on its deadline
+    pub fn killed(&self, plugin: &'static str) -> Verdict {
+        let mut plugins = self.plugins.lock().unwrap();
+        let status = plugins.entry(plugin).or_default();
+        status.kills += 1;
+        self.strike(plugin, status, &format!("⏱️ [WATCHDOG] Killed a call of '{}' after {}ms", plugin, self.config.call_timeout_ms))
+    }
+
+    /// a call trapped on its own (an exception, out of bounds ...)
+    pub fn trapped(&self, plugin: &'static str) -> Verdict {
+        let mut plugins = self.plugins.lock().unwrap();
+        let status = plugins.entry(plugin).or_default();
+        status.traps += 1;
+        self.strike(plugin, status, &format!("💥 [WATCHDOG] '{}' trapped", plugin))
+    }
+
+    /// one more failed call in a row: restart, or quarantine at `strikes`.
+    /// with the watchdog off a trapped plugin is only ever restarted.
+    fn strike(&self, plugin: &'static str, status: &mut Status, what: &str) -> Verdict {
+        if !self.config.enabled {
+            log_msg(&format!("{}, restarting it", what));
+            return Verdict::Restart;
+        }
+        status.strikes += 1;
+        if status.strikes < self.config.strikes.max(1) {
+            log_msg(&format!("{} (strike {} of {}), restarting it", what, status.strikes, self.config.strikes));
+            return Verdict::Restart;
+        }
+        status.quarantined_since_ms = Some(now_ms());
+        log_msg(&format!(
+            "🚫 [WATCHDOG] '{}' failed {} calls in a row, quarantined until it is reloaded or its .wasm changes",
+            plugin, status.strikes
+        ));
+        Verdict::Quarantine
+    }
+
+    /// the plugin was reloaded: a clean slate
+    pub fn reset(&self, plugin: &str) {
+        self.plugins.lock().unwrap().retain(|name, _| *name != plugin);
+    }
+
+    pub fn status(&self, plugin: &str) -> Status {
+        self.plugins.lock().unwrap().get(plugin).cloned().unwrap_or_default()
+    }
+
+    /// plugins in quarantine
+    pub fn quarantined(&self) -> Vec<&'static str> {
+        let plugins = self.plugins.lock().unwrap();
+        let mut names: Vec<&'static str> = plugins.iter().filter(|(_, s)| s.quarantined_since_ms.is_some()).map(|(name, _)| *name).collect();
+        names.sort();
+        names
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_traps_and_kills_share_the_strikes() {
+        let watchdog = Watchdog::new(PluginWatchdogConfig { strikes: 3, ..Default::default() });
+        assert!(matches!(watchdog.trapped("dht22"), Verdict::Restart));
+        assert!(matches!(watchdog.killed("dht22"), Verdict::Restart));
+        assert!(matches!(watchdog.trapped("dht22"), Verdict::Quarantine));
+        assert_eq!(watchdog.quarantined(), vec!["dht22"]);
+        let status = watchdog.status("dht22");
+        assert_eq!((status.traps, status.kills), (2, 1));
+    }
+
+    #[test]
+    fn test_a_returned_call_clears_the_strikes() {
+        let watchdog = Watchdog::new(PluginWatchdogConfig { strikes: 2, ..Default::default() });
+        assert!(matches!(watchdog.trapped("bme680"), Verdict::Restart));
+        watchdog.returned("bme680");
+        assert!(matches!(watchdog.trapped("bme680"), Verdict::Restart));
+        assert!(watchdog.quarantined().is_empty());
+    }
+
+    #[test]
+    fn test_disabled_watchdog_restarts_but_never_quarantines() {
+        let watchdog = Watchdog::new(PluginWatchdogConfig { enabled: false, strikes: 1, ..Default::default() });
+        for _ in 0..3 {
+            assert!(matches!(watchdog.trapped("dht22"), Verdict::Restart));
+        }
+        assert!(watchdog.quarantined().is_empty());
+        assert_eq!(watchdog.status("dht22").traps, 3);
+    }
+}
@@ -0,0 +1,225 @@
+//! ==============================================================================
+//! ws.rs - persistent websocket channel between spoke and hub
+//! ==============================================================================
+//!
+//! purpose:
+//!     with cluster.transport = "websocket" a spoke keeps one websocket open
+//!     to the hub instead of a post per poll plus a command long-poll:
+//!       - upstream:   reading batches and command results
+//!       - downstream: commands from the hub's CommandQueue, pushed the
+//!                     moment they are queued (sub-second latency)
+//!
+//! protocol:
+//!     GET /ws?node_id=X upgrades to a websocket. every frame is a json text
+//!     message tagged by "type":
+//!         {"type": "readings", "readings": [...]}          spoke -> hub
+//!         {"type": "result", "id": 7, "ok": true, ...}     spoke -> hub
+//!         {"type": "command", "command": {...}}            hub -> spoke
+//!     deploy-plugin artifacts are still fetched over http
+//!     (GET /api/command/{id}/artifact) to keep binary blobs off the socket.
+//!
+//! fallback:
+//!     while the socket is down the spoke's polling loop pushes over http as
+//!     before (WsUplink::send returns false), and the uplink task keeps
+//!     reconnecting to whichever hub failover currently considers active.
+//!
+//! relationships:
+//!     - used by: main.rs (/ws route on the hub, uplink on spokes)
+//!     - uses: commands.rs (queue on the hub, execution on the spoke)
+//!     - uses: tls.rs (same client config as the http channel)
+//!
+//! ==============================================================================
+
+use crate::commands::{Command, CommandResult};
+use crate::domain::SensorReading;
+use crate::log_msg;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// how long the hub waits for commands before sending a keepalive ping
+const IDLE_PING: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum WsMessage {
+    Readings { readings: Vec<SensorReading> },
+    Command { command: Command },
+    Result {
+        id: u64,
+        #[serde(flatten)]
+        result: CommandResult,
+    },
+}
+
+/// WsMessage::Readings on the wire, serialized from the poll loop's readings
+/// as they are instead of a copy of them
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum ReadingsFrame<'a> {
+    Readings { readings: &'a [SensorReading] },
+}
+
+// ==============================================================================
+// hub side
+// ==============================================================================
+
+/// serve one connected spoke until the socket closes
+pub async fn serve_spoke(socket: axum::extract::ws::WebSocket, node_id: String, api: crate::ApiState) {
+    use axum::extract::ws::Message;
+
+    log_msg(&format!("🔌 [WS] Spoke '{}' connected", node_id));
+    let (mut sink, mut stream) = socket.split();
+
+    'conn: loop {
+        tokio::select! {
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::Readings { readings }) => crate::ingest_readings(&api, readings, "WS").await,
+                    Ok(WsMessage::Result { id, result }) => {
+                        api.commands.complete(id, &result);
+                    }
+                    Ok(WsMessage::Command { .. }) => {} // commands only flow downstream
+                    Err(e) => tracing::debug!("bad ws frame from {}: {}", node_id, e),
+                },
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {} // ping/pong/binary
+            },
+            commands = api.commands.take(&node_id, IDLE_PING) => {
+                if commands.is_empty() && sink.send(Message::Ping(Vec::new())).await.is_err() {
+                    break;
+                }
+                for command in commands {
+                    let id = command.id;
+                    let frame = serde_json::to_string(&WsMessage::Command { command }).unwrap_or_default();
+                    if sink.send(Message::Text(frame)).await.is_err() {
+                        api.commands.complete(id, &CommandResult { ok: false, message: "websocket closed before delivery".into() });
+                        break 'conn;
+                    }
+                }
+            }
+        }
+    }
+    log_msg(&format!("🔌 [WS] Spoke '{}' disconnected", node_id));
+}
+
+// ==============================================================================
+// spoke side
+// ==============================================================================
+
+/// handle the polling loop uses to send readings over the socket
+pub struct WsUplink {
+    /// readings frames, serialized by send
+    tx: mpsc::Sender<String>,
+    connected: Arc<AtomicBool>,
+    task: tokio::task::AbortHandle,
+}
+
+impl WsUplink {
+    /// queue a batch for the socket. false = not connected, push over http instead.
+    pub fn send(&self, readings: &[SensorReading]) -> bool {
+        if !self.connected.load(Ordering::SeqCst) {
+            return false;
+        }
+        match serde_json::to_string(&ReadingsFrame::Readings { readings }) {
+            Ok(frame) => self.tx.try_send(frame).is_ok(),
+            Err(_) => false,
+        }
+    }
+}
+
+/// dropping the handle closes the socket (role.rs, when a spoke is promoted)
+impl Drop for WsUplink {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// keep a websocket open to the active hub, forwarding readings upstream and
+/// executing commands that arrive downstream
+pub fn spawn_uplink(
+    client: reqwest::Client,
+    hub_base: impl Fn() -> String + Send + 'static,
+    config: crate::config::HostConfig,
+    runtime: crate::runtime::WasmRuntime,
+) -> anyhow::Result<WsUplink> {
+    let connector = match config.cluster.tls.enabled {
+        true => Some(tokio_tungstenite::Connector::Rustls(Arc::new(crate::tls::client_config(&config.cluster.tls)?))),
+        false => None,
+    };
+    let (tx, mut rx) = mpsc::channel::<String>(16);
+    let connected = Arc::new(AtomicBool::new(false));
+    let link = connected.clone();
+    let task = tokio::spawn(async move {
+        loop {
+            let base = hub_base();
+            let url = format!("{}/ws?node_id={}", ws_url(&base), config.cluster.node_id);
+            match tokio_tungstenite::connect_async_tls_with_config(url.as_str(), None, true, connector.clone()).await {
+                Ok((socket, _)) => {
+                    log_msg(&format!("🔌 [WS] Connected to hub {}", base));
+                    connected.store(true, Ordering::SeqCst);
+                    let reason = run_uplink(socket, &mut rx, &client, &base, &config, &runtime).await;
+                    connected.store(false, Ordering::SeqCst);
+                    log_msg(&format!("🔌 [WS] Hub connection closed ({}), falling back to http", reason));
+                }
+                Err(e) => tracing::debug!("ws connect to {} failed: {}", url, e),
+            }
+            // batches queued while we were down are stale by now
+            while rx.try_recv().is_ok() {}
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        }
+    });
+    Ok(WsUplink { tx, connected: link, task: task.abort_handle() })
+}
+
+async fn run_uplink(
+    socket: tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    rx: &mut mpsc::Receiver<String>,
+    client: &reqwest::Client,
+    base: &str,
+    config: &crate::config::HostConfig,
+    runtime: &crate::runtime::WasmRuntime,
+) -> String {
+    use tokio_tungstenite::tungstenite::Message;
+
+    let (mut sink, mut stream) = socket.split();
+    let (results_tx, mut results_rx) = mpsc::channel::<WsMessage>(16);
+
+    loop {
+        let frame = tokio::select! {
+            Some(frame) = rx.recv() => frame,
+            Some(result) = results_rx.recv() => serde_json::to_string(&result).unwrap_or_default(),
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    if let Ok(WsMessage::Command { command }) = serde_json::from_str(&text) {
+                        // run commands off the socket task so a slow deploy doesn't block readings
+                        let (client, base, config, runtime, results_tx) =
+                            (client.clone(), base.to_string(), config.clone(), runtime.clone(), results_tx.clone());
+                        tokio::spawn(async move {
+                            let result = crate::commands::run(&client, &base, &command, &config, &runtime).await;
+                            let _ = results_tx.send(WsMessage::Result { id: command.id, result }).await;
+                        });
+                    }
+                    continue;
+                }
+                Some(Ok(Message::Close(_))) | None => return "closed by hub".to_string(),
+                Some(Err(e)) => return e.to_string(),
+                Some(Ok(_)) => continue, // pings are answered by tungstenite
+            },
+        };
+        if let Err(e) = sink.send(Message::Text(frame)).await {
+            return e.to_string();
+        }
+    }
+}
+
+/// http(s)://host:port -> ws(s)://host:port
+fn ws_url(base: &str) -> String {
+    match base.strip_prefix("https://") {
+        Some(rest) => format!("wss://{}", rest),
+        None => format!("ws://{}", base.trim_start_matches("http://")),
+    }
+}
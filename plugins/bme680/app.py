@@ -5,6 +5,7 @@ Controls LED 2 for air quality status (Pi4 only)
 """
 from wit_world.exports import Bme680Logic
 from wit_world.exports.bme680_logic import Bme680Reading
+from wit_world.imports.reading_types import ReadingQuality
 from wit_world.imports import gpio_provider, led_controller, buzzer_controller, i2c
 
 
@@ -367,7 +368,9 @@ class Bme680Logic(Bme680Logic):
                     gas_resistance=gas,
                     iaq_score=iaq,
                     iaq_accuracy=iaq_accuracy,
-                    timestamp_ms=timestamp
+                    timestamp_ms=timestamp,
+                    # the iaq score is a guess until the gas burn-in is done
+                    quality=ReadingQuality.OK if iaq_accuracy else ReadingQuality.ESTIMATED
                 ))
             else:
                 print("⚠️ BME680 read failed")
@@ -59,6 +59,12 @@ class DashboardLogic(DashboardLogic):
         pizero_ram_total = pizero.get("memory_total_mb", 0)
         pizero_online = pizero.get("online", False)
         
+        # Display units chosen by the host (units = "imperial" converts the values)
+        units = state.get("units", {})
+        temp_unit = units.get("temperature", "°C")
+        pressure_unit = units.get("pressure", "hPa")
+        pressure_text = f"{pressure:.2f}" if pressure_unit == "inHg" else f"{pressure:.0f}"
+        
         # Network health from PiZero pings
         network = state.get("network", {})
         hub_ping = network.get("192.168.7.10", -1)
@@ -318,7 +324,7 @@ class DashboardLogic(DashboardLogic):
     <div class="grid">
         <div class="card" id="dht-card">
             <div class="card-title">DHT22 [ROOM]<span id="dht-offline" class="offline-badge" style="display:none">OFFLINE</span></div>
-            <div class="value" id="dht-temp">{dht_temp:.1f}<span class="unit">°C</span></div>
+            <div class="value" id="dht-temp">{dht_temp:.1f}<span class="unit">{temp_unit}</span></div>
             <div class="metrics">
                 <div class="metric"><span>HUMIDITY</span><span id="dht-hum">{dht_hum:.0f}%</span></div>
             </div>
@@ -326,10 +332,10 @@ class DashboardLogic(DashboardLogic):
         
         <div class="card" id="bme-card">
             <div class="card-title">BME680 [AIR]<span id="bme-source" class="source-label source-main">source:main:spoke-pi4</span><span id="bme-offline" class="offline-badge" style="display:none">OFFLINE</span></div>
-            <div class="value" id="bme-temp">{bme_temp:.1f}<span class="unit">°C</span></div>
+            <div class="value" id="bme-temp">{bme_temp:.1f}<span class="unit">{temp_unit}</span></div>
             <div class="metrics">
                 <div class="metric"><span>HUMIDITY</span><span id="bme-hum">{bme_hum:.0f}%</span></div>
-                <div class="metric"><span>PRESSURE</span><span id="bme-pressure">{pressure:.0f}hPa</span></div>
+                <div class="metric"><span>PRESSURE</span><span id="bme-pressure">{pressure_text}{pressure_unit}</span></div>
                 <div class="metric"><span>GAS</span><span id="bme-gas">{gas:.0f}KΩ</span></div>
                 <div class="metric"><span>IAQ</span><span id="bme-iaq" class="iaq {iaq_class}">{iaq} {iaq_text}</span></div>
             </div>
@@ -337,7 +343,7 @@ class DashboardLogic(DashboardLogic):
         
         <div class="card" id="hub-card">
             <div class="card-title" id="hub-title">REVPI HUB<span id="hub-offline" class="offline-badge" style="display:none">OFFLINE</span></div>
-            <div class="value" id="hub-temp">{hub_cpu:.1f}<span class="unit">°C</span></div>
+            <div class="value" id="hub-temp">{hub_cpu:.1f}<span class="unit">{temp_unit}</span></div>
             <div class="metrics">
                 <div class="metric"><span>CPU</span><span id="hub-load">{hub_load:.1f}%</span></div>
                 <div class="metric"><span>RAM</span><span id="hub-ram">{hub_ram_used}/{hub_ram_total}MB</span></div>
@@ -346,7 +352,7 @@ class DashboardLogic(DashboardLogic):
         
         <div class="card" id="pi4-card">
             <div class="card-title" id="pi4-title">PI4 SPOKE<span id="pi4-offline" class="offline-badge" style="display:none">OFFLINE</span><span class="fan-status"><span class="fan-icon {'spinning' if pi4_fan_on else ''}" id="pi4-fan-icon">🌀</span><span id="pi4-fan-text" class="{'fan-on' if pi4_fan_on else 'fan-off'}">{'FAN ON' if pi4_fan_on else 'FAN OFF'}</span></span></div>
-            <div class="value" id="pi4-temp">{pi4_cpu:.1f}<span class="unit">°C</span></div>
+            <div class="value" id="pi4-temp">{pi4_cpu:.1f}<span class="unit">{temp_unit}</span></div>
             <div class="metrics">
                 <div class="metric"><span>CPU</span><span id="pi4-load">{pi4_load:.1f}%</span></div>
                 <div class="metric"><span>RAM</span><span id="pi4-ram">{pi4_ram_used}/{pi4_ram_total}MB</span></div>
@@ -355,7 +361,7 @@ class DashboardLogic(DashboardLogic):
         
         <div class="card" id="pizero-card">
             <div class="card-title" id="pizero-title">PIZERO <span class="node-status"><span class="dot {'online' if pizero_online else 'offline'}" id="pizero-dot"></span><span id="pizero-status">{'ONLINE' if pizero_online else 'OFFLINE'}</span></span></div>
-            <div class="value" id="pizero-temp">{pizero_cpu:.1f}<span class="unit">°C</span></div>
+            <div class="value" id="pizero-temp">{pizero_cpu:.1f}<span class="unit">{temp_unit}</span></div>
             <div class="metrics">
                 <div class="metric"><span>CPU</span><span id="pizero-load">{pizero_load:.1f}%</span></div>
                 <div class="metric"><span>RAM</span><span id="pizero-ram">{pizero_ram_used}/{pizero_ram_total}MB</span></div>
@@ -388,6 +394,8 @@ class DashboardLogic(DashboardLogic):
     
     <script>
         let currentNode = 'hub';
+        let logSocket = null;
+        const MAX_LOG_LINES = 500;
         const logUrls = {{
             hub: '/api/logs',
             pi4: 'http://192.168.7.11:3000/api/logs',
@@ -404,6 +412,35 @@ class DashboardLogic(DashboardLogic):
             document.querySelectorAll('.tab').forEach(t => t.classList.remove('active'));
             event.target.classList.add('active');
             fetchLogs();
+            streamLogs();
+        }}
+        
+        // Hub logs stream live over /ws/logs; other nodes (and a dropped socket) fall back to polling
+        function streamLogs() {{
+            if (logSocket) {{
+                logSocket.close();
+                logSocket = null;
+            }}
+            if (currentNode !== 'hub' || !window.WebSocket) return;
+            const socket = new WebSocket((location.protocol === 'https:' ? 'wss://' : 'ws://') + location.host + '/ws/logs');
+            socket.onmessage = (msg) => {{
+                const frame = JSON.parse(msg.data);
+                if (frame.type !== 'log' || currentNode !== 'hub') return;
+                const container = document.getElementById('log-content');
+                const wasAtBottom = container.scrollHeight - container.clientHeight <= container.scrollTop + 50;
+                const line = document.createElement('div');
+                line.className = 'log-line';
+                line.innerHTML = frame.line;
+                container.appendChild(line);
+                while (container.children.length > MAX_LOG_LINES) container.removeChild(container.firstChild);
+                if (wasAtBottom) {{
+                    container.scrollTop = container.scrollHeight;
+                }}
+            }};
+            socket.onclose = () => {{
+                if (logSocket === socket) logSocket = null;
+            }};
+            logSocket = socket;
         }}
         
         async function fetchLogs() {{
@@ -439,6 +476,9 @@ class DashboardLogic(DashboardLogic):
                 const readings = state.readings || [];
                 console.log('[LIVE UPDATE] Got', readings.length, 'readings');
                 
+                // Display unit of a field from the attached schema (host may convert to imperial)
+                const unitOf = (r, field, fallback) => (r.schema && r.schema[field] && r.schema[field].unit) || fallback;
+                
                 // Helper to find a reading by sensor_id pattern
                 const findReading = (pattern) => readings.find(r => r.sensor_id && r.sensor_id.includes(pattern));
                 
@@ -446,7 +486,7 @@ class DashboardLogic(DashboardLogic):
                 const dht = findReading('dht22');
                 if (dht && dht.data) {{
                     const el = document.getElementById('dht-temp');
-                    if (el && dht.data.temperature != null) el.innerHTML = dht.data.temperature.toFixed(1) + '<span class="unit">°C</span>';
+                    if (el && dht.data.temperature != null) el.innerHTML = dht.data.temperature.toFixed(1) + '<span class="unit">' + unitOf(dht, 'temperature', '°C') + '</span>';
                     const hum = document.getElementById('dht-hum');
                     if (hum && dht.data.humidity != null) hum.textContent = dht.data.humidity.toFixed(0) + '%';
                 }}
@@ -457,11 +497,11 @@ class DashboardLogic(DashboardLogic):
                 const bme = bme_pi4_data || bme_pizero_data;  // Prefer Pi4, fallback to PiZero
                 if (bme && bme.data) {{
                     const el = document.getElementById('bme-temp');
-                    if (el && bme.data.temperature != null) el.innerHTML = bme.data.temperature.toFixed(1) + '<span class="unit">°C</span>';
+                    if (el && bme.data.temperature != null) el.innerHTML = bme.data.temperature.toFixed(1) + '<span class="unit">' + unitOf(bme, 'temperature', '°C') + '</span>';
                     const hum = document.getElementById('bme-hum');
                     if (hum && bme.data.humidity != null) hum.textContent = bme.data.humidity.toFixed(0) + '%';
                     const pres = document.getElementById('bme-pressure');
-                    if (pres && bme.data.pressure != null) pres.textContent = bme.data.pressure.toFixed(0) + 'hPa';
+                    if (pres && bme.data.pressure != null) pres.textContent = (unitOf(bme, 'pressure', 'hPa') === 'inHg' ? bme.data.pressure.toFixed(2) : bme.data.pressure.toFixed(0)) + unitOf(bme, 'pressure', 'hPa');
                     const gas = document.getElementById('bme-gas');
                     if (gas && bme.data.gas_resistance != null) gas.textContent = bme.data.gas_resistance.toFixed(0) + 'KΩ';
                     // Update IAQ with calibrating support
@@ -484,7 +524,7 @@ class DashboardLogic(DashboardLogic):
                 const hub = readings.find(r => r.sensor_id && r.sensor_id.includes('hub:') && r.sensor_id.includes('monitor'));
                 if (hub && hub.data) {{
                     const el = document.getElementById('hub-temp');
-                    if (el && hub.data.cpu_temp != null) el.innerHTML = hub.data.cpu_temp.toFixed(1) + '<span class="unit">°C</span>';
+                    if (el && hub.data.cpu_temp != null) el.innerHTML = hub.data.cpu_temp.toFixed(1) + '<span class="unit">' + unitOf(hub, 'cpu_temp', '°C') + '</span>';
                     const load = document.getElementById('hub-load');
                     if (load && hub.data.cpu_usage != null) load.textContent = hub.data.cpu_usage.toFixed(1) + '%';
                     const ram = document.getElementById('hub-ram');
@@ -502,7 +542,7 @@ class DashboardLogic(DashboardLogic):
                 const pi4 = readings.find(r => r.sensor_id && r.sensor_id.includes('pi4') && r.sensor_id.includes('monitor'));
                 if (pi4 && pi4.data) {{
                     const el = document.getElementById('pi4-temp');
-                    if (el && pi4.data.cpu_temp != null) el.innerHTML = pi4.data.cpu_temp.toFixed(1) + '<span class="unit">°C</span>';
+                    if (el && pi4.data.cpu_temp != null) el.innerHTML = pi4.data.cpu_temp.toFixed(1) + '<span class="unit">' + unitOf(pi4, 'cpu_temp', '°C') + '</span>';
                     const load = document.getElementById('pi4-load');
                     if (load && pi4.data.cpu_usage != null) load.textContent = pi4.data.cpu_usage.toFixed(1) + '%';
                     const ram = document.getElementById('pi4-ram');
@@ -523,7 +563,7 @@ class DashboardLogic(DashboardLogic):
                 const pizero = readings.find(r => r.sensor_id && r.sensor_id.includes('pizero') && r.sensor_id.includes('monitor'));
                 if (pizero && pizero.data) {{
                     const el = document.getElementById('pizero-temp');
-                    if (el && pizero.data.cpu_temp != null) el.innerHTML = pizero.data.cpu_temp.toFixed(1) + '<span class="unit">°C</span>';
+                    if (el && pizero.data.cpu_temp != null) el.innerHTML = pizero.data.cpu_temp.toFixed(1) + '<span class="unit">' + unitOf(pizero, 'cpu_temp', '°C') + '</span>';
                     const load = document.getElementById('pizero-load');
                     if (load && pizero.data.cpu_usage != null) load.textContent = pizero.data.cpu_usage.toFixed(1) + '%';
                     const ram = document.getElementById('pizero-ram');
@@ -637,8 +677,13 @@ class DashboardLogic(DashboardLogic):
         }}
         
         fetchLogs();
+        streamLogs();
         fetchSensorData();
-        setInterval(fetchLogs, 3000);
+        setInterval(() => {{
+            if (logSocket) return;
+            fetchLogs();
+            streamLogs();
+        }}, 3000);
         setInterval(fetchSensorData, 3000);
     </script>
 </body>
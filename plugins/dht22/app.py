@@ -4,6 +4,7 @@ Controls LED 1 for room temperature status
 """
 from wit_world.exports import Dht22Logic
 from wit_world.exports.dht22_logic import Dht22Reading
+from wit_world.imports.reading_types import ReadingQuality
 from wit_world.imports import gpio_provider, led_controller, buzzer_controller
 
 # Thresholds
@@ -61,7 +62,8 @@ class Dht22Logic(Dht22Logic):
                 sensor_id="dht22-gpio4",
                 temperature=temp,
                 humidity=hum,
-                timestamp_ms=timestamp
+                timestamp_ms=timestamp,
+                quality=ReadingQuality.OK
             ))
                 
         except Exception as e:
@@ -3,13 +3,16 @@ Pi4 Monitor Plugin - System health monitoring for Raspberry Pi 4
 Controls LED 3 for CPU temperature status
 Controls cooling fan via GPIO 27 relay with hysteresis
 """
+import os
 from wit_world.exports import PiMonitorLogic
 from wit_world.exports.pi_monitor_logic import PiStats
+from wit_world.imports.reading_types import ReadingQuality
 from wit_world.imports import gpio_provider, led_controller, system_info, buzzer_controller, fan_controller
 
-# Fan temperature thresholds with hysteresis (2°C deadband)
-FAN_ON_THRESHOLD = 40.0   # Turn fan ON when CPU temp exceeds this
-FAN_OFF_THRESHOLD = 28.0  # Turn fan OFF when CPU temp drops below this
+# Fan temperature thresholds with hysteresis, from the host's [fan] section
+FAN_ON_THRESHOLD = float(os.environ.get("HARVESTER_FAN_ON", "40.0"))   # Turn fan ON when CPU temp exceeds this
+FAN_OFF_THRESHOLD = float(os.environ.get("HARVESTER_FAN_OFF", "28.0"))  # Turn fan OFF when CPU temp drops below this
+FAN_AUTO = os.environ.get("HARVESTER_FAN_AUTO", "1") == "1"            # [fan] auto = false: leave the fan alone
 
 
 class PiMonitorLogic(PiMonitorLogic):
@@ -24,7 +27,9 @@ class PiMonitorLogic(PiMonitorLogic):
         current_fan_state = fan_controller.get_fan_state()
         
         # Fan control with hysteresis to prevent rapid cycling
-        if cpu_temp >= FAN_ON_THRESHOLD and not current_fan_state:
+        if not FAN_AUTO:
+            pass
+        elif cpu_temp >= FAN_ON_THRESHOLD and not current_fan_state:
             fan_controller.set_fan(True)
             buzzer_controller.beep(3, 200, 100)  # 3 long beeps = fan starting
             print(f"🌀 [FAN] Starting - CPU at {cpu_temp:.1f}°C (threshold: {FAN_ON_THRESHOLD}°C)")
@@ -57,5 +62,6 @@ class PiMonitorLogic(PiMonitorLogic):
             memory_total_mb=mem_total,
             uptime_seconds=uptime,
             timestamp_ms=timestamp,
-            fan_on=fan_on
+            fan_on=fan_on,
+            quality=ReadingQuality.OK
         )
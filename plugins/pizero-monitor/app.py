@@ -17,6 +17,7 @@ Build:
 
 from wit_world.exports import PiMonitorLogic
 from wit_world.exports.pi_monitor_logic import PiStats
+from wit_world.imports.reading_types import ReadingQuality
 from wit_world.imports import gpio_provider, system_info
 
 
@@ -37,5 +38,6 @@ class PiMonitorLogic(PiMonitorLogic):
             memory_total_mb=total_mb,
             uptime_seconds=uptime,
             timestamp_ms=gpio_provider.get_timestamp_ms(),
-            fan_on=False  # No fan on Pi Zero
+            fan_on=False,  # No fan on Pi Zero
+            quality=ReadingQuality.OK
         )
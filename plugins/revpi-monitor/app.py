@@ -4,6 +4,7 @@ Controls LED 0 for CPU temperature status
 """
 from wit_world.exports import PiMonitorLogic
 from wit_world.exports.pi_monitor_logic import PiStats
+from wit_world.imports.reading_types import ReadingQuality
 from wit_world.imports import gpio_provider, led_controller, system_info, buzzer_controller
 
 
@@ -36,5 +37,6 @@ class PiMonitorLogic(PiMonitorLogic):
             memory_total_mb=mem_total,
             uptime_seconds=uptime,
             timestamp_ms=timestamp,
-            fan_on=False  # No fan on RevPi Hub
+            fan_on=False,  # No fan on RevPi Hub
+            quality=ReadingQuality.OK
         )